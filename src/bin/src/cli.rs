@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use ferrumc_world::dimension::Dimension;
 use tracing::Level;
 
 #[derive(Parser)]
@@ -18,6 +19,16 @@ pub enum Command {
     Setup,
     /// Import the world data
     Import(ImportArgs),
+    /// Export the world data to vanilla region files
+    Export(ExportArgs),
+    /// Re-compress every stored chunk with the compression settings from the config
+    Recompress,
+    /// Upgrade every stored chunk to the current chunk format version
+    Migrate,
+    /// Generate every missing chunk around spawn ahead of time
+    Pregen(PregenArgs),
+    /// Check every stored chunk for corruption
+    Verify(VerifyArgs),
     /// Start the server
     Run,
 }
@@ -37,6 +48,35 @@ pub struct ImportArgs {
     pub max_concurrent_tasks: usize,
 }
 
+#[derive(Debug, Clone, Parser)]
+pub struct ExportArgs {
+    /// Path to the folder the world should be exported to
+    ///
+    /// Region files are written to `region`, `DIM-1/region` and `DIM1/region` inside this folder, the same layout as a vanilla world save.
+    #[clap(long, required = true)]
+    pub export_path: String,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct PregenArgs {
+    /// How many chunks out from spawn to generate, in every direction
+    #[clap(long, required = true)]
+    pub radius: u32,
+    /// The dimension to generate chunks in
+    #[clap(long, default_value_t = Dimension::Overworld)]
+    pub dimension: Dimension,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct VerifyArgs {
+    /// Move corrupted chunks to the `chunks_quarantine` table so they stop being loaded
+    #[clap(long)]
+    pub quarantine: bool,
+    /// Quarantine corrupted chunks and generate new ones in their place
+    #[clap(long)]
+    pub regenerate: bool,
+}
+
 // Wrapper struct for the Level enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct LogLevel(Level);
@@ -12,6 +12,7 @@ use crossbeam_channel::Sender;
 use ferrumc_commands::infrastructure::register_command_systems;
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_net::connection::{handle_connection, NewConnection};
+use ferrumc_net::proxy_protocol::client_address;
 use ferrumc_net::server::create_server_listener;
 use ferrumc_net::PacketSender;
 use ferrumc_scheduler::MissedTickBehavior;
@@ -173,6 +174,25 @@ fn build_timed_scheduler() -> Scheduler {
             .with_behavior(MissedTickBehavior::Skip),
     );
 
+    // World backups
+    let backups = &get_global_config().backups;
+    if backups.enabled {
+        if backups.interval == 0 {
+            warn!("Backup interval is set to 0, scheduled backups are disabled.");
+        } else {
+            let build_world_backup = |s: &mut Schedule| {
+                s.add_systems(crate::systems::world_backup::backup_world);
+            };
+            let period = Duration::from_secs(backups.interval * 60);
+            timed.register(
+                TimedSchedule::new("world_backup", period, build_world_backup)
+                    .with_behavior(MissedTickBehavior::Skip)
+                    // Don't take a backup straight away on startup.
+                    .with_phase(period),
+            );
+        }
+    }
+
     // Player count refresh
     let build_player_count = |s: &mut Schedule| {
         s.add_systems(crate::systems::player_count_update::player_count_updater);
@@ -243,15 +263,22 @@ fn tcp_conn_acceptor(
                         tokio::select! {
                             accept_result = listener.accept() => {
                                 match accept_result {
-                                    Ok((stream, _)) => {
-                                        let addy = stream.peer_addr()?;
-                                        debug!("Got TCP connection from {}", addy);
+                                    Ok((mut stream, peer)) => {
+                                        debug!("Got TCP connection from {}", peer);
                                         tokio::spawn({
                                             let state = Arc::clone(&state);
                                             let packet_sender = Arc::clone(&packet_sender);
                                             let sender = Arc::clone(&sender);
                                             async move {
-                                                _ = handle_connection(state, stream, packet_sender, sender)
+                                                // Behind a load balancer, the real address is in the PROXY protocol header
+                                                let addy = match client_address(&mut stream).await {
+                                                    Ok(addy) => addy,
+                                                    Err(e) => {
+                                                        warn!("Refused connection from {}: {}", peer, e);
+                                                        return;
+                                                    }
+                                                };
+                                                _ = handle_connection(state, stream, addy, packet_sender, sender)
                                                     .instrument(info_span!("conn", %addy).or_current())
                                                     .await;
                                             }
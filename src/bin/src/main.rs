@@ -9,14 +9,18 @@ use ferrumc_state::player_cache::PlayerCache;
 use ferrumc_state::player_list::PlayerList;
 use ferrumc_state::{GlobalState, ServerState};
 use ferrumc_threadpool::ThreadPool;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::errors::WorldError;
+use ferrumc_world::metadata::{GeneratorSettings, WorldMetadata};
 use ferrumc_world::World;
-use ferrumc_world_gen::WorldGenerator;
+use ferrumc_world_gen::{registered_chunk_generator, ChunkGenerator, WorldGenerator};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub(crate) mod errors;
-use crate::cli::{CLIArgs, Command, ImportArgs};
+use crate::cli::{CLIArgs, Command, ExportArgs, ImportArgs, PregenArgs, VerifyArgs};
 mod chunk_sending;
 mod cli;
 mod game_loop;
@@ -58,6 +62,58 @@ fn main() {
                 info!("Import completed successfully.");
             }
         }
+        Some(Command::Export(export_args)) => {
+            info!("Starting export...");
+            if let Err(e) = handle_export(export_args) {
+                error!("Export failed with the following error: {}", e.to_string());
+            } else {
+                info!("Export completed successfully.");
+            }
+        }
+        Some(Command::Recompress) => {
+            info!("Starting re-compression...");
+            if let Err(e) = handle_recompress() {
+                error!(
+                    "Re-compression failed with the following error: {}",
+                    e.to_string()
+                );
+            } else {
+                info!("Re-compression completed successfully.");
+            }
+        }
+        Some(Command::Migrate) => {
+            info!("Starting chunk migration...");
+            if let Err(e) = handle_migrate() {
+                error!(
+                    "Migration failed with the following error: {}",
+                    e.to_string()
+                );
+            } else {
+                info!("Migration completed successfully.");
+            }
+        }
+        Some(Command::Pregen(pregen_args)) => {
+            info!("Starting pre-generation...");
+            if let Err(e) = handle_pregen(pregen_args) {
+                error!(
+                    "Pre-generation failed with the following error: {}",
+                    e.to_string()
+                );
+            } else {
+                info!("Pre-generation completed successfully.");
+            }
+        }
+        Some(Command::Verify(verify_args)) => {
+            info!("Starting verification...");
+            if let Err(e) = handle_verify(verify_args) {
+                error!(
+                    "Verification failed with the following error: {}",
+                    e.to_string()
+                );
+            } else {
+                info!("Verification completed successfully.");
+            }
+        }
         Some(Command::Run) | None => {
             info!("Starting server...");
             if let Err(e) = ferrumc_config::setup::setup() {
@@ -75,36 +131,23 @@ fn main() {
 }
 
 fn generate_chunks(state: GlobalState) -> Result<(), BinaryError> {
-    info!("No overworld spawn chunk found, generating spawn chunks...");
-    // Generate a 12x12 chunk area around the spawn point
-    let mut chunks = Vec::new();
-    let start = Instant::now();
-    let radius = get_global_config().chunk_render_distance as i32;
-    for x in -radius..=radius {
-        for z in -radius..=radius {
-            chunks.push((x, z));
-        }
-    }
-    let mut batch = state.thread_pool.batch();
-    for (x, z) in chunks {
-        let state_clone = state.clone();
-        batch.execute(move || {
-            let chunk = state_clone
+    info!(
+        "No overworld spawn chunk found, generating spawn chunks from seed {}...",
+        state.world_metadata.generator.seed as i64
+    );
+    let radius = get_global_config().chunk_render_distance;
+    let generator_state = state.clone();
+    state.world.pregenerate(
+        Dimension::Overworld.as_str(),
+        radius,
+        &state.thread_pool,
+        move |x, z| {
+            generator_state
                 .terrain_generator
-                .generate_chunk(x, z)
-                .map(Arc::new);
-            if let Err(e) = chunk {
-                error!("Error generating chunk ({}, {}): {:?}", x, z, e);
-            } else {
-                let chunk = chunk.unwrap();
-                if let Err(e) = state_clone.world.save_chunk(chunk) {
-                    error!("Error saving chunk ({}, {}): {:?}", x, z, e);
-                }
-            }
-        });
-    }
-    batch.wait();
-    info!("Finished generating spawn chunks in {:?}", start.elapsed());
+                .generate_chunk(x, z, Dimension::Overworld)
+                .map_err(|e| WorldError::WorldGenerationError(e.to_string()))
+        },
+    )?;
     Ok(())
 }
 
@@ -112,7 +155,10 @@ fn entry(start_time: Instant) -> Result<(), BinaryError> {
     let state = create_state(start_time)?;
     let global_state = Arc::new(state);
     create_whitelist();
-    if !global_state.world.chunk_exists(0, 0, "overworld")? {
+    if !global_state
+        .world
+        .chunk_exists(0, 0, Dimension::Overworld.as_str())?
+    {
         generate_chunks(global_state.clone())?;
     }
 
@@ -157,10 +203,139 @@ fn handle_import(import_args: ImportArgs) -> Result<(), BinaryError> {
     Ok(())
 }
 
+fn handle_export(export_args: ExportArgs) -> Result<(), BinaryError> {
+    //! Handles the export of the world.
+    info!("Exporting world...");
+
+    let world = World::new(&get_global_config().database.db_path);
+
+    let mut export_path = PathBuf::from(export_args.export_path);
+    if export_path.is_relative() {
+        export_path = get_root_path().join(export_path);
+    }
+
+    if let Err(e) = world.export(export_path, ThreadPool::new()) {
+        error!("Could not export world: {}", e.to_string());
+        return Err(BinaryError::Custom("Could not export world.".to_string()));
+    }
+
+    Ok(())
+}
+
+fn handle_recompress() -> Result<(), BinaryError> {
+    //! Handles re-compressing the stored chunks after the compression config changed.
+    let world = World::new(&get_global_config().database.db_path);
+
+    if let Err(e) = world.recompress() {
+        error!("Could not re-compress world: {}", e.to_string());
+        return Err(BinaryError::Custom(
+            "Could not re-compress world.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn handle_migrate() -> Result<(), BinaryError> {
+    //! Handles upgrading the stored chunks to the current chunk format version.
+    let world = World::new(&get_global_config().database.db_path);
+
+    if let Err(e) = world.migrate_chunks() {
+        error!("Could not migrate world: {}", e.to_string());
+        return Err(BinaryError::Custom("Could not migrate world.".to_string()));
+    }
+
+    Ok(())
+}
+
+fn handle_pregen(pregen_args: PregenArgs) -> Result<(), BinaryError> {
+    //! Handles generating the chunks around spawn ahead of time.
+    let world = World::new(&get_global_config().database.db_path);
+    let generator = world_generator(&world.load_metadata(GeneratorSettings::from_config())?)?;
+    let dimension = pregen_args.dimension;
+
+    let result = world.pregenerate(
+        dimension.as_str(),
+        pregen_args.radius,
+        &ThreadPool::new(),
+        move |x, z| {
+            generator
+                .generate_chunk(x, z, dimension)
+                .map_err(|e| WorldError::WorldGenerationError(e.to_string()))
+        },
+    );
+    if let Err(e) = result {
+        error!("Could not pre-generate world: {}", e.to_string());
+        return Err(BinaryError::Custom(
+            "Could not pre-generate world.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn handle_verify(verify_args: VerifyArgs) -> Result<(), BinaryError> {
+    //! Handles checking the stored chunks for corruption, and repairing them if asked to.
+    let world = World::new(&get_global_config().database.db_path);
+
+    let report = world.verify()?;
+    for chunk in &report.corrupted {
+        match chunk.position {
+            Some((x, z, dimension)) => {
+                warn!(
+                    "Chunk ({}, {}) in {} is corrupted: {}",
+                    x, z, dimension, chunk.problem
+                )
+            }
+            None => warn!(
+                "Chunk with key {:#x} is corrupted: {}",
+                chunk.key, chunk.problem
+            ),
+        }
+    }
+    if report.corrupted.is_empty() {
+        info!("All {} chunks are healthy.", report.checked);
+        return Ok(());
+    }
+
+    if verify_args.quarantine || verify_args.regenerate {
+        let moved = world.quarantine_chunks(&report.corrupted)?;
+        info!("Quarantined {} corrupted chunks.", moved);
+    } else {
+        info!("Run again with --quarantine or --regenerate to repair the world.");
+    }
+    if verify_args.regenerate {
+        let generator = world_generator(&world.load_metadata(GeneratorSettings::from_config())?)?;
+        let regenerated = world.regenerate_chunks(&report.corrupted, |x, z, dimension| {
+            generator
+                .generate_chunk(x, z, dimension)
+                .map_err(|e| WorldError::WorldGenerationError(e.to_string()))
+        })?;
+        info!("Regenerated {} chunks.", regenerated);
+    }
+
+    Ok(())
+}
+
+/// The generator for a world: the one registered by another crate if there is one, otherwise the
+/// built-in generator set up the way the world was first generated.
+fn world_generator(metadata: &WorldMetadata) -> Result<Arc<dyn ChunkGenerator>, BinaryError> {
+    if let Some(generator) = registered_chunk_generator() {
+        info!("Using a registered chunk generator.");
+        return Ok(generator);
+    }
+    Ok(Arc::new(WorldGenerator::from_settings(
+        &metadata.generator,
+    )?))
+}
+
 fn create_state(start_time: Instant) -> Result<ServerState, BinaryError> {
+    let world = World::new(&get_global_config().database.db_path);
+    let world_metadata = world.load_metadata(GeneratorSettings::from_config())?;
     Ok(ServerState {
-        world: World::new(&get_global_config().database.db_path),
-        terrain_generator: WorldGenerator::new(0),
+        terrain_generator: world_generator(&world_metadata)?,
+        world_metadata,
+        world,
         shut_down: false.into(),
         players: PlayerList::default(),
         player_cache: PlayerCache::default(),
@@ -17,6 +17,7 @@ mod set_player_position;
 mod set_player_position_and_rotation;
 mod set_player_rotation;
 mod swing_arm;
+mod wand;
 
 pub fn register_packet_handlers(schedule: &mut Schedule) {
     // Added separately so if we mess up the signature of one of the systems we can know exactly
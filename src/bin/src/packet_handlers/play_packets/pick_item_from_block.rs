@@ -1,5 +1,6 @@
 use bevy_ecs::prelude::{Entity, Query, Res};
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_inventories::item::ItemID;
 use ferrumc_inventories::slot::InventorySlot;
@@ -22,11 +23,12 @@ pub fn handle(
         &mut Inventory,
         &mut Hotbar,
         &StreamWriter,
+        &DimensionComponent,
     )>,
 ) {
     for (packet, sender_entity) in events.0.try_iter() {
         // 1. Get player's components
-        let (entity, identity, abilities, mut inventory, mut hotbar, writer) =
+        let (entity, identity, abilities, mut inventory, mut hotbar, writer, dimension) =
             match player_inv_query.get_mut(sender_entity) {
                 Ok(data) => data,
                 Err(e) => {
@@ -47,7 +49,7 @@ pub fn handle(
             packet.location.x,
             packet.location.y as i32,
             packet.location.z,
-            "overworld", // TODO: Remove overworld hard coding for the dimension
+            dimension.0.as_str(),
         ) {
             Ok(id) => id,
             Err(e) => {
@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
 use bevy_ecs::prelude::{Entity, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::world_edit::{is_holding_wand, WorldEditSession};
 use ferrumc_core::collisions::bounds::CollisionBounds;
+use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_core::transform::position::Position;
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::block_change_ack::BlockChangeAck;
@@ -12,8 +15,10 @@ use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
 use tracing::{debug, error, trace};
 
+use crate::packet_handlers::play_packets::wand::{resend_clicked_block, select_corner, Corner};
 use ferrumc_inventories::hotbar::Hotbar;
 use ferrumc_inventories::inventory::Inventory;
+use ferrumc_world::block_log::BlockChange;
 use ferrumc_world::block_state_id::BlockStateId;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -33,11 +38,19 @@ static ITEM_TO_BLOCK_MAPPING: Lazy<HashMap<i32, i32>> = Lazy::new(|| {
 pub fn handle(
     receiver: Res<PlaceBlockReceiver>,
     state: Res<GlobalStateResource>,
-    query: Query<(Entity, &StreamWriter, &Inventory, &Hotbar)>,
-    pos_q: Query<(&Position, &CollisionBounds)>,
+    query: Query<(
+        Entity,
+        &StreamWriter,
+        &Inventory,
+        &Hotbar,
+        &DimensionComponent,
+        &PlayerIdentity,
+    )>,
+    pos_q: Query<(&Position, &CollisionBounds, Option<&DimensionComponent>)>,
+    mut sessions: Query<&mut WorldEditSession>,
 ) {
     'ev_loop: for (event, eid) in receiver.0.try_iter() {
-        let Ok((entity, conn, inventory, hotbar)) = query.get(eid) else {
+        let Ok((entity, conn, inventory, hotbar, dimension, identity)) = query.get(eid) else {
             debug!("Could not get connection for entity {:?}", eid);
             continue;
         };
@@ -47,6 +60,20 @@ pub fn handle(
         }
         match event.hand.0 {
             0 => {
+                // Right clicking with the wand selects the second corner instead of using it.
+                if is_holding_wand(inventory, hotbar) {
+                    if let Ok(mut session) = sessions.get_mut(eid) {
+                        select_corner(eid, &mut session, Corner::Second, &event.position);
+                    }
+                    resend_clicked_block(
+                        &state.0,
+                        conn,
+                        dimension.0,
+                        &event.position,
+                        event.sequence,
+                    );
+                    continue 'ev_loop;
+                }
                 let Ok(slot) = hotbar.get_selected_item(inventory) else {
                     error!("Could not fetch {:?}", eid);
                     continue 'ev_loop;
@@ -68,7 +95,7 @@ pub fn handle(
                     let mut chunk = match state.0.world.load_chunk_owned(
                         event.position.x >> 4,
                         event.position.z >> 4,
-                        "overworld",
+                        dimension.0.as_str(),
                     ) {
                         Ok(chunk) => chunk,
                         Err(e) => {
@@ -102,19 +129,21 @@ pub fn handle(
                     );
                     // Check if the block collides with any entities
                     let does_collide = {
-                        pos_q.into_iter().any(|(pos, bounds)| {
-                            bounds.collides(
-                                (pos.x, pos.y, pos.z),
-                                &CollisionBounds {
-                                    x_offset_start: 0.0,
-                                    x_offset_end: 1.0,
-                                    y_offset_start: 0.0,
-                                    y_offset_end: 1.0,
-                                    z_offset_start: 0.0,
-                                    z_offset_end: 1.0,
-                                },
-                                (x as f64, y as f64, z as f64),
-                            )
+                        pos_q.into_iter().any(|(pos, bounds, entity_dimension)| {
+                            // Entities without a dimension are assumed to be in the overworld
+                            entity_dimension.copied().unwrap_or_default() == *dimension
+                                && bounds.collides(
+                                    (pos.x, pos.y, pos.z),
+                                    &CollisionBounds {
+                                        x_offset_start: 0.0,
+                                        x_offset_end: 1.0,
+                                        y_offset_start: 0.0,
+                                        y_offset_end: 1.0,
+                                        z_offset_start: 0.0,
+                                        z_offset_end: 1.0,
+                                    },
+                                    (x as f64, y as f64, z as f64),
+                                )
                         })
                     };
                     if does_collide {
@@ -129,15 +158,24 @@ pub fn handle(
                         continue 'ev_loop;
                     }
 
-                    if let Err(err) = chunk.set_block(
-                        x & 0xF,
-                        y as i32,
-                        z & 0xF,
-                        BlockStateId(*mapped_block_state_id as u32),
-                    ) {
+                    let new_block = BlockStateId(*mapped_block_state_id as u32);
+                    let old_block = chunk.get_block(x, y as i32, z).unwrap_or_default();
+                    if let Err(err) = chunk.set_block(x & 0xF, y as i32, z & 0xF, new_block) {
                         error!("Failed to set block: {:?}", err);
                         continue 'ev_loop;
                     }
+                    if let Err(err) = chunk.update_light(x & 0xF, y as i32, z & 0xF) {
+                        error!("Failed to update light: {:?}", err);
+                        continue 'ev_loop;
+                    }
+                    state.0.world.log_block_change(BlockChange::new(
+                        identity.uuid.as_u128(),
+                        identity.username.clone(),
+                        (x, y as i32, z),
+                        dimension.0,
+                        old_block,
+                        new_block,
+                    ));
                     let ack_packet = BlockChangeAck {
                         sequence: event.sequence,
                     };
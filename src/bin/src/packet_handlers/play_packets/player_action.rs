@@ -1,8 +1,14 @@
 use std::sync::Arc;
 
 use crate::errors::BinaryError;
+use crate::packet_handlers::play_packets::wand::{resend_clicked_block, select_corner, Corner};
 use bevy_ecs::prelude::{Entity, MessageWriter, Query, Res};
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::world_edit::{is_holding_wand, WorldEditSession};
+use ferrumc_core::identity::player_identity::PlayerIdentity;
+use ferrumc_inventories::hotbar::Hotbar;
+use ferrumc_inventories::inventory::Inventory;
 use ferrumc_messages::player_digging::*;
 
 use ferrumc_net::connection::StreamWriter;
@@ -11,14 +17,16 @@ use ferrumc_net::packets::outgoing::block_update::BlockUpdate;
 use ferrumc_net::PlayerActionReceiver;
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
+use ferrumc_world::block_log::BlockChange;
 use ferrumc_world::block_state_id::BlockStateId;
 use tracing::{error, trace, warn};
 
 pub fn handle(
     receiver: Res<PlayerActionReceiver>,
     state: Res<GlobalStateResource>,
-    broadcast_query: Query<(Entity, &StreamWriter)>,
-    player_query: Query<&PlayerAbilities>,
+    broadcast_query: Query<(Entity, &StreamWriter, &DimensionComponent)>,
+    player_query: Query<(&PlayerAbilities, &DimensionComponent, &PlayerIdentity)>,
+    mut wand_query: Query<(&Inventory, &Hotbar, &mut WorldEditSession)>,
     mut start_dig_events: MessageWriter<PlayerStartedDigging>,
     mut cancel_dig_events: MessageWriter<PlayerCancelledDigging>,
     mut finish_dig_events: MessageWriter<PlayerFinishedDigging>,
@@ -26,7 +34,7 @@ pub fn handle(
     // https://minecraft.wiki/w/Minecraft_Wiki:Projects/wiki.vg_merge/Protocol?oldid=2773393#Player_Action
     for (event, trigger_eid) in receiver.0.try_iter() {
         // Get the player's abilities to check their gamemode
-        let Ok(abilities) = player_query.get(trigger_eid) else {
+        let Ok((abilities, dimension, identity)) = player_query.get(trigger_eid) else {
             warn!(
                 "PlayerAction: Player {:?} has no PlayerAbilities component",
                 trigger_eid
@@ -34,6 +42,26 @@ pub fn handle(
             continue;
         };
 
+        // Left clicking with the wand selects the first corner instead of breaking the block.
+        // Only the digging statuses are about a block; the rest (dropping items etc.) carry on.
+        if let Ok((inventory, hotbar, mut session)) = wand_query.get_mut(trigger_eid) {
+            if event.status.0 <= 2 && is_holding_wand(inventory, hotbar) {
+                if event.status.0 == 0 {
+                    select_corner(trigger_eid, &mut session, Corner::First, &event.location);
+                }
+                if let Ok((_, conn, _)) = broadcast_query.get(trigger_eid) {
+                    resend_clicked_block(
+                        &state.0,
+                        conn,
+                        dimension.0,
+                        &event.location,
+                        event.sequence,
+                    );
+                }
+                continue;
+            }
+        }
+
         if abilities.creative_mode {
             // --- CREATIVE MODE LOGIC ---
             // Only instabreak (status 0) is relevant in creative.
@@ -42,7 +70,7 @@ pub fn handle(
                     let mut chunk = match state.0.clone().world.load_chunk_owned(
                         event.location.x >> 4,
                         event.location.z >> 4,
-                        "overworld",
+                        dimension.0.as_str(),
                     ) {
                         Ok(chunk) => chunk,
                         Err(e) => {
@@ -51,7 +79,11 @@ pub fn handle(
                                 .0
                                 .clone()
                                 .terrain_generator
-                                .generate_chunk(event.location.x >> 4, event.location.z >> 4)
+                                .generate_chunk(
+                                    event.location.x >> 4,
+                                    event.location.z >> 4,
+                                    dimension.0,
+                                )
                                 .map_err(BinaryError::WorldGen)?
                         }
                     };
@@ -60,19 +92,33 @@ pub fn handle(
                         event.location.y as i32,
                         event.location.z.abs() % 16,
                     );
+                    let old_block = chunk
+                        .get_block(event.location.x, relative_y, event.location.z)
+                        .map_err(BinaryError::World)?;
                     chunk
                         .set_block(relative_x, relative_y, relative_z, BlockStateId::default())
                         .map_err(BinaryError::World)?;
+                    chunk
+                        .update_light(relative_x, relative_y, relative_z)
+                        .map_err(BinaryError::World)?;
 
                     state
                         .0
                         .world
                         .save_chunk(Arc::new(chunk))
                         .map_err(BinaryError::World)?;
+                    state.0.world.log_block_change(BlockChange::new(
+                        identity.uuid.as_u128(),
+                        identity.username.clone(),
+                        (event.location.x, relative_y, event.location.z),
+                        dimension.0,
+                        old_block,
+                        BlockStateId::default(),
+                    ));
 
                     // Broadcast the change
-                    for (eid, conn) in &broadcast_query {
-                        if !state.0.players.is_connected(eid) {
+                    for (eid, conn, player_dimension) in &broadcast_query {
+                        if !state.0.players.is_connected(eid) || player_dimension != dimension {
                             continue;
                         }
 
@@ -1,4 +1,5 @@
 use bevy_ecs::prelude::{Entity, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_core::transform::position::Position;
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::synchronize_player_position::SynchronizePlayerPositionPacket;
@@ -10,10 +11,10 @@ use tracing::warn;
 pub fn handle(
     ev: Res<PlayerLoadedReceiver>,
     state: Res<GlobalStateResource>,
-    query: Query<(Entity, &Position, &StreamWriter)>,
+    query: Query<(Entity, &Position, &StreamWriter, &DimensionComponent)>,
 ) {
     for (_, player) in ev.0.try_iter() {
-        let Ok((entity, player_pos, conn)) = query.get(player) else {
+        let Ok((entity, player_pos, conn, dimension)) = query.get(player) else {
             warn!("Player position not found in query.");
             continue;
         };
@@ -28,7 +29,7 @@ pub fn handle(
             player_pos.x as i32,
             player_pos.y as i32,
             player_pos.z as i32,
-            "overworld",
+            dimension.0.as_str(),
         );
         if let Ok(head_block) = head_block {
             if head_block == BlockStateId(0) {
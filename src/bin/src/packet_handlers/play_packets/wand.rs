@@ -0,0 +1,72 @@
+use bevy_ecs::prelude::Entity;
+use ferrumc_components::player::dimension::Dimension;
+use ferrumc_components::player::world_edit::WorldEditSession;
+use ferrumc_core::mq;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::block_change_ack::BlockChangeAck;
+use ferrumc_net::packets::outgoing::block_update::BlockUpdate;
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_state::GlobalState;
+use tracing::error;
+
+/// Which corner of the selection a wand click sets.
+pub(super) enum Corner {
+    First,
+    Second,
+}
+
+/// Sets a corner of the player's selection to the clicked block.
+pub(super) fn select_corner(
+    player: Entity,
+    session: &mut WorldEditSession,
+    corner: Corner,
+    location: &NetworkPosition,
+) {
+    let position = (location.x, location.y as i32, location.z);
+    let (name, slot) = match corner {
+        Corner::First => ("First", &mut session.pos1),
+        Corner::Second => ("Second", &mut session.pos2),
+    };
+    *slot = Some(position);
+    mq::queue(
+        format!(
+            "{name} position set to ({}, {}, {}).",
+            position.0, position.1, position.2
+        )
+        .into(),
+        false,
+        player,
+    );
+}
+
+/// Sends the client the block that's really at a position it clicked with the wand, since it may
+/// have predicted breaking or stripping it.
+pub(super) fn resend_clicked_block(
+    state: &GlobalState,
+    conn: &StreamWriter,
+    dimension: Dimension,
+    location: &NetworkPosition,
+    sequence: VarInt,
+) {
+    let block = state
+        .world
+        .get_block_and_fetch(
+            location.x,
+            location.y as i32,
+            location.z,
+            dimension.as_str(),
+        )
+        .unwrap_or_default();
+    let update = BlockUpdate {
+        location: location.clone(),
+        block_state_id: VarInt::from(block),
+    };
+    if let Err(err) = conn.send_packet_ref(&update) {
+        error!("Failed to send block update packet: {:?}", err);
+        return;
+    }
+    if let Err(err) = conn.send_packet_ref(&BlockChangeAck { sequence }) {
+        error!("Failed to send block change ack packet: {:?}", err);
+    }
+}
@@ -3,8 +3,8 @@ use ferrumc_components::{
     active_effects::ActiveEffects,
     health::Health,
     player::{
-        abilities::PlayerAbilities, experience::Experience, gamemode::GameModeComponent,
-        gameplay_state::ender_chest::EnderChest, hunger::Hunger,
+        abilities::PlayerAbilities, dimension::DimensionComponent, experience::Experience,
+        gamemode::GameModeComponent, gameplay_state::ender_chest::EnderChest, hunger::Hunger,
     },
 };
 use ferrumc_core::{
@@ -27,6 +27,7 @@ type PlayerCacheQuery<'a> = (
     &'a GameModeComponent,
     &'a Position,
     &'a Rotation,
+    &'a DimensionComponent,
     &'a Inventory,
     &'a Health,
     &'a Hunger,
@@ -61,6 +62,7 @@ pub fn connection_killer(
             gamemode,
             pos,
             rot,
+            dimension,
             inv,
             health,
             hunger,
@@ -107,6 +109,7 @@ pub fn connection_killer(
                 gamemode: gamemode.0,
                 position: pos.clone(),
                 rotation: *rot,
+                dimension: dimension.0,
                 inventory: inv.clone(),
                 health: *health,
                 hunger: *hunger,
@@ -1,5 +1,6 @@
 use crate::systems::send_chunks::send_chunks;
 use bevy_ecs::prelude::{MessageReader, Query, Res};
+use ferrumc_components::player::dimension::DimensionComponent;
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_core::chunks::cross_chunk_boundary_event::ChunkBoundaryCrossed;
 use ferrumc_net::connection::StreamWriter;
@@ -8,7 +9,7 @@ use std::collections::HashSet;
 
 pub fn cross_chunk_boundary(
     mut messages: MessageReader<ChunkBoundaryCrossed>,
-    mut query: Query<&mut StreamWriter>,
+    mut query: Query<(&mut StreamWriter, &DimensionComponent)>,
     state: Res<GlobalStateResource>,
 ) {
     if messages.is_empty() {
@@ -32,16 +33,18 @@ pub fn cross_chunk_boundary(
                 new_chunk_seen.insert((x, z));
             }
         }
+        let Ok((mut conn, dimension)) = query.get_mut(event.player) else {
+            continue;
+        };
         let needed_chunks: Vec<_> = new_chunk_seen
             .iter()
             .filter(|chunk| !old_chunk_seen.contains(chunk))
             .map(|chunk| {
                 let (x, z) = *chunk;
-                (x, z, "overworld".to_string())
+                (x, z, dimension.0.as_str().to_string())
             })
             .collect();
         let center_chunk = (event.new_chunk.0, event.new_chunk.1);
-        let mut conn = query.get_mut(event.player).expect("Player does not exist");
         send_chunks(state.0.clone(), needed_chunks, &mut conn, center_chunk)
             .expect("Failed to send chunks")
     }
@@ -4,18 +4,26 @@ use std::time::{Duration, Instant};
 
 use crate::BinaryError;
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::{Dimension, DimensionComponent};
 use ferrumc_components::player::gameplay_state::digging::PlayerDigging;
+use ferrumc_core::identity::player_identity::PlayerIdentity;
 use ferrumc_data::blocks::types::Block;
 use ferrumc_messages::player_digging::*;
 use ferrumc_net::connection::StreamWriter;
 use ferrumc_net::packets::outgoing::{block_change_ack::BlockChangeAck, block_update::BlockUpdate};
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalStateResource;
+use ferrumc_world::block_log::BlockChange;
 use ferrumc_world::block_state_id::BlockStateId;
 use tracing::{debug, error, trace, warn};
 
 // A query for just the components needed to acknowledge a dig packet
-type DiggingPlayerQuery<'a> = (Entity, &'a StreamWriter, Option<&'a PlayerDigging>);
+type DiggingPlayerQuery<'a> = (
+    Entity,
+    &'a StreamWriter,
+    Option<&'a PlayerDigging>,
+    &'a DimensionComponent,
+);
 
 /// Handles the PlayerStartDiggingEvent.
 /// This system starts the digging timer.
@@ -31,12 +39,16 @@ pub fn handle_start_digging(
             event.player, event.position
         );
 
+        let Ok((_, _, _, dimension)) = player_query.get(event.player) else {
+            continue;
+        };
+
         // --- 1. Get BlockStateId from the world ---
         let block_state_id = match state.0.world.get_block_and_fetch(
             event.position.x,
             event.position.y as i32,
             event.position.z,
-            "overworld", // TODO: remove hardcoded dimension
+            dimension.0.as_str(),
         ) {
             Ok(id) => id,
             Err(e) => {
@@ -78,7 +90,7 @@ pub fn handle_start_digging(
 
             // We must still send an ACK to the client.
             // But we do not add the PlayerDigging component.
-            if let Ok((_, writer, _)) = player_query.get_mut(event.player) {
+            if let Ok((_, writer, _, _)) = player_query.get_mut(event.player) {
                 let ack_packet = BlockChangeAck {
                     sequence: event.sequence,
                 };
@@ -112,7 +124,7 @@ pub fn handle_start_digging(
         });
 
         // --- 7. Acknowledge the client ---
-        if let Ok((_, writer, _)) = player_query.get_mut(event.player) {
+        if let Ok((_, writer, _, _)) = player_query.get_mut(event.player) {
             let ack_packet = BlockChangeAck {
                 sequence: event.sequence,
             };
@@ -140,7 +152,7 @@ pub fn handle_cancel_digging(
         commands.entity(event.player).remove::<PlayerDigging>();
 
         // Acknowledge the cancellation.
-        if let Ok((_, writer, _)) = player_query.get_mut(event.player) {
+        if let Ok((_, writer, _, _)) = player_query.get_mut(event.player) {
             let ack_packet = BlockChangeAck {
                 sequence: event.sequence,
             };
@@ -161,10 +173,13 @@ pub fn handle_finish_digging(
     mut events: MessageReader<PlayerFinishedDigging>,
     state: Res<GlobalStateResource>,
     mut player_query: Query<DiggingPlayerQuery>,
-    broadcast_query: Query<(Entity, &StreamWriter)>, // For broadcasting the break
+    broadcast_query: Query<(Entity, &StreamWriter, &DimensionComponent)>, // For broadcasting the break
+    identity_query: Query<&PlayerIdentity>,                               // For logging the break
 ) {
     for event in events.read() {
-        let Ok((_player_entity, writer, digging_opt)) = player_query.get_mut(event.player) else {
+        let Ok((_player_entity, writer, digging_opt, dimension)) =
+            player_query.get_mut(event.player)
+        else {
             warn!(
                 "Player {:?} sent FinishDigging but query failed.",
                 event.player
@@ -220,7 +235,7 @@ pub fn handle_finish_digging(
                 event.position.x,
                 event.position.y as i32,
                 event.position.z,
-                "overworld",
+                dimension.0.as_str(),
             ) {
                 Ok(id) => id,
                 Err(e) => {
@@ -252,7 +267,13 @@ pub fn handle_finish_digging(
 
             // We wrap the block-breaking logic in its own function
             // to handle the errors cleanly (replaces `try` block).
-            if let Err(e) = break_block(&state, &broadcast_query, &event.position) {
+            if let Err(e) = break_block(
+                &state,
+                &broadcast_query,
+                identity_query.get(event.player).ok(),
+                &event.position,
+                dimension.0,
+            ) {
                 error!("Error handling finished digging: {:?}", e);
             }
         }
@@ -274,48 +295,64 @@ pub fn handle_finish_digging(
 /// Helper function to contain the block-breaking logic (replaces `try` block)
 fn break_block(
     state: &Res<GlobalStateResource>,
-    broadcast_query: &Query<(Entity, &StreamWriter)>,
+    broadcast_query: &Query<(Entity, &StreamWriter, &DimensionComponent)>,
+    player: Option<&PlayerIdentity>,
     position: &ferrumc_net_codec::net_types::network_position::NetworkPosition,
+    dimension: Dimension,
 ) -> Result<(), BinaryError> {
-    let mut chunk =
-        match state
-            .0
-            .clone()
-            .world
-            .load_chunk_owned(position.x >> 4, position.z >> 4, "overworld")
-        {
-            Ok(chunk) => chunk,
-            Err(e) => {
-                trace!("Chunk not found, generating new chunk: {:?}", e);
-                state
-                    .0
-                    .clone()
-                    .terrain_generator
-                    .generate_chunk(position.x >> 4, position.z >> 4)
-                    .map_err(BinaryError::WorldGen)?
-            }
-        };
+    let mut chunk = match state.0.clone().world.load_chunk_owned(
+        position.x >> 4,
+        position.z >> 4,
+        dimension.as_str(),
+    ) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            trace!("Chunk not found, generating new chunk: {:?}", e);
+            state
+                .0
+                .clone()
+                .terrain_generator
+                .generate_chunk(position.x >> 4, position.z >> 4, dimension)
+                .map_err(BinaryError::WorldGen)?
+        }
+    };
     let (relative_x, relative_y, relative_z) = (
         position.x.abs() % 16,
         position.y as i32,
         position.z.abs() % 16,
     );
+    let old_block = chunk
+        .get_block(position.x, relative_y, position.z)
+        .map_err(BinaryError::World)?;
     chunk
         .set_block(relative_x, relative_y, relative_z, BlockStateId::default())
         .map_err(BinaryError::World)?;
+    chunk
+        .update_light(relative_x, relative_y, relative_z)
+        .map_err(BinaryError::World)?;
     state
         .0
         .world
         .save_chunk(Arc::new(chunk))
         .map_err(BinaryError::World)?;
+    if let Some(player) = player {
+        state.0.world.log_block_change(BlockChange::new(
+            player.uuid.as_u128(),
+            player.username.clone(),
+            (position.x, relative_y, position.z),
+            dimension,
+            old_block,
+            BlockStateId::default(),
+        ));
+    }
 
     // Broadcast the block break to all players
     let block_update_packet = BlockUpdate {
         location: position.clone(),
         block_state_id: VarInt::from(BlockStateId::default()),
     };
-    for (eid, conn) in broadcast_query {
-        if !state.0.players.is_connected(eid) {
+    for (eid, conn, player_dimension) in broadcast_query {
+        if !state.0.players.is_connected(eid) || player_dimension.0 != dimension {
             continue;
         }
         conn.send_packet_ref(&block_update_packet)
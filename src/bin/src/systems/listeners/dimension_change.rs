@@ -0,0 +1,118 @@
+use crate::systems::send_chunks::send_chunks;
+use bevy_ecs::prelude::*;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_components::player::gamemode::GameModeComponent;
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_core::identity::player_identity::PlayerIdentity;
+use ferrumc_core::transform::position::Position;
+use ferrumc_core::transform::rotation::Rotation;
+use ferrumc_messages::PlayerDimensionChanged;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::game_event::GameEventPacket;
+use ferrumc_net::packets::outgoing::respawn::RespawnPacket;
+use ferrumc_net::packets::outgoing::synchronize_player_position::SynchronizePlayerPositionPacket;
+use ferrumc_net::packets::outgoing::system_message::SystemMessagePacket;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_text::{Color, NamedColor, TextComponent, TextComponentBuilder};
+use tracing::{error, info};
+
+/// Listens for `PlayerDimensionChanged` and moves the player into the new dimension.
+pub fn handle(
+    mut events: MessageReader<PlayerDimensionChanged>,
+    mut player_query: Query<(
+        &PlayerIdentity,
+        &mut DimensionComponent,
+        &mut Position,
+        &Rotation,
+        &GameModeComponent,
+        &mut StreamWriter,
+    )>,
+    state: Res<GlobalStateResource>,
+) {
+    for event in events.read() {
+        let Ok((identity, mut dimension, mut position, rotation, gamemode, mut writer)) =
+            player_query.get_mut(event.player)
+        else {
+            // Player might have disconnected in the same tick
+            continue;
+        };
+
+        // --- 1. Update server-side components ---
+        dimension.0 = event.new_dimension;
+        if let Some(new_position) = &event.position {
+            *position = new_position.clone();
+        }
+
+        // --- 2. Tell the client to switch worlds ---
+        let respawn_packet = RespawnPacket::change_dimension(
+            dimension.0,
+            gamemode.0 as u8,
+            state.0.world_metadata.hashed_seed(),
+        );
+        if let Err(e) = writer.send_packet_ref(&respawn_packet) {
+            error!(
+                "Failed to send respawn packet to {}: {:?}",
+                identity.username, e
+            );
+            continue;
+        }
+
+        // The client resets its position on respawn, so it has to be sent again
+        let position_packet = SynchronizePlayerPositionPacket::new(
+            (position.x, position.y, position.z),
+            (0.0, 0.0, 0.0),
+            rotation.yaw,
+            rotation.pitch,
+            0,
+            VarInt::new(0),
+        );
+        if let Err(e) = writer.send_packet_ref(&position_packet) {
+            error!(
+                "Failed to send position packet to {}: {:?}",
+                identity.username, e
+            );
+        }
+
+        // "Start waiting for level chunks"
+        let game_event = GameEventPacket::new(13, 0.0);
+        if let Err(e) = writer.send_packet_ref(&game_event) {
+            error!(
+                "Failed to send game event packet to {}: {:?}",
+                identity.username, e
+            );
+        }
+
+        // --- 3. Send the chunks around the player in the new dimension ---
+        let center = (position.x as i32 >> 4, position.z as i32 >> 4);
+        let radius = get_global_config().chunk_render_distance as i32;
+        let mut chunks = Vec::new();
+        for x in center.0 - radius..=center.0 + radius {
+            for z in center.1 - radius..=center.1 + radius {
+                chunks.push((x, z, dimension.0.as_str().to_string()));
+            }
+        }
+        if let Err(e) = send_chunks(state.0.clone(), chunks, &mut writer, center) {
+            error!("Failed to send chunks to {}: {:?}", identity.username, e);
+        }
+
+        // --- 4. Send confirmation chat message ---
+        let msg = TextComponentBuilder::new("Moved to ")
+            .extra(
+                TextComponent::from(dimension.0.identifier()).color(Color::Named(NamedColor::Aqua)),
+            )
+            .build();
+        let chat_packet = SystemMessagePacket {
+            message: msg,
+            overlay: false,
+        };
+        if let Err(e) = writer.send_packet_ref(&chat_packet) {
+            error!(
+                "Failed to send dimension change message to {}: {:?}",
+                identity.username, e
+            );
+        }
+
+        info!("Moved {} to {}", identity.username, dimension.0);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod digging_system;
+pub mod dimension_change;
 pub mod gamemode_change;
 pub mod player_join_message;
 pub mod player_leave_message;
@@ -7,6 +8,7 @@ pub fn register_gameplay_listeners(schedule: &mut bevy_ecs::schedule::Schedule)
     schedule.add_systems(player_leave_message::handle);
     schedule.add_systems(player_join_message::handle);
     schedule.add_systems(gamemode_change::handle);
+    schedule.add_systems(dimension_change::handle);
     schedule.add_systems(digging_system::handle_start_digging);
     schedule.add_systems(digging_system::handle_cancel_digging);
     schedule.add_systems(digging_system::handle_finish_digging);
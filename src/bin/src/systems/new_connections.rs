@@ -5,11 +5,13 @@ use ferrumc_components::{
     health::Health,
     player::{
         abilities::PlayerAbilities,
+        dimension::{Dimension, DimensionComponent},
         experience::Experience,
         gamemode::{GameMode, GameModeComponent},
         gameplay_state::ender_chest::EnderChest,
         hunger::Hunger,
         player_bundle::PlayerBundle,
+        world_edit::WorldEditSession,
     },
 };
 use ferrumc_core::{
@@ -45,6 +47,7 @@ pub fn accept_new_connections(
             gamemode,
             position,
             rotation,
+            dimension,
             inventory,
             health,
             hunger,
@@ -62,6 +65,7 @@ pub fn accept_new_connections(
                     data.gamemode,
                     data.position,
                     data.rotation,
+                    data.dimension,
                     data.inventory,
                     data.health,
                     data.hunger,
@@ -77,6 +81,7 @@ pub fn accept_new_connections(
                     GameMode::default(),
                     Position::default(),
                     Rotation::default(),
+                    Dimension::default(),
                     Inventory::default(),
                     Health::default(),
                     Hunger::default(),
@@ -93,6 +98,7 @@ pub fn accept_new_connections(
             gamemode: GameModeComponent(gamemode),
             position,
             rotation,
+            dimension: DimensionComponent(dimension),
             on_ground: OnGround::default(),
             chunk_receiver: ChunkReceiver::default(),
             inventory,
@@ -102,6 +108,7 @@ pub fn accept_new_connections(
             hunger,
             experience,
             active_effects,
+            world_edit: WorldEditSession::default(),
         };
 
         // --- 3. Spawn the PlayerBundle, then .insert() the network components ---
@@ -11,6 +11,7 @@ use ferrumc_net_codec::encode::NetEncode;
 use ferrumc_net_codec::encode::NetEncodeOpts::WithLength;
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_state::GlobalState;
+use ferrumc_world::dimension::Dimension;
 use std::sync::atomic::Ordering;
 use tracing::{error, trace};
 
@@ -60,7 +61,7 @@ pub fn send_chunks(
                 // Don't bother saving the chunk if it hasn't been edited yet
                 let chunk = state_clone
                     .terrain_generator
-                    .generate_chunk(x, z)
+                    .generate_chunk(x, z, Dimension::from_name(&dim).unwrap_or_default())
                     .map_err(|err| NetError::Misc(err.to_string()))?;
                 Ok((ChunkAndLightData::from_chunk(&chunk), x, z))
             }?;
@@ -0,0 +1,19 @@
+use bevy_ecs::prelude::Res;
+use ferrumc_state::GlobalStateResource;
+use tracing::{error, info};
+
+pub fn backup_world(state: Res<GlobalStateResource>) {
+    if state.0.shut_down.load(std::sync::atomic::Ordering::Relaxed) {
+        return;
+    }
+
+    // Frequency is controlled by the schedule period. The backup itself runs off the main thread
+    // so the server keeps ticking while the database is copied.
+    let _handle = state.0.thread_pool.oneshot({
+        let state = state.0.clone();
+        move || match state.world.backup() {
+            Ok(path) => info!("Scheduled backup saved to {}", path.display()),
+            Err(e) => error!("Scheduled backup failed: {}", e),
+        }
+    });
+}
@@ -4,6 +4,7 @@ use crate::errors::AnvilError;
 use memmap2::Mmap;
 use std::io::Read;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::error;
 use yazi::Adler32;
 
@@ -90,6 +91,66 @@ pub fn load_anvil_file(file_path: PathBuf) -> Result<LoadedAnvilFile, AnvilError
     })
 }
 
+/// Write a region file containing the given chunks
+///
+/// Each chunk is given as its coordinates inside the region (only the lowest 5 bits are used) and
+/// its uncompressed NBT data. The chunks are zlib compressed, which is what vanilla uses by default.
+/// An existing file at the path will be overwritten.
+///
+/// Arguments:
+///
+/// * `file_path` - The path to write the region file to, usually named `r.<x>.<z>.mca`
+/// * `chunks` - The chunks to write, as `(x, z, nbt_data)`
+///
+/// Returns:
+///
+/// * `Result<(), AnvilError>` - An error if a chunk could not be compressed, is too large or the
+///   file could not be written
+pub fn write_anvil_file(
+    file_path: PathBuf,
+    chunks: &[(u32, u32, Vec<u8>)],
+) -> Result<(), AnvilError> {
+    let mut locations = [0u8; 4096];
+    let mut timestamps = [0u8; 4096];
+    let mut data = Vec::new();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as u32)
+        .unwrap_or_default();
+
+    for (x, z, nbt) in chunks {
+        let compressed = yazi::compress(nbt, yazi::Format::Zlib, yazi::CompressionLevel::Default)
+            .map_err(|_| AnvilError::CompressionError)?;
+        // The length includes the compression type byte but not the length itself
+        let length = compressed.len() as u32 + 1;
+        let sectors = (length as usize + 4).div_ceil(4096);
+        // Vanilla stores chunks this large in separate .mcc files, which we don't support
+        if sectors > 255 {
+            return Err(AnvilError::ChunkTooLarge(*x, *z));
+        }
+        // The first two sectors are the location and timestamp tables
+        let offset = 2 + (data.len() / 4096) as u32;
+
+        let index = (((x & 31) + (z & 31) * 32) * 4) as usize;
+        locations[index..index + 3].copy_from_slice(&offset.to_be_bytes()[1..]);
+        locations[index + 3] = sectors as u8;
+        timestamps[index..index + 4].copy_from_slice(&timestamp.to_be_bytes());
+
+        data.extend_from_slice(&length.to_be_bytes());
+        // Zlib
+        data.push(2);
+        data.extend_from_slice(&compressed);
+        // Pad to the next sector boundary
+        data.resize(data.len().div_ceil(4096) * 4096, 0);
+    }
+
+    let mut file_data = Vec::with_capacity(8192 + data.len());
+    file_data.extend_from_slice(&locations);
+    file_data.extend_from_slice(&timestamps);
+    file_data.extend_from_slice(&data);
+    std::fs::write(&file_path, file_data).map_err(|e| AnvilError::UnableToWriteFile(file_path, e))
+}
+
 impl LoadedAnvilFile {
     /// Get all the locations from the table
     ///
@@ -275,6 +336,29 @@ mod tests {
         assert_eq!(chunk.clone().unwrap(), fast_chunk.unwrap());
     }
 
+    #[test]
+    fn test_write_anvil_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("r.0.0.mca");
+        let small = vec![1u8; 100];
+        // Large enough to span multiple sectors
+        let large = (0..20000).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>();
+        write_anvil_file(
+            file_path.clone(),
+            &[(0, 0, small.clone()), (5, 17, large.clone())],
+        )
+        .unwrap();
+
+        let mut region = Region::from_stream(File::open(&file_path).unwrap()).unwrap();
+        assert_eq!(region.read_chunk(0, 0).unwrap(), Some(small.clone()));
+        assert_eq!(region.read_chunk(5, 17).unwrap(), Some(large));
+        assert_eq!(region.read_chunk(1, 1).unwrap(), None);
+
+        let loaded_file = load_anvil_file(file_path).unwrap();
+        assert_eq!(loaded_file.get_locations().len(), 2);
+        assert_eq!(loaded_file.get_chunk(0, 0).unwrap(), Some(small));
+    }
+
     #[test]
     fn test_get_chunk_from_location() {
         let file_path = PathBuf::from(root!(".etc/r.0.0.mca"));
@@ -1,5 +1,5 @@
 use crate::de::converter::FromNbt;
-use crate::{NBTSerializable, NBTSerializeOptions};
+use crate::{NBTError, NBTSerializable, NBTSerializeOptions, Result};
 use ferrumc_general_purpose::simd::arrays;
 use ferrumc_net_codec::encode::errors::NetEncodeError;
 use ferrumc_net_codec::encode::{NetEncode, NetEncodeOpts};
@@ -26,7 +26,14 @@ pub enum NbtTag {
 
 impl From<u8> for NbtTag {
     fn from(tag: u8) -> Self {
-        match tag {
+        NbtTag::from_id(tag).unwrap_or_else(|| panic!("Invalid NbtTag: {tag}"))
+    }
+}
+
+impl NbtTag {
+    /// The tag with the given ID, or `None` if there isn't one.
+    pub const fn from_id(tag: u8) -> Option<Self> {
+        Some(match tag {
             0 => NbtTag::End,
             1 => NbtTag::Byte,
             2 => NbtTag::Short,
@@ -40,8 +47,8 @@ impl From<u8> for NbtTag {
             10 => NbtTag::Compound,
             11 => NbtTag::IntArray,
             12 => NbtTag::LongArray,
-            _ => panic!("Invalid NbtTag: {tag}"),
-        }
+            _ => return None,
+        })
     }
 }
 
@@ -160,6 +167,22 @@ impl<'a> NbtTape<'a> {
         self.parse_tag();
     }
 
+    /// Checks that `data` is a whole root compound the tape can parse: every length fits in the
+    /// data, every tag ID exists, every string is UTF-8 and nothing is nested too deeply.
+    ///
+    /// [NbtTape::parse] trusts its input and panics (or worse) on anything else, so data that
+    /// could be truncated or corrupt, like files players upload, should be checked with this
+    /// first.
+    pub fn validate(data: &[u8]) -> Result<()> {
+        let mut checker = NbtChecker { data, pos: 0 };
+        let root = checker.byte()?;
+        if root != NbtTag::Compound as u8 {
+            return Err(NBTError::InvalidRootCompound(root));
+        }
+        checker.string()?;
+        checker.payload(NbtTag::Compound, 0)
+    }
+
     fn parse_tag(&mut self) {
         let tag = NbtTag::from(self.read_byte());
         if tag != NbtTag::Compound {
@@ -217,7 +240,7 @@ impl<'a> NbtTape<'a> {
                         NbtDeserializableOptions::TagType(el_type.clone()),
                     );
 
-                    let element = T::from_nbt(&tape, &nbt_element).unwrap();
+                    let element = T::from_nbt(&tape, &nbt_element).ok()?;
 
                     elements.push(element);
                 }
@@ -225,12 +248,11 @@ impl<'a> NbtTape<'a> {
             }
             NbtTapeElement::ByteArray(data) => {
                 // I mean you wouldn't want to get the wrong type of data right?
-                let data_vec = (*data).to_vec();
-                let data = unsafe { std::mem::transmute::<Vec<i8>, Vec<T>>(data_vec) };
-
                 if size_of::<T>() != size_of::<i8>() {
-                    panic!("Invalid type conversion!");
+                    return None;
                 }
+                let data_vec = (*data).to_vec();
+                let data = unsafe { std::mem::transmute::<Vec<i8>, Vec<T>>(data_vec) };
 
                 // safety: there is none :) jk its a byte array so its fine
                 // todo: revisit and see if this is actually safe
@@ -242,11 +264,17 @@ impl<'a> NbtTape<'a> {
                 Some(data)
             }
             NbtTapeElement::IntArray(data) => {
+                if size_of::<T>() != size_of::<i32>() {
+                    return None;
+                }
                 let data = data.clone();
                 let data = unsafe { std::mem::transmute::<Vec<i32>, Vec<T>>(data) };
                 Some(data)
             }
             NbtTapeElement::LongArray(data) => {
+                if size_of::<T>() != size_of::<i64>() {
+                    return None;
+                }
                 let data = data.clone();
                 let data = unsafe { std::mem::transmute::<Vec<i64>, Vec<T>>(data) };
                 Some(data)
@@ -376,6 +404,106 @@ impl NbtTape<'_> {
     }
 }
 
+/// How deeply compounds and lists may be nested, the same limit vanilla has.
+const MAX_DEPTH: usize = 512;
+
+/// Walks NBT data the way [NbtTape] parses it, but checks every read first. See
+/// [NbtTape::validate].
+struct NbtChecker<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> NbtChecker<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(NBTError::ReachedEOF)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<()> {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn tag(&mut self) -> Result<NbtTag> {
+        let tag = self.byte()?;
+        NbtTag::from_id(tag).ok_or(NBTError::InvalidTagType(tag))
+    }
+
+    /// The length before an array or list, which the tape reads as an unsigned size.
+    fn length(&mut self) -> Result<usize> {
+        let length = i32::from_be_bytes(self.bytes(4)?.try_into()?);
+        usize::try_from(length).map_err(|_| NBTError::InvalidNBTData)
+    }
+
+    fn string(&mut self) -> Result<()> {
+        let length = u16::from_be_bytes(self.bytes(2)?.try_into()?);
+        std::str::from_utf8(self.bytes(length as usize)?)?;
+        Ok(())
+    }
+
+    fn array(&mut self, element_size: usize) -> Result<()> {
+        let length = self.length()?;
+        self.skip(
+            length
+                .checked_mul(element_size)
+                .ok_or(NBTError::ReachedEOF)?,
+        )
+    }
+
+    fn payload(&mut self, tag: NbtTag, depth: usize) -> Result<()> {
+        match tag {
+            NbtTag::End => {}
+            NbtTag::Byte => self.skip(1)?,
+            NbtTag::Short => self.skip(2)?,
+            NbtTag::Int | NbtTag::Float => self.skip(4)?,
+            NbtTag::Long | NbtTag::Double => self.skip(8)?,
+            NbtTag::ByteArray => self.array(1)?,
+            NbtTag::IntArray => self.array(4)?,
+            NbtTag::LongArray => self.array(8)?,
+            NbtTag::String => self.string()?,
+            NbtTag::List => {
+                if depth >= MAX_DEPTH {
+                    return Err(NBTError::InvalidNBTData);
+                }
+                let el_type = self.tag()?;
+                let length = self.length()?;
+                // Every other element takes at least a byte, so the data running out stops the
+                // loop, but ends take none.
+                if el_type == NbtTag::End && length > 0 {
+                    return Err(NBTError::InvalidNBTData);
+                }
+                for _ in 0..length {
+                    self.payload(el_type.clone(), depth + 1)?;
+                }
+            }
+            NbtTag::Compound => {
+                if depth >= MAX_DEPTH {
+                    return Err(NBTError::InvalidNBTData);
+                }
+                loop {
+                    let tag = self.tag()?;
+                    if tag == NbtTag::End {
+                        break;
+                    }
+                    self.string()?;
+                    self.payload(tag, depth + 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 pub enum NbtDeserializableOptions {
     None,
     TagType(NbtTag),
@@ -0,0 +1,44 @@
+use crate::{
+    arg::{utils::parser_error, CommandArgument, ParserResult},
+    CommandContext, Suggestion,
+};
+
+use super::primitive::{PrimitiveArgument, PrimitiveArgumentType};
+use ferrumc_world::block_state_id::{BlockStateId, DEFAULT_STATES};
+
+/// How many block names are suggested at once, so typing the first letter doesn't send every
+/// block in the game.
+const MAX_SUGGESTIONS: usize = 50;
+
+impl CommandArgument for BlockStateId {
+    fn parse(ctx: &mut CommandContext) -> ParserResult<Self> {
+        let str = ctx.input.read_string();
+
+        BlockStateId::from_state_string(&str)
+            .ok_or_else(|| parser_error(&format!("invalid block state: {str}")))
+    }
+
+    fn primitive() -> PrimitiveArgument {
+        PrimitiveArgument {
+            argument_type: PrimitiveArgumentType::BlockState,
+            flags: None,
+        }
+    }
+
+    fn suggest(ctx: &mut CommandContext) -> Vec<Suggestion> {
+        let input = ctx.input.read_string();
+        let input = input.strip_prefix("minecraft:").unwrap_or(&input);
+
+        let mut names = DEFAULT_STATES
+            .keys()
+            .map(|name| name.trim_start_matches("minecraft:"))
+            .filter(|name| name.starts_with(input))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(Suggestion::of)
+            .collect()
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{
+    arg::{utils::parser_error, CommandArgument, ParserResult},
+    CommandContext, Suggestion,
+};
+
+use super::PrimitiveArgument;
+use ferrumc_components::player::dimension::Dimension;
+
+impl CommandArgument for Dimension {
+    fn parse(ctx: &mut CommandContext) -> ParserResult<Self> {
+        let str = ctx.input.read_string();
+
+        Dimension::from_name(&str).ok_or_else(|| parser_error(&format!("invalid dimension: {str}")))
+    }
+
+    fn primitive() -> PrimitiveArgument {
+        PrimitiveArgument::word()
+    }
+
+    fn suggest(ctx: &mut CommandContext) -> Vec<Suggestion> {
+        ctx.input.read_string();
+
+        Dimension::ALL
+            .into_iter()
+            .map(|dimension| Suggestion::of(dimension.as_str()))
+            .collect()
+    }
+}
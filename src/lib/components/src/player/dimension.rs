@@ -0,0 +1,6 @@
+use bevy_ecs::prelude::Component;
+pub use ferrumc_world::dimension::Dimension;
+
+/// The component storing the dimension a player is currently in.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DimensionComponent(pub Dimension);
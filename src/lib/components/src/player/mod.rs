@@ -1,8 +1,10 @@
 pub mod abilities;
 pub mod client_information;
+pub mod dimension;
 pub mod experience;
 pub mod gamemode;
 pub mod gameplay_state;
 pub mod hunger;
 pub mod player_bundle;
 pub mod view_distance;
+pub mod world_edit;
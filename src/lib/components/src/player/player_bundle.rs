@@ -2,8 +2,9 @@ use crate::{
     active_effects::ActiveEffects,
     health::Health,
     player::{
-        abilities::PlayerAbilities, experience::Experience, gamemode::GameModeComponent,
-        gameplay_state::ender_chest::EnderChest, hunger::Hunger,
+        abilities::PlayerAbilities, dimension::DimensionComponent, experience::Experience,
+        gamemode::GameModeComponent, gameplay_state::ender_chest::EnderChest, hunger::Hunger,
+        world_edit::WorldEditSession,
     },
 };
 use bevy_ecs::prelude::Bundle;
@@ -27,6 +28,7 @@ pub struct PlayerBundle {
     // Position/World
     pub position: Position,
     pub rotation: Rotation,
+    pub dimension: DimensionComponent,
     pub on_ground: OnGround,
     pub chunk_receiver: ChunkReceiver,
 
@@ -40,4 +42,7 @@ pub struct PlayerBundle {
     pub hunger: Hunger,
     pub experience: Experience,
     pub active_effects: ActiveEffects,
+
+    // Building
+    pub world_edit: WorldEditSession,
 }
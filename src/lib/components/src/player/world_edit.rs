@@ -0,0 +1,57 @@
+use bevy_ecs::prelude::Component;
+use ferrumc_inventories::hotbar::Hotbar;
+use ferrumc_inventories::inventory::Inventory;
+use ferrumc_inventories::item::ItemID;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::region::{AppliedEdits, Region};
+use ferrumc_world::schematic::Schematic;
+use std::collections::VecDeque;
+
+/// The item that selects positions for building commands: left click for the first corner, right
+/// click for the second.
+pub const WAND_ITEM: &str = "minecraft:wooden_axe";
+
+/// Whether the player is holding the [WAND_ITEM] in their main hand.
+pub fn is_holding_wand(inventory: &Inventory, hotbar: &Hotbar) -> bool {
+    let Ok(Some(slot)) = hotbar.get_selected_item(inventory) else {
+        return false;
+    };
+    slot.item_id.is_some() && slot.item_id == ItemID::from_name(WAND_ITEM)
+}
+
+/// How many edits `//undo` can go back.
+pub const MAX_UNDO_HISTORY: usize = 16;
+
+/// A player's selection, clipboard and undo history for building commands like `//set`.
+#[derive(Component, Debug, Clone, Default)]
+pub struct WorldEditSession {
+    pub pos1: Option<(i32, i32, i32)>,
+    pub pos2: Option<(i32, i32, i32)>,
+    /// What `//copy` last copied, relative to where the player stood.
+    pub clipboard: Option<Schematic>,
+    history: VecDeque<(Dimension, AppliedEdits)>,
+}
+
+impl WorldEditSession {
+    /// The region between the two selected positions, if both are set.
+    pub fn selection(&self) -> Option<Region> {
+        Some(Region::new(self.pos1?, self.pos2?))
+    }
+
+    /// Remembers an edit so it can be undone, forgetting the oldest one if the history is full.
+    /// Edits that didn't change anything aren't kept.
+    pub fn push_undo(&mut self, dimension: Dimension, edits: AppliedEdits) {
+        if edits.previous.is_empty() {
+            return;
+        }
+        if self.history.len() == MAX_UNDO_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((dimension, edits));
+    }
+
+    /// Takes the most recent edit off the history.
+    pub fn pop_undo(&mut self) -> Option<(Dimension, AppliedEdits)> {
+        self.history.pop_back()
+    }
+}
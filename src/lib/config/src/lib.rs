@@ -15,5 +15,12 @@ pub mod setup;
 pub mod whitelist;
 
 // Re-exports
+pub use server_config::BackupConfig;
+pub use server_config::DatabaseBackend;
+pub use server_config::DatabaseCompression;
 pub use server_config::DatabaseConfig;
+pub use server_config::ProxyConfig;
+pub use server_config::ProxyMode;
 pub use server_config::ServerConfig;
+pub use server_config::WorldGenConfig;
+pub use server_config::WorldGeneratorKind;
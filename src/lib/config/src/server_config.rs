@@ -32,7 +32,10 @@ pub fn set_global_config(config: ServerConfig) {
 /// - `max_players`: The maximum number of players that can be connected to the server.
 /// - `tps`: The ticks per second that the server will run at.
 /// - `database` - [DatabaseConfig]: The configuration for the database.
+/// - `backups` - [BackupConfig]: The configuration for world backups.
 /// - `world`: The name of the world that the server will load.
+/// - `world_gen` - [WorldGenConfig]: The configuration for generating new worlds.
+/// - `proxy` - [ProxyConfig]: The configuration for running behind a proxy.
 /// - `network_compression_threshold`: The threshold at which the server will compress network packets.
 /// - `whitelist`: Whether the server whitelist is enabled or not.
 /// - `chunk_render_distance`: The render distance of the chunks. This is the number of chunks that will be
@@ -45,7 +48,13 @@ pub struct ServerConfig {
     pub max_players: u32,
     pub tps: u32,
     pub database: DatabaseConfig,
+    #[serde(default)]
+    pub backups: BackupConfig,
     pub world: String,
+    #[serde(default)]
+    pub world_gen: WorldGenConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
     pub network_compression_threshold: i32, // Can be negative
     pub verify_decompressed_packets: bool,
     pub encryption_enabled: bool,
@@ -58,8 +67,13 @@ pub struct ServerConfig {
 /// The database configuration section from [ServerConfig].
 ///
 /// Fields:
+/// - `backend` - [DatabaseBackend]: Which storage backend to keep the world in.
 /// - `db_path`: The path to the database. This is relative to the server root path.
 /// - `verify_chunk_data`: Whether to verify chunk data when loading it from the database.
+/// - `compression` - [DatabaseCompression]: The algorithm new chunks are compressed with. Every chunk
+///   records the algorithm it was written with, so changing this doesn't break existing worlds.
+/// - `compression_level`: The compression level to use. The valid range depends on the algorithm:
+///   0-9 for gzip, deflate and zlib, 0-11 for brotli and 0-22 for zstd.
 /// - `map_size`: The max size of the database's memory map. Basically you need this to be big enough
 ///   to hold everything before it starts writing to disk. This isn't memory use though, it's just
 ///   how much we can map into memory if needed, so you can set this to an insane number if you want,
@@ -68,13 +82,170 @@ pub struct ServerConfig {
 /// - `cache_capacity`: How big the cache can be in kb.
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct DatabaseConfig {
+    #[serde(default)]
+    pub backend: DatabaseBackend,
     pub db_path: String,
     pub verify_chunk_data: bool,
+    #[serde(default)]
+    pub compression: DatabaseCompression,
+    #[serde(default = "default_compression_level")]
+    pub compression_level: u32,
     pub map_size: u64,
     pub cache_ttl: u64,
     pub cache_capacity: u64,
 }
 
+fn default_compression_level() -> u32 {
+    1
+}
+
+/// The chunk compression enum for [DatabaseConfig].
+///
+/// Variants:
+/// - `Gzip`, `Zstd`, `Brotli`, `Deflate`, `Zlib`: The algorithm of the same name.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseCompression {
+    Gzip,
+    Zstd,
+    Brotli,
+    Deflate,
+    #[default]
+    Zlib,
+}
+
+/// The storage backend enum for [DatabaseConfig].
+///
+/// Variants:
+/// - `Lmdb`: An LMDB database on disk at `db_path`.
+/// - `Memory`: Everything is kept in memory and lost on shutdown. `db_path` and `map_size` are
+///   ignored.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseBackend {
+    #[default]
+    Lmdb,
+    Memory,
+}
+
+/// The backup configuration section from [ServerConfig].
+///
+/// Fields:
+/// - `enabled`: Whether backups are taken automatically while the server is running. Backups can
+///   still be taken by hand with `/save-backup` when this is off.
+/// - `interval`: How often an automatic backup is taken, in minutes.
+/// - `path`: The folder backups are stored in. This is relative to the server root path.
+/// - `retention`: How many backups to keep. Once there are more, the oldest ones are deleted. Set
+///   to 0 to keep every backup.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    pub interval: u64,
+    pub path: String,
+    pub retention: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 60,
+            path: "backups".to_string(),
+            retention: 5,
+        }
+    }
+}
+
+/// The world generation section from [ServerConfig].
+///
+/// These are only read when a world is first created. The world remembers the generator it was
+/// made with, so changing them later doesn't affect existing worlds.
+///
+/// Fields:
+/// - `seed`: The seed new worlds are generated from. Left empty, a random seed is picked. Text
+///   that isn't a number is turned into one the same way vanilla does.
+/// - `generator` - [WorldGeneratorKind]: Which generator makes the overworld's terrain.
+/// - `flat_layers`: The layers of a flat world from the bottom of the world up, e.g.
+///   `bedrock,2*dirt,grass_block`. A block can be repeated with `count*block`.
+/// - `flat_biome`: The biome of a flat world.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WorldGenConfig {
+    #[serde(default)]
+    pub seed: String,
+    pub generator: WorldGeneratorKind,
+    pub flat_layers: String,
+    pub flat_biome: String,
+}
+
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        Self {
+            seed: String::new(),
+            generator: WorldGeneratorKind::default(),
+            flat_layers: "bedrock,2*dirt,grass_block".to_string(),
+            flat_biome: "plains".to_string(),
+        }
+    }
+}
+
+/// The world generator enum for [WorldGenConfig].
+///
+/// Variants:
+/// - `Noise`: Vanilla-like terrain with biomes picked by climate.
+/// - `Flat`: Flat layers of blocks in a single biome, like vanilla's superflat worlds.
+/// - `Void`: No blocks at all, in the void biome.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorldGeneratorKind {
+    #[default]
+    Noise,
+    Flat,
+    Void,
+}
+
+/// The proxy section from [ServerConfig].
+///
+/// A proxy logs players in itself and forwards who they are to the server, so in either forwarding
+/// mode players aren't authenticated with Mojang and their connections aren't encrypted by the
+/// server, whatever `online_mode` and `encryption_enabled` say.
+///
+/// Fields:
+/// - `mode` - [ProxyMode]: How the proxy forwards players.
+/// - `velocity_secret`: The forwarding secret from Velocity's `forwarding.secret` file. Players
+///   forwarded without it are turned away. The server won't start in velocity mode without one.
+/// - `proxy_protocol`: Whether every connection starts with a PROXY protocol header, which TCP load
+///   balancers like HAProxy send to pass on the address the connection came from.
+/// - `trusted_proxies`: The addresses and CIDR ranges, like `10.0.0.0/8`, allowed to send PROXY
+///   protocol headers and BungeeCord forwarding. Connections from anywhere else are refused, so
+///   the server won't start with it empty when either is used.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    #[serde(default)]
+    pub velocity_secret: String,
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+/// The forwarding mode enum for [ProxyConfig].
+///
+/// Variants:
+/// - `None`: Players connect to the server directly.
+/// - `Velocity`: Velocity's modern forwarding, which is signed with the forwarding secret.
+/// - `BungeeCord`: BungeeCord's legacy IP forwarding through the handshake. It can't be verified,
+///   so it's only accepted from `trusted_proxies`, which must hold the proxy's address, and the
+///   server should be firewalled off from everyone but the proxy.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    #[default]
+    None,
+    Velocity,
+    BungeeCord,
+}
+
 fn create_config() -> ServerConfig {
     let config_location = get_root_path().join("configs");
     let main_config_file = config_location.join("config.toml");
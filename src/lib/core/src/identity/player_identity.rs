@@ -1,4 +1,5 @@
 use bevy_ecs::prelude::Component;
+use std::net::IpAddr;
 use typename::TypeName;
 
 #[derive(TypeName, Debug, Component, Default, Clone)]
@@ -7,6 +8,9 @@ pub struct PlayerIdentity {
     pub uuid: uuid::Uuid,
     pub short_uuid: i32,
     pub properties: Vec<PlayerProperty>,
+    /// The address the player is connecting from. Behind a proxy, this is the address the proxy
+    /// forwarded rather than the proxy's own.
+    pub address: Option<IpAddr>,
 }
 
 impl PlayerIdentity {
@@ -16,6 +20,7 @@ impl PlayerIdentity {
             uuid: uuid::Uuid::from_u128(uuid),
             short_uuid: uuid as i32,
             properties,
+            address: None,
         }
     }
 }
@@ -5,15 +5,20 @@ use crate::player_cache::PlayerCache;
 use crate::player_list::PlayerList;
 use bevy_ecs::prelude::Resource;
 use ferrumc_threadpool::ThreadPool;
+use ferrumc_world::metadata::WorldMetadata;
 use ferrumc_world::World;
-use ferrumc_world_gen::WorldGenerator;
+use ferrumc_world_gen::ChunkGenerator;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Instant;
 
 pub struct ServerState {
     pub world: World,
-    pub terrain_generator: WorldGenerator,
+    /// The seed, generator and spawn point the world was created with.
+    pub world_metadata: WorldMetadata,
+    /// Makes the terrain of chunks that haven't been generated yet, see
+    /// [register_chunk_generator](ferrumc_world_gen::register_chunk_generator).
+    pub terrain_generator: Arc<dyn ChunkGenerator>,
     pub shut_down: AtomicBool,
     pub players: PlayerList, // (UUID, Username)
     pub player_cache: PlayerCache,
@@ -2,6 +2,7 @@ use dashmap::DashMap;
 use ferrumc_components::active_effects::ActiveEffects;
 use ferrumc_components::health::Health;
 use ferrumc_components::player::abilities::PlayerAbilities;
+use ferrumc_components::player::dimension::Dimension;
 use ferrumc_components::player::experience::Experience;
 use ferrumc_components::player::gamemode::GameMode;
 use ferrumc_components::player::gameplay_state::ender_chest::EnderChest;
@@ -18,6 +19,7 @@ pub struct OfflinePlayerData {
     pub gamemode: GameMode,
     pub position: Position,
     pub rotation: Rotation,
+    pub dimension: Dimension,
     pub inventory: Inventory,
     pub health: Health,
     pub hunger: Hunger,
@@ -92,10 +92,14 @@ pub(crate) fn build() -> TokenStream {
             .expect("Failed to parse biome.json");
 
     let mut constants = TokenStream::new();
+    let mut type_from_id_arms = TokenStream::new();
     let mut type_from_name = TokenStream::new();
 
-    for (name, biome) in biomes.iter() {
+    // The biome registry is sent to the client sorted by name, so the network id of a biome is
+    // its index in the sorted map
+    for (id, (name, biome)) in biomes.iter().enumerate() {
         let const_ident = format_ident!("{}", name.to_shouty_snake_case());
+        let id_lit = LitInt::new(&id.to_string(), Span::call_site());
 
         let has_precipitation = LitBool::new(biome.has_precipitation, Span::call_site());
         let temperature = LitFloat::new(&format!("{:.1}", biome.temperature), Span::call_site());
@@ -135,6 +139,7 @@ pub(crate) fn build() -> TokenStream {
 
         constants.extend(quote! {
             pub const #const_ident: Biome = Biome {
+                id: #id_lit,
                 name: #name,
                 has_precipitation: #has_precipitation,
                 temperature: #temperature,
@@ -149,6 +154,10 @@ pub(crate) fn build() -> TokenStream {
             };
         });
 
+        type_from_id_arms.extend(quote! {
+            #id_lit => Some(&Self::#const_ident),
+        });
+
         type_from_name.extend(quote! {
             #name => Some(&Self::#const_ident),
         });
@@ -158,6 +167,7 @@ pub(crate) fn build() -> TokenStream {
 
         #[derive(Debug, Clone, Copy, PartialEq)]
         pub struct Biome {
+            pub id: u16,
             pub name: &'static str,
             pub has_precipitation: bool,
             pub temperature: f64,
@@ -183,6 +193,14 @@ pub(crate) fn build() -> TokenStream {
                 }
             }
 
+            #[doc = r" Try to get a `Biome` from its network ID."]
+            pub const fn from_id(id: u16) -> Option<&'static Self> {
+                match id {
+                    #type_from_id_arms
+                    _ => None
+                }
+            }
+
             #[doc = r" Check if this biome has precipitation (rain/snow)."]
             pub const fn has_precipitation(&self) -> bool {
                 self.has_precipitation
@@ -53,6 +53,39 @@ fn sanitize_name(name: &str) -> String {
     name.replace("minecraft:", "").replace(':', "_")
 }
 
+/// Works out how much light a block state absorbs, from 0 (none) to 15 (all of it).
+///
+/// The extracted data doesn't include this, so it's approximated from the state's outline: full
+/// cubes block all light unless they're see-through, a few blocks like leaves and water dim it
+/// slightly, and everything else lets it pass.
+fn opacity(name: &str, state: &State, shapes: &[Shape]) -> u8 {
+    let name = sanitize_name(name);
+    if matches!(
+        name.as_str(),
+        "water" | "bubble_column" | "ice" | "frosted_ice"
+    ) || name.ends_with("leaves")
+    {
+        return 1;
+    }
+    let see_through = (name.contains("glass") && name != "tinted_glass")
+        || name.ends_with("grate")
+        || matches!(
+            name.as_str(),
+            "barrier" | "beacon" | "light" | "slime_block" | "spawner" | "trial_spawner" | "vault"
+        );
+    let full_cube = match state.outline_shapes.as_slice() {
+        [shape] => shapes
+            .get(*shape as usize)
+            .is_some_and(|shape| shape.min == [0.0; 3] && shape.max == [1.0; 3]),
+        _ => false,
+    };
+    if full_cube && !see_through {
+        15
+    } else {
+        0
+    }
+}
+
 fn format_float(f: f32) -> String {
     if f.fract() == 0.0 {
         format!("{}.0", f)
@@ -90,6 +123,7 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     types_content.push_str("    pub jump_velocity_multiplier: f32,\n");
     types_content.push_str("    pub luminance: u32,\n");
     types_content.push_str("    pub item_id: u32,\n");
+    types_content.push_str("    pub default_state_id: u32,\n");
     types_content.push_str("}\n\n");
 
     types_content.push_str("#[derive(Debug, Clone, Copy)]\n");
@@ -106,6 +140,7 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     types_content.push_str("pub struct BlockState {\n");
     types_content.push_str("    pub id: u32,\n");
     types_content.push_str("    pub luminance: u32,\n");
+    types_content.push_str("    pub opacity: u8,\n");
     types_content.push_str("    pub piston_behavior: &'static str,\n");
     types_content.push_str("    pub collision_shapes: &'static [u32],\n");
     types_content.push_str("    pub outline_shapes: &'static [u32],\n");
@@ -170,6 +205,10 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
         let first_state = &block.states[0];
         content.push_str(&format!("    luminance: {},\n", first_state.luminance));
         content.push_str(&format!("    item_id: {},\n", block.item_id));
+        content.push_str(&format!(
+            "    default_state_id: {},\n",
+            block.default_state_id
+        ));
         content.push_str("};\n\n");
 
         // States
@@ -179,6 +218,10 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
                 content.push_str("    BlockState {\n");
                 content.push_str(&format!("        id: {},\n", state.id));
                 content.push_str(&format!("        luminance: {},\n", state.luminance));
+                content.push_str(&format!(
+                    "        opacity: {},\n",
+                    opacity(&block.name, state, &data.shapes)
+                ));
                 content.push_str(&format!(
                     "        piston_behavior: \"{}\",\n",
                     state.piston_behavior
@@ -243,6 +286,24 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     }
     mod_content.push_str("];\n\n");
 
+    // Block state lookup array, indexed by state id
+    let mut states = data
+        .blocks
+        .iter()
+        .flat_map(|block| {
+            let sanitized_name = sanitize_name(&block.name);
+            block.states.iter().enumerate().map(move |(index, state)| {
+                (state.id, format!("&{}::STATES[{}]", sanitized_name, index))
+            })
+        })
+        .collect::<Vec<_>>();
+    states.sort_by_key(|(id, _)| *id);
+    mod_content.push_str("pub const ALL_STATES: &[&BlockState] = &[\n");
+    for (_, state) in &states {
+        mod_content.push_str(&format!("    {},\n", state));
+    }
+    mod_content.push_str("];\n\n");
+
     // Re-exports for direct access (blocks::STONE instead of blocks::stone::STONE)
     mod_content.push_str("// Re-exports for direct access to block constants\n");
     for block in &data.blocks {
@@ -280,6 +341,12 @@ pub fn build() -> Result<(), Box<dyn std::error::Error>> {
     mod_content.push_str("            _ => None,\n");
     mod_content.push_str("        }\n");
     mod_content.push_str("    }\n");
+    mod_content.push_str("}\n\n");
+
+    mod_content.push_str("impl BlockState {\n");
+    mod_content.push_str("    pub fn by_id(id: u32) -> Option<&'static BlockState> {\n");
+    mod_content.push_str("        ALL_STATES.get(id as usize).copied()\n");
+    mod_content.push_str("    }\n");
     mod_content.push_str("}\n");
 
     fs::write(blocks_dir.join("mod.rs"), mod_content)?;
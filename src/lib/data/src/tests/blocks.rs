@@ -245,3 +245,23 @@ fn test_blocks_consistency() {
     assert_eq!(direct_stone.hardness, id_lookup.hardness);
     assert_eq!(direct_stone.hardness, name_lookup.hardness);
 }
+
+#[test]
+fn test_block_state_light_properties() {
+    let air = blocks::BlockState::by_id(0).unwrap();
+    assert_eq!(air.luminance, 0);
+    assert_eq!(air.opacity, 0);
+
+    let stone = blocks::BlockState::by_id(1).unwrap();
+    assert_eq!(stone.opacity, 15);
+
+    let glowstone = blocks::glowstone::STATES[0];
+    assert_eq!(glowstone.luminance, 15);
+    assert_eq!(
+        blocks::BlockState::by_id(glowstone.id).unwrap().id,
+        glowstone.id
+    );
+
+    assert_eq!(blocks::glass::STATES[0].opacity, 0);
+    assert!(blocks::BlockState::by_id(u32::MAX).is_none());
+}
@@ -0,0 +1,225 @@
+use std::time::Duration;
+
+use bevy_ecs::prelude::*;
+use ferrumc_commands::arg::primitive::string::SingleWord;
+use ferrumc_commands::Sender;
+use ferrumc_components::player::dimension::DimensionComponent;
+use ferrumc_core::transform::position::Position;
+use ferrumc_macros::command;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::block_update::BlockUpdate;
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_state::{GlobalState, GlobalStateResource};
+use ferrumc_world::block_log::{now_millis, BlockChange, ReplayedBlock};
+use ferrumc_world::block_state_id::BlockStateId;
+use tracing::error;
+
+/// How far from the sender `/inspect` looks for changes, in blocks.
+const INSPECT_RADIUS: i32 = 5;
+/// How many changes `/inspect` lists at most.
+const INSPECT_LIMIT: usize = 10;
+/// How far back `/inspect` looks for changes.
+const INSPECT_LOOKBACK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The block's name without the `minecraft:` prefix.
+fn block_name(block: BlockStateId) -> String {
+    block
+        .to_block_data()
+        .map(|data| data.name.trim_start_matches("minecraft:").to_string())
+        .unwrap_or_else(|| format!("#{}", block.0))
+}
+
+/// Describes how long ago something happened, e.g. `3m`.
+fn format_age(millis: u64) -> String {
+    let seconds = millis / 1000;
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3_600 => format!("{}m", seconds / 60),
+        3_600..86_400 => format!("{}h", seconds / 3_600),
+        _ => format!("{}d", seconds / 86_400),
+    }
+}
+
+fn describe_change(change: &BlockChange, now: u64) -> String {
+    let air = BlockStateId::default();
+    let action = if change.new_block == air {
+        format!("broke {}", block_name(change.old_block))
+    } else if change.old_block == air {
+        format!("placed {}", block_name(change.new_block))
+    } else {
+        format!(
+            "replaced {} with {}",
+            block_name(change.old_block),
+            block_name(change.new_block)
+        )
+    };
+    format!(
+        "{} ago: {} {} at ({}, {}, {}){}",
+        format_age(now.saturating_sub(change.timestamp)),
+        change.player_name,
+        action,
+        change.x,
+        change.y,
+        change.z,
+        if change.rolled_back {
+            " (rolled back)"
+        } else {
+            ""
+        }
+    )
+}
+
+/// Tells every player in the dimension about the blocks a rollback or restore set.
+fn send_block_updates(
+    state: &GlobalState,
+    blocks: &[ReplayedBlock],
+    players: &Query<(Entity, &StreamWriter, &DimensionComponent)>,
+) {
+    for (entity, writer, dimension) in players {
+        if !state.players.is_connected(entity) {
+            continue;
+        }
+        for block in blocks.iter().filter(|block| block.dimension == dimension.0) {
+            let packet = BlockUpdate {
+                location: NetworkPosition {
+                    x: block.x,
+                    y: block.y as i16,
+                    z: block.z,
+                },
+                block_state_id: VarInt::from(block.block),
+            };
+            if let Err(e) = writer.send_packet_ref(&packet) {
+                error!("Failed to send block update to {:?}: {:?}", entity, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Lists the latest block changes around the sender.
+#[command("inspect")]
+fn inspect_command(
+    #[sender] sender: Sender,
+    state: Res<GlobalStateResource>,
+    player_query: Query<(&Position, &DimensionComponent)>,
+) {
+    let Sender::Player(entity) = sender else {
+        sender.send_message("Error: The server can't inspect blocks.".into(), false);
+        return;
+    };
+    let Ok((position, dimension)) = player_query.get(entity) else {
+        sender.send_message(
+            "Error: Could not find your player components.".into(),
+            false,
+        );
+        return;
+    };
+
+    let (x, y, z) = (
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    );
+    let since = now_millis().saturating_sub(INSPECT_LOOKBACK.as_millis() as u64);
+    let changes = state.0.world.block_changes(since, INSPECT_LIMIT, |change| {
+        change.dimension == dimension.0
+            && (change.x - x).abs() <= INSPECT_RADIUS
+            && (change.y - y).abs() <= INSPECT_RADIUS
+            && (change.z - z).abs() <= INSPECT_RADIUS
+    });
+    let changes = match changes {
+        Ok(changes) => changes,
+        Err(e) => {
+            sender.send_message(
+                format!("Error: Could not read the block log: {e}").into(),
+                false,
+            );
+            return;
+        }
+    };
+
+    if changes.is_empty() {
+        sender.send_message(
+            format!("No block changes within {INSPECT_RADIUS} blocks in the last week.").into(),
+            false,
+        );
+        return;
+    }
+    let now = now_millis();
+    sender.send_message(
+        format!("Latest block changes within {INSPECT_RADIUS} blocks:").into(),
+        false,
+    );
+    for (_, change) in &changes {
+        sender.send_message(describe_change(change, now).into(), false);
+    }
+}
+
+/// Tells the sender off unless they're the console. Rolling back changes affects everyone's
+/// builds, so players can't do it.
+fn is_console(sender: Sender) -> bool {
+    if sender != Sender::Server {
+        sender.send_message(
+            "Error: Only the server console can roll back changes.".into(),
+            false,
+        );
+        return false;
+    }
+    true
+}
+
+/// Undoes the block changes a player made in the given amount of time.
+#[command("rollback")]
+fn rollback_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    #[arg] time: Duration,
+    state: Res<GlobalStateResource>,
+    players: Query<(Entity, &StreamWriter, &DimensionComponent)>,
+) {
+    if !is_console(sender) {
+        return;
+    }
+    let since = now_millis().saturating_sub(time.as_millis() as u64);
+    match state.0.world.rollback_changes(&player, since) {
+        Ok(blocks) => {
+            send_block_updates(&state.0, &blocks, &players);
+            sender.send_message(
+                format!(
+                    "Rolled back {} blocks changed by {}.",
+                    blocks.len(),
+                    *player
+                )
+                .into(),
+                false,
+            );
+        }
+        Err(e) => sender.send_message(format!("Error: Rollback failed: {e}").into(), false),
+    }
+}
+
+/// Redoes block changes that were rolled back with `/rollback`.
+#[command("restore")]
+fn restore_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    #[arg] time: Duration,
+    state: Res<GlobalStateResource>,
+    players: Query<(Entity, &StreamWriter, &DimensionComponent)>,
+) {
+    if !is_console(sender) {
+        return;
+    }
+    let since = now_millis().saturating_sub(time.as_millis() as u64);
+    match state.0.world.restore_changes(&player, since) {
+        Ok(blocks) => {
+            send_block_updates(&state.0, &blocks, &players);
+            sender.send_message(
+                format!("Restored {} blocks changed by {}.", blocks.len(), *player).into(),
+                false,
+            );
+        }
+        Err(e) => sender.send_message(format!("Error: Restore failed: {e}").into(), false),
+    }
+}
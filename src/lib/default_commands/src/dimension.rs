@@ -0,0 +1,61 @@
+use bevy_ecs::prelude::*;
+use ferrumc_commands::Sender;
+use ferrumc_components::player::dimension::{Dimension, DimensionComponent};
+use ferrumc_core::transform::position::Position;
+use ferrumc_macros::command;
+use ferrumc_messages::PlayerDimensionChanged;
+
+/// Works out where a player ends up when moving between dimensions, roughly the way portals
+/// do it: coordinates are scaled by 8 between the overworld and the nether, and the end always
+/// puts you on the main island.
+fn arrival_position(from: Dimension, to: Dimension, position: &Position) -> Position {
+    match (from, to) {
+        (_, Dimension::End) => Position::new(0.5, 72.0, 0.5),
+        (Dimension::Overworld, Dimension::Nether) => {
+            Position::new(position.x / 8.0, 64.0, position.z / 8.0)
+        }
+        (Dimension::Nether, Dimension::Overworld) => {
+            Position::new(position.x * 8.0, 128.0, position.z * 8.0)
+        }
+        (Dimension::End, _) => Position::new(0.5, 128.0, 0.5),
+        _ => position.clone(),
+    }
+}
+
+/// Moves the sender into another dimension.
+#[command("dimension")]
+fn dimension_command(
+    #[sender] sender: Sender,
+    #[arg] dimension: Dimension,
+    player_query: Query<(&DimensionComponent, &Position)>,
+    mut dimension_events: MessageWriter<PlayerDimensionChanged>,
+) {
+    // 1. Ensure the sender is a player
+    let player_entity = match sender {
+        Sender::Server => {
+            sender.send_message("Error: The server can't change dimension.".into(), false);
+            return;
+        }
+        Sender::Player(entity) => entity,
+    };
+
+    let Ok((current_dimension, position)) = player_query.get(player_entity) else {
+        sender.send_message(
+            "Error: Could not find your player components.".into(),
+            false,
+        );
+        return;
+    };
+
+    if current_dimension.0 == dimension {
+        sender.send_message(format!("You are already in {dimension}.").into(), false);
+        return;
+    }
+
+    // 2. Fire the event
+    dimension_events.write(PlayerDimensionChanged {
+        player: player_entity,
+        new_dimension: dimension,
+        position: Some(arrival_position(current_dimension.0, dimension, position)),
+    });
+}
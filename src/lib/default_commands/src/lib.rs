@@ -1,7 +1,13 @@
+pub mod block_log;
+pub mod dimension;
 pub mod echo;
 pub mod fly;
 pub mod gamemode;
 pub mod nested;
+pub mod pregen;
+pub mod save_backup;
+pub mod seed;
+pub mod world_edit;
 
 /// Static library initialisation shenanigans.
 pub fn init() {}
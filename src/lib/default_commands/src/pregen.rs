@@ -0,0 +1,72 @@
+use bevy_ecs::prelude::*;
+use ferrumc_commands::arg::primitive::int::Integer;
+use ferrumc_commands::Sender;
+use ferrumc_components::player::dimension::Dimension;
+use ferrumc_macros::command;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_world::errors::WorldError;
+
+/// The largest radius `/pregen` takes, about a million chunks. Larger areas can be generated
+/// with `ferrumc pregen` while the server is stopped.
+const MAX_RADIUS: i32 = 512;
+
+/// Generates every missing chunk within a radius of spawn.
+#[command("pregen")]
+fn pregen_command(
+    #[sender] sender: Sender,
+    #[arg] radius: Integer<0, MAX_RADIUS>,
+    #[arg] dimension: Dimension,
+    state: Res<GlobalStateResource>,
+) {
+    if sender != Sender::Server {
+        sender.send_message(
+            "Error: Only the server console can pre-generate chunks.".into(),
+            false,
+        );
+        return;
+    }
+    let radius = radius.unsigned_abs();
+    sender.send_message(
+        format!("Pre-generating chunks within {radius} chunks of spawn in {dimension}...").into(),
+        false,
+    );
+
+    // Pre-generation queues its chunks on the thread pool, which isn't allowed from one of the
+    // pool's own threads, so it gets a thread of its own.
+    let state = state.0.clone();
+    let spawned = std::thread::Builder::new()
+        .name("pregen".to_string())
+        .spawn(move || {
+            let generator_state = state.clone();
+            let result = state.world.pregenerate(
+                dimension.as_str(),
+                radius,
+                &state.thread_pool,
+                move |x, z| {
+                    generator_state
+                        .terrain_generator
+                        .generate_chunk(x, z, dimension)
+                        .map_err(|e| WorldError::WorldGenerationError(e.to_string()))
+                },
+            );
+            match result {
+                Ok(summary) => sender.send_message(
+                    format!(
+                        "Pre-generated {} chunks, {} already existed and {} failed.",
+                        summary.generated, summary.skipped, summary.failed
+                    )
+                    .into(),
+                    false,
+                ),
+                Err(e) => {
+                    sender.send_message(format!("Error: Pre-generation failed: {e}").into(), false)
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        sender.send_message(
+            format!("Error: Could not start pre-generation: {e}").into(),
+            false,
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use bevy_ecs::prelude::*;
+use ferrumc_commands::Sender;
+use ferrumc_macros::command;
+use ferrumc_state::GlobalStateResource;
+
+/// Backs up the world without stopping the server.
+#[command("save-backup")]
+fn save_backup_command(#[sender] sender: Sender, state: Res<GlobalStateResource>) {
+    // Old backups are pruned as new ones are taken, so players mustn't be able to take them.
+    if sender != Sender::Server {
+        sender.send_message(
+            "Error: Only the server console can back up the world.".into(),
+            false,
+        );
+        return;
+    }
+    sender.send_message("Saving a backup of the world...".into(), false);
+
+    // Copying the database can take a while, so don't hold up the tick.
+    let _handle = state.0.thread_pool.oneshot({
+        let state = state.0.clone();
+        move || match state.world.backup() {
+            Ok(path) => {
+                sender.send_message(format!("Backup saved to {}.", path.display()).into(), false)
+            }
+            Err(e) => sender.send_message(format!("Error: Backup failed: {e}").into(), false),
+        }
+    });
+}
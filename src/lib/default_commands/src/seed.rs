@@ -0,0 +1,36 @@
+use bevy_ecs::prelude::*;
+use ferrumc_commands::Sender;
+use ferrumc_macros::command;
+use ferrumc_state::GlobalStateResource;
+use ferrumc_text::{ClickEvent, HoverEvent, NamedColor, TextComponent, TextComponentBuilder};
+
+/// Shows the seed the world was generated from.
+#[command("seed")]
+fn seed_command(#[sender] sender: Sender, state: Res<GlobalStateResource>) {
+    // Players only get the hashed seed, so that the real one can't be worked out.
+    if sender != Sender::Server {
+        sender.send_message(
+            "Error: Only the server console can see the seed.".into(),
+            false,
+        );
+        return;
+    }
+    // Vanilla shows seeds as signed numbers, which is also how they're typed into the config.
+    let seed = (state.0.world_metadata.generator.seed as i64).to_string();
+
+    sender.send_message(
+        TextComponentBuilder::new("Seed: [")
+            .extra(
+                TextComponentBuilder::new(seed.clone())
+                    .color(NamedColor::Green)
+                    .click_event(ClickEvent::CopyToClipboard(seed))
+                    .hover_event(HoverEvent::ShowText(Box::new(TextComponent::from(
+                        "Click to copy to clipboard",
+                    ))))
+                    .build(),
+            )
+            .extra(TextComponent::from("]"))
+            .build(),
+        false,
+    );
+}
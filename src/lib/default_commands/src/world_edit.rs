@@ -0,0 +1,489 @@
+use bevy_ecs::prelude::*;
+use ferrumc_commands::arg::primitive::string::SingleWord;
+use ferrumc_commands::Sender;
+use ferrumc_components::player::dimension::{Dimension, DimensionComponent};
+use ferrumc_components::player::world_edit::WorldEditSession;
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_core::identity::player_identity::PlayerIdentity;
+use ferrumc_core::transform::position::Position;
+use ferrumc_macros::command;
+use ferrumc_net::connection::StreamWriter;
+use ferrumc_net::packets::outgoing::chunk_and_light_data::ChunkAndLightData;
+use ferrumc_state::{GlobalState, GlobalStateResource};
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::errors::WorldError;
+use ferrumc_world::region::{AppliedEdits, Region, MAX_EDIT_VOLUME};
+use ferrumc_world::World;
+use std::sync::Arc;
+use tracing::error;
+
+/// The name edits made from the console are logged under, so they can be rolled back with
+/// `/rollback Server`.
+const CONSOLE_NAME: &str = "Server";
+
+type Players<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static StreamWriter,
+        &'static PlayerIdentity,
+        &'static Position,
+        &'static DimensionComponent,
+    ),
+>;
+
+/// The block the player is standing in.
+fn block_position(position: &Position) -> (i32, i32, i32) {
+    (
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    )
+}
+
+/// Gets the player that sent a command along with where they are, telling the sender off if it
+/// isn't a player.
+fn sending_player(
+    sender: Sender,
+    players: &Players,
+) -> Option<(Entity, (i32, i32, i32), Dimension)> {
+    let Sender::Player(entity) = sender else {
+        sender.send_message("Error: Only players can select blocks.".into(), false);
+        return None;
+    };
+    let Ok((_, _, _, position, dimension)) = players.get(entity) else {
+        sender.send_message(
+            "Error: Could not find your player components.".into(),
+            false,
+        );
+        return None;
+    };
+    Some((entity, block_position(position), dimension.0))
+}
+
+/// Gets the player whose selection a command edits along with where they are. Editing commands
+/// can only be run from the console for now, since any player could otherwise change huge parts
+/// of the world.
+fn target_player(
+    sender: Sender,
+    name: &str,
+    players: &Players,
+) -> Option<(Entity, (i32, i32, i32), Dimension)> {
+    if sender != Sender::Server {
+        sender.send_message(
+            "Error: Only the server console can edit the world.".into(),
+            false,
+        );
+        return None;
+    }
+    let target = players
+        .iter()
+        .find(|(_, _, identity, _, _)| identity.username.eq_ignore_ascii_case(name));
+    let Some((entity, _, _, position, dimension)) = target else {
+        sender.send_message(format!("Error: {name} is not online.").into(), false);
+        return None;
+    };
+    Some((entity, block_position(position), dimension.0))
+}
+
+/// Gets the sender's selection, telling them why if they don't have a usable one.
+fn selection(sender: Sender, session: &WorldEditSession) -> Option<Region> {
+    let Some(region) = session.selection() else {
+        sender.send_message(
+            "Error: Select two positions first, with //pos1 and //pos2 or by clicking with a wooden axe."
+                .into(),
+            false,
+        );
+        return None;
+    };
+    if region.volume() > MAX_EDIT_VOLUME {
+        sender.send_message(
+            format!(
+                "Error: The selection holds {} blocks, but at most {MAX_EDIT_VOLUME} can be edited at once.",
+                region.volume()
+            )
+            .into(),
+            false,
+        );
+        return None;
+    }
+    Some(region)
+}
+
+/// Generates and saves the given chunks if they don't exist yet. Chunks players see aren't saved
+/// until they're edited, and edits skip chunks that don't exist.
+fn generate_missing_chunks(
+    state: &GlobalState,
+    dimension: Dimension,
+    chunks: &[(i32, i32)],
+) -> Result<usize, WorldError> {
+    let mut generated = 0;
+    for &(x, z) in chunks {
+        if state.world.chunk_exists(x, z, dimension.as_str())? {
+            continue;
+        }
+        let chunk = state
+            .terrain_generator
+            .generate_chunk(x, z, dimension)
+            .map_err(|e| WorldError::WorldGenerationError(e.to_string()))?;
+        state.world.save_chunk(Arc::new(chunk))?;
+        generated += 1;
+    }
+    Ok(generated)
+}
+
+/// Sends the chunks an edit changed to every player close enough to have them loaded.
+fn resend_chunks(
+    state: &GlobalState,
+    dimension: Dimension,
+    chunks: &[(i32, i32)],
+    players: &Players,
+) {
+    let packets = chunks
+        .iter()
+        .filter_map(|&(x, z)| {
+            let packet = match state.world.load_chunk(x, z, dimension.as_str()) {
+                Ok(chunk) => ChunkAndLightData::from_chunk(&chunk).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match packet {
+                Ok(packet) => Some(((x, z), packet)),
+                Err(e) => {
+                    error!("Failed to create chunk packet for ({}, {}): {}", x, z, e);
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let radius = get_global_config().chunk_render_distance as i32;
+    for (entity, writer, _, position, player_dimension) in players {
+        if player_dimension.0 != dimension || !state.players.is_connected(entity) {
+            continue;
+        }
+        let (block_x, _, block_z) = block_position(position);
+        let (center_x, center_z) = (block_x >> 4, block_z >> 4);
+        for ((x, z), packet) in &packets {
+            if (x - center_x).abs() > radius || (z - center_z).abs() > radius {
+                continue;
+            }
+            if let Err(e) = writer.send_packet_ref(packet) {
+                error!("Failed to send chunk to {:?}: {:?}", entity, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Runs an edit on the chunks given, logs and sends the changes to the players who can see them
+/// and adds the edit to the player's undo history.
+///
+/// Generating chunks is too slow to do during a tick, so if any of the chunks are missing they're
+/// generated in the background instead, and the command has to be run again once they're done.
+fn apply_edit(
+    sender: Sender,
+    state: &GlobalState,
+    session: &mut WorldEditSession,
+    dimension: Dimension,
+    chunks: impl Iterator<Item = (i32, i32)>,
+    players: &Players,
+    edit: impl FnOnce(&World) -> Result<AppliedEdits, WorldError>,
+) {
+    let mut missing = Vec::new();
+    for (x, z) in chunks {
+        match state.world.chunk_exists(x, z, dimension.as_str()) {
+            Ok(true) => {}
+            Ok(false) => missing.push((x, z)),
+            Err(e) => {
+                sender.send_message(format!("Error: Edit failed: {e}").into(), false);
+                return;
+            }
+        }
+    }
+    if !missing.is_empty() {
+        sender.send_message(
+            format!(
+                "Generating {} missing chunks first, run the command again once they're done.",
+                missing.len()
+            )
+            .into(),
+            false,
+        );
+        let _handle = state.thread_pool.oneshot({
+            let state = state.clone();
+            move || match generate_missing_chunks(&state, dimension, &missing) {
+                Ok(generated) => sender.send_message(
+                    format!("Generated {generated} chunks, the edit can be run now.").into(),
+                    false,
+                ),
+                Err(e) => sender.send_message(
+                    format!("Error: Could not generate the missing chunks: {e}").into(),
+                    false,
+                ),
+            }
+        });
+        return;
+    }
+
+    match edit(&state.world) {
+        Ok(edits) => {
+            state.world.log_edits(0, CONSOLE_NAME, dimension, &edits);
+            resend_chunks(state, dimension, &edits.chunks, players);
+            sender.send_message(
+                format!("{} blocks changed.", edits.previous.len()).into(),
+                false,
+            );
+            session.push_undo(dimension, edits);
+        }
+        Err(e) => sender.send_message(format!("Error: Edit failed: {e}").into(), false),
+    }
+}
+
+/// Sets the first corner of the selection to where the sender is standing.
+#[command("/pos1")]
+fn pos1_command(
+    #[sender] sender: Sender,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, position, _)) = sending_player(sender, &players) else {
+        return;
+    };
+    if let Ok(mut session) = sessions.get_mut(entity) {
+        session.pos1 = Some(position);
+        sender.send_message(
+            format!(
+                "First position set to ({}, {}, {}).",
+                position.0, position.1, position.2
+            )
+            .into(),
+            false,
+        );
+    }
+}
+
+/// Sets the second corner of the selection to where the sender is standing.
+#[command("/pos2")]
+fn pos2_command(
+    #[sender] sender: Sender,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, position, _)) = sending_player(sender, &players) else {
+        return;
+    };
+    if let Ok(mut session) = sessions.get_mut(entity) {
+        session.pos2 = Some(position);
+        sender.send_message(
+            format!(
+                "Second position set to ({}, {}, {}).",
+                position.0, position.1, position.2
+            )
+            .into(),
+            false,
+        );
+    }
+}
+
+/// Fills a player's selection with a block.
+#[command("/set")]
+fn set_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    #[arg] block: BlockStateId,
+    state: Res<GlobalStateResource>,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, _, dimension)) = target_player(sender, &player, &players) else {
+        return;
+    };
+    let Ok(mut session) = sessions.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = selection(sender, &session) else {
+        return;
+    };
+    apply_edit(
+        sender,
+        &state.0,
+        &mut session,
+        dimension,
+        region.chunks(),
+        &players,
+        |world| world.edit_blocks(dimension.as_str(), region.positions(), |_, _| Some(block)),
+    );
+}
+
+/// Replaces one block with another throughout a player's selection.
+#[command("/replace")]
+fn replace_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    #[arg] from: BlockStateId,
+    #[arg] to: BlockStateId,
+    state: Res<GlobalStateResource>,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, _, dimension)) = target_player(sender, &player, &players) else {
+        return;
+    };
+    let Ok(mut session) = sessions.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = selection(sender, &session) else {
+        return;
+    };
+    apply_edit(
+        sender,
+        &state.0,
+        &mut session,
+        dimension,
+        region.chunks(),
+        &players,
+        |world| {
+            world.edit_blocks(dimension.as_str(), region.positions(), |_, old_block| {
+                (old_block == from).then_some(to)
+            })
+        },
+    );
+}
+
+/// Builds walls of a block around the sides of a player's selection.
+#[command("/walls")]
+fn walls_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    #[arg] block: BlockStateId,
+    state: Res<GlobalStateResource>,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, _, dimension)) = target_player(sender, &player, &players) else {
+        return;
+    };
+    let Ok(mut session) = sessions.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = selection(sender, &session) else {
+        return;
+    };
+    apply_edit(
+        sender,
+        &state.0,
+        &mut session,
+        dimension,
+        region.chunks(),
+        &players,
+        |world| world.edit_blocks(dimension.as_str(), region.walls(), |_, _| Some(block)),
+    );
+}
+
+/// Copies the selection to the clipboard, relative to where the sender is standing.
+#[command("/copy")]
+fn copy_command(
+    #[sender] sender: Sender,
+    state: Res<GlobalStateResource>,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, position, dimension)) = sending_player(sender, &players) else {
+        return;
+    };
+    let Ok(mut session) = sessions.get_mut(entity) else {
+        return;
+    };
+    let Some(region) = selection(sender, &session) else {
+        return;
+    };
+    match state
+        .0
+        .world
+        .copy_region(&region, dimension.as_str(), position)
+    {
+        Ok(schematic) => {
+            session.clipboard = Some(schematic);
+            sender.send_message(format!("Copied {} blocks.", region.volume()).into(), false);
+        }
+        Err(e) => sender.send_message(format!("Error: Copy failed: {e}").into(), false),
+    }
+}
+
+/// Pastes a player's clipboard relative to where they're standing.
+#[command("/paste")]
+fn paste_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    state: Res<GlobalStateResource>,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, position, dimension)) = target_player(sender, &player, &players) else {
+        return;
+    };
+    let Ok(mut session) = sessions.get_mut(entity) else {
+        return;
+    };
+    let Some(clipboard) = session.clipboard.take() else {
+        sender.send_message(
+            "Error: The clipboard is empty, copy something with //copy first.".into(),
+            false,
+        );
+        return;
+    };
+
+    let min = (
+        position.0 + clipboard.offset.0,
+        position.1 + clipboard.offset.1,
+        position.2 + clipboard.offset.2,
+    );
+    let max = (
+        min.0 + clipboard.width() as i32 - 1,
+        min.1 + clipboard.height() as i32 - 1,
+        min.2 + clipboard.length() as i32 - 1,
+    );
+    apply_edit(
+        sender,
+        &state.0,
+        &mut session,
+        dimension,
+        Region::new(min, max).chunks(),
+        &players,
+        |world| world.paste_schematic(&clipboard, position, dimension.as_str(), false),
+    );
+    session.clipboard = Some(clipboard);
+}
+
+/// Undoes the last edit made with a player's selection or clipboard.
+#[command("/undo")]
+fn undo_command(
+    #[sender] sender: Sender,
+    #[arg] player: SingleWord,
+    state: Res<GlobalStateResource>,
+    mut sessions: Query<&mut WorldEditSession>,
+    players: Players,
+) {
+    let Some((entity, _, _)) = target_player(sender, &player, &players) else {
+        return;
+    };
+    let Ok(mut session) = sessions.get_mut(entity) else {
+        return;
+    };
+    let Some((dimension, edits)) = session.pop_undo() else {
+        sender.send_message("Error: There is nothing to undo.".into(), false);
+        return;
+    };
+    match state.0.world.undo_edits(dimension.as_str(), &edits) {
+        Ok(undone) => {
+            state.0.world.log_edits(0, CONSOLE_NAME, dimension, &undone);
+            resend_chunks(&state.0, dimension, &undone.chunks, &players);
+            sender.send_message(
+                format!("Undid {} block changes.", undone.previous.len()).into(),
+                false,
+            );
+        }
+        Err(e) => sender.send_message(format!("Error: Undo failed: {e}").into(), false),
+    }
+}
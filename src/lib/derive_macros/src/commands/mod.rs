@@ -195,7 +195,7 @@ pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
         .map(|arg| {
             let name = arg.name.clone();
             let required = arg.required;
-            let ty = format_ident!("{}", &arg.ty);
+            let ty = syn::parse_str::<Type>(&arg.ty).expect("invalid arg type");
 
             quote! {
                 ferrumc_commands::arg::CommandArgumentNode {
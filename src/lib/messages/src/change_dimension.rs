@@ -0,0 +1,14 @@
+use bevy_ecs::prelude::{Entity, Message};
+use ferrumc_components::player::dimension::Dimension;
+use ferrumc_core::transform::position::Position;
+
+/// Fired when a player should be moved into another dimension.
+/// This can be triggered by a command or, eventually, a portal.
+#[derive(Message)]
+pub struct PlayerDimensionChanged {
+    pub player: Entity,
+    pub new_dimension: Dimension,
+    /// Where to put the player in the new dimension. If `None`, the player keeps their
+    /// current coordinates.
+    pub position: Option<Position>,
+}
@@ -18,3 +18,6 @@ pub use player_leave::*;
 
 pub mod change_gamemode;
 pub use change_gamemode::*;
+
+pub mod change_dimension;
+pub use change_dimension::*;
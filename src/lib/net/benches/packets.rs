@@ -8,7 +8,7 @@ pub fn bench_packets(c: &mut criterion::BenchmarkGroup<WallTime>) {
 
 fn bench_chunk_packet(c: &mut criterion::BenchmarkGroup<WallTime>) {
     let chunk = ferrumc_world_gen::WorldGenerator::new(0)
-        .generate_chunk(0, 0)
+        .generate_chunk(0, 0, ferrumc_world::dimension::Dimension::Overworld)
         .unwrap();
     let chunk_packet = black_box(
         ferrumc_net::packets::outgoing::chunk_and_light_data::ChunkAndLightData::from_chunk(&chunk)
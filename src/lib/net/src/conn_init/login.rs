@@ -6,10 +6,15 @@ use crate::connection::StreamWriter;
 use crate::errors::{NetAuthenticationError, NetError, PacketError};
 use crate::packets::incoming::packet_skeleton::PacketSkeleton;
 use crate::packets::outgoing::login_success::LoginSuccessProperties;
-use crate::packets::outgoing::set_default_spawn_position::DEFAULT_SPAWN_POSITION;
 use crate::packets::outgoing::{commands::CommandsPacket, registry_data::REGISTRY_PACKETS};
+use crate::proxy::{
+    read_bungeecord_forwarding, read_velocity_forwarding, ForwardedPlayer, VELOCITY_CHANNEL,
+    VELOCITY_FORWARDING_VERSION,
+};
+use crate::proxy_protocol::is_trusted;
 use crate::ConnState::*;
 use ferrumc_config::server_config::get_global_config;
+use ferrumc_config::ProxyMode;
 use ferrumc_core::identity::player_identity::{PlayerIdentity, PlayerProperty};
 use ferrumc_core::transform::position::Position;
 use ferrumc_core::transform::rotation::Rotation;
@@ -24,6 +29,8 @@ use ferrumc_net_encryption::read::EncryptedReader;
 use ferrumc_state::GlobalState;
 
 use rand::RngCore;
+use std::net::IpAddr;
+use std::sync::Arc;
 use tokio::net::tcp::OwnedReadHalf;
 use tracing::{debug, error, trace};
 use uuid::Uuid;
@@ -31,7 +38,8 @@ use uuid::Uuid;
 /// Handles the **login sequence** for a newly connecting client.
 ///
 /// This function follows the Minecraft login/configuration handshake:
-/// 1. Reads the initial login packet and authenticates the username/UUID.
+/// 1. Reads the initial login packet and authenticates the username/UUID, or reads who the player
+///    is from the proxy when one is configured.
 /// 2. Optionally enables network compression.
 /// 3. Sends required handshake completion packets:
 ///    - Login success
@@ -50,6 +58,8 @@ pub(super) async fn login(
     conn_read: &mut EncryptedReader<OwnedReadHalf>,
     conn_write: &StreamWriter,
     state: GlobalState,
+    address: IpAddr,
+    server_address: &str,
 ) -> Result<(bool, LoginResult), NetError> {
     let mut compressed = false;
 
@@ -72,6 +82,34 @@ pub(super) async fn login(
         &NetDecodeOpts::None,
     )?;
 
+    // =============================================================================================
+    // 1.1 Find out who the player is from the proxy, if there is one
+    let proxy_mode = get_global_config().proxy.mode;
+    let forwarded = match proxy_mode {
+        ProxyMode::None => Ok(None),
+        ProxyMode::Velocity => velocity_forwarding(conn_read, conn_write).await.map(Some),
+        ProxyMode::BungeeCord => {
+            // BungeeCord's forwarding isn't signed, so it's only believed from the proxy itself
+            if is_trusted(address, &get_global_config().proxy.trusted_proxies) {
+                read_bungeecord_forwarding(server_address).map(Some)
+            } else {
+                Err(NetAuthenticationError::UntrustedForwarder(address).into())
+            }
+        }
+    };
+    let forwarded = match forwarded {
+        Ok(forwarded) => forwarded,
+        Err(err) => {
+            let disconnect = crate::packets::outgoing::login_disconnect::LoginDisconnectPacket::new(
+                "This server can only be joined through its proxy.",
+            );
+            if let Err(send_err) = conn_write.send_packet(disconnect) {
+                error!("Failed to send login disconnect packet {:?}", send_err);
+            }
+            return Err(err);
+        }
+    };
+
     // =============================================================================================
     // 2 Negotiate compression if configured
     if get_global_config().network_compression_threshold > 0 {
@@ -88,10 +126,13 @@ pub(super) async fn login(
     }
 
     // =============================================================================================
-    // 3 Enable encryption and auth player if configured
+    // 3 Enable encryption and auth player if configured. The proxy has already done both for
+    // forwarded players.
     let mut player_properties = Vec::new();
 
-    if get_global_config().encryption_enabled || get_global_config().online_mode {
+    if proxy_mode == ProxyMode::None
+        && (get_global_config().encryption_enabled || get_global_config().online_mode)
+    {
         let mut verify_token = vec![0u8; 16];
         rand::rng().fill_bytes(&mut verify_token);
 
@@ -156,11 +197,24 @@ pub(super) async fn login(
         }
     }
 
+    // Forwarded players are whoever the proxy says they are
+    let (uuid, username, address) = match forwarded {
+        Some(forwarded) => {
+            player_properties = forwarded.properties;
+            (
+                forwarded.uuid.as_u128(),
+                forwarded.username.unwrap_or(login_start.username),
+                forwarded.address,
+            )
+        }
+        None => (login_start.uuid, login_start.username, address),
+    };
+
     // =============================================================================================
     // 4 Send Login Success (UUID and username acknowledgement)
     let login_success = crate::packets::outgoing::login_success::LoginSuccessPacket {
-        uuid: login_start.uuid,
-        username: &login_start.username,
+        uuid,
+        username: &username,
         properties: LengthPrefixedVec::new(
             player_properties
                 .iter()
@@ -181,10 +235,11 @@ pub(super) async fn login(
 
     // Build PlayerIdentity for server-side tracking
     let player_identity = PlayerIdentity {
-        uuid: Uuid::from_u128(login_start.uuid),
-        username: login_start.username.clone(),
-        short_uuid: login_start.uuid as i32,
+        uuid: Uuid::from_u128(uuid),
+        username,
+        short_uuid: uuid as i32,
         properties: player_properties,
+        address: Some(address),
     };
 
     // =============================================================================================
@@ -295,9 +350,17 @@ pub(super) async fn login(
         .map(|data| data.gamemode)
         .unwrap_or_default();
 
+    let dimension = state
+        .player_cache
+        .get(&player_identity.uuid)
+        .map(|data| data.dimension)
+        .unwrap_or_default();
+
     let login_play = crate::packets::outgoing::login_play::LoginPlayPacket::new(
         player_identity.short_uuid,
         game_mode_to_send as u8,
+        dimension,
+        state.world_metadata.hashed_seed(),
     );
     conn_write.send_packet(login_play)?;
 
@@ -337,13 +400,10 @@ pub(super) async fn login(
             // Found in cache: return clones of the data
             (data.position.clone(), data.rotation)
         } else {
-            // Not found: Use Server Defaults for BOTH
+            // Not found: Use the world's spawn point and a default rotation
+            let [x, y, z] = state.world_metadata.spawn;
             (
-                Position::new(
-                    DEFAULT_SPAWN_POSITION.x as f64,
-                    DEFAULT_SPAWN_POSITION.y as f64,
-                    DEFAULT_SPAWN_POSITION.z as f64,
-                ),
+                Position::new(f64::from(x), f64::from(y), f64::from(z)),
                 // Ensure you have a default rotation that matches the default position
                 Rotation::default(),
             )
@@ -439,7 +499,17 @@ pub(super) async fn login(
             batch.execute({
                 let state = state.clone();
                 move || -> Result<Vec<u8>, NetError> {
-                    let chunk = state.world.load_chunk(x, z, "overworld")?;
+                    // Chunks in dimensions nobody has visited yet won't exist, so generate them
+                    let chunk = if state.world.chunk_exists(x, z, dimension.as_str())? {
+                        state.world.load_chunk(x, z, dimension.as_str())?
+                    } else {
+                        Arc::new(
+                            state
+                                .terrain_generator
+                                .generate_chunk(x, z, dimension)
+                                .map_err(|err| NetError::Misc(err.to_string()))?,
+                        )
+                    };
                     let chunk_data =
                         crate::packets::outgoing::chunk_and_light_data::ChunkAndLightData::from_chunk(
                             &chunk,
@@ -486,3 +556,43 @@ pub(super) async fn login(
         },
     ))
 }
+
+/// Asks Velocity who the player is with a login plugin request, and checks its answer was signed
+/// with the forwarding secret.
+async fn velocity_forwarding(
+    conn_read: &mut EncryptedReader<OwnedReadHalf>,
+    conn_write: &StreamWriter,
+) -> Result<ForwardedPlayer, NetError> {
+    let message_id = i32::from(rand::random::<u16>());
+    conn_write.send_packet(
+        crate::packets::outgoing::login_plugin_request::LoginPluginRequest {
+            message_id: VarInt::new(message_id),
+            channel: VELOCITY_CHANNEL.to_string(),
+            data: vec![VELOCITY_FORWARDING_VERSION],
+        },
+    )?;
+
+    let mut skel = PacketSkeleton::new(conn_read, false, Login).await?;
+    let expected_id = lookup_packet!("login", "serverbound", "custom_query_answer");
+    if skel.id != expected_id {
+        return Err(NetError::Packet(PacketError::UnexpectedPacket {
+            expected: expected_id,
+            received: skel.id,
+            state: Login,
+        }));
+    }
+
+    let response = crate::packets::incoming::login_plugin_response::LoginPluginResponse::decode(
+        &mut skel.data,
+        &NetDecodeOpts::None,
+    )?;
+    // Vanilla clients don't know the channel, so only a proxy understands the request
+    if response.message_id.0 != message_id || !response.understood {
+        return Err(NetAuthenticationError::NotForwarded.into());
+    }
+
+    read_velocity_forwarding(
+        get_global_config().proxy.velocity_secret.as_bytes(),
+        &response.data,
+    )
+}
@@ -14,6 +14,7 @@ use ferrumc_net_codec::net_types::var_int::VarInt;
 use ferrumc_net_encryption::read::EncryptedReader;
 use ferrumc_state::GlobalState;
 use ferrumc_text::{ComponentBuilder, NamedColor, TextComponent};
+use std::net::IpAddr;
 use std::sync::atomic::Ordering;
 use tokio::net::tcp::OwnedReadHalf;
 use tracing::{error, trace};
@@ -46,6 +47,7 @@ pub const PROTOCOL_VERSION_1_21_8: i32 = 772;
 /// - `conn_read`: Read half of the TCP stream for incoming data.
 /// - `conn_write`: Writer for sending packets back to the client.
 /// - `state`: Shared global server state.
+/// - `address`: The address the client is connecting from.
 ///
 /// # Returns
 /// - `(bool, LoginResult)`:
@@ -61,6 +63,7 @@ pub async fn handle_handshake(
     mut conn_read: &mut EncryptedReader<OwnedReadHalf>,
     conn_write: &StreamWriter,
     state: GlobalState,
+    address: IpAddr,
 ) -> Result<(bool, LoginResult), NetError> {
     // Build a PacketSkeleton from the first inbound packet.
     // This handles framing, reading packet ID and payload.
@@ -97,7 +100,16 @@ pub async fn handle_handshake(
     // Branch based on the next connection state requested by the client.
     match hs_packet.next_state.0 {
         1 => status(conn_read, conn_write, state).await,
-        2 => login(conn_read, conn_write, state).await,
+        2 => {
+            login(
+                conn_read,
+                conn_write,
+                state,
+                address,
+                &hs_packet.server_address,
+            )
+            .await
+        }
         3 => {
             // Placeholder for a potential server transfer state (not supported yet).
             trace!("Transfer state (3) not implemented");
@@ -15,6 +15,7 @@ use ferrumc_net_codec::encode::NetEncodeOpts;
 use ferrumc_net_encryption::read::EncryptedReader;
 use ferrumc_net_encryption::write::EncryptedWriter;
 use ferrumc_state::ServerState;
+use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -221,6 +222,8 @@ pub struct DisconnectHandle {
 /// # Parameters
 /// - `state`: Shared global server state.
 /// - `tcp_stream`: The accepted client socket.
+/// - `address`: The address the client is connecting from, see
+///   [client_address](crate::proxy_protocol::client_address).
 /// - `packet_sender`: Channel to the packet handling system.
 /// - `new_join_sender`: Channel to register the new connection in the ECS.
 ///
@@ -229,6 +232,7 @@ pub struct DisconnectHandle {
 pub async fn handle_connection(
     state: Arc<ServerState>,
     tcp_stream: TcpStream,
+    address: SocketAddr,
     packet_sender: Arc<PacketSender>,
     new_join_sender: Arc<Sender<NewConnection>>,
 ) -> Result<(), NetError> {
@@ -251,7 +255,7 @@ pub async fn handle_connection(
 
     let handshake_result = timeout(
         MAX_HANDSHAKE_TIMEOUT,
-        handle_handshake(&mut tcp_reader, &stream, state.clone()),
+        handle_handshake(&mut tcp_reader, &stream, state.clone(), address.ip()),
     )
     .await;
 
@@ -289,6 +293,9 @@ pub async fn handle_connection(
                     NetError::InvalidState(state) => {
                         warn!("Client sent invalid handshake state: {}", state);
                     }
+                    NetError::AuthenticationError(err) => {
+                        warn!("Could not log in {}: {}", address, err);
+                    }
                     _ => {
                         error!("Unhandled handshake error: {}", err);
                     }
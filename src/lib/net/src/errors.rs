@@ -48,6 +48,15 @@ pub enum NetError {
     #[error("Handshake timeout")]
     HandshakeTimeout,
 
+    #[error("Invalid PROXY protocol header: {0}")]
+    InvalidProxyHeader(String),
+
+    #[error("PROXY protocol header from untrusted source {0}")]
+    UntrustedProxy(std::net::IpAddr),
+
+    #[error("Invalid proxy config: {0}")]
+    InvalidProxyConfig(String),
+
     #[error("Packet error: {0}")]
     Packet(PacketError),
 
@@ -168,4 +177,19 @@ pub enum NetAuthenticationError {
 
     #[error("Mojang responded with status code {0}")]
     UnknownStatusError(u16),
+
+    #[error("The player didn't connect through the proxy")]
+    NotForwarded,
+
+    #[error("The forwarded player information wasn't signed with the configured secret")]
+    ForwardingSignatureMismatch,
+
+    #[error("No forwarding secret is set, so forwarded players can't be verified")]
+    MissingForwardingSecret,
+
+    #[error("Forwarded player information from untrusted source {0}")]
+    UntrustedForwarder(std::net::IpAddr),
+
+    #[error("Could not read the forwarded player information: {0}")]
+    MalformedForwarding(String),
 }
@@ -13,6 +13,8 @@ mod conn_init;
 pub mod connection;
 pub mod errors;
 pub mod packets;
+mod proxy;
+pub mod proxy_protocol;
 pub mod server;
 
 setup_packet_handling!("\\src\\packets\\incoming");
@@ -0,0 +1,12 @@
+use ferrumc_macros::{packet, NetDecode};
+use ferrumc_net_codec::net_types::var_int::VarInt;
+
+/// The answer to a [LoginPluginRequest](crate::packets::outgoing::login_plugin_request::LoginPluginRequest).
+#[derive(NetDecode)]
+#[packet(packet_id = "custom_query_answer", state = "login")]
+pub struct LoginPluginResponse {
+    pub message_id: VarInt,
+    /// Whether the client understood the request. `data` is empty when it didn't.
+    pub understood: bool,
+    pub data: Vec<u8>,
+}
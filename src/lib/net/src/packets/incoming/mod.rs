@@ -2,6 +2,7 @@ pub mod ack_finish_configuration;
 pub mod client_information;
 pub mod handshake;
 pub mod login_acknowledged;
+pub mod login_plugin_response;
 pub mod login_start;
 pub mod ping;
 pub mod server_bound_known_packs;
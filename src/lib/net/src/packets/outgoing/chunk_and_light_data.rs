@@ -5,12 +5,17 @@ use ferrumc_net_codec::net_types::bitset::BitSet;
 use ferrumc_net_codec::net_types::byte_array::ByteArray;
 use ferrumc_net_codec::net_types::length_prefixed_vec::LengthPrefixedVec;
 use ferrumc_net_codec::net_types::var_int::VarInt;
-use ferrumc_world::chunk_format::{Chunk, PaletteType};
+use ferrumc_world::chunk_format::{BiomeStates, Chunk, PaletteType};
 use std::io::Cursor;
 use std::ops::Not;
 use tracing::warn;
 
 const SECTIONS: usize = 24; // Number of sections, adjust for your Y range (-64 to 319)
+/// Bits per entry used for biomes sent with a direct palette, enough to fit every biome id in the
+/// registry.
+const DIRECT_BIOME_BITS: u8 = 7;
+/// The client only accepts indirect biome palettes with up to this many bits per entry.
+const MAX_INDIRECT_BIOME_BITS: u8 = 3;
 
 #[derive(NetEncode)]
 pub struct BlockEntity {
@@ -127,21 +132,20 @@ impl ChunkAndLightData {
                 }
             }
 
-            // Empty biome data for now
-            raw_data.write_u8(0)?;
-            // Forest biome id
-            raw_data.write_u8(21)?;
+            write_biomes(&section.biome_states, &mut raw_data)?;
         }
-        let mut sky_light_mask = BitSet::new(SECTIONS + 2);
-        let mut block_light_mask = BitSet::new(SECTIONS + 2);
+        // The section count depends on the dimension the chunk is in
+        let mut sky_light_mask = BitSet::new(chunk.sections.len() + 2);
+        let mut block_light_mask = BitSet::new(chunk.sections.len() + 2);
 
-        // Populate masks based on light data
+        // Populate masks based on light data. Bit 0 is the section below the world, so each
+        // section's bit is one higher than its index.
         for (i, section) in chunk.sections.iter().enumerate() {
-            if !section.sky_light.is_empty() && section.sky_light.len() == 2048 {
-                sky_light_mask.set(i, true);
+            if section.sky_light.len() == 2048 {
+                sky_light_mask.set(i + 1, true);
             }
-            if !section.block_light.is_empty() && section.block_light.len() == 2048 {
-                block_light_mask.set(i, true);
+            if section.block_light.len() == 2048 {
+                block_light_mask.set(i + 1, true);
             }
         }
 
@@ -153,14 +157,14 @@ impl ChunkAndLightData {
         let sky_light_arrays = chunk
             .sections
             .iter()
-            .filter(|section| !section.sky_light.is_empty())
+            .filter(|section| section.sky_light.len() == 2048)
             .map(|section| ByteArray::new(section.sky_light.clone()))
             .collect();
 
         let block_light_arrays = chunk
             .sections
             .iter()
-            .filter(|section| !section.block_light.is_empty())
+            .filter(|section| section.block_light.len() == 2048)
             .map(|section| ByteArray::new(section.block_light.clone()))
             .collect();
         let heightmaps = vec![
@@ -189,3 +193,60 @@ impl ChunkAndLightData {
         })
     }
 }
+
+/// Writes a section's biomes as a paletted container.
+fn write_biomes(
+    biome_states: &BiomeStates,
+    raw_data: &mut Cursor<Vec<u8>>,
+) -> Result<(), NetError> {
+    match biome_states.bits_per_biome {
+        0 => {
+            raw_data.write_u8(0)?;
+            let biome = biome_states.palette.first().copied().unwrap_or_default();
+            biome.write(raw_data)?;
+        }
+        bits_per_biome @ 1..=MAX_INDIRECT_BIOME_BITS => {
+            raw_data.write_u8(bits_per_biome)?;
+            VarInt::new(biome_states.palette.len() as i32).write(raw_data)?;
+            for palette_entry in &biome_states.palette {
+                palette_entry.write(raw_data)?;
+            }
+            for data_entry in &biome_states.data {
+                raw_data.write_i64::<BigEndian>(*data_entry)?;
+            }
+        }
+        _ => {
+            // Too many biomes for an indirect palette, so the ids are sent as-is
+            raw_data.write_u8(DIRECT_BIOME_BITS)?;
+            let per_long = 64 / DIRECT_BIOME_BITS as usize;
+            let mut data = vec![0i64; BiomeStates::CELLS.div_ceil(per_long)];
+            for (index, id) in biome_states.to_ids()?.iter().enumerate() {
+                let offset = (index % per_long) * DIRECT_BIOME_BITS as usize;
+                data[index / per_long] |= (i64::from(*id)) << offset;
+            }
+            for data_entry in data {
+                raw_data.write_i64::<BigEndian>(data_entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_masks_skip_the_section_below_the_world() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        chunk.calculate_light().unwrap();
+        let packet = ChunkAndLightData::from_chunk(&chunk).unwrap();
+        let sections = chunk.sections.len();
+        assert!(!packet.sky_light_mask.get(0));
+        assert!(packet.sky_light_mask.get(1));
+        assert!(packet.sky_light_mask.get(sections));
+        assert!(!packet.sky_light_mask.get(sections + 1));
+        assert!(packet.empty_block_light_mask.get(0));
+        assert!(!packet.block_light_mask.get(0));
+    }
+}
@@ -1,6 +1,7 @@
 use ferrumc_config::server_config::get_global_config;
 use ferrumc_macros::{packet, NetEncode};
 use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_world::dimension::Dimension;
 
 #[derive(NetEncode)]
 #[packet(packet_id = "login", state = "play")]
@@ -30,22 +31,30 @@ pub struct LoginPlayPacket<'a> {
     pub enforces_secure_chat: bool,
 }
 
+/// The identifiers of every dimension that exists on the server.
+pub const DIMENSION_NAMES: &[&str] = &[
+    "minecraft:overworld",
+    "minecraft:the_nether",
+    "minecraft:the_end",
+];
+
 impl LoginPlayPacket<'_> {
-    pub fn new(conn_id: i32, gamemode: u8) -> Self {
+    /// `seed_hash` is the world's [hashed seed](ferrumc_world::metadata::WorldMetadata::hashed_seed).
+    pub fn new(conn_id: i32, gamemode: u8, dimension: Dimension, seed_hash: i64) -> Self {
         Self {
             entity_id: conn_id,
             is_hardcore: false,
-            dimension_length: VarInt::from(1),
-            dimension_names: &["minecraft:overworld"],
+            dimension_length: VarInt::from(DIMENSION_NAMES.len() as i32),
+            dimension_names: DIMENSION_NAMES,
             max_players: VarInt::from(get_global_config().max_players as i32),
             view_distance: VarInt::from(get_global_config().chunk_render_distance as i32),
             simulation_distance: VarInt::from(get_global_config().chunk_render_distance as i32),
             reduced_debug_info: false,
             enable_respawn_screen: true,
             do_limited_crafting: false,
-            dimension_type: VarInt::new(0),
-            dimension_name: "minecraft:overworld",
-            seed_hash: 0,
+            dimension_type: VarInt::new(dimension.dimension_type_id()),
+            dimension_name: dimension.identifier(),
+            seed_hash,
             gamemode,
             previous_gamemode: -1,
             is_debug: false,
@@ -54,7 +63,7 @@ impl LoginPlayPacket<'_> {
             death_dimension_name: None,
             death_location: None,
             portal_cooldown: VarInt::from(0),
-            sea_level: VarInt::from(63),
+            sea_level: VarInt::from(dimension.sea_level()),
             enforces_secure_chat: false,
         }
     }
@@ -0,0 +1,12 @@
+use ferrumc_macros::{packet, NetEncode};
+use ferrumc_net_codec::net_types::var_int::VarInt;
+
+/// Asks the client something on a plugin channel before it has logged in. Only proxies answer
+/// these, a vanilla client says it doesn't understand the channel.
+#[derive(NetEncode)]
+#[packet(packet_id = "custom_query", state = "login")]
+pub struct LoginPluginRequest {
+    pub message_id: VarInt,
+    pub channel: String,
+    pub data: Vec<u8>,
+}
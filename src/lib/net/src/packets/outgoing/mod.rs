@@ -8,9 +8,11 @@ pub mod game_event;
 pub mod keep_alive;
 pub mod login_disconnect;
 pub mod login_play;
+pub mod login_plugin_request;
 pub mod login_success;
 pub mod ping_response;
 pub mod registry_data;
+pub mod respawn;
 pub mod set_center_chunk;
 pub mod set_default_spawn_position;
 pub mod set_held_slot;
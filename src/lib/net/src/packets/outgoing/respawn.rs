@@ -0,0 +1,49 @@
+use ferrumc_macros::{packet, NetEncode};
+use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use ferrumc_world::dimension::Dimension;
+
+/// Keep the player's attributes (e.g. health) across the respawn.
+pub const KEEP_ATTRIBUTES: u8 = 0x01;
+/// Keep the player's metadata (e.g. potion effects) across the respawn.
+pub const KEEP_METADATA: u8 = 0x02;
+
+/// Sent to move a player into a different dimension, or to respawn them after death.
+#[derive(NetEncode)]
+#[packet(packet_id = "respawn", state = "play")]
+pub struct RespawnPacket<'a> {
+    pub dimension_type: VarInt,
+    pub dimension_name: &'a str,
+    pub seed_hash: i64,
+    pub gamemode: u8,
+    pub previous_gamemode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub has_death_location: bool,
+    pub death_dimension_name: Option<&'a str>,
+    pub death_location: Option<NetworkPosition>,
+    pub portal_cooldown: VarInt,
+    pub sea_level: VarInt,
+    pub data_kept: u8,
+}
+
+impl RespawnPacket<'_> {
+    /// Creates a packet that moves the player into `dimension`, keeping all of their data.
+    pub fn change_dimension(dimension: Dimension, gamemode: u8, seed_hash: i64) -> Self {
+        Self {
+            dimension_type: VarInt::new(dimension.dimension_type_id()),
+            dimension_name: dimension.identifier(),
+            seed_hash,
+            gamemode,
+            previous_gamemode: -1,
+            is_debug: false,
+            is_flat: false,
+            has_death_location: false,
+            death_dimension_name: None,
+            death_location: None,
+            portal_cooldown: VarInt::from(0),
+            sea_level: VarInt::from(dimension.sea_level()),
+            data_kept: KEEP_ATTRIBUTES | KEEP_METADATA,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use ferrumc_macros::{packet, NetEncode};
 use ferrumc_net_codec::net_types::network_position::NetworkPosition;
+use ferrumc_world::metadata::DEFAULT_SPAWN;
 
 #[derive(NetEncode)]
 #[packet(packet_id = "set_default_spawn_position", state = "play")]
@@ -8,7 +9,11 @@ pub struct SetDefaultSpawnPositionPacket {
     pub angle: f32,
 }
 
-pub const DEFAULT_SPAWN_POSITION: NetworkPosition = NetworkPosition { x: 0, y: 100, z: 0 };
+pub const DEFAULT_SPAWN_POSITION: NetworkPosition = NetworkPosition {
+    x: DEFAULT_SPAWN[0],
+    y: DEFAULT_SPAWN[1] as i16,
+    z: DEFAULT_SPAWN[2],
+};
 
 const DEFAULT_ANGLE: f32 = 0.0;
 
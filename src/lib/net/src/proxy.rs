@@ -0,0 +1,227 @@
+//! Reads the player information a proxy in front of the server forwards.
+//!
+//! Velocity's modern forwarding answers a login plugin request on [VELOCITY_CHANNEL] with the
+//! information signed by the forwarding secret, see [read_velocity_forwarding]. BungeeCord's
+//! legacy forwarding packs it into the server address of the handshake instead, see
+//! [read_bungeecord_forwarding].
+
+use crate::errors::{NetAuthenticationError, NetError};
+use ferrumc_core::identity::player_identity::PlayerProperty;
+use ferrumc_net_codec::decode::{NetDecode, NetDecodeOpts};
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use hmac::{Hmac, Mac};
+use serde_derive::Deserialize;
+use sha2::Sha256;
+use std::io::Cursor;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+/// The plugin channel Velocity forwards players on.
+pub(crate) const VELOCITY_CHANNEL: &str = "velocity:player_info";
+
+/// The version of Velocity's forwarding we ask for. Later versions only add the chat signing keys
+/// of clients older than 1.19.3.
+pub(crate) const VELOCITY_FORWARDING_VERSION: u8 = 1;
+
+/// The length of the HMAC-SHA256 signature in front of Velocity's forwarded information.
+const SIGNATURE_LENGTH: usize = 32;
+
+/// Who a proxy says a player is.
+#[derive(Debug)]
+pub(crate) struct ForwardedPlayer {
+    /// The address the player connected to the proxy from.
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    /// The player's name. BungeeCord doesn't forward it, so the name from the login start packet
+    /// is used instead.
+    pub username: Option<String>,
+    /// The player's skin and cape. Values stay base64-encoded, the way Mojang sends them.
+    pub properties: Vec<PlayerProperty>,
+}
+
+/// Verifies and reads the answer to a request on the [VELOCITY_CHANNEL].
+///
+/// The answer starts with an HMAC-SHA256 signature of the rest of it, made with the forwarding
+/// secret, so players can't pretend to be someone else by connecting to the server directly. An
+/// empty secret is refused, since anyone could sign with it.
+pub(crate) fn read_velocity_forwarding(
+    secret: &[u8],
+    data: &[u8],
+) -> Result<ForwardedPlayer, NetError> {
+    if secret.is_empty() {
+        return Err(NetAuthenticationError::MissingForwardingSecret.into());
+    }
+    if data.len() < SIGNATURE_LENGTH {
+        return Err(
+            NetAuthenticationError::MalformedForwarding("missing signature".to_string()).into(),
+        );
+    }
+    let (signature, payload) = data.split_at(SIGNATURE_LENGTH);
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret).expect("HMAC can take a key of any length");
+    mac.update(payload);
+    mac.verify_slice(signature)
+        .map_err(|_| NetAuthenticationError::ForwardingSignatureMismatch)?;
+
+    let mut payload = Cursor::new(payload);
+    let opts = NetDecodeOpts::None;
+    let version = VarInt::decode(&mut payload, &opts)?.0;
+    if version < i32::from(VELOCITY_FORWARDING_VERSION) {
+        return Err(NetAuthenticationError::MalformedForwarding(format!(
+            "unsupported forwarding version {version}"
+        ))
+        .into());
+    }
+    let address = String::decode(&mut payload, &opts)?.parse()?;
+    let uuid = Uuid::from_u128(u128::decode(&mut payload, &opts)?);
+    let username = String::decode(&mut payload, &opts)?;
+    let count = VarInt::decode(&mut payload, &opts)?.0;
+    let mut properties = Vec::new();
+    for _ in 0..count {
+        let name = String::decode(&mut payload, &opts)?;
+        let value = String::decode(&mut payload, &opts)?;
+        let signature = if bool::decode(&mut payload, &opts)? {
+            Some(String::decode(&mut payload, &opts)?)
+        } else {
+            None
+        };
+        properties.push(PlayerProperty {
+            name,
+            value,
+            signature,
+        });
+    }
+
+    Ok(ForwardedPlayer {
+        address,
+        uuid,
+        username: Some(username),
+        properties,
+    })
+}
+
+/// Reads the player BungeeCord forwarded in the server address of a handshake, which it writes
+/// as `host\0address\0uuid\0properties`, with the properties as JSON.
+///
+/// Nothing is signed, so this can't tell a real proxy from a player faking one. Only connections
+/// from `trusted_proxies` should be read with it.
+pub(crate) fn read_bungeecord_forwarding(
+    server_address: &str,
+) -> Result<ForwardedPlayer, NetError> {
+    let mut parts = server_address.split('\0').skip(1);
+    let (Some(address), Some(uuid)) = (parts.next(), parts.next()) else {
+        return Err(NetAuthenticationError::NotForwarded.into());
+    };
+    let uuid = Uuid::parse_str(uuid).map_err(|_| NetAuthenticationError::CorruptUuid)?;
+    let properties = match parts.next() {
+        Some(properties) => serde_json::from_str::<Vec<BungeeCordProperty>>(properties)
+            .map_err(|e| NetAuthenticationError::MalformedForwarding(e.to_string()))?
+            .into_iter()
+            .map(|property| PlayerProperty {
+                name: property.name,
+                value: property.value,
+                signature: property.signature,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(ForwardedPlayer {
+        address: address.parse()?,
+        uuid,
+        username: None,
+        properties,
+    })
+}
+
+#[derive(Deserialize)]
+struct BungeeCordProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_net_codec::encode::{NetEncode, NetEncodeOpts};
+
+    fn signed(secret: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(payload);
+        data
+    }
+
+    fn velocity_payload() -> Vec<u8> {
+        let mut payload = Vec::new();
+        let opts = NetEncodeOpts::None;
+        VarInt::new(1).encode(&mut payload, &opts).unwrap();
+        "203.0.113.7".encode(&mut payload, &opts).unwrap();
+        0x1234_u128.encode(&mut payload, &opts).unwrap();
+        "Notch".encode(&mut payload, &opts).unwrap();
+        VarInt::new(1).encode(&mut payload, &opts).unwrap();
+        "textures".encode(&mut payload, &opts).unwrap();
+        "dGV4dHVyZXM=".encode(&mut payload, &opts).unwrap();
+        true.encode(&mut payload, &opts).unwrap();
+        "c2lnbmF0dXJl".encode(&mut payload, &opts).unwrap();
+        payload
+    }
+
+    #[test]
+    fn test_velocity_forwarding() {
+        let data = signed(b"secret", &velocity_payload());
+        let player = read_velocity_forwarding(b"secret", &data).unwrap();
+        assert_eq!(player.address, "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(player.uuid, Uuid::from_u128(0x1234));
+        assert_eq!(player.username.as_deref(), Some("Notch"));
+        assert_eq!(player.properties.len(), 1);
+        assert_eq!(player.properties[0].value, "dGV4dHVyZXM=");
+        assert_eq!(
+            player.properties[0].signature.as_deref(),
+            Some("c2lnbmF0dXJl")
+        );
+    }
+
+    #[test]
+    fn test_velocity_forwarding_with_the_wrong_secret() {
+        let data = signed(b"not the secret", &velocity_payload());
+        assert!(matches!(
+            read_velocity_forwarding(b"secret", &data),
+            Err(NetError::AuthenticationError(
+                NetAuthenticationError::ForwardingSignatureMismatch
+            ))
+        ));
+        // Anyone can sign with an empty secret.
+        let data = signed(b"", &velocity_payload());
+        assert!(matches!(
+            read_velocity_forwarding(b"", &data),
+            Err(NetError::AuthenticationError(
+                NetAuthenticationError::MissingForwardingSecret
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_bungeecord_forwarding() {
+        let address = [
+            "play.example.com",
+            "2001:db8::1",
+            "069a79f444e94726a5befca90e38aaf5",
+            r#"[{"name":"textures","value":"dGV4dHVyZXM="}]"#,
+        ]
+        .join("\0");
+        let player = read_bungeecord_forwarding(&address).unwrap();
+        assert_eq!(player.address, "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            player.uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(player.username, None);
+        assert_eq!(player.properties[0].name, "textures");
+        assert_eq!(player.properties[0].signature, None);
+
+        assert!(read_bungeecord_forwarding("play.example.com").is_err());
+    }
+}
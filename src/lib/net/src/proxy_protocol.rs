@@ -0,0 +1,254 @@
+//! The PROXY protocol, which TCP load balancers like HAProxy use to pass on the address a
+//! connection really came from.
+//!
+//! With `proxy_protocol` on in the proxy config section, every connection has to start with a
+//! version 1 (text) or version 2 (binary) header before its handshake. Anyone who can connect
+//! could claim any address in one, so connections from sources that aren't in `trusted_proxies`
+//! are turned away. With `trusted_proxies` empty, every connection is.
+
+use crate::errors::NetError;
+use ferrumc_config::server_config::get_global_config;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// The first 12 bytes of a version 2 header.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// The longest a version 1 header can be, including its CRLF.
+const V1_MAX_LENGTH: usize = 107;
+
+/// How long a load balancer gets to send its header.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The address a connection really came from.
+///
+/// Without the PROXY protocol this is just the address of the other end of the socket. With it,
+/// the header is read off the stream and the address in it is used instead, unless the header
+/// doesn't carry one, like those of a load balancer's health checks.
+pub async fn client_address(stream: &mut TcpStream) -> Result<SocketAddr, NetError> {
+    let peer = stream.peer_addr()?;
+    let config = &get_global_config().proxy;
+    if !config.proxy_protocol {
+        return Ok(peer);
+    }
+    if !is_trusted(peer.ip(), &config.trusted_proxies) {
+        return Err(NetError::UntrustedProxy(peer.ip()));
+    }
+
+    let source = timeout(HEADER_TIMEOUT, read_header(stream))
+        .await
+        .map_err(|_| NetError::HandshakeTimeout)??;
+    Ok(source.unwrap_or(peer))
+}
+
+/// Reads a version 1 or version 2 header, and returns the source address in it.
+pub(crate) async fn read_header<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<SocketAddr>, NetError> {
+    // Every header is at least this long, so this never reads past the end of one.
+    let mut start = [0; V2_SIGNATURE.len()];
+    reader.read_exact(&mut start).await?;
+    if start == V2_SIGNATURE {
+        read_v2(reader).await
+    } else if start.starts_with(b"PROXY ") {
+        read_v1(reader, &start).await
+    } else {
+        Err(invalid("missing header"))
+    }
+}
+
+/// Reads the rest of a version 1 header, like `PROXY TCP4 203.0.113.7 10.0.0.1 51234 25565\r\n`.
+async fn read_v1<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    start: &[u8],
+) -> Result<Option<SocketAddr>, NetError> {
+    let mut line = start.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(invalid("version 1 header is too long"));
+        }
+        line.push(reader.read_u8().await?);
+    }
+    let line = std::str::from_utf8(&line[..line.len() - 2])
+        .map_err(|_| invalid("version 1 header isn't text"))?;
+
+    match line.split(' ').collect::<Vec<_>>().as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", source, _, source_port, _] => {
+            let port = source_port
+                .parse()
+                .map_err(|_| invalid("invalid source port"))?;
+            Ok(Some(SocketAddr::new(source.parse()?, port)))
+        }
+        _ => Err(invalid("malformed version 1 header")),
+    }
+}
+
+/// Reads the rest of a version 2 header, after its signature.
+async fn read_v2<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>, NetError> {
+    let version_command = reader.read_u8().await?;
+    let family = reader.read_u8().await?;
+    let length = reader.read_u16().await?;
+    let mut addresses = vec![0; usize::from(length)];
+    reader.read_exact(&mut addresses).await?;
+
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported version"));
+    }
+    match version_command & 0x0F {
+        // LOCAL: the load balancer connected by itself, e.g. for a health check
+        0x0 => return Ok(None),
+        // PROXY
+        0x1 => {}
+        _ => return Err(invalid("unsupported command")),
+    }
+
+    // The addresses are the source and then the destination, followed by their ports. Anything
+    // after those is extra information this doesn't need.
+    match family >> 4 {
+        // AF_INET
+        0x1 => {
+            let Some(addresses) = addresses.get(..12) else {
+                return Err(invalid("IPv4 addresses are cut short"));
+            };
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&addresses[..4]).unwrap());
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        // AF_INET6
+        0x2 => {
+            let Some(addresses) = addresses.get(..36) else {
+                return Err(invalid("IPv6 addresses are cut short"));
+            };
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&addresses[..16]).unwrap());
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(ip), port)))
+        }
+        // AF_UNSPEC and AF_UNIX don't have an address that's any use
+        _ => Ok(None),
+    }
+}
+
+fn invalid(problem: &str) -> NetError {
+    NetError::InvalidProxyHeader(problem.to_string())
+}
+
+/// Whether `ip` is one of the `trusted` sources, which are single addresses or CIDR ranges like
+/// `10.0.0.0/8`. Entries that can't be read don't match anything.
+pub(crate) fn is_trusted(ip: IpAddr, trusted: &[String]) -> bool {
+    // A socket listening on IPv6 sees IPv4 clients as IPv4-mapped IPv6 addresses
+    let ip = ip.to_canonical();
+    trusted
+        .iter()
+        .filter_map(|entry| parse_range(entry))
+        .any(|(network, prefix)| match (ip, network) {
+            (IpAddr::V4(ip), IpAddr::V4(network)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(ip) & mask == u32::from(network) & mask
+            }
+            (IpAddr::V6(ip), IpAddr::V6(network)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(ip) & mask == u128::from(network) & mask
+            }
+            _ => false,
+        })
+}
+
+/// Reads an entry of `trusted_proxies` into a network address and the length of its prefix. A
+/// single address is a range with a full-length prefix.
+pub(crate) fn parse_range(entry: &str) -> Option<(IpAddr, u32)> {
+    let (address, prefix) = match entry.trim().split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (entry.trim(), None),
+    };
+    let address: IpAddr = address.parse().ok()?;
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        Some(prefix) => prefix.parse().ok().filter(|prefix| *prefix <= bits)?,
+        None => bits,
+    };
+    Some((address, prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read(mut header: &[u8]) -> Result<Option<SocketAddr>, NetError> {
+        read_header(&mut header).await
+    }
+
+    #[tokio::test]
+    async fn test_v1_headers() {
+        assert_eq!(
+            read(b"PROXY TCP4 203.0.113.7 10.0.0.1 51234 25565\r\n")
+                .await
+                .unwrap(),
+            Some("203.0.113.7:51234".parse().unwrap())
+        );
+        assert_eq!(
+            read(b"PROXY TCP6 2001:db8::1 2001:db8::2 51234 25565\r\n")
+                .await
+                .unwrap(),
+            Some("[2001:db8::1]:51234".parse().unwrap())
+        );
+        assert_eq!(read(b"PROXY UNKNOWN\r\n").await.unwrap(), None);
+        assert!(read(b"PROXY TCP4 not.an.address 10.0.0.1 1 2\r\n")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_v2_headers() {
+        let mut header = V2_SIGNATURE.to_vec();
+        // PROXY over TCP/IPv4, with 12 bytes of addresses and a 4 byte TLV after them
+        header.extend_from_slice(&[0x21, 0x11, 0, 16]);
+        header.extend_from_slice(&[203, 0, 113, 7, 10, 0, 0, 1]);
+        header.extend_from_slice(&51234_u16.to_be_bytes());
+        header.extend_from_slice(&25565_u16.to_be_bytes());
+        header.extend_from_slice(&[0x04, 0, 1, 0]);
+        // The handshake that follows the header
+        header.push(0x10);
+
+        let mut reader = header.as_slice();
+        assert_eq!(
+            read_header(&mut reader).await.unwrap(),
+            Some("203.0.113.7:51234".parse().unwrap())
+        );
+        assert_eq!(reader, [0x10]);
+
+        let mut local = V2_SIGNATURE.to_vec();
+        local.extend_from_slice(&[0x20, 0x00, 0, 0]);
+        assert_eq!(read(&local).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_missing_header() {
+        // A handshake without a header in front of it
+        assert!(read(b"\x10\x00\xF8\x05\x09localhost").await.is_err());
+    }
+
+    #[test]
+    fn test_trusted_sources() {
+        let trusted = vec![
+            "10.0.0.0/8".to_string(),
+            "192.168.1.5".to_string(),
+            "2001:db8::/32".to_string(),
+            "not an address".to_string(),
+        ];
+        assert!(is_trusted("10.1.2.3".parse().unwrap(), &trusted));
+        assert!(is_trusted("::ffff:10.1.2.3".parse().unwrap(), &trusted));
+        assert!(is_trusted("192.168.1.5".parse().unwrap(), &trusted));
+        assert!(!is_trusted("192.168.1.6".parse().unwrap(), &trusted));
+        assert!(is_trusted("2001:db8:1::1".parse().unwrap(), &trusted));
+        assert!(!is_trusted("2001:db9::1".parse().unwrap(), &trusted));
+        assert!(is_trusted(
+            "203.0.113.7".parse().unwrap(),
+            &["0.0.0.0/0".to_string()]
+        ));
+        assert_eq!(parse_range("10.0.0.0/33"), None);
+    }
+}
@@ -1,10 +1,16 @@
 use crate::errors::NetError;
+use crate::proxy_protocol::parse_range;
 use ferrumc_config::server_config::get_global_config;
+use ferrumc_config::{ProxyConfig, ProxyMode};
 use tokio::net::TcpListener;
-use tracing::{debug, error};
+use tracing::{debug, error, info, warn};
 
 pub async fn create_server_listener() -> Result<TcpListener, NetError> {
     let config = get_global_config();
+    if let Err(e) = check_proxy_config(&config.proxy) {
+        error!("{}", e);
+        return Err(e);
+    }
     let server_addy = format!("{}:{}", config.host, config.port);
     let server_addy = server_addy.as_str();
 
@@ -20,5 +26,43 @@ pub async fn create_server_listener() -> Result<TcpListener, NetError> {
 
     debug!("Server listening on {}", server_addy);
 
+    let proxy = &config.proxy;
+    if proxy.mode == ProxyMode::BungeeCord {
+        warn!(
+            "BungeeCord forwarding can't be verified, so it's only accepted from {}. Make sure a \
+             firewall keeps everyone but the proxy away from port {}",
+            proxy.trusted_proxies.join(", "),
+            config.port
+        );
+    }
+    if proxy.proxy_protocol {
+        info!("Expecting a PROXY protocol header on every connection");
+    }
+    for entry in &proxy.trusted_proxies {
+        if parse_range(entry).is_none() {
+            error!("Ignoring invalid trusted proxy '{}'", entry);
+        }
+    }
+
     Ok(listener?)
 }
+
+/// Refuses proxy settings that would let players claim to be someone else.
+fn check_proxy_config(proxy: &ProxyConfig) -> Result<(), NetError> {
+    match proxy.mode {
+        ProxyMode::Velocity if proxy.velocity_secret.is_empty() => Err(
+            NetError::InvalidProxyConfig("velocity mode needs a velocity_secret".to_string()),
+        ),
+        ProxyMode::BungeeCord if proxy.trusted_proxies.is_empty() => {
+            Err(NetError::InvalidProxyConfig(
+                "bungeecord mode needs the proxy's address in trusted_proxies".to_string(),
+            ))
+        }
+        _ if proxy.proxy_protocol && proxy.trusted_proxies.is_empty() => {
+            Err(NetError::InvalidProxyConfig(
+                "proxy_protocol needs the load balancer's address in trusted_proxies".to_string(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
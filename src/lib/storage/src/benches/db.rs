@@ -1,4 +1,5 @@
 use ferrumc_storage::lmdb::LmdbBackend;
+use ferrumc_storage::StorageBackend;
 use rand::Rng;
 use std::collections::HashSet;
 
@@ -4,6 +4,7 @@ use crate::compressors::gzip::{compress_gzip, decompress_gzip};
 use crate::compressors::zlib::{compress_zlib, decompress_zlib};
 use crate::compressors::zstd::{compress_zstd, decompress_zstd};
 use crate::errors::StorageError;
+use ferrumc_config::DatabaseCompression;
 
 pub mod brotli;
 pub mod deflate;
@@ -11,7 +12,7 @@ pub mod gzip;
 pub mod zlib;
 pub mod zstd;
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressorType {
     Gzip,
     Zstd,
@@ -20,7 +21,54 @@ pub enum CompressorType {
     Zlib,
 }
 
-#[derive(Clone, Copy)]
+impl CompressorType {
+    pub const ALL: [CompressorType; 5] = [
+        CompressorType::Gzip,
+        CompressorType::Zstd,
+        CompressorType::Brotli,
+        CompressorType::Deflate,
+        CompressorType::Zlib,
+    ];
+
+    /// The tag stored next to compressed data so it can be decompressed with the right algorithm
+    /// later on. These are persisted, so they must never change.
+    pub fn id(&self) -> u8 {
+        match self {
+            CompressorType::Gzip => 0,
+            CompressorType::Zstd => 1,
+            CompressorType::Brotli => 2,
+            CompressorType::Deflate => 3,
+            CompressorType::Zlib => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|algorithm| algorithm.id() == id)
+    }
+
+    /// The highest compression level the algorithm accepts.
+    pub fn max_level(&self) -> u32 {
+        match self {
+            CompressorType::Gzip | CompressorType::Deflate | CompressorType::Zlib => 9,
+            CompressorType::Zstd => 22,
+            CompressorType::Brotli => 11,
+        }
+    }
+}
+
+impl From<DatabaseCompression> for CompressorType {
+    fn from(compression: DatabaseCompression) -> Self {
+        match compression {
+            DatabaseCompression::Gzip => CompressorType::Gzip,
+            DatabaseCompression::Zstd => CompressorType::Zstd,
+            DatabaseCompression::Brotli => CompressorType::Brotli,
+            DatabaseCompression::Deflate => CompressorType::Deflate,
+            DatabaseCompression::Zlib => CompressorType::Zlib,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Compressor {
     pub algorithm: CompressorType,
     pub level: u32,
@@ -51,3 +99,26 @@ impl Compressor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_round_trip() {
+        for algorithm in CompressorType::ALL {
+            assert_eq!(CompressorType::from_id(algorithm.id()), Some(algorithm));
+        }
+        assert_eq!(CompressorType::from_id(u8::MAX), None);
+    }
+
+    #[test]
+    fn test_every_algorithm_round_trips() {
+        let data = b"FerrumC FerrumC FerrumC FerrumC FerrumC FerrumC".repeat(32);
+        for algorithm in CompressorType::ALL {
+            let compressor = Compressor::create(algorithm, algorithm.max_level());
+            let compressed = compressor.compress(&data).unwrap();
+            assert_eq!(compressor.decompress(&compressed).unwrap(), data);
+        }
+    }
+}
@@ -35,6 +35,8 @@ pub enum StorageError {
     GenericIoError(io::Error),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Failed to back up database: {0}")]
+    BackupError(String),
 }
 
 impl From<io::Error> for StorageError {
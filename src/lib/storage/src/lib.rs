@@ -1,2 +1,81 @@
+pub mod compressors;
 pub mod errors;
 pub mod lmdb;
+pub mod memory;
+
+use crate::errors::StorageError;
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// A key-value store that world data can be persisted to.
+///
+/// Keys are `u128`s grouped into named tables. Every method takes `&self`, so implementations are
+/// expected to handle their own locking and be cheap to share between threads.
+pub trait StorageBackend: Debug + Send + Sync {
+    /// Inserts a new value, failing with [StorageError::KeyExists] if the key is already present.
+    /// The table is created if it doesn't exist yet.
+    fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError>;
+
+    /// Gets the value stored under `key`, or `None` if there isn't one.
+    fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Deletes a value, failing with [StorageError::KeyNotFound] if the key isn't present.
+    fn delete(&self, table: String, key: u128) -> Result<(), StorageError>;
+
+    /// Replaces an existing value, failing with [StorageError::KeyNotFound] if the key isn't
+    /// present.
+    fn update(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError>;
+
+    /// Inserts or replaces a value.
+    fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError>;
+
+    /// Inserts or replaces several values at once. The table is created if it doesn't exist yet.
+    fn batch_upsert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError>;
+
+    /// Whether a value is stored under `key`.
+    fn exists(&self, table: String, key: u128) -> Result<bool, StorageError>;
+
+    /// Whether the table has been created.
+    fn table_exists(&self, table: String) -> Result<bool, StorageError>;
+
+    /// A human-readable description of the backend, for logging.
+    fn details(&self) -> String;
+
+    /// Inserts several new values at once, failing if any of the keys are already present. The
+    /// table is created if it doesn't exist yet.
+    fn batch_insert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError>;
+
+    /// Gets several values at once. The results are in the same order as `keys`.
+    fn batch_get(
+        &self,
+        table: String,
+        keys: Vec<u128>,
+    ) -> Result<Vec<Option<Vec<u8>>>, StorageError>;
+
+    /// Returns every key in the table, in ascending order.
+    fn get_all_keys(&self, table: String) -> Result<Vec<u128>, StorageError>;
+
+    /// Gets up to `limit` entries with keys in `range`, in ascending order of key, or descending
+    /// if `reverse` is set.
+    fn get_range(
+        &self,
+        table: String,
+        range: RangeInclusive<u128>,
+        limit: usize,
+        reverse: bool,
+    ) -> Result<Vec<(u128, Vec<u8>)>, StorageError>;
+
+    /// Makes sure everything written so far is durable.
+    fn flush(&self) -> Result<(), StorageError>;
+
+    /// Creates an empty table. Does nothing if the table already exists.
+    fn create_table(&self, table: String) -> Result<(), StorageError>;
+
+    /// Flushes and releases the backend.
+    fn close(&self) -> Result<(), StorageError>;
+
+    /// Writes a consistent copy of the whole database into the `destination` directory. Other
+    /// threads may keep reading and writing while the copy is taken.
+    fn backup(&self, destination: &Path) -> Result<(), StorageError>;
+}
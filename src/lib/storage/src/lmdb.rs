@@ -1,11 +1,13 @@
 use crate::errors::StorageError;
+use crate::StorageBackend;
 use heed;
 use heed::byteorder::BigEndian;
 use heed::types::{Bytes, U128};
-use heed::{Database, Env, EnvOpenOptions, WithoutTls};
+use heed::{CompactionOption, Database, Env, EnvOpenOptions, WithoutTls};
 use parking_lot::Mutex;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -50,7 +52,7 @@ impl LmdbBackend {
                     EnvOpenOptions::new()
                         .read_txn_without_tls()
                         // Change this as more tables are needed.
-                        .max_dbs(2)
+                        .max_dbs(4)
                         .map_size(rounded_map_size)
                         .open(checked_path)
                         .map_err(|e| StorageError::DatabaseInitError(e.to_string()))?,
@@ -59,8 +61,10 @@ impl LmdbBackend {
             Ok(backend)
         }
     }
+}
 
-    pub fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+impl StorageBackend for LmdbBackend {
+    fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> =
@@ -73,7 +77,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
+    fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
         let env = self.env.lock();
         let ro_txn = env.read_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -87,7 +91,7 @@ impl LmdbBackend {
         }
     }
 
-    pub fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
+    fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -101,7 +105,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn update(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+    fn update(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -115,7 +119,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
+    fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -126,11 +130,7 @@ impl LmdbBackend {
         Ok(true)
     }
 
-    pub fn batch_upsert(
-        &self,
-        table: String,
-        data: Vec<(u128, Vec<u8>)>,
-    ) -> Result<(), StorageError> {
+    fn batch_upsert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
 
@@ -161,7 +161,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
+    fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
         let env = self.env.lock();
         let ro_txn = env.read_txn()?;
         let db: Database<U128<BigEndian>, Bytes> = env
@@ -170,22 +170,18 @@ impl LmdbBackend {
         Ok(db.get(&ro_txn, &key)?.is_some())
     }
 
-    pub fn table_exists(&self, table: String) -> Result<bool, StorageError> {
+    fn table_exists(&self, table: String) -> Result<bool, StorageError> {
         let env = self.env.lock();
         let ro_txn = env.read_txn()?;
         let db = env.open_database::<U128<BigEndian>, Bytes>(&ro_txn, Some(&table))?;
         Ok(db.is_some())
     }
 
-    pub fn details(&self) -> String {
+    fn details(&self) -> String {
         format!("LMDB (heed 0.20.5): {:?}", self.env.lock().info())
     }
 
-    pub fn batch_insert(
-        &self,
-        table: String,
-        data: Vec<(u128, Vec<u8>)>,
-    ) -> Result<(), StorageError> {
+    fn batch_insert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         let db = env.create_database::<U128<BigEndian>, Bytes>(&mut rw_txn, Some(&table))?;
@@ -204,7 +200,7 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn batch_get(
+    fn batch_get(
         &self,
         table: String,
         keys: Vec<u128>,
@@ -226,14 +222,53 @@ impl LmdbBackend {
         Ok(values)
     }
 
-    pub fn flush(&self) -> Result<(), StorageError> {
+    fn get_all_keys(&self, table: String) -> Result<Vec<u128>, StorageError> {
+        let env = self.env.lock();
+        let ro_txn = env.read_txn()?;
+        let db: Database<U128<BigEndian>, Bytes> = env
+            .open_database(&ro_txn, Some(&table))?
+            .ok_or(StorageError::TableError("Table not found".to_string()))?;
+        let mut keys = Vec::new();
+        for entry in db.iter(&ro_txn)? {
+            let (key, _) = entry?;
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    fn get_range(
+        &self,
+        table: String,
+        range: RangeInclusive<u128>,
+        limit: usize,
+        reverse: bool,
+    ) -> Result<Vec<(u128, Vec<u8>)>, StorageError> {
+        let env = self.env.lock();
+        let ro_txn = env.read_txn()?;
+        let db: Database<U128<BigEndian>, Bytes> = env
+            .open_database(&ro_txn, Some(&table))?
+            .ok_or(StorageError::TableError("Table not found".to_string()))?;
+        let entries: Box<dyn Iterator<Item = heed::Result<(u128, &[u8])>>> = if reverse {
+            Box::new(db.rev_range(&ro_txn, &range)?)
+        } else {
+            Box::new(db.range(&ro_txn, &range)?)
+        };
+        let mut values = Vec::new();
+        for entry in entries.take(limit) {
+            let (key, value) = entry?;
+            values.push((key, value.to_vec()));
+        }
+        Ok(values)
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
         let env = self.env.lock();
         env.clear_stale_readers()?;
         env.force_sync()?;
         Ok(())
     }
 
-    pub fn create_table(&self, table: String) -> Result<(), StorageError> {
+    fn create_table(&self, table: String) -> Result<(), StorageError> {
         let env = self.env.lock();
         let mut rw_txn = env.write_txn()?;
         env.create_database::<U128<BigEndian>, Bytes>(&mut rw_txn, Some(&table))?;
@@ -241,10 +276,21 @@ impl LmdbBackend {
         Ok(())
     }
 
-    pub fn close(&self) -> Result<(), StorageError> {
+    fn close(&self) -> Result<(), StorageError> {
         self.flush()?;
         Ok(())
     }
+
+    fn backup(&self, destination: &Path) -> Result<(), StorageError> {
+        // Cloning the environment lets the copy run without holding the lock. LMDB copies from
+        // inside its own read transaction, so the copy is consistent even while chunks are being
+        // written.
+        let env = self.env.lock().clone();
+        std::fs::create_dir_all(destination)?;
+        env.copy_to_path(destination.join("data.mdb"), CompactionOption::Enabled)
+            .map_err(|e| StorageError::BackupError(e.to_string()))?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -299,6 +345,43 @@ mod tests {
         remove_dir_all(path).unwrap();
     }
 
+    #[test]
+    fn test_get_all_keys() {
+        let path = tempdir().unwrap().keep();
+        {
+            let backend = LmdbBackend::initialize(Some(path.clone())).unwrap();
+            backend.create_table("test_table".to_string()).unwrap();
+            for key in [3u128, 1, 2] {
+                backend
+                    .insert("test_table".to_string(), key, vec![key as u8])
+                    .unwrap();
+            }
+            let keys = backend.get_all_keys("test_table".to_string()).unwrap();
+            assert_eq!(keys, vec![1, 2, 3]);
+        }
+        remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_backup() {
+        let path = tempdir().unwrap().keep();
+        let backup_path = tempdir().unwrap().keep();
+        {
+            let backend = LmdbBackend::initialize(Some(path.clone())).unwrap();
+            backend
+                .insert("test_table".to_string(), 1, vec![1, 2, 3])
+                .unwrap();
+            backend.backup(&backup_path).unwrap();
+            let restored = LmdbBackend::initialize(Some(backup_path.clone())).unwrap();
+            assert_eq!(
+                restored.get("test_table".to_string(), 1).unwrap(),
+                Some(vec![1, 2, 3])
+            );
+        }
+        remove_dir_all(path).unwrap();
+        remove_dir_all(backup_path).unwrap();
+    }
+
     #[test]
     fn test_concurrent_write() {
         let path = tempdir().unwrap().keep();
@@ -0,0 +1,257 @@
+use crate::errors::StorageError;
+use crate::StorageBackend;
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+
+type Table = BTreeMap<u128, Vec<u8>>;
+
+/// A storage backend that keeps everything in memory.
+///
+/// Nothing is ever written to disk, so all data is lost once the last clone of the backend is
+/// dropped. Useful for tests and for throwaway worlds like minigame arenas. Errors mirror
+/// [LmdbBackend](crate::lmdb::LmdbBackend) so the two can be swapped without changing behaviour.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    tables: Arc<RwLock<HashMap<String, Table>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn table_not_found() -> StorageError {
+    StorageError::TableError("Table not found".to_string())
+}
+
+impl StorageBackend for MemoryBackend {
+    fn insert(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.entry(table).or_default();
+        if table.contains_key(&key) {
+            return Err(StorageError::KeyExists(key as u64));
+        }
+        table.insert(key, value);
+        Ok(())
+    }
+
+    fn get(&self, table: String, key: u128) -> Result<Option<Vec<u8>>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(table.get(&key).cloned())
+    }
+
+    fn delete(&self, table: String, key: u128) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.get_mut(&table).ok_or_else(table_not_found)?;
+        table
+            .remove(&key)
+            .map(|_| ())
+            .ok_or(StorageError::KeyNotFound(key as u64))
+    }
+
+    fn update(&self, table: String, key: u128, value: Vec<u8>) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.get_mut(&table).ok_or_else(table_not_found)?;
+        let Some(existing) = table.get_mut(&key) else {
+            return Err(StorageError::KeyNotFound(key as u64));
+        };
+        *existing = value;
+        Ok(())
+    }
+
+    fn upsert(&self, table: String, key: u128, value: Vec<u8>) -> Result<bool, StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.get_mut(&table).ok_or_else(table_not_found)?;
+        table.insert(key, value);
+        Ok(true)
+    }
+
+    fn batch_upsert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        tables.entry(table).or_default().extend(data);
+        Ok(())
+    }
+
+    fn exists(&self, table: String, key: u128) -> Result<bool, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(table.contains_key(&key))
+    }
+
+    fn table_exists(&self, table: String) -> Result<bool, StorageError> {
+        Ok(self.tables.read().contains_key(&table))
+    }
+
+    fn details(&self) -> String {
+        let tables = self.tables.read();
+        let entries: usize = tables.values().map(|table| table.len()).sum();
+        format!("In-memory: {} tables, {} entries", tables.len(), entries)
+    }
+
+    fn batch_insert(&self, table: String, data: Vec<(u128, Vec<u8>)>) -> Result<(), StorageError> {
+        let mut tables = self.tables.write();
+        let table = tables.entry(table).or_default();
+        // Check everything up front so a failed batch doesn't leave half its data behind, the
+        // same as an aborted LMDB transaction.
+        if let Some((key, _)) = data.iter().find(|(key, _)| table.contains_key(key)) {
+            return Err(StorageError::KeyExists(*key as u64));
+        }
+        table.extend(data);
+        Ok(())
+    }
+
+    fn batch_get(
+        &self,
+        table: String,
+        keys: Vec<u128>,
+    ) -> Result<Vec<Option<Vec<u8>>>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(keys.iter().map(|key| table.get(key).cloned()).collect())
+    }
+
+    fn get_all_keys(&self, table: String) -> Result<Vec<u128>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        Ok(table.keys().copied().collect())
+    }
+
+    fn get_range(
+        &self,
+        table: String,
+        range: RangeInclusive<u128>,
+        limit: usize,
+        reverse: bool,
+    ) -> Result<Vec<(u128, Vec<u8>)>, StorageError> {
+        let tables = self.tables.read();
+        let table = tables.get(&table).ok_or_else(table_not_found)?;
+        let entries = table.range(range).map(|(key, value)| (*key, value.clone()));
+        Ok(if reverse {
+            entries.rev().take(limit).collect()
+        } else {
+            entries.take(limit).collect()
+        })
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn create_table(&self, table: String) -> Result<(), StorageError> {
+        self.tables.write().entry(table).or_default();
+        Ok(())
+    }
+
+    fn close(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    fn backup(&self, _destination: &Path) -> Result<(), StorageError> {
+        Err(StorageError::BackupError(
+            "the in-memory backend has nothing on disk to back up".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let backend = MemoryBackend::new();
+        backend.create_table("test_table".to_string()).unwrap();
+        let value = vec![1, 2, 3, 4, 5];
+        backend
+            .insert("test_table".to_string(), 42, value.clone())
+            .unwrap();
+        assert_eq!(
+            backend.get("test_table".to_string(), 42).unwrap(),
+            Some(value)
+        );
+        assert!(matches!(
+            backend.insert("test_table".to_string(), 42, vec![]),
+            Err(StorageError::KeyExists(42))
+        ));
+    }
+
+    #[test]
+    fn test_missing_table() {
+        let backend = MemoryBackend::new();
+        assert!(!backend.table_exists("test_table".to_string()).unwrap());
+        assert!(backend.get("test_table".to_string(), 1).is_err());
+        assert!(backend.upsert("test_table".to_string(), 1, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let backend = MemoryBackend::new();
+        backend.create_table("test_table".to_string()).unwrap();
+        assert!(backend
+            .update("test_table".to_string(), 1, vec![1])
+            .is_err());
+        backend
+            .upsert("test_table".to_string(), 1, vec![1])
+            .unwrap();
+        backend
+            .update("test_table".to_string(), 1, vec![2])
+            .unwrap();
+        assert_eq!(
+            backend.get("test_table".to_string(), 1).unwrap(),
+            Some(vec![2])
+        );
+        backend.delete("test_table".to_string(), 1).unwrap();
+        assert!(!backend.exists("test_table".to_string(), 1).unwrap());
+        assert!(backend.delete("test_table".to_string(), 1).is_err());
+    }
+
+    #[test]
+    fn test_batch_operations() {
+        let backend = MemoryBackend::new();
+        backend
+            .batch_insert("test_table".to_string(), vec![(3, vec![3]), (1, vec![1])])
+            .unwrap();
+        // A conflicting batch is rejected as a whole.
+        assert!(backend
+            .batch_insert("test_table".to_string(), vec![(2, vec![2]), (3, vec![3])])
+            .is_err());
+        backend
+            .batch_upsert("test_table".to_string(), vec![(3, vec![30]), (4, vec![4])])
+            .unwrap();
+        assert_eq!(
+            backend.get_all_keys("test_table".to_string()).unwrap(),
+            vec![1, 3, 4]
+        );
+        assert_eq!(
+            backend
+                .batch_get("test_table".to_string(), vec![4, 2, 3])
+                .unwrap(),
+            vec![Some(vec![4]), None, Some(vec![30])]
+        );
+        assert_eq!(
+            backend
+                .get_range("test_table".to_string(), 2..=4, 1, true)
+                .unwrap(),
+            vec![(4, vec![4])]
+        );
+        assert_eq!(
+            backend
+                .get_range("test_table".to_string(), 0..=3, 10, false)
+                .unwrap(),
+            vec![(1, vec![1]), (3, vec![30])]
+        );
+    }
+
+    #[test]
+    fn test_clones_share_data() {
+        let backend = MemoryBackend::new();
+        let clone = backend.clone();
+        clone.insert("test_table".to_string(), 1, vec![1]).unwrap();
+        assert!(backend.exists("test_table".to_string(), 1).unwrap());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::errors::WorldError;
+use crate::World;
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_general_purpose::paths::get_root_path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+static BACKUP_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Clears [BACKUP_IN_PROGRESS] when the backup finishes, even if it panicked.
+struct BackupGuard;
+
+impl Drop for BackupGuard {
+    fn drop(&mut self) {
+        BACKUP_IN_PROGRESS.store(false, Ordering::Release);
+    }
+}
+
+impl World {
+    /// Takes a backup of the world without stopping the server, using the backup path and
+    /// retention policy from the config.
+    ///
+    /// See [World::backup_to] for how the backup is taken.
+    pub fn backup(&self) -> Result<PathBuf, WorldError> {
+        let config = &get_global_config().backups;
+        let mut backup_root = PathBuf::from(&config.path);
+        if backup_root.is_relative() {
+            backup_root = get_root_path().join(backup_root);
+        }
+        self.backup_to(&backup_root, config.retention)
+    }
+
+    /// Takes a backup of the world into a new timestamped folder inside `backup_root`.
+    ///
+    /// Every cached chunk is synced first so the backup has the latest changes, then the storage
+    /// backend writes a consistent copy of itself. Afterwards only the newest `retention` backups
+    /// are kept, or all of them if `retention` is 0. Only one backup can run at a time.
+    ///
+    /// Returns the folder the backup was written to.
+    pub fn backup_to(&self, backup_root: &Path, retention: usize) -> Result<PathBuf, WorldError> {
+        if BACKUP_IN_PROGRESS.swap(true, Ordering::AcqRel) {
+            return Err(WorldError::BackupInProgress);
+        }
+        let _guard = BackupGuard;
+
+        let start = std::time::Instant::now();
+        self.sync()?;
+
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let name = backup_folder_name(seconds);
+        let mut destination = backup_root.join(&name);
+        let mut attempt = 1;
+        while destination.exists() {
+            destination = backup_root.join(format!("{name}-{attempt}"));
+            attempt += 1;
+        }
+
+        self.storage_backend.backup(&destination)?;
+        info!(
+            "Backed up world to {} in {:?}",
+            destination.display(),
+            start.elapsed()
+        );
+
+        if retention > 0 {
+            prune_backups(backup_root, retention)?;
+        }
+        Ok(destination)
+    }
+}
+
+/// Formats a UNIX timestamp as a UTC `YYYY-MM-DD_HH-MM-SS` folder name, which sorts in the same
+/// order as the backups were taken.
+fn backup_folder_name(unix_seconds: u64) -> String {
+    let days = (unix_seconds / 86_400) as i64;
+    let seconds_of_day = unix_seconds % 86_400;
+
+    // Converts days since the epoch into a civil date. See
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Whether a folder name looks like one made by [backup_folder_name], so that pruning never
+/// touches anything else that happens to be in the backup folder.
+fn is_backup_folder_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() >= 19
+        && bytes[..19]
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| match index {
+                4 | 7 | 13 | 16 => *byte == b'-',
+                10 => *byte == b'_',
+                _ => byte.is_ascii_digit(),
+            })
+}
+
+/// Deletes the oldest backups in `backup_root` until only `retention` are left. Returns how many
+/// were deleted.
+fn prune_backups(backup_root: &Path, retention: usize) -> Result<usize, WorldError> {
+    let mut backups = Vec::new();
+    for entry in backup_root.read_dir()? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && is_backup_folder_name(&name) {
+            backups.push(name);
+        }
+    }
+    if backups.len() <= retention {
+        return Ok(0);
+    }
+    backups.sort();
+
+    let to_remove = backups.len() - retention;
+    for name in &backups[..to_remove] {
+        if let Err(e) = std::fs::remove_dir_all(backup_root.join(name)) {
+            warn!("Failed to delete old backup {}: {}", name, e);
+        } else {
+            info!("Deleted old backup {}", name);
+        }
+    }
+    Ok(to_remove)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_backup_folder_names() {
+        assert_eq!(backup_folder_name(0), "1970-01-01_00-00-00");
+        assert_eq!(
+            backup_folder_name(951_782_400 + 3_723),
+            "2000-02-29_01-02-03"
+        );
+        assert!(is_backup_folder_name(&backup_folder_name(1_700_000_000)));
+        assert!(is_backup_folder_name("2024-01-01_00-00-00-1"));
+        assert!(!is_backup_folder_name("notes"));
+    }
+
+    #[test]
+    fn test_prune_backups() {
+        let root = tempfile::tempdir().unwrap();
+        for name in [
+            "2024-01-01_00-00-00",
+            "2024-01-02_00-00-00",
+            "2024-01-03_00-00-00",
+            "2024-01-03_00-00-00-1",
+            "notes",
+        ] {
+            std::fs::create_dir(root.path().join(name)).unwrap();
+        }
+
+        assert_eq!(prune_backups(root.path(), 2).unwrap(), 2);
+        let mut left = root
+            .path()
+            .read_dir()
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        left.sort();
+        assert_eq!(
+            left,
+            vec!["2024-01-03_00-00-00", "2024-01-03_00-00-00-1", "notes"]
+        );
+    }
+
+    #[test]
+    fn test_memory_backend_cannot_be_backed_up() {
+        let root = tempfile::tempdir().unwrap();
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        assert!(world.backup_to(root.path(), 0).is_err());
+        // A failed backup must not block the next one.
+        assert!(!BACKUP_IN_PROGRESS.load(Ordering::Acquire));
+    }
+}
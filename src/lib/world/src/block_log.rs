@@ -0,0 +1,360 @@
+//! A log of the blocks players change, so griefing can be tracked down and undone.
+//!
+//! Every change is recorded with [World::log_block_change] and kept in memory until the next
+//! [World::sync], which writes it to the [BLOCK_LOG_TABLE]. Records are keyed by the time they
+//! were made, so looking up recent changes only has to read the records in the time range, newest
+//! first, until enough of them have been found.
+//! [World::rollback_changes] puts back the blocks a player changed, and
+//! [World::restore_changes] undoes a rollback.
+
+use crate::block_state_id::BlockStateId;
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::World;
+use bitcode_derive::{Decode, Encode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// The table block changes are stored in.
+pub const BLOCK_LOG_TABLE: &str = "block_log";
+
+/// Tells apart changes made in the same millisecond, since the timestamp alone isn't unique.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// How many records are read from the storage backend at a time when looking up changes.
+const BLOCK_LOG_PAGE_SIZE: usize = 1024;
+
+/// A single block changed by a player.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct BlockChange {
+    /// The UUID of the player who made the change.
+    pub player: u128,
+    /// The player's username at the time, so the log can be read without them being online.
+    pub player_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub dimension: Dimension,
+    pub old_block: BlockStateId,
+    pub new_block: BlockStateId,
+    /// When the change was made, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// Whether the change has been undone by a rollback.
+    pub rolled_back: bool,
+}
+
+/// A block set by a rollback or restore, which clients need to be told about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayedBlock {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub dimension: Dimension,
+    pub block: BlockStateId,
+}
+
+impl BlockChange {
+    /// Creates a record of a change made just now.
+    pub fn new(
+        player: u128,
+        player_name: String,
+        (x, y, z): (i32, i32, i32),
+        dimension: Dimension,
+        old_block: BlockStateId,
+        new_block: BlockStateId,
+    ) -> Self {
+        BlockChange {
+            player,
+            player_name,
+            x,
+            y,
+            z,
+            dimension,
+            old_block,
+            new_block,
+            timestamp: now_millis(),
+            rolled_back: false,
+        }
+    }
+}
+
+/// The current time in milliseconds since the UNIX epoch.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Builds the key a change is stored under. The timestamp goes in the top half so keys sort by
+/// time.
+fn log_key(timestamp: u64) -> u128 {
+    ((timestamp as u128) << 64) | SEQUENCE.fetch_add(1, Ordering::Relaxed) as u128
+}
+
+impl World {
+    /// Records a block change. It's kept in memory until the next [World::sync].
+    pub fn log_block_change(&self, change: BlockChange) {
+        let key = log_key(change.timestamp);
+        self.pending_block_changes
+            .lock()
+            .expect("Block log lock poisoned")
+            .push((key, change));
+    }
+
+    /// Writes the block changes recorded since the last flush to the storage backend.
+    pub(crate) fn flush_block_log(&self) -> Result<(), WorldError> {
+        let pending = std::mem::take(
+            &mut *self
+                .pending_block_changes
+                .lock()
+                .expect("Block log lock poisoned"),
+        );
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let records = pending
+            .into_iter()
+            .map(|(key, change)| (key, bitcode::encode(&change)))
+            .collect();
+        self.storage_backend
+            .batch_upsert(BLOCK_LOG_TABLE.to_string(), records)?;
+        Ok(())
+    }
+
+    /// Gets up to `limit` logged block changes made at or after `since` (in milliseconds since
+    /// the UNIX epoch) that `filter` accepts, newest first, along with the keys they're stored
+    /// under.
+    ///
+    /// The records are read in pages going back in time, so only the time range is read, and less
+    /// than that once `limit` changes are found.
+    pub fn block_changes(
+        &self,
+        since: u64,
+        limit: usize,
+        filter: impl Fn(&BlockChange) -> bool,
+    ) -> Result<Vec<(u128, BlockChange)>, WorldError> {
+        self.flush_block_log()?;
+        if !self
+            .storage_backend
+            .table_exists(BLOCK_LOG_TABLE.to_string())?
+        {
+            return Ok(Vec::new());
+        }
+        let start = u128::from(since) << 64;
+        let mut end = u128::MAX;
+        let mut changes = Vec::new();
+        while changes.len() < limit {
+            let page = self.storage_backend.get_range(
+                BLOCK_LOG_TABLE.to_string(),
+                start..=end,
+                BLOCK_LOG_PAGE_SIZE,
+                true,
+            )?;
+            let last_page = page.len() < BLOCK_LOG_PAGE_SIZE;
+            for (key, record) in page {
+                let change: BlockChange = bitcode::decode(&record)
+                    .map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))?;
+                if filter(&change) {
+                    changes.push((key, change));
+                    if changes.len() == limit {
+                        break;
+                    }
+                }
+                end = key;
+            }
+            // The oldest key read is the new end, so carry on from the one before it
+            match end.checked_sub(1) {
+                Some(before) if !last_page && before >= start => end = before,
+                _ => break,
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Undoes every change `player_name` (case-insensitive) made at or after `since`, putting back
+    /// the blocks that were there before. Changes that were already rolled back are skipped.
+    ///
+    /// Returns the blocks that were set.
+    pub fn rollback_changes(
+        &self,
+        player_name: &str,
+        since: u64,
+    ) -> Result<Vec<ReplayedBlock>, WorldError> {
+        // Newest first, so a block changed several times ends up as it was before the first change.
+        let changes = self.block_changes(since, usize::MAX, |change| {
+            !change.rolled_back && change.player_name.eq_ignore_ascii_case(player_name)
+        })?;
+        self.replay_changes(changes.into_iter(), true)
+    }
+
+    /// Redoes the changes of `player_name` (case-insensitive) from `since` onwards that were
+    /// rolled back, undoing [World::rollback_changes].
+    ///
+    /// Returns the blocks that were set.
+    pub fn restore_changes(
+        &self,
+        player_name: &str,
+        since: u64,
+    ) -> Result<Vec<ReplayedBlock>, WorldError> {
+        let changes = self.block_changes(since, usize::MAX, |change| {
+            change.rolled_back && change.player_name.eq_ignore_ascii_case(player_name)
+        })?;
+        self.replay_changes(changes.into_iter().rev(), false)
+    }
+
+    /// Sets the old block of each change if `rollback` is set, or the new block otherwise, one
+    /// [EditBatch](crate::edit_batch::EditBatch) per chunk. Later changes to the same block win.
+    /// The changes are then marked as rolled back or not.
+    fn replay_changes(
+        &self,
+        changes: impl Iterator<Item = (u128, BlockChange)>,
+        rollback: bool,
+    ) -> Result<Vec<ReplayedBlock>, WorldError> {
+        let mut per_chunk: HashMap<(i32, i32, Dimension), Vec<(u128, BlockChange)>> =
+            HashMap::new();
+        for (key, change) in changes {
+            per_chunk
+                .entry((change.x >> 4, change.z >> 4, change.dimension))
+                .or_default()
+                .push((key, change));
+        }
+
+        let mut replayed = HashMap::new();
+        let mut updated_records = Vec::new();
+        for ((chunk_x, chunk_z, dimension), changes) in per_chunk {
+            let result = self.edit_chunk(chunk_x, chunk_z, dimension.as_str(), |batch| {
+                for (_, change) in &changes {
+                    let block = if rollback {
+                        change.old_block
+                    } else {
+                        change.new_block
+                    };
+                    batch.set_block(change.x, change.y, change.z, block);
+                    replayed.insert((change.x, change.y, change.z, dimension), block);
+                }
+            });
+            match result {
+                Ok(()) => {}
+                Err(WorldError::ChunkNotFound) => {
+                    warn!(
+                        "Skipping {} block changes in missing chunk ({}, {}) in {}",
+                        changes.len(),
+                        chunk_x,
+                        chunk_z,
+                        dimension
+                    );
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            for (key, mut change) in changes {
+                change.rolled_back = rollback;
+                updated_records.push((key, bitcode::encode(&change)));
+            }
+        }
+        if !updated_records.is_empty() {
+            self.storage_backend
+                .batch_upsert(BLOCK_LOG_TABLE.to_string(), updated_records)?;
+        }
+
+        Ok(replayed
+            .into_iter()
+            .map(|((x, y, z, dimension), block)| ReplayedBlock {
+                x,
+                y,
+                z,
+                dimension,
+                block,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    fn place(world: &World, name: &str, pos: (i32, i32, i32), block: BlockStateId) {
+        let old_block = world
+            .get_block_and_fetch(pos.0, pos.1, pos.2, "overworld")
+            .unwrap();
+        world
+            .set_block_and_fetch(pos.0, pos.1, pos.2, "overworld", block)
+            .unwrap();
+        world.log_block_change(BlockChange::new(
+            1,
+            name.to_string(),
+            pos,
+            Dimension::Overworld,
+            old_block,
+            block,
+        ));
+    }
+
+    #[test]
+    fn test_rollback_and_restore() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        world
+            .save_chunk(Arc::new(Chunk::new(0, 0, "overworld".to_string())))
+            .unwrap();
+        let start = now_millis();
+        place(&world, "Griefer", (1, 10, 1), block!("stone"));
+        place(&world, "Griefer", (1, 10, 1), block!("dirt"));
+        place(&world, "Builder", (2, 10, 2), block!("stone"));
+
+        let replayed = world.rollback_changes("griefer", start).unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].block, BlockStateId::default());
+        let block_at = |x, z| world.get_block_and_fetch(x, 10, z, "overworld").unwrap();
+        assert_eq!(block_at(1, 1), BlockStateId::default());
+        assert_eq!(block_at(2, 2), block!("stone"));
+        // Rolling back twice does nothing.
+        assert!(world.rollback_changes("griefer", start).unwrap().is_empty());
+
+        world.restore_changes("Griefer", start).unwrap();
+        assert_eq!(block_at(1, 1), block!("dirt"));
+        let rolled_back = world.block_changes(start, usize::MAX, |change| change.rolled_back);
+        assert!(rolled_back.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_block_changes_since() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        let mut old = BlockChange::new(
+            7,
+            "Someone".to_string(),
+            (0, 0, 0),
+            Dimension::Overworld,
+            BlockStateId::default(),
+            block!("stone"),
+        );
+        old.timestamp -= 60_000;
+        world.log_block_change(old);
+        world.log_block_change(BlockChange::new(
+            7,
+            "Someone".to_string(),
+            (0, 1, 0),
+            Dimension::Overworld,
+            BlockStateId::default(),
+            block!("stone"),
+        ));
+        world.sync().unwrap();
+
+        let recent = world
+            .block_changes(now_millis() - 30_000, usize::MAX, |_| true)
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].1.y, 1);
+        let all = world.block_changes(0, usize::MAX, |_| true).unwrap();
+        assert_eq!(all.len(), 2);
+        // The newest change comes first.
+        assert_eq!(world.block_changes(0, 1, |_| true).unwrap(), all[..1]);
+    }
+}
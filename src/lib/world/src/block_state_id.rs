@@ -2,6 +2,7 @@ use crate::vanilla_chunk_format::BlockData;
 use ahash::RandomState;
 use bitcode_derive::{Decode, Encode};
 use deepsize::DeepSizeOf;
+use ferrumc_data::blocks::ALL_BLOCKS;
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -40,6 +41,19 @@ lazy_static! {
         .enumerate()
         .map(|(k, v)| (v.clone(), k as i32))
         .collect();
+    /// The default state of each block, keyed by block name, e.g. `minecraft:stone`. These are
+    /// vanilla's defaults, e.g. `minecraft:oak_log` maps to `axis=y`.
+    pub static ref DEFAULT_STATES: HashMap<String, BlockStateId, RandomState> = ALL_BLOCKS
+        .iter()
+        .map(|block| {
+            let name = if block.name.contains(':') {
+                block.name.to_string()
+            } else {
+                format!("minecraft:{}", block.name)
+            };
+            (name, BlockStateId(block.default_state_id))
+        })
+        .collect();
 }
 
 /// An ID for a block, and it's state in the world. Use this over `BlockData` unless you need to
@@ -65,6 +79,52 @@ impl BlockStateId {
         ID2BLOCK.get(self.0 as usize).cloned()
     }
 
+    /// Parses a block state written the way commands and schematics write them, e.g. `stone` or
+    /// `minecraft:oak_stairs[facing=north,half=bottom]`. The namespace may be left out, and any
+    /// properties that aren't given take their value from the block's default state.
+    ///
+    /// Returns `None` if the block or any of the properties don't exist.
+    pub fn from_state_string(state: &str) -> Option<Self> {
+        let (name, properties) = match state.split_once('[') {
+            Some((name, properties)) => (name, properties.strip_suffix(']')?),
+            None => (state, ""),
+        };
+        let name = if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("minecraft:{name}")
+        };
+        let default = *DEFAULT_STATES.get(&name)?;
+        if properties.trim().is_empty() {
+            return Some(default);
+        }
+
+        let mut data = default.to_block_data()?;
+        let block_properties = data.properties.as_mut()?;
+        for property in properties.split(',') {
+            let (key, value) = property.split_once('=')?;
+            let slot = block_properties.get_mut(key.trim())?;
+            *slot = value.trim().to_string();
+        }
+        BLOCK2ID.get(&data).map(|id| BlockStateId(*id as u32))
+    }
+
+    /// Formats the block state the way [BlockStateId::from_state_string] reads it, with every
+    /// property given. Returns `None` if the ID isn't a known block state.
+    pub fn to_state_string(&self) -> Option<String> {
+        let data = self.to_block_data()?;
+        match data.properties {
+            Some(properties) if !properties.is_empty() => {
+                let properties = properties
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>();
+                Some(format!("{}[{}]", data.name, properties.join(",")))
+            }
+            _ => Some(data.name),
+        }
+    }
+
     pub fn from_varint(var_int: VarInt) -> Self {
         BlockStateId(var_int.0 as u32)
     }
@@ -131,3 +191,48 @@ impl Default for BlockStateId {
         Self(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_state_strings() {
+        assert_eq!(
+            BlockStateId::from_state_string("minecraft:air"),
+            Some(BlockStateId::default())
+        );
+        assert_eq!(
+            BlockStateId::from_state_string("stone"),
+            Some(block!("stone"))
+        );
+        // Missing properties come from the default state.
+        assert_eq!(
+            BlockStateId::from_state_string("oak_log"),
+            Some(block!("oak_log", { axis: "y" }))
+        );
+        assert_eq!(
+            BlockStateId::from_state_string("grass_block"),
+            Some(block!("grass_block", { snowy: false }))
+        );
+        assert_eq!(
+            BlockStateId::from_state_string("water[level=0]"),
+            Some(block!("water", { level: 0 }))
+        );
+        let log = block!("oak_log", { axis: "x" });
+        assert_eq!(
+            BlockStateId::from_state_string("oak_log[axis=x]"),
+            Some(log)
+        );
+        assert_eq!(
+            log.to_state_string().as_deref(),
+            Some("minecraft:oak_log[axis=x]")
+        );
+        assert_eq!(BlockStateId::from_state_string("oak_log[size=x]"), None);
+        assert_eq!(
+            BlockStateId::from_state_string("minecraft:not_a_block"),
+            None
+        );
+    }
+}
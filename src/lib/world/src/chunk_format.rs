@@ -1,10 +1,13 @@
 use crate::block_state_id::{BlockStateId, BLOCK2ID};
+use crate::dimension::Dimension;
 use crate::vanilla_chunk_format;
 use crate::vanilla_chunk_format::VanillaChunk;
 use crate::{errors::WorldError, vanilla_chunk_format::VanillaHeightmaps};
 use bitcode_derive::{Decode, Encode};
 use deepsize::DeepSizeOf;
+use ferrumc_data::generated::biomes::Biome;
 use ferrumc_general_purpose::data_packing::i32::read_nbit_i32;
+use ferrumc_general_purpose::data_packing::u32::{read_nbit_u32, write_nbit_u32};
 use ferrumc_macros::{block, NBTDeserialize, NBTSerialize};
 use ferrumc_net_codec::net_types::var_int::VarInt;
 use std::cmp::max;
@@ -54,6 +57,21 @@ pub struct BlockStates {
     pub block_counts: HashMap<BlockStateId, i32>,
 }
 
+impl BlockStates {
+    /// Counts the blocks in `block_counts` that aren't air, void air or cave air, which is what
+    /// `non_air_blocks` should always be.
+    pub fn count_non_air_blocks(&self) -> u16 {
+        self.block_counts
+            .iter()
+            .filter(|(block, _)| {
+                // Air, void air and cave air respectively
+                ![0, 12958, 12959].contains(&block.0)
+            })
+            .map(|(_, count)| *count as u16)
+            .sum()
+    }
+}
+
 #[derive(Encode, Decode, Clone, DeepSizeOf, Eq, PartialEq, Debug)]
 pub enum PaletteType {
     Single(VarInt),
@@ -75,6 +93,165 @@ pub struct BiomeStates {
     pub palette: Vec<VarInt>,
 }
 
+impl BiomeStates {
+    /// Biomes are stored in 4x4x4 cells rather than per block, so a section only has 64 of them.
+    pub const CELLS: usize = 64;
+
+    /// Creates biome states where the whole section is the given biome.
+    pub fn single(biome_id: u16) -> Self {
+        BiomeStates {
+            bits_per_biome: 0,
+            data: vec![],
+            palette: vec![VarInt::from(i32::from(biome_id))],
+        }
+    }
+
+    /// Packs the biome ids of every cell in the section, indexed by `(y * 4 + z) * 4 + x`.
+    ///
+    /// The palette only contains the biomes that are used and the bits per entry are worked out
+    /// the same way vanilla does it, so the result can be written to region files as-is.
+    pub fn from_ids(ids: &[u16; Self::CELLS]) -> Result<Self, WorldError> {
+        let mut palette: Vec<u16> = Vec::new();
+        for id in ids {
+            if !palette.contains(id) {
+                palette.push(*id);
+            }
+        }
+        if palette.len() == 1 {
+            return Ok(Self::single(palette[0]));
+        }
+        let bits_per_biome = (palette.len() as f32).log2().ceil() as u8;
+        let per_long = 64 / bits_per_biome as usize;
+        let mut data = vec![0i64; Self::CELLS.div_ceil(per_long)];
+        for (index, id) in ids.iter().enumerate() {
+            let palette_index = palette.iter().position(|entry| entry == id).unwrap_or(0);
+            write_nbit_u32(
+                &mut data[index / per_long],
+                ((index % per_long) * bits_per_biome as usize) as u32,
+                palette_index as u32,
+                bits_per_biome,
+            )?;
+        }
+        Ok(BiomeStates {
+            bits_per_biome,
+            data,
+            palette: palette
+                .into_iter()
+                .map(|id| VarInt::from(i32::from(id)))
+                .collect(),
+        })
+    }
+
+    /// Unpacks the biome ids of every cell in the section. See [`BiomeStates::from_ids`] for the
+    /// order they're in.
+    pub fn to_ids(&self) -> Result<[u16; Self::CELLS], WorldError> {
+        if self.bits_per_biome == 0 {
+            let id = self.palette.first().ok_or_else(|| {
+                WorldError::InvalidBiomeData("Biome palette is empty".to_string())
+            })?;
+            return Ok([id.0 as u16; Self::CELLS]);
+        }
+        let per_long = 64 / self.bits_per_biome as usize;
+        let mut ids = [0u16; Self::CELLS];
+        for (index, id) in ids.iter_mut().enumerate() {
+            let packed = self.data.get(index / per_long).ok_or_else(|| {
+                WorldError::InvalidBiomeData(format!("Missing biome data at index {index}"))
+            })?;
+            let offset = (index % per_long) * self.bits_per_biome as usize;
+            let palette_index = read_nbit_u32(packed, self.bits_per_biome, offset as u32)?;
+            *id = self
+                .palette
+                .get(palette_index as usize)
+                .ok_or_else(|| {
+                    WorldError::InvalidBiomeData(format!(
+                        "Palette index {palette_index} is out of bounds"
+                    ))
+                })?
+                .0 as u16;
+        }
+        Ok(ids)
+    }
+
+    /// Gets the biome id of the cell containing the given block. Only the block's position inside
+    /// the section matters.
+    pub fn get_biome(&self, x: i32, y: i32, z: i32) -> Result<u16, WorldError> {
+        Ok(self.to_ids()?[Self::cell_index(x, y, z)])
+    }
+
+    /// Sets the biome of the cell containing the given block.
+    pub fn set_biome(&mut self, x: i32, y: i32, z: i32, biome_id: u16) -> Result<(), WorldError> {
+        let mut ids = self.to_ids()?;
+        ids[Self::cell_index(x, y, z)] = biome_id;
+        *self = Self::from_ids(&ids)?;
+        Ok(())
+    }
+
+    pub(crate) fn cell_index(x: i32, y: i32, z: i32) -> usize {
+        ((((y & 0xF) >> 2) * 4 + ((z & 0xF) >> 2)) * 4 + ((x & 0xF) >> 2)) as usize
+    }
+}
+
+/// Converts a section's biomes from the format vanilla stores them in. Biomes we don't know about
+/// are replaced with the dimension's default biome.
+fn convert_to_biome_states(
+    biomes: Option<&vanilla_chunk_format::Biomes>,
+    dimension: Dimension,
+) -> Result<BiomeStates, WorldError> {
+    let default_biome = dimension.default_biome().id;
+    let Some(biomes) = biomes else {
+        return Ok(BiomeStates::single(default_biome));
+    };
+    let palette: Vec<u16> = biomes
+        .palette
+        .iter()
+        .map(|name| match Biome::from_name(name) {
+            Some(biome) => biome.id,
+            None => {
+                error!("Could not find biome id for palette entry: {}", name);
+                default_biome
+            }
+        })
+        .collect();
+    let data = biomes.data.as_deref().unwrap_or_default();
+    if palette.len() <= 1 || data.is_empty() {
+        return Ok(BiomeStates::single(
+            palette.first().copied().unwrap_or(default_biome),
+        ));
+    }
+    let bits_per_biome = (palette.len() as f32).log2().ceil() as u8;
+    let biome_states = BiomeStates {
+        bits_per_biome,
+        data: data.to_vec(),
+        palette: palette
+            .into_iter()
+            .map(|id| VarInt::from(i32::from(id)))
+            .collect(),
+    };
+    // Repack it so unused and duplicate palette entries get dropped
+    BiomeStates::from_ids(&biome_states.to_ids()?)
+}
+
+/// Converts a section's biomes into the format vanilla stores them in.
+fn convert_to_vanilla_biomes(
+    biome_states: &BiomeStates,
+) -> Result<vanilla_chunk_format::Biomes, WorldError> {
+    let palette = biome_states
+        .palette
+        .iter()
+        .map(|id| match Biome::from_id(id.0 as u16) {
+            Some(biome) => Ok(format!("minecraft:{}", biome.name)),
+            None => Err(WorldError::InvalidBiomeData(format!(
+                "Unknown biome id: {}",
+                id.0
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(vanilla_chunk_format::Biomes {
+        data: (biome_states.bits_per_biome != 0).then(|| biome_states.data.clone()),
+        palette,
+    })
+}
+
 fn convert_to_net_palette(
     vanilla_palettes: Vec<vanilla_chunk_format::BlockData>,
 ) -> Result<Vec<VarInt>, WorldError> {
@@ -93,6 +270,54 @@ fn convert_to_net_palette(
     Ok(new_palette)
 }
 
+/// The data version of the chunks we write out, matching Minecraft 1.21.8.
+pub(crate) const VANILLA_DATA_VERSION: i32 = 4440;
+
+/// Vanilla works out how many bits each entry uses from the length of the palette, so packed data
+/// using any other width has to be repacked before it can be exported.
+fn repack_for_vanilla(
+    bits_per_block: u8,
+    data: &[i64],
+    palette_len: usize,
+) -> Result<Vec<i64>, WorldError> {
+    let vanilla_bits = max((palette_len as f32).log2().ceil() as u8, 4);
+    if vanilla_bits == bits_per_block {
+        return Ok(data.to_vec());
+    }
+    let old_per_long = 64 / bits_per_block as usize;
+    let new_per_long = 64 / vanilla_bits as usize;
+    let mut repacked = vec![0i64; 4096usize.div_ceil(new_per_long)];
+    for index in 0..4096 {
+        let packed = data.get(index / old_per_long).ok_or_else(|| {
+            WorldError::InvalidBlockStateData(format!("Missing block data at index {index}"))
+        })?;
+        let offset = (index % old_per_long) * bits_per_block as usize;
+        let value = read_nbit_i32(packed, bits_per_block as usize, offset as u32)?;
+        let offset = (index % new_per_long) * vanilla_bits as usize;
+        write_nbit_u32(
+            &mut repacked[index / new_per_long],
+            offset as u32,
+            value as u32,
+            vanilla_bits,
+        )?;
+    }
+    Ok(repacked)
+}
+
+fn convert_to_vanilla_palette(palette: &[VarInt]) -> Vec<vanilla_chunk_format::BlockData> {
+    palette
+        .iter()
+        .map(|id| {
+            BlockStateId::from_varint(*id)
+                .to_block_data()
+                .unwrap_or_else(|| {
+                    error!("Could not find block data for block state id: {}", id.0);
+                    vanilla_chunk_format::BlockData::default()
+                })
+        })
+        .collect()
+}
+
 impl Heightmaps {
     pub fn new() -> Self {
         Heightmaps {
@@ -119,6 +344,8 @@ impl From<VanillaHeightmaps> for Heightmaps {
 
 impl VanillaChunk {
     pub fn to_custom_format(&self) -> Result<Chunk, WorldError> {
+        let dimension = self.dimension.clone().unwrap_or("overworld".to_string());
+        let dimension_kind = Dimension::from_name(&dimension).unwrap_or_default();
         let mut sections = Vec::new();
         for section in self.sections.as_ref().unwrap() {
             let y = section.y;
@@ -156,8 +383,13 @@ impl VanillaChunk {
                 }
             }
             let block_data = if raw_block_data.is_empty() {
-                block_counts.insert(BlockStateId::default(), 4096);
-                PaletteType::Single(VarInt::from(0))
+                // Sections made up of a single block only store the palette
+                let block = palette
+                    .first()
+                    .map(|block| block.to_block_state_id())
+                    .unwrap_or_default();
+                block_counts.insert(block, 4096);
+                PaletteType::Single(block.into())
             } else {
                 PaletteType::Indirect {
                     bits_per_block,
@@ -189,12 +421,7 @@ impl VanillaChunk {
                 .iter()
                 .map(|&x| x as u8)
                 .collect();
-            let biome_states = BiomeStates {
-                // TODO: Implement biome states properly
-                bits_per_biome: 4,
-                data: vec![],
-                palette: vec![VarInt::from(0); 1],
-            };
+            let biome_states = convert_to_biome_states(section.biomes.as_ref(), dimension_kind)?;
             let section = Section {
                 y,
                 block_states,
@@ -205,35 +432,102 @@ impl VanillaChunk {
             sections.push(section);
         }
 
-        let dimension = self.dimension.clone().unwrap_or("overworld".to_string());
-
         let heightmaps: Heightmaps = self.heightmaps.clone().map(Into::into).unwrap_or_default();
 
-        Ok(Chunk {
+        let mut chunk = Chunk {
             x: self.x_pos,
             z: self.z_pos,
             dimension,
             sections,
             heightmaps,
-        })
+        };
+        // Chunks saved before they were fully generated don't have heightmaps yet
+        if chunk.heightmaps.world_surface.is_empty() || chunk.heightmaps.motion_blocking.is_empty()
+        {
+            chunk.calculate_heightmaps()?;
+        }
+        Ok(chunk)
     }
 }
 
 impl Chunk {
+    /// Converts the chunk back into the format vanilla stores in region files.
+    ///
+    /// Light is exported as-is but flagged as stale so vanilla recalculates it on load.
+    pub(crate) fn to_vanilla_format(&self) -> Result<VanillaChunk, WorldError> {
+        let mut sections = Vec::with_capacity(self.sections.len());
+        for section in &self.sections {
+            let (data, palette) = match &section.block_states.block_data {
+                PaletteType::Single(id) => (None, convert_to_vanilla_palette(&[*id])),
+                PaletteType::Indirect {
+                    bits_per_block,
+                    data,
+                    palette,
+                } => (
+                    Some(repack_for_vanilla(*bits_per_block, data, palette.len())?),
+                    convert_to_vanilla_palette(palette),
+                ),
+                PaletteType::Direct { .. } => {
+                    return Err(WorldError::InvalidBlockStateData(
+                        "Direct palettes can't be exported".to_string(),
+                    ));
+                }
+            };
+            let to_nibbles = |light: &Vec<u8>| {
+                (light.len() == 2048).then(|| light.iter().map(|&x| x as i8).collect())
+            };
+            sections.push(vanilla_chunk_format::Section {
+                block_states: Some(vanilla_chunk_format::BlockStates {
+                    data,
+                    palette: Some(palette),
+                }),
+                biomes: Some(convert_to_vanilla_biomes(&section.biome_states)?),
+                y: section.y,
+                block_light: to_nibbles(&section.block_light),
+                sky_light: to_nibbles(&section.sky_light),
+            });
+        }
+
+        let non_empty = |heightmap: &Vec<i64>| (!heightmap.is_empty()).then(|| heightmap.clone());
+
+        Ok(VanillaChunk {
+            dimension: Some(self.dimension.clone()),
+            status: "minecraft:full".to_string(),
+            data_version: VANILLA_DATA_VERSION,
+            heightmaps: Some(VanillaHeightmaps {
+                motion_blocking: non_empty(&self.heightmaps.motion_blocking),
+                world_surface: non_empty(&self.heightmaps.world_surface),
+            }),
+            is_light_on: Some(0),
+            inhabited_time: Some(0),
+            y_pos: self
+                .sections
+                .iter()
+                .map(|s| i32::from(s.y))
+                .min()
+                .unwrap_or(0),
+            x_pos: self.x,
+            z_pos: self.z,
+            structures: None,
+            last_update: Some(0),
+            sections: Some(sections),
+        })
+    }
+
+    /// Creates an empty chunk. The number of sections and the starting biome depend on the
+    /// dimension; unknown dimension names fall back to the overworld's.
     pub fn new(x: i32, z: i32, dimension: String) -> Self {
-        let mut sections: Vec<Section> = (-4..20)
+        let dimension_kind = Dimension::from_name(&dimension).unwrap_or_default();
+        let section_range = dimension_kind.section_range();
+        let mut sections: Vec<Section> = section_range
             .map(|y| Section {
-                y: y as i8,
+                y,
                 block_states: BlockStates {
                     non_air_blocks: 0,
                     block_data: PaletteType::Single(VarInt::from(0)),
                     block_counts: HashMap::from([(BlockStateId::default(), 4096)]),
                 },
-                biome_states: BiomeStates {
-                    bits_per_biome: 0,
-                    data: vec![],
-                    palette: vec![VarInt::from(0)],
-                },
+                biome_states: BiomeStates::single(dimension_kind.default_biome().id),
                 block_light: vec![255; 2048],
                 sky_light: vec![255; 2048],
             })
@@ -247,7 +541,7 @@ impl Chunk {
             z,
             dimension,
             sections,
-            heightmaps: Heightmaps::new(),
+            heightmaps: Heightmaps::empty(dimension_kind.height()),
         }
     }
 }
@@ -323,4 +617,95 @@ mod tests {
         assert!(chunk.set_block(0, 0, 0, block).is_ok());
         assert!(chunk.get_block(0, 0, 0).is_ok());
     }
+
+    #[test]
+    fn test_chunk_sections_match_dimension() {
+        let overworld = Chunk::new(0, 0, "overworld".to_string());
+        assert_eq!(overworld.sections.len(), 24);
+        assert_eq!(overworld.sections.first().unwrap().y, -4);
+
+        let nether = Chunk::new(0, 0, "the_nether".to_string());
+        assert_eq!(nether.sections.len(), 16);
+        assert_eq!(nether.sections.first().unwrap().y, 0);
+    }
+
+    #[test]
+    fn test_vanilla_round_trip() {
+        let mut chunk = Chunk::new(3, -7, "the_nether".to_string());
+        chunk.fill(block!("netherrack")).unwrap();
+        let blocks = [
+            block!("stone"),
+            block!("dirt"),
+            block!("glass"),
+            block!("bedrock"),
+        ];
+        for (i, block) in blocks.iter().cycle().take(40).enumerate() {
+            chunk
+                .set_block(i as i32 % 16, i as i32 * 3, i as i32 / 16, *block)
+                .unwrap();
+        }
+
+        chunk
+            .set_biome_column(0, 0, Biome::SOUL_SAND_VALLEY.id)
+            .unwrap();
+        chunk
+            .set_biome(8, 100, 12, Biome::BASALT_DELTAS.id)
+            .unwrap();
+
+        let imported = chunk
+            .to_vanilla_format()
+            .unwrap()
+            .to_custom_format()
+            .unwrap();
+        for section in &chunk.sections {
+            let imported_section = imported.sections.iter().find(|s| s.y == section.y).unwrap();
+            assert_eq!(
+                imported_section.biome_states.to_ids().unwrap(),
+                section.biome_states.to_ids().unwrap()
+            );
+        }
+        assert_eq!(imported.x, 3);
+        assert_eq!(imported.z, -7);
+        assert_eq!(imported.dimension, "the_nether");
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..256 {
+                    assert_eq!(
+                        imported.get_block(x, y, z).unwrap(),
+                        chunk.get_block(x, y, z).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_biome_states_pack_and_unpack() {
+        let mut ids = [Biome::PLAINS.id; BiomeStates::CELLS];
+        assert_eq!(BiomeStates::from_ids(&ids).unwrap().bits_per_biome, 0);
+
+        ids[5] = Biome::FOREST.id;
+        ids[63] = Biome::DESERT.id;
+        let states = BiomeStates::from_ids(&ids).unwrap();
+        assert_eq!(states.bits_per_biome, 2);
+        assert_eq!(states.palette.len(), 3);
+        assert_eq!(states.to_ids().unwrap(), ids);
+    }
+
+    #[test]
+    fn test_set_biome() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        assert_eq!(chunk.get_biome(0, 0, 0).unwrap(), Biome::PLAINS.id);
+
+        chunk.set_biome(5, -30, 9, Biome::DESERT.id).unwrap();
+        // Every block in the same 4x4x4 cell shares the biome
+        assert_eq!(chunk.get_biome(4, -32, 8).unwrap(), Biome::DESERT.id);
+        assert_eq!(chunk.get_biome(7, -29, 11).unwrap(), Biome::DESERT.id);
+        assert_eq!(chunk.get_biome(8, -30, 9).unwrap(), Biome::PLAINS.id);
+
+        chunk.set_biome_column(15, 15, Biome::FOREST.id).unwrap();
+        assert_eq!(chunk.get_biome(12, -64, 12).unwrap(), Biome::FOREST.id);
+        assert_eq!(chunk.get_biome(15, 319, 15).unwrap(), Biome::FOREST.id);
+        assert_eq!(chunk.get_biome(11, 0, 15).unwrap(), Biome::PLAINS.id);
+    }
 }
@@ -0,0 +1,310 @@
+use crate::chunk_format::Chunk;
+use crate::errors::WorldError;
+use crate::errors::WorldError::CorruptedChunkData;
+use crate::migrations::{MigrationRegistry, CURRENT_FORMAT_VERSION};
+use crate::{progress_bar, World};
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_storage::compressors::{Compressor, CompressorType};
+use tracing::{info, warn};
+
+// Stored chunk records look like this:
+//
+// | magic (1) | format version (2, BE) | algorithm id (1) | level (1) | adler32 of data (4, BE) | data |
+//
+// where data is the bitcode encoding of the chunk, compressed. Two older layouts are still read,
+// both of which hold format version 1:
+// - The same header with a different magic and without the format version.
+// - A bare zlib stream, from before the compression was configurable. A zlib stream always starts
+//   with a byte whose low nibble is 8, so neither magic byte can be mistaken for one.
+const RECORD_MAGIC: u8 = 0xFD;
+const HEADER_LEN: usize = 9;
+const UNVERSIONED_RECORD_MAGIC: u8 = 0xFC;
+const UNVERSIONED_HEADER_LEN: usize = 7;
+
+/// How many chunks are read and written per transaction when rewriting every chunk.
+const REWRITE_BATCH_SIZE: usize = 1024;
+
+/// What a stored chunk record's header says about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecordHeader {
+    /// The compressor the record was written with, or `None` for bare zlib records.
+    pub compressor: Option<Compressor>,
+    /// The [format version](crate::migrations) of the chunk data.
+    pub format_version: u16,
+    checksum: Option<u32>,
+    data_offset: usize,
+}
+
+/// The compressor new chunks are written with, as set in the config.
+pub(crate) fn configured_compressor() -> Compressor {
+    let config = &get_global_config().database;
+    Compressor::create(config.compression.into(), config.compression_level)
+}
+
+/// Makes sure the configured compression level is one the configured algorithm accepts.
+pub(crate) fn check_compression_config() -> Result<(), WorldError> {
+    let compressor = configured_compressor();
+    if compressor.level > compressor.algorithm.max_level() {
+        return Err(WorldError::InvalidCompressor(format!(
+            "compression level {} is too high for {:?}, the maximum is {}",
+            compressor.level,
+            compressor.algorithm,
+            compressor.algorithm.max_level()
+        )));
+    }
+    Ok(())
+}
+
+/// Compresses `data` and prepends the record header.
+pub(crate) fn compress_record(
+    data: &[u8],
+    compressor: Compressor,
+    format_version: u16,
+) -> Result<Vec<u8>, WorldError> {
+    let checksum = yazi::Adler32::from_buf(data).finish();
+    let compressed = compressor.compress(data)?;
+    let mut record = Vec::with_capacity(HEADER_LEN + compressed.len());
+    record.push(RECORD_MAGIC);
+    record.extend_from_slice(&format_version.to_be_bytes());
+    record.push(compressor.algorithm.id());
+    record.push(compressor.level as u8);
+    record.extend_from_slice(&checksum.to_be_bytes());
+    record.extend_from_slice(&compressed);
+    Ok(record)
+}
+
+/// Reads the header of a record without decompressing it.
+pub(crate) fn read_header(record: &[u8]) -> Result<RecordHeader, WorldError> {
+    let (format_version, data_offset) = match record.first() {
+        Some(&RECORD_MAGIC) => (
+            record
+                .get(1..3)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]])),
+            HEADER_LEN,
+        ),
+        Some(&UNVERSIONED_RECORD_MAGIC) => (Some(1), UNVERSIONED_HEADER_LEN),
+        _ => {
+            return Ok(RecordHeader {
+                compressor: None,
+                format_version: 1,
+                checksum: None,
+                data_offset: 0,
+            });
+        }
+    };
+    let (Some(format_version), true) = (format_version, record.len() >= data_offset) else {
+        return Err(WorldError::DecompressionError(
+            "Chunk record is shorter than its header".to_string(),
+        ));
+    };
+    // The algorithm, level and checksum are always the last 6 bytes of the header.
+    let header = &record[data_offset - 6..data_offset];
+    let algorithm = CompressorType::from_id(header[0]).ok_or_else(|| {
+        WorldError::DecompressionError(format!("Unknown compression algorithm id {}", header[0]))
+    })?;
+    Ok(RecordHeader {
+        compressor: Some(Compressor::create(algorithm, header[1] as u32)),
+        format_version,
+        checksum: Some(u32::from_be_bytes([
+            header[2], header[3], header[4], header[5],
+        ])),
+        data_offset,
+    })
+}
+
+/// Decompresses a record, in any of the layouts described at the top of this file, and checks
+/// its checksum if `verify_chunk_data` is on. The data is returned as stored, without migrating
+/// it to the current format version.
+pub(crate) fn decompress_record(record: &[u8]) -> Result<(RecordHeader, Vec<u8>), WorldError> {
+    decompress_record_checked(record, get_global_config().database.verify_chunk_data)
+}
+
+/// Same as [decompress_record], but whether the checksum is checked is up to the caller.
+pub(crate) fn decompress_record_checked(
+    record: &[u8],
+    verify: bool,
+) -> Result<(RecordHeader, Vec<u8>), WorldError> {
+    let header = read_header(record)?;
+    let Some(compressor) = header.compressor else {
+        let (data, checksum) = yazi::decompress(record, yazi::Format::Zlib)?;
+        if verify {
+            if let Some(expected_checksum) = checksum {
+                let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
+                if real_checksum != expected_checksum {
+                    return Err(CorruptedChunkData(real_checksum, expected_checksum));
+                }
+            } else {
+                warn!("Chunk data does not have a checksum, skipping verification.");
+            }
+        }
+        return Ok((header, data));
+    };
+    let data = compressor.decompress(&record[header.data_offset..])?;
+    if let (true, Some(expected_checksum)) = (verify, header.checksum) {
+        let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
+        if real_checksum != expected_checksum {
+            return Err(CorruptedChunkData(real_checksum, expected_checksum));
+        }
+    }
+    Ok((header, data))
+}
+
+/// Encodes and compresses a chunk with the configured compressor.
+pub(crate) fn encode_chunk(chunk: &Chunk) -> Result<Vec<u8>, WorldError> {
+    compress_record(
+        &bitcode::encode(chunk),
+        configured_compressor(),
+        CURRENT_FORMAT_VERSION,
+    )
+}
+
+/// Decompresses and decodes a stored chunk record, migrating it to the current format version
+/// first if it is older.
+pub(crate) fn decode_chunk(record: &[u8]) -> Result<Chunk, WorldError> {
+    let (header, data) = decompress_record(record)?;
+    let data = MigrationRegistry::global().migrate(header.format_version, data)?;
+    bitcode::decode(&data).map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))
+}
+
+impl World {
+    /// Re-compresses every stored chunk that wasn't written with the compression settings from
+    /// the config, so that a world can be moved over to a new algorithm or level in one go.
+    ///
+    /// Chunks that already match are left alone. Returns how many chunks were rewritten.
+    pub fn recompress(&self) -> Result<usize, WorldError> {
+        let target = configured_compressor();
+        self.rewrite_chunk_records(
+            format!(
+                "Re-compressing chunks with {:?} level {}...",
+                target.algorithm, target.level
+            ),
+            |record| {
+                let header = read_header(record)?;
+                if header.compressor == Some(target) {
+                    return Ok(None);
+                }
+                let (header, data) = decompress_record(record)?;
+                compress_record(&data, target, header.format_version).map(Some)
+            },
+        )
+    }
+
+    /// Passes every stored chunk record through `rewrite`, saving the records it returns. Records
+    /// it returns `None` for are left as they are. Returns how many records were rewritten.
+    pub(crate) fn rewrite_chunk_records(
+        &self,
+        message: String,
+        rewrite: impl Fn(&[u8]) -> Result<Option<Vec<u8>>, WorldError>,
+    ) -> Result<usize, WorldError> {
+        if !self.storage_backend.table_exists("chunks".to_string())? {
+            return Ok(0);
+        }
+        let keys = self.storage_backend.get_all_keys("chunks".to_string())?;
+
+        let progress = progress_bar(keys.len() as u64, message);
+
+        let start = std::time::Instant::now();
+        let mut rewritten = 0;
+        for batch in keys.chunks(REWRITE_BATCH_SIZE) {
+            let records = self
+                .storage_backend
+                .batch_get("chunks".to_string(), batch.to_vec())?;
+            let mut updated = Vec::new();
+            for (key, record) in batch.iter().zip(records) {
+                let Some(record) = record else {
+                    continue;
+                };
+                if let Some(new_record) = rewrite(&record)? {
+                    updated.push((*key, new_record));
+                }
+            }
+            rewritten += updated.len();
+            if !updated.is_empty() {
+                self.storage_backend
+                    .batch_upsert("chunks".to_string(), updated)?;
+            }
+            progress.inc(batch.len() as u64);
+        }
+        self.storage_backend.flush()?;
+
+        progress.finish_and_clear();
+        info!(
+            "Rewrote {} of {} chunks in {:?}",
+            rewritten,
+            keys.len(),
+            start.elapsed()
+        );
+        Ok(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_storage::memory::MemoryBackend;
+    use ferrumc_storage::StorageBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_record_round_trip() {
+        let data = bitcode::encode(&Chunk::new(0, 0, "overworld".to_string()));
+        for algorithm in CompressorType::ALL {
+            let compressor = Compressor::create(algorithm, 5);
+            let record = compress_record(&data, compressor, 7).unwrap();
+            let header = read_header(&record).unwrap();
+            assert_eq!(header.compressor, Some(compressor));
+            assert_eq!(header.format_version, 7);
+            assert_eq!(decompress_record(&record).unwrap().1, data);
+        }
+    }
+
+    #[test]
+    fn test_legacy_records_still_load() {
+        let chunk = Chunk::new(0, 0, "overworld".to_string());
+        let data = bitcode::encode(&chunk);
+        let bare_zlib =
+            yazi::compress(&data, yazi::Format::Zlib, yazi::CompressionLevel::BestSpeed).unwrap();
+        assert_eq!(read_header(&bare_zlib).unwrap().compressor, None);
+        assert_eq!(decode_chunk(&bare_zlib).unwrap().x, 0);
+
+        // The header from before format versions is the current one minus the version bytes.
+        let mut unversioned = compress_record(&data, configured_compressor(), 1).unwrap();
+        unversioned.drain(0..3);
+        unversioned.insert(0, UNVERSIONED_RECORD_MAGIC);
+        let header = read_header(&unversioned).unwrap();
+        assert_eq!(header.compressor, Some(configured_compressor()));
+        assert_eq!(header.format_version, 1);
+        assert_eq!(decode_chunk(&unversioned).unwrap().x, 0);
+    }
+
+    #[test]
+    fn test_truncated_header() {
+        assert!(read_header(&[RECORD_MAGIC, 0]).is_err());
+        assert!(read_header(&[UNVERSIONED_RECORD_MAGIC]).is_err());
+    }
+
+    #[test]
+    fn test_recompress() {
+        let backend = Arc::new(MemoryBackend::new());
+        let world = World::with_backend(backend.clone());
+        let data = bitcode::encode(&Chunk::new(0, 0, "overworld".to_string()));
+        let other = CompressorType::ALL
+            .into_iter()
+            .find(|algorithm| *algorithm != configured_compressor().algorithm)
+            .unwrap();
+        let stale = compress_record(&data, Compressor::create(other, 3), 1).unwrap();
+        let current = compress_record(&data, configured_compressor(), 1).unwrap();
+        backend
+            .batch_insert("chunks".to_string(), vec![(1, stale), (2, current)])
+            .unwrap();
+
+        assert_eq!(world.recompress().unwrap(), 1);
+        for key in [1, 2] {
+            let record = backend.get("chunks".to_string(), key).unwrap().unwrap();
+            let (header, decompressed) = decompress_record(&record).unwrap();
+            assert_eq!(header.compressor, Some(configured_compressor()));
+            assert_eq!(decompressed, data);
+        }
+        assert_eq!(world.recompress().unwrap(), 0);
+    }
+}
@@ -1,38 +1,42 @@
 use crate::chunk_format::Chunk;
+use crate::compression::{decode_chunk, encode_chunk};
 use crate::errors::WorldError;
-use crate::errors::WorldError::CorruptedChunkData;
 // db_functions.rs
-use crate::warn;
-use crate::World;
-use ferrumc_config::server_config::get_global_config;
+use crate::{ChunkKey, DirtyChunks, World};
+use ferrumc_storage::errors::StorageError;
+use ferrumc_storage::StorageBackend;
 use std::hash::Hasher;
 use std::sync::Arc;
 use tracing::trace;
-use yazi::CompressionLevel;
 
 impl World {
-    /// Save a chunk to the storage backend
+    /// Save a chunk to the world
     ///
-    /// This function will save a chunk to the storage backend and update the cache with the new
-    /// chunk data. If the chunk already exists in the cache, it will be updated with the new data.
+    /// The chunk is put in the cache straight away, replacing any older version, and marked as
+    /// dirty. It is only written to the storage backend on the next [World::sync] or when it is
+    /// evicted from the cache, so saving the same chunk many times in a row is cheap.
     pub fn save_chunk(&self, chunk: Arc<Chunk>) -> Result<(), WorldError> {
-        let ret = save_chunk_internal(self, &chunk);
-        self.cache
-            .insert((chunk.x, chunk.z, chunk.dimension.clone()), chunk);
-        ret
+        let key = (chunk.x, chunk.z, chunk.dimension.clone());
+        self.dirty_chunks.insert(key.clone(), chunk.clone());
+        self.cache.insert(key, chunk);
+        Ok(())
     }
 
     /// Load a chunk from the storage backend. If the chunk is in the cache, it will be returned
     /// from the cache instead of the storage backend. If the chunk is not in the cache, it will be
     /// loaded from the storage backend and inserted into the cache.
     pub fn load_chunk(&self, x: i32, z: i32, dimension: &str) -> Result<Arc<Chunk>, WorldError> {
-        if let Some(chunk) = self.cache.get(&(x, z, dimension.to_string())) {
+        let key = (x, z, dimension.to_string());
+        if let Some(chunk) = self.cache.get(&key) {
+            return Ok(chunk);
+        }
+        if let Some(chunk) = self.dirty_chunk(&key) {
+            self.cache.insert(key, chunk.clone());
             return Ok(chunk);
         }
         let chunk = load_chunk_internal(self, x, z, dimension);
         if let Ok(ref chunk) = chunk {
-            self.cache
-                .insert((x, z, dimension.to_string()), Arc::from(chunk.clone()));
+            self.cache.insert(key, Arc::from(chunk.clone()));
         }
         chunk.map(Arc::new)
     }
@@ -43,11 +47,12 @@ impl World {
 
     /// Check if a chunk exists in the storage backend.
     ///
-    /// It will first check if the chunk is in the cache and if it is, it will return true. If the
-    /// chunk is not in the cache, it will check the storage backend for the chunk, returning true
+    /// It will first check if the chunk is in the cache or waiting to be written and if it is, it
+    /// will return true. Otherwise, it will check the storage backend for the chunk, returning true
     /// if it exists and false if it does not.
     pub fn chunk_exists(&self, x: i32, z: i32, dimension: &str) -> Result<bool, WorldError> {
-        if self.cache.contains_key(&(x, z, dimension.to_string())) {
+        let key = (x, z, dimension.to_string());
+        if self.cache.contains_key(&key) || self.dirty_chunks.contains_key(&key) {
             return Ok(true);
         }
         chunk_exists_internal(self, x, z, dimension)
@@ -55,25 +60,46 @@ impl World {
 
     /// Delete a chunk from the storage backend.
     ///
-    /// This function will remove the chunk from the cache and delete it from the storage backend.
+    /// This function will remove the chunk from the cache, drop any unsaved changes to it and
+    /// delete it from the storage backend.
     pub fn delete_chunk(&self, x: i32, z: i32, dimension: &str) -> Result<(), WorldError> {
-        self.cache.remove(&(x, z, dimension.to_string()));
-        delete_chunk_internal(self, x, z, dimension)
+        let key = (x, z, dimension.to_string());
+        let was_dirty = self.dirty_chunks.remove(&key).is_some();
+        self.cache.remove(&key);
+        match delete_chunk_internal(self, x, z, dimension) {
+            // A chunk that was saved but never synced isn't in the storage backend yet.
+            Err(WorldError::DatabaseError(
+                StorageError::KeyNotFound(_) | StorageError::TableError(_),
+            )) if was_dirty => Ok(()),
+            result => result,
+        }
     }
 
     /// Sync the storage backend.
     ///
-    /// This function will save all chunks in the cache to the storage backend and then sync the
-    /// storage backend. This should be run after inserting or updating a large number of chunks
-    /// to ensure that the data is properly saved to disk.
+    /// This function will write every dirty chunk and any logged block changes to the storage
+    /// backend and then sync the storage backend. Chunks that haven't changed since they were last
+    /// written are skipped. This should be run regularly and before shutting down so that no
+    /// changes are lost.
     pub fn sync(&self) -> Result<(), WorldError> {
-        for (k, v) in self.cache.iter() {
-            trace!("Syncing chunk: {:?}", (k.0, k.1));
-            save_chunk_internal(self, &v)?;
+        let keys: Vec<ChunkKey> = self
+            .dirty_chunks
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        trace!("Syncing {} dirty chunks", keys.len());
+        for key in keys {
+            flush_dirty_chunk(self.storage_backend.as_ref(), &self.dirty_chunks, &key)?;
         }
+        self.flush_block_log()?;
         sync_internal(self)
     }
 
+    /// How many chunks have been changed since they were last written to the storage backend.
+    pub fn dirty_chunk_count(&self) -> usize {
+        self.dirty_chunks.len()
+    }
+
     /// Load a batch of chunks from the storage backend.
     ///
     /// This function attempts to load as many chunks as it can find from the cache first, then fetches
@@ -86,7 +112,11 @@ impl World {
         let mut found_chunks = Vec::new();
         let mut missing_chunks = Vec::new();
         for coord in coords {
-            if let Some(chunk) = self.cache.get(&(coord.0, coord.1, coord.2.to_string())) {
+            let key = (coord.0, coord.1, coord.2.to_string());
+            if let Some(chunk) = self.cache.get(&key) {
+                found_chunks.push(chunk);
+            } else if let Some(chunk) = self.dirty_chunk(&key) {
+                self.cache.insert(key, chunk.clone());
                 found_chunks.push(chunk);
             } else {
                 missing_chunks.push(*coord);
@@ -109,30 +139,50 @@ impl World {
     /// they are needed.
     pub fn pre_cache(&self, x: i32, z: i32, dimension: &str) -> Result<(), WorldError> {
         if self.cache.get(&(x, z, dimension.to_string())).is_none() {
-            let chunk = load_chunk_internal(self, x, z, dimension)?;
-            self.cache
-                .insert((x, z, dimension.to_string()), Arc::new(chunk));
+            self.load_chunk(x, z, dimension)?;
         }
         Ok(())
     }
+
+    /// Returns the unsaved version of a chunk, if it has one.
+    fn dirty_chunk(&self, key: &ChunkKey) -> Option<Arc<Chunk>> {
+        // Clone the chunk out so the map isn't locked while the caller touches the cache, whose
+        // eviction listener also uses the map.
+        self.dirty_chunks
+            .get(key)
+            .map(|entry| entry.value().clone())
+    }
 }
 
-pub(crate) fn save_chunk_internal(world: &World, chunk: &Chunk) -> Result<(), WorldError> {
-    if !world.storage_backend.table_exists("chunks".to_string())? {
-        world.storage_backend.create_table("chunks".to_string())?;
+pub(crate) fn save_chunk_internal(
+    storage_backend: &dyn StorageBackend,
+    chunk: &Chunk,
+) -> Result<(), WorldError> {
+    if !storage_backend.table_exists("chunks".to_string())? {
+        storage_backend.create_table("chunks".to_string())?;
     }
-    let as_bytes = yazi::compress(
-        &bitcode::encode(chunk),
-        yazi::Format::Zlib,
-        CompressionLevel::BestSpeed,
-    )?;
+    let as_bytes = encode_chunk(chunk)?;
     let digest = create_key(chunk.dimension.as_str(), chunk.x, chunk.z);
-    world
-        .storage_backend
-        .upsert("chunks".to_string(), digest, as_bytes)?;
+    storage_backend.upsert("chunks".to_string(), digest, as_bytes)?;
     Ok(())
 }
 
+/// Writes a dirty chunk to the storage backend and marks it as clean. Returns whether the chunk
+/// was dirty.
+pub(crate) fn flush_dirty_chunk(
+    storage_backend: &dyn StorageBackend,
+    dirty_chunks: &DirtyChunks,
+    key: &ChunkKey,
+) -> Result<bool, WorldError> {
+    let Some(chunk) = dirty_chunks.get(key).map(|entry| entry.value().clone()) else {
+        return Ok(false);
+    };
+    save_chunk_internal(storage_backend, &chunk)?;
+    // If the chunk was saved again while it was being written, it's still dirty.
+    dirty_chunks.remove_if(key, |_, current| Arc::ptr_eq(current, &chunk));
+    Ok(true)
+}
+
 pub(crate) fn load_chunk_internal(
     world: &World,
     x: i32,
@@ -141,22 +191,7 @@ pub(crate) fn load_chunk_internal(
 ) -> Result<Chunk, WorldError> {
     let digest = create_key(dimension, x, z);
     match world.storage_backend.get("chunks".to_string(), digest)? {
-        Some(compressed) => {
-            let (data, checksum) = yazi::decompress(compressed.as_slice(), yazi::Format::Zlib)?;
-            if get_global_config().database.verify_chunk_data {
-                if let Some(expected_checksum) = checksum {
-                    let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
-                    if real_checksum != expected_checksum {
-                        return Err(CorruptedChunkData(real_checksum, expected_checksum));
-                    }
-                } else {
-                    warn!("Chunk data does not have a checksum, skipping verification.");
-                }
-            }
-            let chunk: Chunk = bitcode::decode(&data)
-                .map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))?;
-            Ok(chunk)
-        }
+        Some(compressed) => decode_chunk(&compressed),
         None => Err(WorldError::ChunkNotFound),
     }
 }
@@ -174,22 +209,7 @@ pub(crate) fn load_chunk_batch_internal(
         .batch_get("chunks".to_string(), digests)?
         .iter()
         .map(|chunk| match chunk {
-            Some(compressed) => {
-                let (data, checksum) = yazi::decompress(compressed, yazi::Format::Zlib)?;
-                if get_global_config().database.verify_chunk_data {
-                    if let Some(expected_checksum) = checksum {
-                        let real_checksum = yazi::Adler32::from_buf(data.as_slice()).finish();
-                        if real_checksum != expected_checksum {
-                            return Err(CorruptedChunkData(real_checksum, expected_checksum));
-                        }
-                    } else {
-                        warn!("Chunk data does not have a checksum, skipping verification.");
-                    }
-                }
-                let chunk: Chunk = bitcode::decode(&data)
-                    .map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))?;
-                Ok(chunk)
-            }
+            Some(compressed) => decode_chunk(compressed),
             None => Err(WorldError::ChunkNotFound),
         })
         .collect()
@@ -224,7 +244,20 @@ pub(crate) fn sync_internal(world: &World) -> Result<(), WorldError> {
     Ok(())
 }
 
-fn create_key(dimension: &str, x: i32, z: i32) -> u128 {
+/// The part of a chunk key that identifies the dimension.
+pub(crate) fn dimension_hash(dimension: &str) -> u32 {
+    (create_key(dimension, 0, 0) >> 96) as u32
+}
+
+/// Splits a chunk key back into its dimension hash and chunk coordinates.
+pub(crate) fn split_key(key: u128) -> (u32, i32, i32) {
+    let dim_hash = (key >> 96) as u32;
+    let x = ((key >> 48) & 0xFFFF_FFFF) as u32 as i32;
+    let z = (key & 0xFFFF_FFFF) as u32 as i32;
+    (dim_hash, x, z)
+}
+
+pub(crate) fn create_key(dimension: &str, x: i32, z: i32) -> u128 {
     let mut key = 0u128;
     let mut hasher = wyhash::WyHash::with_seed(0);
     hasher.write(dimension.as_bytes());
@@ -239,3 +272,68 @@ fn create_key(dimension: &str, x: i32, z: i32) -> u128 {
 
     key
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+
+    #[test]
+    fn test_saved_chunks_are_written_on_sync() {
+        let backend = Arc::new(MemoryBackend::new());
+        let world = World::with_backend(backend.clone());
+        world
+            .save_chunk(Arc::new(Chunk::new(0, 0, "overworld".to_string())))
+            .unwrap();
+        assert_eq!(world.dirty_chunk_count(), 1);
+        assert!(load_chunk_internal(&world, 0, 0, "overworld").is_err());
+
+        world.sync().unwrap();
+        assert_eq!(world.dirty_chunk_count(), 0);
+        assert!(load_chunk_internal(&world, 0, 0, "overworld").is_ok());
+
+        // Nothing changed, so the next sync has nothing to write.
+        backend
+            .delete("chunks".to_string(), create_key("overworld", 0, 0))
+            .unwrap();
+        world.sync().unwrap();
+        assert!(load_chunk_internal(&world, 0, 0, "overworld").is_err());
+    }
+
+    #[test]
+    fn test_dirty_chunks_survive_leaving_the_cache() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        world
+            .save_chunk(Arc::new(Chunk::new(1, 2, "overworld".to_string())))
+            .unwrap();
+        world
+            .edit_chunk(1, 2, "overworld", |batch| {
+                batch.set_block(0, 10, 0, block!("stone"));
+            })
+            .unwrap();
+        world.cache.invalidate_all();
+
+        let chunk = world.load_chunk(1, 2, "overworld").unwrap();
+        assert_eq!(chunk.get_block(0, 10, 0).unwrap(), block!("stone"));
+        assert!(world.chunk_exists(1, 2, "overworld").unwrap());
+    }
+
+    #[test]
+    fn test_flush_dirty_chunk() {
+        let backend = MemoryBackend::new();
+        let dirty = DirtyChunks::new();
+        let key = (4, 5, "overworld".to_string());
+        assert!(!flush_dirty_chunk(&backend, &dirty, &key).unwrap());
+
+        dirty.insert(
+            key.clone(),
+            Arc::new(Chunk::new(4, 5, "overworld".to_string())),
+        );
+        assert!(flush_dirty_chunk(&backend, &dirty, &key).unwrap());
+        assert!(dirty.is_empty());
+        assert!(backend
+            .exists("chunks".to_string(), create_key("overworld", 4, 5))
+            .unwrap());
+    }
+}
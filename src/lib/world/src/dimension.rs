@@ -0,0 +1,161 @@
+use bitcode_derive::{Decode, Encode};
+use deepsize::DeepSizeOf;
+use ferrumc_data::generated::biomes::Biome;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The dimensions a chunk (or a player) can live in.
+///
+/// Chunks are keyed in storage by the string returned from [`Dimension::as_str`], so the
+/// names here must stay stable.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Encode,
+    Decode,
+    Serialize,
+    Deserialize,
+    DeepSizeOf,
+)]
+pub enum Dimension {
+    #[default]
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    /// Every dimension the server knows about, in the order they are sent to the client.
+    pub const ALL: [Dimension; 3] = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+
+    /// The name used as the dimension part of chunk keys, e.g. `"the_nether"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "the_nether",
+            Dimension::End => "the_end",
+        }
+    }
+
+    /// The namespaced identifier of the dimension, e.g. `"minecraft:the_nether"`.
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::Nether => "minecraft:the_nether",
+            Dimension::End => "minecraft:the_end",
+        }
+    }
+
+    /// The network id of this dimension's entry in the `minecraft:dimension_type` registry.
+    ///
+    /// This is the index of the entry in the registry data we send during configuration.
+    pub fn dimension_type_id(&self) -> i32 {
+        match self {
+            Dimension::Overworld => 0,
+            Dimension::Nether => 3,
+            Dimension::End => 2,
+        }
+    }
+
+    /// The lowest buildable Y level.
+    pub fn min_y(&self) -> i32 {
+        match self {
+            Dimension::Overworld => -64,
+            Dimension::Nether | Dimension::End => 0,
+        }
+    }
+
+    /// The total buildable height, in blocks.
+    pub fn height(&self) -> i32 {
+        match self {
+            Dimension::Overworld => 384,
+            Dimension::Nether | Dimension::End => 256,
+        }
+    }
+
+    /// The range of section Y indices a chunk in this dimension has.
+    pub fn section_range(&self) -> std::ops::Range<i8> {
+        let min = (self.min_y() >> 4) as i8;
+        min..min + (self.height() >> 4) as i8
+    }
+
+    /// The Y level of the dimension's sea surface, as told to the client.
+    pub fn sea_level(&self) -> i32 {
+        match self {
+            Dimension::Overworld => 63,
+            Dimension::Nether => 32,
+            Dimension::End => 0,
+        }
+    }
+
+    /// The biome new chunks in this dimension start out with.
+    pub fn default_biome(&self) -> &'static Biome {
+        match self {
+            Dimension::Overworld => &Biome::PLAINS,
+            Dimension::Nether => &Biome::NETHER_WASTES,
+            Dimension::End => &Biome::THE_END,
+        }
+    }
+
+    /// Whether the dimension has sky light.
+    pub fn has_skylight(&self) -> bool {
+        matches!(self, Dimension::Overworld)
+    }
+
+    /// Parses a dimension from either its plain name (`"the_nether"`) or its namespaced
+    /// identifier (`"minecraft:the_nether"`). A few common aliases are also accepted.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        match name.to_lowercase().as_str() {
+            "overworld" => Some(Dimension::Overworld),
+            "the_nether" | "nether" => Some(Dimension::Nether),
+            "the_end" | "end" => Some(Dimension::End),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Dimension {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Dimension::from_name(s).ok_or_else(|| format!("Unknown dimension: {s}"))
+    }
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for dimension in Dimension::ALL {
+            assert_eq!(Dimension::from_name(dimension.as_str()), Some(dimension));
+            assert_eq!(
+                Dimension::from_name(dimension.identifier()),
+                Some(dimension)
+            );
+        }
+        assert_eq!(Dimension::from_name("nether"), Some(Dimension::Nether));
+        assert_eq!(Dimension::from_name("mars"), None);
+    }
+
+    #[test]
+    fn test_section_ranges() {
+        assert_eq!(Dimension::Overworld.section_range(), -4..20);
+        assert_eq!(Dimension::Nether.section_range(), 0..16);
+        assert_eq!(Dimension::End.section_range(), 0..16);
+    }
+}
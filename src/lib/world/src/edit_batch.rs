@@ -1,5 +1,6 @@
 use crate::block_state_id::BlockStateId;
 use crate::chunk_format::{BiomeStates, BlockStates, Chunk, PaletteType};
+use crate::dimension::Dimension;
 use crate::WorldError;
 use ahash::{AHashMap, AHashSet, AHasher};
 use ferrumc_general_purpose::data_packing::i32::read_nbit_i32;
@@ -8,6 +9,10 @@ use ferrumc_net_codec::net_types::var_int::VarInt;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 
+/// Batches that change more blocks than this relight the whole chunk and recalculate its
+/// heightmaps instead of updating them around each changed block.
+const INCREMENTAL_UPDATE_LIMIT: usize = 64;
+
 /// A batched block editing utility for a single Minecraft chunk.
 ///
 /// `EditBatch` lets you queue many block edits and apply them all at once with high efficiency.
@@ -79,7 +84,7 @@ impl<'a> EditBatch<'a> {
 
     /// Applies all edits in the batch to the chunk.
     ///
-    /// This will modify the chunk in place and clear the batch.
+    /// This will modify the chunk in place, update its light and heightmaps and clear the batch.
     /// Will return an error if the batch has already been used or if there are no edits.
     pub fn apply(&mut self) -> Result<(), WorldError> {
         if self.used {
@@ -109,6 +114,7 @@ impl<'a> EditBatch<'a> {
             all_blocks.insert(&edit.block);
         }
 
+        let mut changed = Vec::new();
         for (section_y, edits_vec) in section_edits {
             if edits_vec.is_empty() || edits_vec.iter().all(|e| e.is_none()) {
                 continue;
@@ -137,12 +143,12 @@ impl<'a> EditBatch<'a> {
                             block_data: PaletteType::Single(VarInt::default()),
                             block_counts: HashMap::from([(BlockStateId::default(), 4096)]),
                         },
-                        // Biomes don't really matter for this, so we can just use empty data
-                        biome_states: BiomeStates {
-                            bits_per_biome: 0,
-                            data: vec![],
-                            palette: vec![],
-                        },
+                        biome_states: BiomeStates::single(
+                            Dimension::from_name(&self.chunk.dimension)
+                                .unwrap_or_default()
+                                .default_biome()
+                                .id,
+                        ),
                         block_light: vec![255; 2048],
                         sky_light: vec![255; 2048],
                     };
@@ -245,6 +251,7 @@ impl<'a> EditBatch<'a> {
                 if old_block_index == palette_index as i32 {
                     continue;
                 }
+                changed.push((edit.x, edit.y, edit.z, edit.block));
 
                 if let Some(old_block_state_id) = palette.get(old_block_index as usize) {
                     if let Some(count) =
@@ -288,11 +295,7 @@ impl<'a> EditBatch<'a> {
                 *current_count -= count;
             }
 
-            section.block_states.non_air_blocks = *section
-                .block_states
-                .block_counts
-                .get(&BlockStateId::default())
-                .unwrap_or(&4096) as u16;
+            section.block_states.non_air_blocks = section.block_states.count_non_air_blocks();
 
             // Only optimise if the palette changed after edits
             if get_palette_hash(palette) != palette_hash {
@@ -300,6 +303,16 @@ impl<'a> EditBatch<'a> {
             }
         }
 
+        if changed.len() > INCREMENTAL_UPDATE_LIMIT {
+            self.chunk.calculate_light()?;
+            self.chunk.calculate_heightmaps()?;
+        } else {
+            for (x, y, z, block) in changed {
+                self.chunk.update_light(x, y, z)?;
+                self.chunk.update_heightmaps(x, y, z, block)?;
+            }
+        }
+
         // Clear edits after applying
         self.edits.clear();
         self.used = true;
@@ -361,5 +374,7 @@ mod tests {
                 }
             }
         }
+        let section = chunk.sections.iter().find(|s| s.y == 0).unwrap();
+        assert_eq!(section.block_states.non_air_blocks, 64);
     }
 }
@@ -1,5 +1,6 @@
 use crate::block_state_id::{BlockStateId, ID2BLOCK};
-use crate::chunk_format::{BlockStates, Chunk, PaletteType, Section};
+use crate::chunk_format::{BiomeStates, BlockStates, Chunk, PaletteType, Section};
+use crate::edit_batch::EditBatch;
 use crate::errors::WorldError;
 use crate::World;
 use ferrumc_general_purpose::data_packing::i32::read_nbit_i32;
@@ -78,6 +79,7 @@ impl World {
         debug!("Chunk: {}, {}", chunk_x, chunk_z);
 
         chunk.set_block(x, y, z, block)?;
+        chunk.update_light(x, y, z)?;
         for section in &mut chunk.sections {
             section.optimise()?;
         }
@@ -86,6 +88,33 @@ impl World {
         self.save_chunk(Arc::new(chunk))?;
         Ok(())
     }
+
+    /// Edits a chunk through an [EditBatch] and saves it, marking it as dirty so it gets written
+    /// on the next sync.
+    ///
+    /// Coordinates given to the batch are relative to the chunk. If `edit` doesn't queue any
+    /// edits, the chunk is left untouched and isn't saved.
+    ///
+    /// # Errors
+    ///
+    /// * `WorldError::ChunkNotFound` - If the chunk doesn't exist.
+    /// * Anything [EditBatch::apply] can return.
+    pub fn edit_chunk(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        dimension: &str,
+        edit: impl FnOnce(&mut EditBatch),
+    ) -> Result<(), WorldError> {
+        let mut chunk = self.load_chunk_owned(chunk_x, chunk_z, dimension)?;
+        let mut batch = EditBatch::new(&mut chunk);
+        edit(&mut batch);
+        if batch.edits.is_empty() {
+            return Ok(());
+        }
+        batch.apply()?;
+        self.save_chunk(Arc::new(chunk))
+    }
 }
 
 impl BlockStates {
@@ -195,6 +224,10 @@ impl Chunk {
     /// If the block is not in the palette, it is added.
     /// If the palette is in single block mode, it is converted to palette'd mode.
     ///
+    /// The light isn't updated, so that setting many blocks doesn't relight the chunk each time.
+    /// Call [Chunk::update_light] for the block once it's set, or use an
+    /// [EditBatch](crate::edit_batch::EditBatch), which relights once for all of its edits.
+    ///
     /// # Arguments
     ///
     /// * `x` - The x-coordinate of the block.
@@ -312,8 +345,7 @@ impl Chunk {
                     });
                 // Set block
                 let blocks_per_i64 = (64f64 / *bits_per_block as f64).floor() as usize;
-                let index =
-                    ((y.abs() & 0xf) * 256 + (z.abs() & 0xf) * 16 + (x.abs() & 0xf)) as usize;
+                let index = ((y & 0xf) * 256 + (z & 0xf) * 16 + (x & 0xf)) as usize;
                 let i64_index = index / blocks_per_i64;
                 let packed_u64 =
                     data.get_mut(i64_index)
@@ -337,21 +369,12 @@ impl Chunk {
             }
         }
 
-        section.block_states.non_air_blocks = section
-            .block_states
-            .block_counts
-            .iter()
-            .filter(|(block, _)| {
-                // Air, void air and cave air respectively
-                ![0, 12958, 12959].contains(&block.0)
-            })
-            .map(|(_, count)| *count as u16)
-            .sum();
+        section.block_states.non_air_blocks = section.block_states.count_non_air_blocks();
 
         self.sections
             .iter_mut()
             .for_each(|section| section.optimise().unwrap());
-        Ok(())
+        self.update_heightmaps(x, y, z, block)
     }
 
     /// Gets the block at the specified coordinates.
@@ -375,7 +398,7 @@ impl Chunk {
         let section = self
             .sections
             .iter()
-            .find(|section| section.y == (y / 16) as i8)
+            .find(|section| section.y == (y >> 4) as i8)
             .ok_or(WorldError::SectionOutOfBounds(y >> 4))?;
         match &section.block_states.block_data {
             PaletteType::Single(val) => Ok(BlockStateId::from_varint(*val)),
@@ -426,7 +449,8 @@ impl Chunk {
             .iter_mut()
             .find(|section| section.y == section_y)
         {
-            section.fill(block)
+            section.fill(block)?;
+            self.update_heightmaps_for_section(section_y, block)
         } else {
             Err(WorldError::SectionOutOfBounds(section_y as i32))
         }
@@ -446,8 +470,88 @@ impl Chunk {
         for section in &mut self.sections {
             section.fill(block)?;
         }
+        self.calculate_heightmaps()
+    }
+
+    /// Gets the id of the biome at the specified coordinates.
+    ///
+    /// Biomes are stored in 4x4x4 cells, so every block in the same cell has the same biome.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the block.
+    /// * `y` - The y-coordinate of the block.
+    /// * `z` - The z-coordinate of the block.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u16)` - The network id of the biome, see [`ferrumc_data::generated::biomes::Biome`].
+    /// * `Err(WorldError)` - If the section is out of bounds or its biome data is invalid.
+    pub fn get_biome(&self, x: i32, y: i32, z: i32) -> Result<u16, WorldError> {
+        let section = self
+            .sections
+            .iter()
+            .find(|section| section.y == (y >> 4) as i8)
+            .ok_or(WorldError::SectionOutOfBounds(y >> 4))?;
+        section.biome_states.get_biome(x, y, z)
+    }
+
+    /// Sets the biome of the 4x4x4 cell containing the specified coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the block.
+    /// * `y` - The y-coordinate of the block.
+    /// * `z` - The z-coordinate of the block.
+    /// * `biome_id` - The network id of the biome to set.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the biome was successfully set.
+    /// * `Err(WorldError)` - If the section is out of bounds or its biome data is invalid.
+    pub fn set_biome(&mut self, x: i32, y: i32, z: i32, biome_id: u16) -> Result<(), WorldError> {
+        let section = self
+            .sections
+            .iter_mut()
+            .find(|section| section.y == (y >> 4) as i8)
+            .ok_or(WorldError::SectionOutOfBounds(y >> 4))?;
+        section.biome_states.set_biome(x, y, z, biome_id)
+    }
+
+    /// Sets the biome of the whole 4x4 column of cells containing the specified x and z
+    /// coordinates, from the bottom of the chunk to the top.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the column.
+    /// * `z` - The z-coordinate of the column.
+    /// * `biome_id` - The network id of the biome to set.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the biome was successfully set.
+    /// * `Err(WorldError)` - If the biome data of a section is invalid.
+    pub fn set_biome_column(&mut self, x: i32, z: i32, biome_id: u16) -> Result<(), WorldError> {
+        for section in &mut self.sections {
+            let mut ids = section.biome_states.to_ids()?;
+            for y in (0..16).step_by(4) {
+                ids[BiomeStates::cell_index(x, y, z)] = biome_id;
+            }
+            section.biome_states = BiomeStates::from_ids(&ids)?;
+        }
         Ok(())
     }
+
+    /// Fills the whole chunk with the specified biome.
+    ///
+    /// # Arguments
+    ///
+    /// * `biome_id` - The network id of the biome to fill the chunk with.
+    pub fn fill_biome(&mut self, biome_id: u16) {
+        for section in &mut self.sections {
+            section.biome_states = BiomeStates::single(biome_id);
+        }
+    }
 }
 
 impl Section {
@@ -36,6 +36,12 @@ pub enum WorldError {
     ChunkNotFound,
     #[error("Anvil Decode Error: {0}")]
     AnvilDecodeError(AnvilError),
+    #[error("Anvil Encode Error: {0}")]
+    AnvilEncodeError(AnvilError),
+    #[error("Invalid Export Path: {0}")]
+    InvalidExportPath(String),
+    #[error("{0} of {1} region files failed to export")]
+    ExportFailed(usize, usize),
     #[error("Missing block mapping: {0}")]
     MissingBlockMapping(BlockStateId),
     #[error("Invalid memory map size: {0}")]
@@ -50,6 +56,8 @@ pub enum WorldError {
     InvalidBlock(BlockStateId),
     #[error("Invalid batching operation: {0}")]
     InvalidBatchingOperation(String),
+    #[error("Invalid biome data: {0}")]
+    InvalidBiomeData(String),
     #[error("Invalid block state ID: {0}")]
     InvalidBlockStateId(u32),
     #[error("World generation error: {0}")]
@@ -60,6 +68,18 @@ pub enum WorldError {
     DecompressionError(String),
     #[error("Corrupted chunk data: got checksum {0}, expected checksum {1}")]
     CorruptedChunkData(u32, u32),
+    #[error("Chunk format version {0} is newer than the newest supported version {1}")]
+    UnsupportedChunkVersion(u16, u16),
+    #[error("No migration registered from chunk format version {0}")]
+    MissingChunkMigration(u16),
+    #[error("A backup is already in progress")]
+    BackupInProgress,
+    #[error("Pre-generation radius {0} reaches past the world border")]
+    InvalidPregenRadius(u32),
+    #[error("Invalid schematic: {0}")]
+    InvalidSchematic(String),
+    #[error("Invalid structure template: {0}")]
+    InvalidStructure(String),
     #[error("NBT data error: {0}")]
     NBTError(#[from] ferrumc_nbt::errors::NBTError),
 }
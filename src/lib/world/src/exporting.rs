@@ -0,0 +1,135 @@
+use crate::db_functions::{dimension_hash, load_chunk_internal, split_key};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::{progress_bar, World};
+use ferrumc_anvil::write_anvil_file;
+use ferrumc_nbt::{NBTSerializable, NBTSerializeOptions};
+use ferrumc_threadpool::ThreadPool;
+use std::collections::HashMap;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+impl World {
+    /// Exports every stored chunk to vanilla Anvil region files.
+    ///
+    /// The chunks are laid out the same way vanilla does it, so `export_dir` can be used as a
+    /// world folder (or imported again with [`World::import`]):
+    /// - Overworld chunks go in `region/`
+    /// - Nether chunks go in `DIM-1/region/`
+    /// - End chunks go in `DIM1/region/`
+    ///
+    /// Existing region files in the export directory will be overwritten. Every region is
+    /// attempted even if some fail, but the export only succeeds if they all did.
+    pub fn export(&self, export_dir: PathBuf, threadpool: ThreadPool) -> Result<(), WorldError> {
+        check_export_path_validity(&export_dir)?;
+
+        // Make sure anything only in the cache makes it into the export
+        self.sync()?;
+
+        if !self.storage_backend.table_exists("chunks".to_string())? {
+            warn!("There are no chunks to export.");
+            return Ok(());
+        }
+
+        let keys = self.storage_backend.get_all_keys("chunks".to_string())?;
+        let total_chunks = keys.len() as u64;
+
+        // Group the chunks by the region file they belong in. The coordinates and dimension are
+        // recovered from the keys so we don't have to decode every chunk twice.
+        let dimension_hashes = Dimension::ALL.map(|dim| (dimension_hash(dim.as_str()), dim));
+        let mut regions: HashMap<(Dimension, i32, i32), Vec<(i32, i32)>> = HashMap::new();
+        for key in keys {
+            let (dim_hash, x, z) = split_key(key);
+            let Some((_, dimension)) = dimension_hashes.iter().find(|(hash, _)| *hash == dim_hash)
+            else {
+                warn!("Skipping chunk ({}, {}) in an unknown dimension", x, z);
+                continue;
+            };
+            regions
+                .entry((*dimension, x >> 5, z >> 5))
+                .or_default()
+                .push((x, z));
+        }
+
+        let progress = progress_bar(total_chunks, "Exporting chunks...");
+
+        let start = std::time::Instant::now();
+
+        for dimension in Dimension::ALL {
+            if regions.keys().any(|(dim, _, _)| *dim == dimension) {
+                create_dir_all(region_dir(&export_dir, dimension))?;
+            }
+        }
+
+        let total_regions = regions.len();
+        let mut batch = threadpool.batch();
+        let arc_self = Arc::new(self.clone());
+
+        for ((dimension, region_x, region_z), coords) in regions {
+            batch.execute({
+                let self_clone = arc_self.clone();
+                let progress = progress.clone();
+                let file_path =
+                    region_dir(&export_dir, dimension).join(format!("r.{region_x}.{region_z}.mca"));
+                move || -> Result<(), WorldError> {
+                    let mut chunks = Vec::with_capacity(coords.len());
+                    for (x, z) in coords {
+                        let chunk = load_chunk_internal(&self_clone, x, z, dimension.as_str())?;
+                        let mut nbt = Vec::new();
+                        chunk
+                            .to_vanilla_format()?
+                            .serialize(&mut nbt, &NBTSerializeOptions::WithHeader(""));
+                        chunks.push((x as u32, z as u32, nbt));
+                        progress.inc(1);
+                    }
+                    write_anvil_file(file_path, &chunks).map_err(WorldError::AnvilEncodeError)
+                }
+            })
+        }
+
+        let mut failed_regions = 0;
+        for result in batch.wait() {
+            if let Err(e) = result {
+                error!("Error exporting region: {}", e);
+                failed_regions += 1;
+            }
+        }
+
+        if failed_regions > 0 {
+            progress.abandon_with_message("Export failed");
+            return Err(WorldError::ExportFailed(failed_regions, total_regions));
+        }
+        progress.finish_with_message("Export complete");
+
+        info!(
+            "Exported {} chunks in {:?}",
+            progress.position(),
+            start.elapsed()
+        );
+
+        Ok(())
+    }
+}
+
+/// The directory vanilla keeps a dimension's region files in, relative to the world folder.
+fn region_dir(export_dir: &Path, dimension: Dimension) -> PathBuf {
+    match dimension {
+        Dimension::Overworld => export_dir.join("region"),
+        Dimension::Nether => export_dir.join("DIM-1").join("region"),
+        Dimension::End => export_dir.join("DIM1").join("region"),
+    }
+}
+
+fn check_export_path_validity(export_dir: &Path) -> Result<(), WorldError> {
+    if export_dir.is_file() {
+        return Err(WorldError::InvalidExportPath(
+            export_dir.display().to_string(),
+        ));
+    }
+    if !export_dir.exists() {
+        create_dir_all(export_dir)?;
+    }
+    Ok(())
+}
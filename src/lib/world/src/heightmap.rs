@@ -0,0 +1,396 @@
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::chunk_format::{Chunk, Heightmaps, PaletteType, Section};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use ferrumc_data::blocks::BlockState;
+use ferrumc_general_purpose::data_packing::u32::{read_nbit_u32, write_nbit_u32};
+use lazy_static::lazy_static;
+use std::cmp::Reverse;
+
+const WORLD_SURFACE_FLAG: u8 = 1;
+const MOTION_BLOCKING_FLAG: u8 = 1 << 1;
+
+lazy_static! {
+    /// Which heightmaps each block state counts towards, indexed by block state id.
+    static ref HEIGHTMAP_FLAGS: Vec<u8> = ID2BLOCK
+        .iter()
+        .enumerate()
+        .map(|(id, block_data)| {
+            let name = block_data.name.trim_start_matches("minecraft:");
+            let mut flags = 0;
+            if !matches!(name, "air" | "void_air" | "cave_air") {
+                flags |= WORLD_SURFACE_FLAG;
+            }
+            let collides = BlockState::by_id(id as u32)
+                .is_some_and(|state| !state.collision_shapes.is_empty());
+            let has_fluid = matches!(name, "water" | "lava" | "bubble_column")
+                || block_data
+                    .properties
+                    .as_ref()
+                    .and_then(|properties| properties.get("waterlogged"))
+                    .is_some_and(|waterlogged| waterlogged == "true");
+            if collides || has_fluid {
+                flags |= MOTION_BLOCKING_FLAG;
+            }
+            flags
+        })
+        .collect();
+}
+
+/// The heightmaps we keep track of for each chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightmapType {
+    /// The highest block that isn't air. The client uses this to decide where rain and snow stop.
+    WorldSurface,
+    /// The highest block that blocks movement or has a fluid in it.
+    MotionBlocking,
+}
+
+impl HeightmapType {
+    /// Every heightmap type that is stored in a chunk.
+    pub const ALL: [HeightmapType; 2] =
+        [HeightmapType::WorldSurface, HeightmapType::MotionBlocking];
+
+    /// Whether the given block counts towards this heightmap.
+    pub fn includes(&self, block: BlockStateId) -> bool {
+        let flag = match self {
+            HeightmapType::WorldSurface => WORLD_SURFACE_FLAG,
+            HeightmapType::MotionBlocking => MOTION_BLOCKING_FLAG,
+        };
+        HEIGHTMAP_FLAGS
+            .get(block.0 as usize)
+            .is_some_and(|flags| flags & flag != 0)
+    }
+}
+
+impl Heightmaps {
+    /// Creates heightmaps for a dimension of the given height where every column is empty.
+    pub fn empty(height: i32) -> Self {
+        let longs = 256usize.div_ceil(64 / Self::bits_per_entry(height) as usize);
+        Heightmaps {
+            motion_blocking: vec![0; longs],
+            world_surface: vec![0; longs],
+        }
+    }
+
+    /// Vanilla stores each column as the number of blocks from the bottom of the world to the top
+    /// of the highest block, so it needs enough bits to fit the height plus one.
+    fn bits_per_entry(height: i32) -> u8 {
+        ((height + 1) as f32).log2().ceil() as u8
+    }
+
+    fn data(&self, heightmap_type: HeightmapType) -> &Vec<i64> {
+        match heightmap_type {
+            HeightmapType::WorldSurface => &self.world_surface,
+            HeightmapType::MotionBlocking => &self.motion_blocking,
+        }
+    }
+
+    fn data_mut(&mut self, heightmap_type: HeightmapType) -> &mut Vec<i64> {
+        match heightmap_type {
+            HeightmapType::WorldSurface => &mut self.world_surface,
+            HeightmapType::MotionBlocking => &mut self.motion_blocking,
+        }
+    }
+
+    /// Whether both heightmaps have the right amount of data for a dimension of the given height.
+    fn is_valid(&self, height: i32) -> bool {
+        let longs = 256usize.div_ceil(64 / Self::bits_per_entry(height) as usize);
+        self.world_surface.len() == longs && self.motion_blocking.len() == longs
+    }
+
+    fn get(&self, heightmap_type: HeightmapType, height: i32, x: i32, z: i32) -> u32 {
+        let bits = Self::bits_per_entry(height);
+        let per_long = 64 / bits as usize;
+        let index = ((z & 0xF) * 16 + (x & 0xF)) as usize;
+        self.data(heightmap_type)
+            .get(index / per_long)
+            .and_then(|packed| {
+                read_nbit_u32(packed, bits, ((index % per_long) * bits as usize) as u32).ok()
+            })
+            .unwrap_or(0)
+    }
+
+    fn set(
+        &mut self,
+        heightmap_type: HeightmapType,
+        height: i32,
+        x: i32,
+        z: i32,
+        value: u32,
+    ) -> Result<(), WorldError> {
+        let bits = Self::bits_per_entry(height);
+        let per_long = 64 / bits as usize;
+        let index = ((z & 0xF) * 16 + (x & 0xF)) as usize;
+        let packed = self
+            .data_mut(heightmap_type)
+            .get_mut(index / per_long)
+            .ok_or_else(|| {
+                WorldError::InvalidBlockStateData(format!(
+                    "Missing heightmap data at index {index}"
+                ))
+            })?;
+        write_nbit_u32(
+            packed,
+            ((index % per_long) * bits as usize) as u32,
+            value,
+            bits,
+        )?;
+        Ok(())
+    }
+}
+
+impl Chunk {
+    /// Gets the Y level of the highest block in a column that counts towards the given heightmap.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the column.
+    /// * `z` - The z-coordinate of the column.
+    /// * `heightmap_type` - Which blocks count, see [`HeightmapType`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(i32))` - The Y level of the highest block.
+    /// * `Ok(None)` - If no block in the column counts towards the heightmap.
+    /// * `Err(WorldError)` - If the heightmaps were missing and the column couldn't be read.
+    pub fn highest_block(
+        &self,
+        x: i32,
+        z: i32,
+        heightmap_type: HeightmapType,
+    ) -> Result<Option<i32>, WorldError> {
+        let dimension = self.dimension_kind();
+        if !self.heightmaps.is_valid(dimension.height()) {
+            return self.scan_column(x, z, i32::MAX, heightmap_type);
+        }
+        let value = self
+            .heightmaps
+            .get(heightmap_type, dimension.height(), x, z);
+        Ok((value != 0).then(|| dimension.min_y() + value as i32 - 1))
+    }
+
+    /// Recalculates both heightmaps for every column in the chunk.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the heightmaps were successfully calculated.
+    /// * `Err(WorldError)` - If the block data of a section is invalid.
+    pub fn calculate_heightmaps(&mut self) -> Result<(), WorldError> {
+        let dimension = self.dimension_kind();
+        let mut heightmaps = Heightmaps::empty(dimension.height());
+        for heightmap_type in HeightmapType::ALL {
+            for x in 0..16 {
+                for z in 0..16 {
+                    let top = self.scan_column(x, z, i32::MAX, heightmap_type)?;
+                    heightmaps.set(
+                        heightmap_type,
+                        dimension.height(),
+                        x,
+                        z,
+                        Self::heightmap_value(dimension, top),
+                    )?;
+                }
+            }
+        }
+        self.heightmaps = heightmaps;
+        Ok(())
+    }
+
+    /// Updates the heightmaps after the block at the given position has been set.
+    ///
+    /// Only the block's column is looked at, and only if the block was or now is the highest one.
+    pub(crate) fn update_heightmaps(
+        &mut self,
+        x: i32,
+        y: i32,
+        z: i32,
+        block: BlockStateId,
+    ) -> Result<(), WorldError> {
+        let dimension = self.dimension_kind();
+        if !self.heightmaps.is_valid(dimension.height()) {
+            return self.calculate_heightmaps();
+        }
+        for heightmap_type in HeightmapType::ALL {
+            let top = self.highest_block(x, z, heightmap_type)?;
+            let new_top = if heightmap_type.includes(block) {
+                if top.is_some_and(|top| top >= y) {
+                    continue;
+                }
+                Some(y)
+            } else if top == Some(y) {
+                // The highest block was removed, so look for the next one down
+                self.scan_column(x, z, y, heightmap_type)?
+            } else {
+                continue;
+            };
+            self.heightmaps.set(
+                heightmap_type,
+                dimension.height(),
+                x,
+                z,
+                Self::heightmap_value(dimension, new_top),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Updates the heightmaps after a whole section has been filled with one block.
+    pub(crate) fn update_heightmaps_for_section(
+        &mut self,
+        section_y: i8,
+        block: BlockStateId,
+    ) -> Result<(), WorldError> {
+        let dimension = self.dimension_kind();
+        if !self.heightmaps.is_valid(dimension.height()) {
+            return self.calculate_heightmaps();
+        }
+        let bottom = i32::from(section_y) * 16;
+        let top = bottom + 15;
+        for heightmap_type in HeightmapType::ALL {
+            let included = heightmap_type.includes(block);
+            for x in 0..16 {
+                for z in 0..16 {
+                    let current = self.highest_block(x, z, heightmap_type)?;
+                    let new_top = if included {
+                        if current.is_some_and(|current| current >= top) {
+                            continue;
+                        }
+                        Some(top)
+                    } else if current.is_some_and(|current| (bottom..=top).contains(&current)) {
+                        self.scan_column(x, z, bottom, heightmap_type)?
+                    } else {
+                        continue;
+                    };
+                    self.heightmaps.set(
+                        heightmap_type,
+                        dimension.height(),
+                        x,
+                        z,
+                        Self::heightmap_value(dimension, new_top),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn dimension_kind(&self) -> Dimension {
+        Dimension::from_name(&self.dimension).unwrap_or_default()
+    }
+
+    fn heightmap_value(dimension: Dimension, top: Option<i32>) -> u32 {
+        top.map_or(0, |top| (top - dimension.min_y() + 1).max(0) as u32)
+    }
+
+    /// Finds the highest block below `below` in a column that counts towards the heightmap.
+    fn scan_column(
+        &self,
+        x: i32,
+        z: i32,
+        below: i32,
+        heightmap_type: HeightmapType,
+    ) -> Result<Option<i32>, WorldError> {
+        let mut sections: Vec<&Section> = self
+            .sections
+            .iter()
+            .filter(|section| i32::from(section.y) * 16 < below)
+            .collect();
+        sections.sort_by_key(|section| Reverse(section.y));
+        for section in sections {
+            let bottom = i32::from(section.y) * 16;
+            let top = (bottom + 15).min(below - 1);
+            // Sections of a single block can be checked all at once
+            if let PaletteType::Single(id) = &section.block_states.block_data {
+                if heightmap_type.includes(BlockStateId::from_varint(*id)) {
+                    return Ok(Some(top));
+                }
+                continue;
+            }
+            for y in (bottom..=top).rev() {
+                if heightmap_type.includes(self.get_block(x, y, z)?) {
+                    return Ok(Some(y));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_batch::EditBatch;
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_new_chunk_is_empty() {
+        let chunk = Chunk::new(0, 0, "overworld".to_string());
+        for heightmap_type in HeightmapType::ALL {
+            assert_eq!(chunk.highest_block(0, 0, heightmap_type).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_set_block_updates_heightmaps() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        chunk.set_section(-4, block!("stone")).unwrap();
+        assert_eq!(
+            chunk
+                .highest_block(3, 3, HeightmapType::WorldSurface)
+                .unwrap(),
+            Some(-49)
+        );
+
+        chunk.set_block(3, 70, 3, block!("stone")).unwrap();
+        chunk.set_block(3, 80, 3, block!("short_grass")).unwrap();
+        assert_eq!(
+            chunk
+                .highest_block(3, 3, HeightmapType::WorldSurface)
+                .unwrap(),
+            Some(80)
+        );
+        // Grass doesn't stop anything from moving through it
+        assert_eq!(
+            chunk
+                .highest_block(3, 3, HeightmapType::MotionBlocking)
+                .unwrap(),
+            Some(70)
+        );
+
+        chunk.set_block(3, 80, 3, block!("air")).unwrap();
+        chunk.set_block(3, 70, 3, block!("air")).unwrap();
+        assert_eq!(
+            chunk
+                .highest_block(3, 3, HeightmapType::WorldSurface)
+                .unwrap(),
+            Some(-49)
+        );
+    }
+
+    #[test]
+    fn test_edit_batch_updates_heightmaps() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        let mut batch = EditBatch::new(&mut chunk);
+        for x in 0..16 {
+            for z in 0..16 {
+                batch.set_block(x, x + z, z, block!("dirt"));
+            }
+        }
+        batch.apply().unwrap();
+        for x in 0..16 {
+            for z in 0..16 {
+                assert_eq!(
+                    chunk
+                        .highest_block(x, z, HeightmapType::MotionBlocking)
+                        .unwrap(),
+                    Some(x + z)
+                );
+            }
+        }
+
+        let mut recalculated = chunk.clone();
+        recalculated.calculate_heightmaps().unwrap();
+        assert_eq!(recalculated.heightmaps, chunk.heightmaps);
+    }
+}
@@ -1,9 +1,9 @@
+use crate::db_functions::save_chunk_internal;
 use crate::errors::WorldError;
 use crate::vanilla_chunk_format::VanillaChunk;
-use crate::World;
+use crate::{progress_bar, World};
 use ferrumc_anvil::load_anvil_file;
 use ferrumc_threadpool::ThreadPool;
-use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -42,14 +42,7 @@ impl World {
         check_paths_validity(&import_dir)?;
 
         let total_chunks = self.get_chunk_count(&import_dir)?;
-        let progress_style = ProgressStyle::default_bar()
-            .template("[{elapsed_precise}/{eta_precise} eta] {bar:40.cyan/blue} {percent}%, {pos:>7}/{len:7}, {msg}")
-            .unwrap();
-
-        let progress = ProgressBar::new(total_chunks);
-        progress.set_style(progress_style);
-
-        progress.set_message("Setting up database and preparing import...");
+        let progress = progress_bar(total_chunks, "Setting up database and preparing import...");
 
         self.storage_backend.create_table("chunks".to_string())?;
 
@@ -91,8 +84,12 @@ impl World {
                             let self_clone = arc_self.clone();
                             let progress = progress.clone();
                             move || {
-                                let res =
-                                    self_clone.save_chunk(vanilla_chunk.to_custom_format()?.into());
+                                // Write straight to the backend, there's no point keeping every
+                                // imported chunk around as dirty until the next sync.
+                                let res = save_chunk_internal(
+                                    self_clone.storage_backend.as_ref(),
+                                    &vanilla_chunk.to_custom_format()?,
+                                );
                                 progress.inc(1);
                                 if index == location_count - 1 {
                                     self_clone.storage_backend.flush()?;
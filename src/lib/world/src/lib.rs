@@ -1,30 +1,71 @@
+mod backups;
+pub mod block_log;
 pub mod block_state_id;
 pub mod chunk_format;
+mod compression;
 mod db_functions;
+pub mod dimension;
 pub mod edit_batch;
 pub mod edits;
 pub mod errors;
+mod exporting;
+pub mod heightmap;
 mod importing;
+pub mod lighting;
+pub mod metadata;
+pub mod migrations;
+pub mod pregen;
+pub mod region;
+pub mod schematic;
+pub mod structure;
 pub mod vanilla_chunk_format;
+pub mod verify;
 
+use crate::block_log::BlockChange;
 use crate::chunk_format::Chunk;
 use crate::errors::WorldError;
+use dashmap::DashMap;
 use deepsize::DeepSizeOf;
 use ferrumc_config::server_config::get_global_config;
+use ferrumc_config::DatabaseBackend;
 use ferrumc_general_purpose::paths::get_root_path;
 use ferrumc_storage::lmdb::LmdbBackend;
+use ferrumc_storage::memory::MemoryBackend;
+use ferrumc_storage::StorageBackend;
+use indicatif::{ProgressBar, ProgressStyle};
+use moka::notification::RemovalCause;
 use moka::sync::Cache;
+use std::borrow::Cow;
 use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
 use std::process::exit;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tracing::{error, trace, warn};
 
+/// Chunk coordinates and dimension, as used to key the chunk cache.
+pub(crate) type ChunkKey = (i32, i32, String);
+/// Chunks that have been saved but not written to the storage backend yet.
+pub(crate) type DirtyChunks = DashMap<ChunkKey, Arc<Chunk>>;
+
+/// The progress bar shown while working through `len` chunks in a long-running task like an
+/// import, export or pre-generation, starting out showing `message`.
+pub(crate) fn progress_bar(len: u64, message: impl Into<Cow<'static, str>>) -> ProgressBar {
+    let style = ProgressStyle::default_bar()
+        .template("[{elapsed_precise}/{eta_precise} eta] {bar:40.cyan/blue} {percent}%, {pos:>7}/{len:7}, {msg}")
+        .unwrap();
+    let progress = ProgressBar::new(len);
+    progress.set_style(style);
+    progress.set_message(message);
+    progress
+}
+
 #[derive(Clone)]
 pub struct World {
-    storage_backend: LmdbBackend,
-    cache: Cache<(i32, i32, String), Arc<Chunk>>,
+    storage_backend: Arc<dyn StorageBackend>,
+    cache: Cache<ChunkKey, Arc<Chunk>>,
+    dirty_chunks: Arc<DirtyChunks>,
+    pending_block_changes: Arc<Mutex<Vec<(u128, BlockChange)>>>,
 }
 
 fn check_config_validity() -> Result<(), WorldError> {
@@ -33,6 +74,11 @@ fn check_config_validity() -> Result<(), WorldError> {
     // the importing logic.
 
     let config = get_global_config();
+    compression::check_compression_config()?;
+    // The in-memory backend never touches the disk, so there's no path or map size to check.
+    if config.database.backend == DatabaseBackend::Memory {
+        return Ok(());
+    }
     let db_path = get_root_path().join(&config.database.db_path);
 
     if config.database.map_size == 0 {
@@ -76,6 +122,9 @@ fn check_config_validity() -> Result<(), WorldError> {
 impl World {
     /// Creates a new world instance.
     ///
+    /// The storage backend is picked from the `database.backend` config option. For the LMDB
+    /// backend, `backend_path` is where the database lives.
+    ///
     /// You'd probably want to call this at the start of your program. And then use the returned
     /// in a state struct or something.
     pub fn new(backend_path: impl Into<PathBuf>) -> Self {
@@ -83,13 +132,24 @@ impl World {
             error!("Fatal error in database config: {}", e);
             exit(1);
         }
-        let mut backend_path = backend_path.into();
-        // Clones are kinda ok here since this is only run once at startup.
-        if backend_path.is_relative() {
-            backend_path = get_root_path().join(backend_path);
-        }
-        let storage_backend =
-            LmdbBackend::initialize(Some(backend_path)).expect("Failed to initialize database");
+
+        let storage_backend: Arc<dyn StorageBackend> = match get_global_config().database.backend {
+            DatabaseBackend::Lmdb => {
+                let mut backend_path = backend_path.into();
+                // Clones are kinda ok here since this is only run once at startup.
+                if backend_path.is_relative() {
+                    backend_path = get_root_path().join(backend_path);
+                }
+                Arc::new(
+                    LmdbBackend::initialize(Some(backend_path))
+                        .expect("Failed to initialize database"),
+                )
+            }
+            DatabaseBackend::Memory => {
+                warn!("Using the in-memory storage backend. The world will not be saved to disk.");
+                Arc::new(MemoryBackend::new())
+            }
+        };
 
         if get_global_config().database.cache_ttl != 0
             && get_global_config().database.cache_capacity == 0
@@ -98,8 +158,34 @@ impl World {
             exit(1);
         }
 
-        let eviction_listener = move |key, _, cause| {
-            trace!("Evicting key: {:?}, cause: {:?}", key, cause);
+        Self::with_backend(storage_backend)
+    }
+
+    /// Creates a world on top of an already initialized storage backend, skipping the config
+    /// checks done by [World::new].
+    ///
+    /// Mostly useful for tests, which can pass a fresh
+    /// [MemoryBackend](ferrumc_storage::memory::MemoryBackend).
+    pub fn with_backend(storage_backend: Arc<dyn StorageBackend>) -> Self {
+        let dirty_chunks = Arc::new(DirtyChunks::new());
+
+        // Chunks are only written to the backend on sync, so one that falls out of the cache with
+        // unsaved changes has to be written now or the next load would miss them. Explicit removals
+        // and replacements don't count, as those either delete the chunk or keep it around.
+        let eviction_listener = {
+            let storage_backend = storage_backend.clone();
+            let dirty_chunks = dirty_chunks.clone();
+            move |key: Arc<ChunkKey>, _, cause: RemovalCause| {
+                trace!("Evicting key: {:?}, cause: {:?}", key, cause);
+                if !cause.was_evicted() {
+                    return;
+                }
+                if let Err(e) =
+                    db_functions::flush_dirty_chunk(storage_backend.as_ref(), &dirty_chunks, &key)
+                {
+                    error!("Failed to save evicted chunk {:?}: {}", key, e);
+                }
+            }
         };
 
         let cache = Cache::builder()
@@ -112,6 +198,8 @@ impl World {
         World {
             storage_backend,
             cache,
+            dirty_chunks,
+            pending_block_changes: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -119,6 +207,26 @@ impl World {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::block_state_id::BlockStateId;
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_memory_backend_round_trip() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        assert!(!world.chunk_exists(3, -2, "overworld").unwrap());
+
+        let mut chunk = Chunk::new(3, -2, "overworld".to_string());
+        chunk.set_block(1, 2, 3, block!("stone")).unwrap();
+        world.save_chunk(Arc::new(chunk)).unwrap();
+        world.sync().unwrap();
+
+        // Go around the cache so the chunk actually comes back out of the backend.
+        let loaded = db_functions::load_chunk_internal(&world, 3, -2, "overworld").unwrap();
+        assert_eq!(loaded.get_block(1, 2, 3).unwrap(), block!("stone"));
+
+        world.delete_chunk(3, -2, "overworld").unwrap();
+        assert!(!world.chunk_exists(3, -2, "overworld").unwrap());
+    }
 
     #[test]
     #[ignore]
@@ -0,0 +1,518 @@
+use crate::block_state_id::BlockStateId;
+use crate::chunk_format::{Chunk, PaletteType, Section};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use ferrumc_data::blocks::BlockState;
+use ferrumc_general_purpose::data_packing::u32::read_nbit_u32;
+use std::collections::VecDeque;
+
+/// The brightest a light level can be.
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+/// Light is stored as one nibble per block, so a section's light array is 2048 bytes long.
+const LIGHT_ARRAY_LEN: usize = 2048;
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// The two kinds of light the client renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightType {
+    /// Light coming from the sky. Only dimensions with a sky have it.
+    Sky,
+    /// Light emitted by blocks such as torches and glowstone.
+    Block,
+}
+
+/// How a block state interacts with light.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LightProperties {
+    /// The light level the block gives off.
+    pub emission: u8,
+    /// How much light is lost passing through the block. Light always loses at least one level
+    /// per block it travels, so anything from 0 to 1 behaves the same for spreading light.
+    pub opacity: u8,
+}
+
+impl LightProperties {
+    /// Looks up the light properties of a block state from the generated block data. Unknown
+    /// states are treated like air.
+    pub fn of(block: BlockStateId) -> Self {
+        match BlockState::by_id(block.0) {
+            Some(state) => LightProperties {
+                emission: state.luminance.min(MAX_LIGHT_LEVEL as u32) as u8,
+                opacity: state.opacity.min(MAX_LIGHT_LEVEL),
+            },
+            None => LightProperties::default(),
+        }
+    }
+}
+
+impl Chunk {
+    /// Calculates the sky and block light of the whole chunk from scratch.
+    ///
+    /// This should be done once a chunk has been generated. Light only spreads within the chunk,
+    /// so light from neighbouring chunks isn't taken into account.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the light was successfully calculated.
+    /// * `Err(WorldError)` - If the block data of a section is invalid.
+    pub fn calculate_light(&mut self) -> Result<(), WorldError> {
+        let mut view = LightView::new(self);
+        view.clear();
+
+        if view.has_skylight {
+            // Sky light goes straight down until it hits something, then spreads from there
+            let mut queue = VecDeque::new();
+            for x in 0..16 {
+                for z in 0..16 {
+                    let mut level = MAX_LIGHT_LEVEL;
+                    for y in (view.min_y..view.max_y).rev() {
+                        let opacity = view.properties(x, y, z)?.opacity;
+                        level = if level == MAX_LIGHT_LEVEL && opacity == 0 {
+                            MAX_LIGHT_LEVEL
+                        } else {
+                            level.saturating_sub(opacity.max(1))
+                        };
+                        if level == 0 {
+                            break;
+                        }
+                        view.set_light(LightType::Sky, x, y, z, level);
+                        queue.push_back((x, y, z));
+                    }
+                }
+            }
+            view.propagate(LightType::Sky, queue)?;
+        }
+
+        let mut queue = VecDeque::new();
+        for y in view.min_y..view.max_y {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let emission = view.properties(x, y, z)?.emission;
+                    if emission > 0 {
+                        view.set_light(LightType::Block, x, y, z, emission);
+                        queue.push_back((x, y, z));
+                    }
+                }
+            }
+        }
+        view.propagate(LightType::Block, queue)
+    }
+
+    /// Updates the light around a block after it has changed.
+    ///
+    /// Light that came from or passed through the old block is removed and then spread again from
+    /// whatever is still lighting the area, so only the affected blocks are touched.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x-coordinate of the changed block.
+    /// * `y` - The y-coordinate of the changed block.
+    /// * `z` - The z-coordinate of the changed block.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the light was successfully updated.
+    /// * `Err(WorldError)` - If the block data of a section is invalid.
+    pub fn update_light(&mut self, x: i32, y: i32, z: i32) -> Result<(), WorldError> {
+        let (x, z) = (x & 0xF, z & 0xF);
+        let mut view = LightView::new(self);
+        if !view.contains(x, y, z) {
+            return Ok(());
+        }
+        let properties = view.properties(x, y, z)?;
+
+        for light_type in [LightType::Sky, LightType::Block] {
+            if light_type == LightType::Sky && !view.has_skylight {
+                continue;
+            }
+            let old_level = view.light(light_type, x, y, z);
+            view.set_light(light_type, x, y, z, 0);
+            let mut queue = view.unpropagate(light_type, (x, y, z), old_level);
+
+            // The block can be lit again by itself or anything next to it
+            match light_type {
+                LightType::Block if properties.emission > 0 => {
+                    view.set_light(light_type, x, y, z, properties.emission);
+                    queue.push_back((x, y, z));
+                }
+                LightType::Sky if y == view.max_y - 1 => {
+                    // Nothing is above the top of the chunk, so the sky shines straight in
+                    let level = LightView::spread_level(
+                        light_type,
+                        MAX_LIGHT_LEVEL,
+                        -1,
+                        properties.opacity,
+                    );
+                    view.set_light(light_type, x, y, z, level);
+                    queue.push_back((x, y, z));
+                }
+                _ => {}
+            }
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if view.contains(nx, ny, nz) && view.light(light_type, nx, ny, nz) > 0 {
+                    queue.push_back((nx, ny, nz));
+                }
+            }
+            view.propagate(light_type, queue)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the light level at the specified coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `light_type` - Whether to get the sky or block light.
+    /// * `x` - The x-coordinate of the block.
+    /// * `y` - The y-coordinate of the block.
+    /// * `z` - The z-coordinate of the block.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(u8)` - The light level, from 0 to 15.
+    /// * `Err(WorldError)` - If the section is out of bounds.
+    pub fn get_light(
+        &self,
+        light_type: LightType,
+        x: i32,
+        y: i32,
+        z: i32,
+    ) -> Result<u8, WorldError> {
+        let section = self
+            .sections
+            .iter()
+            .find(|section| section.y == (y >> 4) as i8)
+            .ok_or(WorldError::SectionOutOfBounds(y >> 4))?;
+        let light = match light_type {
+            LightType::Sky => &section.sky_light,
+            LightType::Block => &section.block_light,
+        };
+        if light.len() != LIGHT_ARRAY_LEN {
+            return Ok(0);
+        }
+        Ok(get_nibble(light, block_index(x, y, z)))
+    }
+}
+
+/// A view of a chunk's sections for reading block light properties and reading and writing light
+/// levels. The x and z coordinates are relative to the chunk while y is the world height.
+struct LightView<'a> {
+    sections: &'a mut [Section],
+    has_skylight: bool,
+    min_y: i32,
+    max_y: i32,
+    /// The index into `sections` of each section from the bottom of the chunk up.
+    section_indexes: Vec<Option<usize>>,
+    /// The light properties of every block in a section, decoded the first time they're needed.
+    properties: Vec<Option<Vec<LightProperties>>>,
+}
+
+impl<'a> LightView<'a> {
+    fn new(chunk: &'a mut Chunk) -> Self {
+        let has_skylight = Dimension::from_name(&chunk.dimension)
+            .unwrap_or_default()
+            .has_skylight();
+        let min_section = chunk.sections.iter().map(|s| i32::from(s.y)).min();
+        let max_section = chunk.sections.iter().map(|s| i32::from(s.y)).max();
+        let (min_section, max_section) = match (min_section, max_section) {
+            (Some(min), Some(max)) => (min, max),
+            _ => (0, -1),
+        };
+        let count = (max_section - min_section + 1) as usize;
+        let mut section_indexes = vec![None; count];
+        for (index, section) in chunk.sections.iter_mut().enumerate() {
+            section_indexes[(i32::from(section.y) - min_section) as usize] = Some(index);
+            for light in [&mut section.sky_light, &mut section.block_light] {
+                if light.len() != LIGHT_ARRAY_LEN {
+                    *light = vec![0; LIGHT_ARRAY_LEN];
+                }
+            }
+        }
+        LightView {
+            sections: &mut chunk.sections,
+            has_skylight,
+            min_y: min_section * 16,
+            max_y: (max_section + 1) * 16,
+            section_indexes,
+            properties: vec![None; count],
+        }
+    }
+
+    fn contains(&self, x: i32, y: i32, z: i32) -> bool {
+        (0..16).contains(&x) && (0..16).contains(&z) && (self.min_y..self.max_y).contains(&y)
+    }
+
+    fn section_slot(&self, y: i32) -> usize {
+        ((y - self.min_y) >> 4) as usize
+    }
+
+    fn clear(&mut self) {
+        for section in self.sections.iter_mut() {
+            section.sky_light.fill(0);
+            section.block_light.fill(0);
+        }
+    }
+
+    fn properties(&mut self, x: i32, y: i32, z: i32) -> Result<LightProperties, WorldError> {
+        let slot = self.section_slot(y);
+        let Some(section_index) = self.section_indexes[slot] else {
+            return Ok(LightProperties::default());
+        };
+        if self.properties[slot].is_none() {
+            self.properties[slot] = Some(section_properties(&self.sections[section_index])?);
+        }
+        Ok(self.properties[slot]
+            .as_ref()
+            .map_or_else(LightProperties::default, |properties| {
+                properties[block_index(x, y, z)]
+            }))
+    }
+
+    fn light(&self, light_type: LightType, x: i32, y: i32, z: i32) -> u8 {
+        match self.section_indexes[self.section_slot(y)] {
+            Some(section_index) => {
+                let section = &self.sections[section_index];
+                let light = match light_type {
+                    LightType::Sky => &section.sky_light,
+                    LightType::Block => &section.block_light,
+                };
+                get_nibble(light, block_index(x, y, z))
+            }
+            None => 0,
+        }
+    }
+
+    fn set_light(&mut self, light_type: LightType, x: i32, y: i32, z: i32, level: u8) {
+        if let Some(section_index) = self.section_indexes[self.section_slot(y)] {
+            let section = &mut self.sections[section_index];
+            let light = match light_type {
+                LightType::Sky => &mut section.sky_light,
+                LightType::Block => &mut section.block_light,
+            };
+            set_nibble(light, block_index(x, y, z), level);
+        }
+    }
+
+    /// The light level a block gets from a lit neighbour, or 0 if it doesn't get any.
+    fn spread_level(light_type: LightType, level: u8, dy: i32, opacity: u8) -> u8 {
+        // Full sky light isn't dimmed going straight down through clear blocks
+        if light_type == LightType::Sky && dy == -1 && level == MAX_LIGHT_LEVEL && opacity == 0 {
+            MAX_LIGHT_LEVEL
+        } else {
+            level.saturating_sub(opacity.max(1))
+        }
+    }
+
+    /// Spreads light outwards from every block in the queue.
+    fn propagate(
+        &mut self,
+        light_type: LightType,
+        mut queue: VecDeque<(i32, i32, i32)>,
+    ) -> Result<(), WorldError> {
+        while let Some((x, y, z)) = queue.pop_front() {
+            let level = self.light(light_type, x, y, z);
+            if level <= 1 {
+                continue;
+            }
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if !self.contains(nx, ny, nz) {
+                    continue;
+                }
+                let opacity = self.properties(nx, ny, nz)?.opacity;
+                let new_level = Self::spread_level(light_type, level, dy, opacity);
+                if new_level > self.light(light_type, nx, ny, nz) {
+                    self.set_light(light_type, nx, ny, nz, new_level);
+                    queue.push_back((nx, ny, nz));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the light that spread from a block which used to have the given level.
+    ///
+    /// Returns the blocks on the edge of the darkened area that are lit by something else, so the
+    /// light can be spread back in from them.
+    fn unpropagate(
+        &mut self,
+        light_type: LightType,
+        start: (i32, i32, i32),
+        start_level: u8,
+    ) -> VecDeque<(i32, i32, i32)> {
+        let mut relight = VecDeque::new();
+        let mut queue = VecDeque::from([(start, start_level)]);
+        while let Some(((x, y, z), level)) = queue.pop_front() {
+            for (dx, dy, dz) in DIRECTIONS {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if !self.contains(nx, ny, nz) {
+                    continue;
+                }
+                let neighbour_level = self.light(light_type, nx, ny, nz);
+                if neighbour_level == 0 {
+                    continue;
+                }
+                let came_from_here = neighbour_level < level
+                    || (light_type == LightType::Sky
+                        && dy == -1
+                        && level == MAX_LIGHT_LEVEL
+                        && neighbour_level == MAX_LIGHT_LEVEL);
+                if came_from_here {
+                    self.set_light(light_type, nx, ny, nz, 0);
+                    queue.push_back(((nx, ny, nz), neighbour_level));
+                } else {
+                    relight.push_back((nx, ny, nz));
+                }
+            }
+        }
+        relight
+    }
+}
+
+/// Decodes the light properties of every block in a section.
+fn section_properties(section: &Section) -> Result<Vec<LightProperties>, WorldError> {
+    match &section.block_states.block_data {
+        PaletteType::Single(id) => Ok(vec![
+            LightProperties::of(BlockStateId::from_varint(*id));
+            4096
+        ]),
+        PaletteType::Indirect {
+            bits_per_block,
+            data,
+            palette,
+        } => {
+            let palette: Vec<LightProperties> = palette
+                .iter()
+                .map(|id| LightProperties::of(BlockStateId::from_varint(*id)))
+                .collect();
+            let blocks_per_i64 = 64 / *bits_per_block as usize;
+            (0..4096)
+                .map(|index| {
+                    let packed = data.get(index / blocks_per_i64).ok_or_else(|| {
+                        WorldError::InvalidBlockStateData(format!(
+                            "Invalid block state data at index {index}"
+                        ))
+                    })?;
+                    let offset = (index % blocks_per_i64) * *bits_per_block as usize;
+                    let palette_index = read_nbit_u32(packed, *bits_per_block, offset as u32)?;
+                    Ok(palette
+                        .get(palette_index as usize)
+                        .copied()
+                        .unwrap_or_default())
+                })
+                .collect()
+        }
+        PaletteType::Direct { .. } => Err(WorldError::InvalidBlockStateData(
+            "Direct palettes can't be lit".to_string(),
+        )),
+    }
+}
+
+fn block_index(x: i32, y: i32, z: i32) -> usize {
+    ((y & 0xF) * 256 + (z & 0xF) * 16 + (x & 0xF)) as usize
+}
+
+fn get_nibble(light: &[u8], index: usize) -> u8 {
+    (light[index >> 1] >> ((index & 1) * 4)) & 0xF
+}
+
+fn set_nibble(light: &mut [u8], index: usize, level: u8) {
+    let shift = (index & 1) * 4;
+    light[index >> 1] = (light[index >> 1] & !(0xF << shift)) | ((level & 0xF) << shift);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::World;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_open_sky_is_fully_lit() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        chunk.set_section(0, block!("stone")).unwrap();
+        chunk.calculate_light().unwrap();
+        assert_eq!(chunk.get_light(LightType::Sky, 3, 100, 3).unwrap(), 15);
+        assert_eq!(chunk.get_light(LightType::Sky, 3, 16, 3).unwrap(), 15);
+        assert_eq!(chunk.get_light(LightType::Sky, 3, 15, 3).unwrap(), 0);
+        assert_eq!(chunk.get_light(LightType::Sky, 3, -10, 3).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_block_light_spreads() {
+        let mut chunk = Chunk::new(0, 0, "the_nether".to_string());
+        chunk.set_block(8, 64, 8, block!("glowstone")).unwrap();
+        chunk.calculate_light().unwrap();
+        assert_eq!(chunk.get_light(LightType::Block, 8, 64, 8).unwrap(), 15);
+        assert_eq!(chunk.get_light(LightType::Block, 8, 64, 11).unwrap(), 12);
+        assert_eq!(chunk.get_light(LightType::Block, 9, 65, 9).unwrap(), 12);
+        // The nether has no sky
+        assert_eq!(chunk.get_light(LightType::Sky, 8, 127, 8).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_incremental_block_light() {
+        let mut chunk = Chunk::new(0, 0, "the_nether".to_string());
+        chunk.calculate_light().unwrap();
+        chunk.set_block(4, 20, 4, block!("glowstone")).unwrap();
+        // Setting a block leaves the light alone until it's updated
+        assert_eq!(chunk.get_light(LightType::Block, 4, 20, 4).unwrap(), 0);
+        chunk.update_light(4, 20, 4).unwrap();
+        assert_eq!(chunk.get_light(LightType::Block, 4, 20, 4).unwrap(), 15);
+        assert_eq!(chunk.get_light(LightType::Block, 4, 20, 6).unwrap(), 13);
+
+        chunk.set_block(4, 20, 4, block!("air")).unwrap();
+        chunk.update_light(4, 20, 4).unwrap();
+        assert_eq!(chunk.get_light(LightType::Block, 4, 20, 4).unwrap(), 0);
+        assert_eq!(chunk.get_light(LightType::Block, 4, 20, 6).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_incremental_sky_light() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        chunk.calculate_light().unwrap();
+        chunk.set_block(5, 100, 5, block!("stone")).unwrap();
+        chunk.update_light(5, 100, 5).unwrap();
+        assert_eq!(chunk.get_light(LightType::Sky, 5, 100, 5).unwrap(), 0);
+        // Light still comes in from the sides
+        assert_eq!(chunk.get_light(LightType::Sky, 5, 99, 5).unwrap(), 14);
+        assert_eq!(chunk.get_light(LightType::Sky, 5, 101, 5).unwrap(), 15);
+
+        chunk.set_block(5, 100, 5, block!("air")).unwrap();
+        chunk.update_light(5, 100, 5).unwrap();
+        assert_eq!(chunk.get_light(LightType::Sky, 5, 99, 5).unwrap(), 15);
+    }
+
+    #[test]
+    fn test_world_edits_update_light() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        chunk.calculate_light().unwrap();
+        world.save_chunk(Arc::new(chunk)).unwrap();
+
+        world
+            .set_block_and_fetch(4, 100, 4, "overworld", block!("glowstone"))
+            .unwrap();
+        let chunk = world.load_chunk(0, 0, "overworld").unwrap();
+        assert_eq!(chunk.get_light(LightType::Block, 4, 100, 6).unwrap(), 13);
+        assert_eq!(chunk.get_light(LightType::Sky, 4, 99, 4).unwrap(), 14);
+
+        world
+            .set_block_and_fetch(4, 100, 4, "overworld", block!("air"))
+            .unwrap();
+        let chunk = world.load_chunk(0, 0, "overworld").unwrap();
+        assert_eq!(chunk.get_light(LightType::Block, 4, 100, 6).unwrap(), 0);
+        assert_eq!(chunk.get_light(LightType::Sky, 4, 99, 4).unwrap(), 15);
+    }
+}
@@ -0,0 +1,290 @@
+//! Settings a world is created with, which have to stay the same for as long as it exists.
+//!
+//! The first time a world is opened, [World::load_metadata] stores a [WorldMetadata] record with
+//! the configured generator and seed in the [METADATA_TABLE]. From then on the stored record wins
+//! over the config, so a restart with a different config can't switch generators or seeds under
+//! chunks that were already generated, which would leave seams wherever old and new terrain meet.
+//!
+//! Worlds from before the record existed were all generated with the noise generator and seed 0,
+//! so that's what they're given instead of the config. Worlds that only stored their
+//! [GeneratorSettings] under the same key have them moved into a [WorldMetadata].
+
+use crate::block_log::now_millis;
+use crate::chunk_format::VANILLA_DATA_VERSION;
+use crate::errors::WorldError;
+use crate::World;
+use bitcode_derive::{Decode, Encode};
+use ferrumc_config::server_config::get_global_config;
+use ferrumc_config::WorldGeneratorKind;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// The table world-wide settings are stored in.
+pub const METADATA_TABLE: &str = "metadata";
+
+/// The key of the [WorldMetadata] in the [METADATA_TABLE].
+const METADATA_KEY: u128 = 0;
+
+/// Where players spawn in a new world, as block coordinates.
+pub const DEFAULT_SPAWN: [i32; 3] = [0, 100, 0];
+
+/// Which generator makes a world's terrain.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum GeneratorPreset {
+    /// Vanilla-like terrain, with biomes picked by climate.
+    Noise,
+    /// Flat layers of blocks in a single biome.
+    Flat {
+        /// The layers from the bottom of the world up, written like `bedrock,2*dirt,grass_block`.
+        layers: String,
+        biome: String,
+    },
+    /// No blocks at all, in the void biome.
+    Void,
+}
+
+/// How a world's terrain is generated.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct GeneratorSettings {
+    pub preset: GeneratorPreset,
+    pub seed: u64,
+}
+
+impl GeneratorSettings {
+    /// The generator and seed set in the `world_gen` config section.
+    pub fn from_config() -> Self {
+        let config = &get_global_config().world_gen;
+        let preset = match config.generator {
+            WorldGeneratorKind::Noise => GeneratorPreset::Noise,
+            WorldGeneratorKind::Flat => GeneratorPreset::Flat {
+                layers: config.flat_layers.clone(),
+                biome: config.flat_biome.clone(),
+            },
+            WorldGeneratorKind::Void => GeneratorPreset::Void,
+        };
+        Self {
+            preset,
+            seed: parse_seed(&config.seed),
+        }
+    }
+}
+
+/// Turns the seed from the config into a number, the way vanilla reads the seed field of its
+/// world creation screen: numbers are used as they are, any other text is hashed and nothing at
+/// all means a random seed.
+pub fn parse_seed(seed: &str) -> u64 {
+    let seed = seed.trim();
+    if seed.is_empty() {
+        return rand::random();
+    }
+    match seed.parse::<i64>() {
+        Ok(seed) => seed as u64,
+        Err(_) => {
+            // Java's String.hashCode, which works on UTF-16 code units.
+            let hash = seed.encode_utf16().fold(0i32, |hash, unit| {
+                hash.wrapping_mul(31).wrapping_add(i32::from(unit))
+            });
+            i64::from(hash) as u64
+        }
+    }
+}
+
+/// Everything a world remembers about itself.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct WorldMetadata {
+    pub generator: GeneratorSettings,
+    /// Where players without a saved position spawn, as block coordinates.
+    pub spawn: [i32; 3],
+    /// When the world was created, in milliseconds since the UNIX epoch.
+    pub created_at: u64,
+    /// The data version of the server that created the world.
+    pub data_version: i32,
+}
+
+impl WorldMetadata {
+    /// The metadata of a world being created right now with `generator`.
+    pub fn new(generator: GeneratorSettings) -> Self {
+        Self {
+            generator,
+            spawn: DEFAULT_SPAWN,
+            created_at: now_millis(),
+            data_version: VANILLA_DATA_VERSION,
+        }
+    }
+
+    /// The seed as vanilla sends it to clients: the first 8 bytes of its SHA-256 hash. Clients only
+    /// use it to blend biome colours, and hashing it keeps the real seed from being worked out.
+    pub fn hashed_seed(&self) -> i64 {
+        let hash = Sha256::digest(self.generator.seed.to_le_bytes());
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        i64::from_le_bytes(bytes)
+    }
+}
+
+/// Reads a stored metadata record, which may still be just the [GeneratorSettings] older worlds
+/// stored. Those are given the defaults for everything else, and `true` is returned alongside so
+/// the record can be rewritten.
+fn decode_metadata(record: &[u8]) -> Result<(WorldMetadata, bool), WorldError> {
+    match bitcode::decode::<WorldMetadata>(record) {
+        Ok(metadata) => Ok((metadata, false)),
+        Err(e) => match bitcode::decode::<GeneratorSettings>(record) {
+            Ok(generator) => Ok((WorldMetadata::new(generator), true)),
+            Err(_) => Err(WorldError::BitcodeDecodeError(e.to_string())),
+        },
+    }
+}
+
+impl World {
+    /// The metadata the world was created with.
+    ///
+    /// A world that doesn't have any yet is new, so it's created with the `configured` generator
+    /// and stored for next time, unless it already has chunks. Those were generated before
+    /// metadata was stored, with the noise generator and seed 0.
+    pub fn load_metadata(
+        &self,
+        configured: GeneratorSettings,
+    ) -> Result<WorldMetadata, WorldError> {
+        if self
+            .storage_backend
+            .table_exists(METADATA_TABLE.to_string())?
+        {
+            if let Some(record) = self
+                .storage_backend
+                .get(METADATA_TABLE.to_string(), METADATA_KEY)?
+            {
+                let (stored, upgraded) = decode_metadata(&record)?;
+                if stored.generator.preset != configured.preset {
+                    warn!(
+                        "The world was created with {:?}, ignoring the configured {:?}",
+                        stored.generator.preset, configured.preset
+                    );
+                }
+                if upgraded {
+                    info!("Moved the stored generator settings into the world metadata");
+                    self.store_metadata(&stored)?;
+                }
+                return Ok(stored);
+            }
+        } else {
+            self.storage_backend
+                .create_table(METADATA_TABLE.to_string())?;
+        }
+
+        let has_chunks = self.storage_backend.table_exists("chunks".to_string())?
+            && !self
+                .storage_backend
+                .get_range("chunks".to_string(), 0..=u128::MAX, 1, false)?
+                .is_empty();
+        let metadata = if has_chunks {
+            let existing = GeneratorSettings {
+                preset: GeneratorPreset::Noise,
+                seed: 0,
+            };
+            if existing != configured {
+                warn!(
+                    "The world already has chunks generated with {:?} and seed 0, ignoring the \
+                     configured generator",
+                    existing.preset
+                );
+            }
+            WorldMetadata::new(existing)
+        } else {
+            WorldMetadata::new(configured)
+        };
+        self.store_metadata(&metadata)?;
+        Ok(metadata)
+    }
+
+    fn store_metadata(&self, metadata: &WorldMetadata) -> Result<(), WorldError> {
+        self.storage_backend.upsert(
+            METADATA_TABLE.to_string(),
+            METADATA_KEY,
+            bitcode::encode(metadata),
+        )?;
+        self.storage_backend.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_first_metadata_is_kept() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        let flat = GeneratorSettings {
+            preset: GeneratorPreset::Flat {
+                layers: "bedrock,2*dirt,grass_block".to_string(),
+                biome: "plains".to_string(),
+            },
+            seed: 7,
+        };
+        let created = world.load_metadata(flat.clone()).unwrap();
+        assert_eq!(created.generator, flat);
+        assert_eq!(created.spawn, DEFAULT_SPAWN);
+        assert_eq!(created.data_version, VANILLA_DATA_VERSION);
+
+        let noise = GeneratorSettings {
+            preset: GeneratorPreset::Noise,
+            seed: 0,
+        };
+        assert_eq!(world.load_metadata(noise).unwrap(), created);
+    }
+
+    #[test]
+    fn test_metadata_of_older_worlds() {
+        let configured = GeneratorSettings {
+            preset: GeneratorPreset::Void,
+            seed: 7,
+        };
+
+        // Chunks without any metadata were generated before it was stored.
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        world
+            .storage_backend
+            .batch_insert("chunks".to_string(), vec![(1, vec![1])])
+            .unwrap();
+        let metadata = world.load_metadata(configured.clone()).unwrap();
+        assert_eq!(metadata.generator.preset, GeneratorPreset::Noise);
+        assert_eq!(metadata.generator.seed, 0);
+
+        // Only the generator settings were stored at first.
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        let flat = GeneratorSettings {
+            preset: GeneratorPreset::Flat {
+                layers: "stone".to_string(),
+                biome: "plains".to_string(),
+            },
+            seed: 3,
+        };
+        world
+            .storage_backend
+            .batch_insert(
+                METADATA_TABLE.to_string(),
+                vec![(METADATA_KEY, bitcode::encode(&flat))],
+            )
+            .unwrap();
+        let metadata = world.load_metadata(configured).unwrap();
+        assert_eq!(metadata.generator, flat);
+        let record = world
+            .storage_backend
+            .get(METADATA_TABLE.to_string(), METADATA_KEY)
+            .unwrap()
+            .unwrap();
+        assert_eq!(bitcode::decode::<WorldMetadata>(&record).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_seeds_from_text() {
+        assert_eq!(parse_seed("12345"), 12345);
+        assert_eq!(parse_seed(" -1 "), u64::MAX);
+        // "hello".hashCode() in Java.
+        assert_eq!(parse_seed("hello"), 99162322);
+        // Negative hashes stay negative as a long.
+        assert_eq!(parse_seed("ferrum") as i64, -1278008809);
+    }
+}
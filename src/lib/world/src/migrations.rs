@@ -0,0 +1,175 @@
+//! Upgrading chunks stored by older versions of FerrumC.
+//!
+//! Chunks are stored as the bitcode encoding of [Chunk], which stops decoding as soon as a field
+//! of `Chunk`, `Section`, `BlockStates`, `PaletteType` or anything else inside it changes. To keep
+//! old worlds loadable, every stored chunk is tagged with the format version it was written with,
+//! and older data is upgraded one version at a time through the migrations in the
+//! [MigrationRegistry]. This happens transparently when a chunk is loaded, or for the whole world
+//! at once with [World::migrate_chunks] (`ferrumc migrate` on the command line).
+//!
+//! When changing the chunk format:
+//! 1. Copy the current definitions into a `vN` module in this file, where `N` is the current
+//!    [CURRENT_FORMAT_VERSION], and trim them down to what's needed to decode them.
+//! 2. Bump [CURRENT_FORMAT_VERSION].
+//! 3. Write a [ChunkMigration] that decodes the data with the `vN` definitions and encodes it in
+//!    the new format, and register it from version `N` where `MIGRATIONS` is built.
+
+use crate::chunk_format::Chunk;
+use crate::compression::{compress_record, configured_compressor, decompress_record, read_header};
+use crate::errors::WorldError;
+use crate::World;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+/// The format version chunks are written with by this build.
+pub const CURRENT_FORMAT_VERSION: u16 = 1;
+
+/// Upgrades the uncompressed data of a chunk from one format version to the next.
+pub type ChunkMigration = fn(Vec<u8>) -> Result<Vec<u8>, WorldError>;
+
+lazy_static! {
+    static ref MIGRATIONS: MigrationRegistry = {
+        // Migrations are registered here as the chunk format changes, e.g.
+        // `registry.register(1, v1::migrate);`. Version 1 is the first versioned format, so there
+        // are none yet.
+        MigrationRegistry::new(CURRENT_FORMAT_VERSION)
+    };
+}
+
+/// A set of migrations that together upgrade chunk data from any older format version to
+/// `current_version`.
+pub struct MigrationRegistry {
+    current_version: u16,
+    migrations: HashMap<u16, ChunkMigration>,
+}
+
+impl MigrationRegistry {
+    /// Creates an empty registry that migrates up to `current_version`.
+    pub fn new(current_version: u16) -> Self {
+        Self {
+            current_version,
+            migrations: HashMap::new(),
+        }
+    }
+
+    /// The registry used when loading chunks, holding every migration up to
+    /// [CURRENT_FORMAT_VERSION].
+    pub fn global() -> &'static MigrationRegistry {
+        &MIGRATIONS
+    }
+
+    /// Registers the migration that upgrades data from `from_version` to `from_version + 1`.
+    pub fn register(&mut self, from_version: u16, migration: ChunkMigration) -> &mut Self {
+        self.migrations.insert(from_version, migration);
+        self
+    }
+
+    pub fn current_version(&self) -> u16 {
+        self.current_version
+    }
+
+    /// Upgrades `data` from `version` to the current version by running each migration in turn.
+    /// Data that is already current is returned untouched.
+    pub fn migrate(&self, version: u16, mut data: Vec<u8>) -> Result<Vec<u8>, WorldError> {
+        if version > self.current_version {
+            return Err(WorldError::UnsupportedChunkVersion(
+                version,
+                self.current_version,
+            ));
+        }
+        for from_version in version..self.current_version {
+            let migration = self
+                .migrations
+                .get(&from_version)
+                .ok_or(WorldError::MissingChunkMigration(from_version))?;
+            data = migration(data)?;
+        }
+        Ok(data)
+    }
+}
+
+impl World {
+    /// Upgrades every stored chunk written with an older format version to
+    /// [CURRENT_FORMAT_VERSION], so they don't have to be migrated every time they're loaded.
+    /// Upgraded chunks are written with the configured compression.
+    ///
+    /// Returns how many chunks were upgraded.
+    pub fn migrate_chunks(&self) -> Result<usize, WorldError> {
+        let registry = MigrationRegistry::global();
+        let compressor = configured_compressor();
+        self.rewrite_chunk_records(
+            format!("Migrating chunks to format version {CURRENT_FORMAT_VERSION}..."),
+            |record| {
+                if read_header(record)?.format_version == CURRENT_FORMAT_VERSION {
+                    return Ok(None);
+                }
+                let (header, data) = decompress_record(record)?;
+                let data = registry.migrate(header.format_version, data)?;
+                // Make sure the migration produced a valid chunk before overwriting the old data.
+                bitcode::decode::<Chunk>(&data)
+                    .map_err(|e| WorldError::BitcodeDecodeError(e.to_string()))?;
+                compress_record(&data, compressor, CURRENT_FORMAT_VERSION).map(Some)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_storage::memory::MemoryBackend;
+    use ferrumc_storage::StorageBackend;
+    use std::sync::Arc;
+
+    fn append_one(mut data: Vec<u8>) -> Result<Vec<u8>, WorldError> {
+        data.push(1);
+        Ok(data)
+    }
+
+    fn append_two(mut data: Vec<u8>) -> Result<Vec<u8>, WorldError> {
+        data.push(2);
+        Ok(data)
+    }
+
+    #[test]
+    fn test_migrations_run_in_order() {
+        let mut registry = MigrationRegistry::new(3);
+        registry.register(2, append_two).register(1, append_one);
+        assert_eq!(registry.migrate(1, vec![0]).unwrap(), vec![0, 1, 2]);
+        assert_eq!(registry.migrate(2, vec![0]).unwrap(), vec![0, 2]);
+        assert_eq!(registry.migrate(3, vec![0]).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_migration_errors() {
+        let mut registry = MigrationRegistry::new(3);
+        registry.register(2, append_two);
+        assert!(matches!(
+            registry.migrate(1, vec![]),
+            Err(WorldError::MissingChunkMigration(1))
+        ));
+        assert!(matches!(
+            registry.migrate(4, vec![]),
+            Err(WorldError::UnsupportedChunkVersion(4, 3))
+        ));
+    }
+
+    #[test]
+    fn test_migrate_chunks() {
+        let backend = Arc::new(MemoryBackend::new());
+        let world = World::with_backend(backend.clone());
+        let data = bitcode::encode(&Chunk::new(0, 0, "overworld".to_string()));
+        let current = compress_record(&data, configured_compressor(), CURRENT_FORMAT_VERSION);
+        backend
+            .insert("chunks".to_string(), 1, current.unwrap())
+            .unwrap();
+        assert_eq!(world.migrate_chunks().unwrap(), 0);
+
+        // Chunks from a newer FerrumC can't be downgraded.
+        let newer = compress_record(&data, configured_compressor(), CURRENT_FORMAT_VERSION + 1);
+        backend
+            .insert("chunks".to_string(), 2, newer.unwrap())
+            .unwrap();
+        assert!(world.migrate_chunks().is_err());
+    }
+}
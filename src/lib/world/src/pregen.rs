@@ -0,0 +1,157 @@
+use crate::chunk_format::Chunk;
+use crate::db_functions::save_chunk_internal;
+use crate::errors::WorldError;
+use crate::{progress_bar, World};
+use ferrumc_threadpool::ThreadPool;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// How many chunks are queued on the thread pool at once, so that huge radii don't queue
+/// millions of tasks up front.
+const PREGEN_BATCH_SIZE: usize = 1024;
+
+/// How many chunks the world border is from the origin. Nothing past it can be reached, so it's
+/// the largest radius that can be pre-generated.
+pub const MAX_PREGEN_RADIUS: u32 = 1_875_000;
+
+/// What happened to the chunks in a pre-generated area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PregenSummary {
+    /// Chunks that were generated and saved.
+    pub generated: usize,
+    /// Chunks that already existed and were left alone.
+    pub skipped: usize,
+    /// Chunks that failed to generate or save. The errors are logged.
+    pub failed: usize,
+}
+
+impl World {
+    /// Generates every missing chunk within `radius` chunks of the origin of `dimension`, so
+    /// players exploring that area don't have to wait for chunks to generate.
+    ///
+    /// `generate` is called on the thread pool for each chunk that doesn't exist yet, and its
+    /// chunks are written straight to the storage backend instead of going through the cache.
+    /// Chunks that already exist, including ones that haven't been synced yet, are skipped.
+    ///
+    /// Fails if `radius` is larger than [MAX_PREGEN_RADIUS].
+    pub fn pregenerate(
+        &self,
+        dimension: &str,
+        radius: u32,
+        threadpool: &ThreadPool,
+        generate: impl Fn(i32, i32) -> Result<Chunk, WorldError> + Send + Sync + 'static,
+    ) -> Result<PregenSummary, WorldError> {
+        let Some(radius) = i32::try_from(radius)
+            .ok()
+            .filter(|_| radius <= MAX_PREGEN_RADIUS)
+        else {
+            return Err(WorldError::InvalidPregenRadius(radius));
+        };
+        let side = 2 * u64::from(radius.unsigned_abs()) + 1;
+        let mut coords =
+            (-radius..=radius).flat_map(move |x| (-radius..=radius).map(move |z| (x, z)));
+
+        let progress = progress_bar(side * side, format!("Generating chunks in {dimension}..."));
+
+        let start = std::time::Instant::now();
+        let world = Arc::new(self.clone());
+        let generate = Arc::new(generate);
+        let mut summary = PregenSummary::default();
+
+        loop {
+            let mut batch_coords = coords.by_ref().take(PREGEN_BATCH_SIZE).peekable();
+            if batch_coords.peek().is_none() {
+                break;
+            }
+            let mut batch = threadpool.batch();
+            for (x, z) in batch_coords {
+                let world = world.clone();
+                let generate = generate.clone();
+                let progress = progress.clone();
+                let dimension = dimension.to_string();
+                batch.execute(move || {
+                    let result =
+                        generate_missing_chunk(&world, x, z, &dimension, generate.as_ref());
+                    if let Err(e) = &result {
+                        error!("Error pre-generating chunk ({}, {}): {}", x, z, e);
+                    }
+                    progress.inc(1);
+                    result
+                });
+            }
+            for result in batch.wait() {
+                match result {
+                    Ok(true) => summary.generated += 1,
+                    Ok(false) => summary.skipped += 1,
+                    Err(_) => summary.failed += 1,
+                }
+            }
+        }
+
+        self.storage_backend.flush()?;
+        progress.finish_and_clear();
+        info!(
+            "Pre-generated {} chunks in {:?} ({} already existed, {} failed)",
+            summary.generated,
+            start.elapsed(),
+            summary.skipped,
+            summary.failed
+        );
+        Ok(summary)
+    }
+}
+
+/// Generates and saves a chunk unless it already exists. Returns whether it was generated.
+fn generate_missing_chunk(
+    world: &World,
+    x: i32,
+    z: i32,
+    dimension: &str,
+    generate: &dyn Fn(i32, i32) -> Result<Chunk, WorldError>,
+) -> Result<bool, WorldError> {
+    if world.chunk_exists(x, z, dimension)? {
+        return Ok(false);
+    }
+    let chunk = generate(x, z)?;
+    save_chunk_internal(world.storage_backend.as_ref(), &chunk)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_storage::memory::MemoryBackend;
+
+    #[test]
+    fn test_pregenerate_skips_existing_chunks() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        world
+            .save_chunk(Arc::new(Chunk::new(0, 0, "overworld".to_string())))
+            .unwrap();
+
+        let generate = |x, z| {
+            if (x, z) == (1, 1) {
+                return Err(WorldError::WorldGenerationError("broken".to_string()));
+            }
+            Ok(Chunk::new(x, z, "overworld".to_string()))
+        };
+        let summary = world
+            .pregenerate("overworld", 1, &ThreadPool::new(), generate)
+            .unwrap();
+        assert_eq!(
+            summary,
+            PregenSummary {
+                generated: 7,
+                skipped: 1,
+                failed: 1,
+            }
+        );
+        assert!(world.chunk_exists(-1, 1, "overworld").unwrap());
+        assert!(!world.chunk_exists(1, 1, "overworld").unwrap());
+
+        assert!(matches!(
+            world.pregenerate("overworld", u32::MAX, &ThreadPool::new(), generate),
+            Err(WorldError::InvalidPregenRadius(u32::MAX))
+        ));
+    }
+}
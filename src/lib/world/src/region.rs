@@ -0,0 +1,321 @@
+//! Editing cuboids of blocks at once, as done by building tools like `//set` and `//paste`.
+//!
+//! Every edit goes through [World::edit_blocks], which batches the changes per chunk with an
+//! [EditBatch](crate::edit_batch::EditBatch) and records the blocks it replaced in an
+//! [AppliedEdits], so the edit can be undone later with [World::undo_edits] and recorded in the
+//! block log with [World::log_edits].
+
+use crate::block_log::BlockChange;
+use crate::block_state_id::BlockStateId;
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::schematic::Schematic;
+use crate::World;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// The most blocks a single edit or schematic may hold, so one command or file can't stall the
+/// server or run it out of memory.
+pub const MAX_EDIT_VOLUME: u64 = 1_000_000;
+
+/// A cuboid of blocks between two corners, both inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: (i32, i32, i32),
+    pub max: (i32, i32, i32),
+}
+
+/// What an edit changed, so it can be undone and sent to the players that can see it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppliedEdits {
+    /// The blocks that were replaced and where they were.
+    pub previous: Vec<((i32, i32, i32), BlockStateId)>,
+    /// The blocks put in their place, in the same order as `previous`.
+    pub placed: Vec<BlockStateId>,
+    /// The chunks that were changed.
+    pub chunks: Vec<(i32, i32)>,
+}
+
+impl Region {
+    /// Creates the region spanning two opposite corners, given in any order.
+    pub fn new(a: (i32, i32, i32), b: (i32, i32, i32)) -> Self {
+        Self {
+            min: (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+            max: (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+        }
+    }
+
+    /// The width (X), height (Y) and length (Z) of the region in blocks.
+    pub fn size(&self) -> (u32, u32, u32) {
+        (
+            self.max.0.abs_diff(self.min.0) + 1,
+            self.max.1.abs_diff(self.min.1) + 1,
+            self.max.2.abs_diff(self.min.2) + 1,
+        )
+    }
+
+    /// How many blocks the region holds.
+    pub fn volume(&self) -> u64 {
+        let (width, height, length) = self.size();
+        width as u64 * height as u64 * length as u64
+    }
+
+    pub fn contains(&self, (x, y, z): (i32, i32, i32)) -> bool {
+        (self.min.0..=self.max.0).contains(&x)
+            && (self.min.1..=self.max.1).contains(&y)
+            && (self.min.2..=self.max.2).contains(&z)
+    }
+
+    /// Every position in the region.
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32, i32)> {
+        let (min, max) = (self.min, self.max);
+        (min.1..=max.1).flat_map(move |y| {
+            (min.2..=max.2).flat_map(move |z| (min.0..=max.0).map(move |x| (x, y, z)))
+        })
+    }
+
+    /// The positions on the four vertical sides of the region, leaving out the floor and ceiling.
+    pub fn walls(&self) -> impl Iterator<Item = (i32, i32, i32)> {
+        let (min, max) = (self.min, self.max);
+        self.positions()
+            .filter(move |(x, _, z)| *x == min.0 || *x == max.0 || *z == min.2 || *z == max.2)
+    }
+
+    /// The chunks the region overlaps.
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (min, max) = (self.min, self.max);
+        ((min.0 >> 4)..=(max.0 >> 4))
+            .flat_map(move |x| ((min.2 >> 4)..=(max.2 >> 4)).map(move |z| (x, z)))
+    }
+}
+
+impl World {
+    /// Calls `edit` with the current block at each of `positions`, and replaces the block with
+    /// whatever it returns. Returning `None`, or the block that's already there, leaves it alone.
+    ///
+    /// The blocks are set with one [EditBatch](crate::edit_batch::EditBatch) per chunk, so each
+    /// chunk is only rewritten once however many blocks change in it. Positions outside the
+    /// dimension's height, or in chunks that don't exist, are skipped.
+    pub fn edit_blocks(
+        &self,
+        dimension: &str,
+        positions: impl IntoIterator<Item = (i32, i32, i32)>,
+        mut edit: impl FnMut((i32, i32, i32), BlockStateId) -> Option<BlockStateId>,
+    ) -> Result<AppliedEdits, WorldError> {
+        let y_range =
+            Dimension::from_name(dimension).map(|dim| dim.min_y()..dim.min_y() + dim.height());
+        let mut per_chunk: HashMap<(i32, i32), Vec<(i32, i32, i32)>> = HashMap::new();
+        for (x, y, z) in positions {
+            if y_range.as_ref().is_some_and(|range| !range.contains(&y)) {
+                continue;
+            }
+            per_chunk
+                .entry((x >> 4, z >> 4))
+                .or_default()
+                .push((x, y, z));
+        }
+
+        let mut applied = AppliedEdits::default();
+        for ((chunk_x, chunk_z), positions) in per_chunk {
+            let chunk = match self.load_chunk(chunk_x, chunk_z, dimension) {
+                Ok(chunk) => chunk,
+                Err(WorldError::ChunkNotFound) => {
+                    warn!(
+                        "Skipping {} block edits in missing chunk ({}, {}) in {}",
+                        positions.len(),
+                        chunk_x,
+                        chunk_z,
+                        dimension
+                    );
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let mut changes = Vec::new();
+            for (x, y, z) in positions {
+                let old_block = chunk.get_block(x, y, z).unwrap_or_default();
+                match edit((x, y, z), old_block) {
+                    Some(new_block) if new_block != old_block => {
+                        changes.push(((x, y, z), old_block, new_block))
+                    }
+                    _ => {}
+                }
+            }
+            if changes.is_empty() {
+                continue;
+            }
+
+            self.edit_chunk(chunk_x, chunk_z, dimension, |batch| {
+                for ((x, y, z), _, new_block) in &changes {
+                    batch.set_block(*x, *y, *z, *new_block);
+                }
+            })?;
+            applied.previous.extend(
+                changes
+                    .iter()
+                    .map(|(position, old_block, _)| (*position, *old_block)),
+            );
+            applied
+                .placed
+                .extend(changes.iter().map(|(_, _, new_block)| *new_block));
+            applied.chunks.push((chunk_x, chunk_z));
+        }
+        Ok(applied)
+    }
+
+    /// Puts back the blocks an edit replaced. The returned edits undo the undo.
+    pub fn undo_edits(
+        &self,
+        dimension: &str,
+        edits: &AppliedEdits,
+    ) -> Result<AppliedEdits, WorldError> {
+        let previous = edits.previous.iter().copied().collect::<HashMap<_, _>>();
+        self.edit_blocks(dimension, previous.keys().copied(), |position, _| {
+            previous.get(&position).copied()
+        })
+    }
+
+    /// Records the blocks an edit changed in the block log under the given player, so they can be
+    /// rolled back like changes made by hand.
+    pub fn log_edits(
+        &self,
+        player: u128,
+        player_name: &str,
+        dimension: Dimension,
+        edits: &AppliedEdits,
+    ) {
+        for ((position, old_block), new_block) in edits.previous.iter().zip(&edits.placed) {
+            self.log_block_change(BlockChange::new(
+                player,
+                player_name.to_string(),
+                *position,
+                dimension,
+                *old_block,
+                *new_block,
+            ));
+        }
+    }
+
+    /// Copies the blocks in a region into a schematic, with its offset set so that pasting it at
+    /// `origin` puts the blocks back where they were. Blocks in missing chunks are copied as air.
+    pub fn copy_region(
+        &self,
+        region: &Region,
+        dimension: &str,
+        origin: (i32, i32, i32),
+    ) -> Result<Schematic, WorldError> {
+        let (width, height, length) = region.size();
+        let as_u16 = |size: u32| u16::try_from(size).ok();
+        let (Some(width), Some(height), Some(length)) =
+            (as_u16(width), as_u16(height), as_u16(length))
+        else {
+            return Err(WorldError::InvalidSchematic(format!(
+                "Region is too large to copy: {width}x{height}x{length}"
+            )));
+        };
+
+        let mut schematic = Schematic::new(width, height, length);
+        schematic.offset = (
+            region.min.0 - origin.0,
+            region.min.1 - origin.1,
+            region.min.2 - origin.2,
+        );
+        for (chunk_x, chunk_z) in region.chunks() {
+            let chunk = match self.load_chunk(chunk_x, chunk_z, dimension) {
+                Ok(chunk) => chunk,
+                Err(WorldError::ChunkNotFound) => continue,
+                Err(e) => return Err(e),
+            };
+            let chunk_region = Region {
+                min: (
+                    region.min.0.max(chunk_x << 4),
+                    region.min.1,
+                    region.min.2.max(chunk_z << 4),
+                ),
+                max: (
+                    region.max.0.min((chunk_x << 4) + 15),
+                    region.max.1,
+                    region.max.2.min((chunk_z << 4) + 15),
+                ),
+            };
+            for (x, y, z) in chunk_region.positions() {
+                let block = chunk.get_block(x, y, z).unwrap_or_default();
+                schematic.set_block(x - region.min.0, y - region.min.1, z - region.min.2, block);
+            }
+        }
+        Ok(schematic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    fn world_with_chunks() -> World {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        for (x, z) in [(-1, 0), (0, 0)] {
+            world
+                .save_chunk(Arc::new(Chunk::new(x, z, "overworld".to_string())))
+                .unwrap();
+        }
+        world
+    }
+
+    #[test]
+    fn test_region_walls() {
+        let region = Region::new((2, 0, 2), (0, 1, 0));
+        assert_eq!(region.size(), (3, 2, 3));
+        assert_eq!(region.volume(), 18);
+        // Everything but the middle column.
+        assert_eq!(region.walls().count(), 16);
+        assert!(region.walls().all(|(x, _, z)| (x, z) != (1, 1)));
+    }
+
+    #[test]
+    fn test_edit_and_undo() {
+        let world = world_with_chunks();
+        let region = Region::new((-2, 10, 0), (1, 10, 0));
+        let edits = world
+            .edit_blocks("overworld", region.positions(), |_, _| {
+                Some(block!("stone"))
+            })
+            .unwrap();
+        assert_eq!(edits.previous.len(), 4);
+        assert_eq!(edits.chunks.len(), 2);
+        let block_at = |x| world.get_block_and_fetch(x, 10, 0, "overworld").unwrap();
+        assert_eq!(block_at(-2), block!("stone"));
+
+        // Setting the same block again changes nothing.
+        let again = world
+            .edit_blocks("overworld", region.positions(), |_, _| {
+                Some(block!("stone"))
+            })
+            .unwrap();
+        assert!(again.previous.is_empty());
+
+        world.log_edits(0, "Server", Dimension::Overworld, &edits);
+        let logged = world.block_changes(0, usize::MAX, |change| change.player_name == "Server");
+        assert_eq!(logged.unwrap().len(), 4);
+
+        world.undo_edits("overworld", &edits).unwrap();
+        assert_eq!(block_at(-2), BlockStateId::default());
+        assert_eq!(block_at(1), BlockStateId::default());
+    }
+
+    #[test]
+    fn test_copy_region() {
+        let world = world_with_chunks();
+        world
+            .set_block_and_fetch(-1, 5, 3, "overworld", block!("dirt"))
+            .unwrap();
+        let region = Region::new((-1, 5, 3), (0, 6, 4));
+        let schematic = world.copy_region(&region, "overworld", (0, 5, 0)).unwrap();
+        assert_eq!(schematic.offset, (-1, 0, 3));
+        assert_eq!(schematic.get_block(0, 0, 0), Some(block!("dirt")));
+        assert_eq!(schematic.get_block(1, 1, 1), Some(BlockStateId::default()));
+    }
+}
@@ -0,0 +1,457 @@
+//! Reading, writing and pasting [Sponge schematics](https://github.com/SpongePowered/Schematic-Specification)
+//! (`.schem` files), the format used by WorldEdit and most other building tools.
+//!
+//! Versions 2 and 3 of the format can be read, and either can be written. Only blocks are
+//! supported; block entities, entities and biomes in a file are ignored. Block states are stored
+//! in the file as strings like `minecraft:oak_stairs[facing=north,half=bottom]`, which are mapped
+//! with [BlockStateId::from_state_string] when loading.
+
+use crate::block_state_id::BlockStateId;
+use crate::chunk_format::VANILLA_DATA_VERSION;
+use crate::errors::WorldError;
+use crate::region::{AppliedEdits, MAX_EDIT_VOLUME};
+use crate::World;
+use ferrumc_macros::{NBTDeserialize, NBTSerialize};
+use ferrumc_nbt::{NBTSerializable, NBTSerializeOptions, NbtTape};
+use ferrumc_net_codec::net_types::var_int::VarInt;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use tracing::warn;
+
+/// The first two bytes of a gzip stream. `.schem` files are almost always gzipped, but plain NBT
+/// is accepted too.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The largest NBT file, once decompressed, that's read. That's plenty for a schematic of
+/// [MAX_EDIT_VOLUME] blocks, and stops a small gzip bomb from filling the memory.
+const MAX_NBT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The versions of the Sponge schematic format that can be written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchematicVersion {
+    V2,
+    #[default]
+    V3,
+}
+
+/// A cuboid of blocks loaded from or to be saved as a Sponge schematic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+    width: u16,
+    height: u16,
+    length: u16,
+    /// Where the schematic is placed relative to the position it's pasted at.
+    pub offset: (i32, i32, i32),
+    /// Blocks ordered by Y, then Z, then X, as in the file format.
+    blocks: Vec<BlockStateId>,
+}
+
+/// A version 3 file, where the schematic is wrapped in an unnamed root compound.
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+#[nbt(is_root)]
+#[nbt(rename = "")]
+struct SpongeRootV3 {
+    #[nbt(rename = "Schematic")]
+    schematic: SpongeSchematic,
+}
+
+/// The fields of both version 2 and version 3 schematics. Version 2 keeps the palette and block
+/// data at the top level, while version 3 moves them into `Blocks`.
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct SpongeSchematic {
+    #[nbt(rename = "Version")]
+    version: i32,
+    #[nbt(rename = "DataVersion")]
+    data_version: Option<i32>,
+    #[nbt(rename = "Width")]
+    width: u16,
+    #[nbt(rename = "Height")]
+    height: u16,
+    #[nbt(rename = "Length")]
+    length: u16,
+    #[nbt(rename = "Offset")]
+    offset: Option<Vec<i32>>,
+    #[nbt(rename = "PaletteMax")]
+    palette_max: Option<i32>,
+    #[nbt(rename = "Palette")]
+    palette: Option<HashMap<String, i32>>,
+    #[nbt(rename = "BlockData")]
+    block_data: Option<Vec<i8>>,
+    #[nbt(rename = "Blocks")]
+    blocks: Option<SpongeBlockContainer>,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct SpongeBlockContainer {
+    #[nbt(rename = "Palette")]
+    palette: HashMap<String, i32>,
+    #[nbt(rename = "Data")]
+    data: Vec<i8>,
+}
+
+impl Schematic {
+    /// Creates a schematic of the given size filled with air.
+    pub fn new(width: u16, height: u16, length: u16) -> Self {
+        Self {
+            width,
+            height,
+            length,
+            offset: (0, 0, 0),
+            blocks: vec![
+                BlockStateId::default();
+                width as usize * height as usize * length as usize
+            ],
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    fn index(&self, x: i32, y: i32, z: i32) -> Option<usize> {
+        if x < 0
+            || y < 0
+            || z < 0
+            || x >= self.width as i32
+            || y >= self.height as i32
+            || z >= self.length as i32
+        {
+            return None;
+        }
+        let (width, length) = (self.width as usize, self.length as usize);
+        Some(x as usize + z as usize * width + y as usize * width * length)
+    }
+
+    /// Gets the block at the given position within the schematic, or `None` if it's out of
+    /// bounds.
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockStateId> {
+        self.index(x, y, z).map(|index| self.blocks[index])
+    }
+
+    /// Sets the block at the given position within the schematic. Positions out of bounds are
+    /// ignored.
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockStateId) {
+        if let Some(index) = self.index(x, y, z) {
+            self.blocks[index] = block;
+        }
+    }
+
+    /// Iterates over every block in the schematic along with its position within it.
+    pub fn blocks(&self) -> impl Iterator<Item = ((i32, i32, i32), BlockStateId)> + '_ {
+        let (width, length) = (self.width as usize, self.length as usize);
+        self.blocks.iter().enumerate().map(move |(index, block)| {
+            let x = index % width;
+            let z = (index / width) % length;
+            let y = index / (width * length);
+            ((x as i32, y as i32, z as i32), *block)
+        })
+    }
+
+    /// Reads a schematic from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WorldError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Writes the schematic to a file in the given format version.
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        version: SchematicVersion,
+    ) -> Result<(), WorldError> {
+        std::fs::write(path, self.to_bytes(version)?)?;
+        Ok(())
+    }
+
+    /// Parses a version 2 or 3 schematic, gzipped or not.
+    ///
+    /// Palette entries that don't match any known block state are replaced with air, with a
+    /// warning. Schematics holding more than [MAX_EDIT_VOLUME] blocks are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WorldError> {
+        let mut decompressed = Vec::new();
+        let bytes = read_nbt(bytes, &mut decompressed, WorldError::InvalidSchematic)?;
+
+        // Version 3 nests everything in a `Schematic` compound, version 2 doesn't.
+        let mut tape = NbtTape::new(bytes);
+        tape.parse();
+        let schematic = if tape.get("Schematic").is_some() {
+            SpongeRootV3::from_bytes(bytes)?.schematic
+        } else {
+            SpongeSchematic::from_bytes(bytes)?
+        };
+        let (palette, data) = match schematic.version {
+            2 => (schematic.palette, schematic.block_data),
+            3 => schematic
+                .blocks
+                .map(|blocks| (Some(blocks.palette), Some(blocks.data)))
+                .unwrap_or_default(),
+            version => {
+                return Err(WorldError::InvalidSchematic(format!(
+                    "Unsupported schematic version {version}"
+                )))
+            }
+        };
+
+        // The size comes straight from the file, so check it before allocating the blocks.
+        let volume =
+            u64::from(schematic.width) * u64::from(schematic.height) * u64::from(schematic.length);
+        if volume > MAX_EDIT_VOLUME {
+            return Err(WorldError::InvalidSchematic(format!(
+                "The schematic holds {volume} blocks, but at most {MAX_EDIT_VOLUME} are allowed"
+            )));
+        }
+        let mut result = Self::new(schematic.width, schematic.height, schematic.length);
+        if let Some(offset) = schematic.offset {
+            let [x, y, z] = offset[..] else {
+                return Err(WorldError::InvalidSchematic(format!(
+                    "Offset has {} values instead of 3",
+                    offset.len()
+                )));
+            };
+            result.offset = (x, y, z);
+        }
+        // Schematics without blocks (e.g. only holding entities) are just air.
+        let (Some(palette), Some(data)) = (palette, data) else {
+            return Ok(result);
+        };
+
+        let palette = parse_palette(&palette)?;
+        let data = data.into_iter().map(|byte| byte as u8).collect::<Vec<_>>();
+        let mut cursor = Cursor::new(data.as_slice());
+        for block in result.blocks.iter_mut() {
+            let id = VarInt::read(&mut cursor)
+                .map_err(|e| WorldError::InvalidSchematic(format!("Bad block data: {e}")))?;
+            *block = *palette.get(id.0 as usize).ok_or_else(|| {
+                WorldError::InvalidSchematic(format!("Palette index {} out of bounds", id.0))
+            })?;
+        }
+        Ok(result)
+    }
+
+    /// Encodes the schematic in the given format version, gzipped as `.schem` files are.
+    pub fn to_bytes(&self, version: SchematicVersion) -> Result<Vec<u8>, WorldError> {
+        let mut palette = HashMap::new();
+        let mut data = Vec::with_capacity(self.blocks.len());
+        for block in &self.blocks {
+            let next_index = palette.len() as i32;
+            let index = *palette.entry(*block).or_insert(next_index);
+            VarInt(index)
+                .write(&mut data)
+                .map_err(|e| WorldError::InvalidSchematic(e.to_string()))?;
+        }
+        let palette = palette
+            .into_iter()
+            .map(|(block, index)| {
+                let state = block
+                    .to_state_string()
+                    .ok_or(WorldError::MissingBlockMapping(block))?;
+                Ok((state, index))
+            })
+            .collect::<Result<HashMap<_, _>, WorldError>>()?;
+        let data = data.into_iter().map(|byte| byte as i8).collect::<Vec<_>>();
+
+        let mut schematic = SpongeSchematic {
+            version: 2,
+            data_version: Some(VANILLA_DATA_VERSION),
+            width: self.width,
+            height: self.height,
+            length: self.length,
+            offset: Some(vec![self.offset.0, self.offset.1, self.offset.2]),
+            palette_max: None,
+            palette: None,
+            block_data: None,
+            blocks: None,
+        };
+        let mut nbt = Vec::new();
+        match version {
+            SchematicVersion::V2 => {
+                schematic.palette_max = Some(palette.len() as i32);
+                schematic.palette = Some(palette);
+                schematic.block_data = Some(data);
+                schematic.serialize(&mut nbt, &NBTSerializeOptions::WithHeader("Schematic"));
+            }
+            SchematicVersion::V3 => {
+                schematic.version = 3;
+                schematic.blocks = Some(SpongeBlockContainer { palette, data });
+                SpongeRootV3 { schematic }
+                    .serialize(&mut nbt, &NBTSerializeOptions::WithHeader(""));
+            }
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&nbt)?;
+        Ok(encoder.finish()?)
+    }
+}
+
+/// Ungzips NBT data if it's gzipped, using `buffer` to hold the decompressed data, and checks
+/// that it can be parsed. The NBT parser trusts its input and panics on truncated or corrupt
+/// data, so anything read from a file has to go through this first.
+///
+/// Data that's too big or isn't valid NBT is reported with the `invalid` error.
+pub(crate) fn read_nbt<'a>(
+    bytes: &'a [u8],
+    buffer: &'a mut Vec<u8>,
+    invalid: fn(String) -> WorldError,
+) -> Result<&'a [u8], WorldError> {
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(bytes)
+            .take(MAX_NBT_SIZE + 1)
+            .read_to_end(buffer)
+            .map_err(|e| invalid(format!("Bad gzip data: {e}")))?;
+        buffer.as_slice()
+    } else {
+        bytes
+    };
+    if bytes.len() as u64 > MAX_NBT_SIZE {
+        return Err(invalid(format!(
+            "The file is over {MAX_NBT_SIZE} bytes once decompressed"
+        )));
+    }
+    NbtTape::validate(bytes).map_err(|e| invalid(format!("Bad NBT data: {e}")))?;
+    Ok(bytes)
+}
+
+/// Maps a schematic palette to block state IDs, indexed by palette index. Every index has to be
+/// less than the number of entries in the palette.
+fn parse_palette(palette: &HashMap<String, i32>) -> Result<Vec<BlockStateId>, WorldError> {
+    let mut blocks = vec![BlockStateId::default(); palette.len()];
+    for (state, index) in palette {
+        if *index < 0 || *index as usize >= palette.len() {
+            return Err(WorldError::InvalidSchematic(format!(
+                "Palette index {index} for {state} is out of bounds"
+            )));
+        }
+        match BlockStateId::from_state_string(state) {
+            Some(block) => blocks[*index as usize] = block,
+            None => warn!("Unknown block state in schematic, using air instead: {state}"),
+        }
+    }
+    Ok(blocks)
+}
+
+impl World {
+    /// Pastes a schematic into the world, with its origin (before applying its offset) at
+    /// `position`. See [World::edit_blocks] for how the blocks are set.
+    ///
+    /// If `skip_air` is set, air in the schematic leaves the existing blocks alone.
+    pub fn paste_schematic(
+        &self,
+        schematic: &Schematic,
+        position: (i32, i32, i32),
+        dimension: &str,
+        skip_air: bool,
+    ) -> Result<AppliedEdits, WorldError> {
+        let origin = (
+            position.0 + schematic.offset.0,
+            position.1 + schematic.offset.1,
+            position.2 + schematic.offset.2,
+        );
+        let positions = schematic
+            .blocks()
+            .filter(|(_, block)| !skip_air || *block != BlockStateId::default())
+            .map(|((x, y, z), _)| (origin.0 + x, origin.1 + y, origin.2 + z));
+        self.edit_blocks(dimension, positions, |(x, y, z), _| {
+            schematic.get_block(x - origin.0, y - origin.1, z - origin.2)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_format::Chunk;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use std::sync::Arc;
+
+    fn sample() -> Schematic {
+        let mut schematic = Schematic::new(3, 2, 4);
+        schematic.offset = (-1, 0, 2);
+        schematic.set_block(0, 0, 0, block!("stone"));
+        schematic.set_block(2, 1, 3, block!("oak_log", { axis: "x" }));
+        schematic
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let schematic = sample();
+        for version in [SchematicVersion::V2, SchematicVersion::V3] {
+            let bytes = schematic.to_bytes(version).unwrap();
+            assert_eq!(Schematic::from_bytes(&bytes).unwrap(), schematic);
+        }
+    }
+
+    /// A version 2 schematic of the given size and palette, with every block set to index 0.
+    fn encode(size: (u16, u16, u16), palette: &[(&str, i32)]) -> Vec<u8> {
+        let (width, height, length) = size;
+        let volume = width as usize * height as usize * length as usize;
+        let schematic = SpongeSchematic {
+            version: 2,
+            data_version: None,
+            width,
+            height,
+            length,
+            offset: None,
+            palette_max: None,
+            palette: Some(
+                palette
+                    .iter()
+                    .map(|(state, index)| (state.to_string(), *index))
+                    .collect(),
+            ),
+            block_data: Some(vec![0; volume.min(64)]),
+            blocks: None,
+        };
+        let mut nbt = Vec::new();
+        schematic.serialize(&mut nbt, &NBTSerializeOptions::WithHeader("Schematic"));
+        nbt
+    }
+
+    #[test]
+    fn test_invalid_schematics() {
+        let valid = encode((2, 2, 2), &[("minecraft:air", 0), ("minecraft:stone", 1)]);
+        assert!(Schematic::from_bytes(&valid).is_ok());
+
+        let negative = encode((2, 2, 2), &[("minecraft:air", 0), ("minecraft:stone", -1)]);
+        assert!(Schematic::from_bytes(&negative).is_err());
+        let past_the_end = encode((2, 2, 2), &[("minecraft:air", 0), ("minecraft:stone", 2)]);
+        assert!(Schematic::from_bytes(&past_the_end).is_err());
+        for length in [1, 10, valid.len() / 2, valid.len() - 1] {
+            assert!(matches!(
+                Schematic::from_bytes(&valid[..length]),
+                Err(WorldError::InvalidSchematic(_))
+            ));
+        }
+        let huge = encode((u16::MAX, u16::MAX, u16::MAX), &[("minecraft:air", 0)]);
+        assert!(Schematic::from_bytes(&huge).is_err());
+    }
+
+    #[test]
+    fn test_paste_across_chunks() {
+        let world = World::with_backend(Arc::new(MemoryBackend::new()));
+        for (x, z) in [(-1, 0), (0, 0)] {
+            world
+                .save_chunk(Arc::new(Chunk::new(x, z, "overworld".to_string())))
+                .unwrap();
+        }
+        let edits = world
+            .paste_schematic(&sample(), (0, 10, 0), "overworld", true)
+            .unwrap();
+        assert_eq!(edits.previous.len(), 2);
+        assert_eq!(edits.chunks.len(), 2);
+        let block_at = |x, y, z| world.get_block_and_fetch(x, y, z, "overworld").unwrap();
+        assert_eq!(block_at(-1, 10, 2), block!("stone"));
+        assert_eq!(block_at(1, 11, 5), block!("oak_log", { axis: "x" }));
+    }
+}
@@ -0,0 +1,583 @@
+//! Loading and placing vanilla structure templates, the `.nbt` files in a data pack's
+//! `data/<namespace>/structure` folder that villages, shipwrecks and other structures are built
+//! from, and that structure blocks save.
+//!
+//! Templates can be rotated and mirrored when placed, the same way vanilla does it, which also
+//! turns the blocks themselves so stairs, logs, fences, rails and signs keep facing the right way.
+//! Blocks are placed through an [EditBatch], either into a chunk that's being generated with
+//! [StructureTemplate::place_in_chunk] or into the world with [World::place_structure]. Block
+//! entities and entities in a template are ignored.
+
+use crate::block_state_id::{BlockStateId, BLOCK2ID};
+use crate::chunk_format::Chunk;
+use crate::dimension::Dimension;
+use crate::edit_batch::EditBatch;
+use crate::errors::WorldError;
+use crate::region::{AppliedEdits, Region};
+use crate::schematic::read_nbt;
+use crate::vanilla_chunk_format::BlockData;
+use crate::World;
+use ferrumc_macros::{NBTDeserialize, NBTSerialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tracing::warn;
+
+/// The horizontal directions in clockwise order, as they're named in block states.
+const HORIZONTAL_DIRECTIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+/// How a structure is turned around the vertical axis when placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Counterclockwise90,
+}
+
+/// How a structure is flipped when placed, before it's rotated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Flips the structure along the Z axis, swapping north and south.
+    LeftRight,
+    /// Flips the structure along the X axis, swapping east and west.
+    FrontBack,
+}
+
+/// How a [StructureTemplate] is placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StructurePlacement {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+    /// The point the structure is rotated around, relative to its origin. Only X and Z are used.
+    pub pivot: (i32, i32, i32),
+    /// Which of the template's palettes to use, wrapping around if it's out of range. Most
+    /// templates only have one, but some like shipwrecks have a palette per variant.
+    pub palette: usize,
+    /// Leaves the existing blocks alone where the template has air.
+    pub ignore_air: bool,
+}
+
+/// A structure template loaded from an `.nbt` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructureTemplate {
+    size: (i32, i32, i32),
+    /// The blocks of each palette, indexed by palette index. Block states that aren't known are
+    /// `None` and never placed.
+    palettes: Vec<Vec<Option<BlockStateId>>>,
+    /// Each block's position within the template and its palette index.
+    blocks: Vec<((i32, i32, i32), usize)>,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+#[nbt(is_root)]
+#[nbt(rename = "")]
+struct TemplateFile {
+    size: Vec<i32>,
+    palette: Option<Vec<BlockData>>,
+    palettes: Option<Vec<Vec<BlockData>>>,
+    blocks: Vec<TemplateBlock>,
+}
+
+#[derive(NBTSerialize, NBTDeserialize, Debug)]
+struct TemplateBlock {
+    pos: Vec<i32>,
+    state: i32,
+}
+
+impl Rotation {
+    /// How many quarter turns clockwise the rotation is.
+    fn quarter_turns(self) -> usize {
+        match self {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::Counterclockwise90 => 3,
+        }
+    }
+
+    /// Rotates a horizontal direction name, leaving anything else alone.
+    fn rotate_direction(self, direction: &str) -> &str {
+        match HORIZONTAL_DIRECTIONS.iter().position(|d| *d == direction) {
+            Some(index) => HORIZONTAL_DIRECTIONS[(index + self.quarter_turns()) % 4],
+            None => direction,
+        }
+    }
+}
+
+impl Mirror {
+    /// Mirrors a horizontal direction name, leaving anything else alone.
+    fn mirror_direction(self, direction: &str) -> &str {
+        match (self, direction) {
+            (Mirror::LeftRight, "north") => "south",
+            (Mirror::LeftRight, "south") => "north",
+            (Mirror::FrontBack, "east") => "west",
+            (Mirror::FrontBack, "west") => "east",
+            _ => direction,
+        }
+    }
+}
+
+impl StructurePlacement {
+    /// Where a position within the template ends up, relative to where the template is placed.
+    pub fn transform_position(&self, (x, y, z): (i32, i32, i32)) -> (i32, i32, i32) {
+        let (x, z) = match self.mirror {
+            Mirror::None => (x, z),
+            Mirror::LeftRight => (x, -z),
+            Mirror::FrontBack => (-x, z),
+        };
+        let (pivot_x, pivot_z) = (self.pivot.0, self.pivot.2);
+        match self.rotation {
+            Rotation::None => (x, y, z),
+            Rotation::Clockwise90 => (pivot_x + pivot_z - z, y, pivot_z - pivot_x + x),
+            Rotation::Clockwise180 => (2 * pivot_x - x, y, 2 * pivot_z - z),
+            Rotation::Counterclockwise90 => (pivot_x - pivot_z + z, y, pivot_x + pivot_z - x),
+        }
+    }
+
+    /// Turns a block state to match the placement, so e.g. stairs keep facing the same way
+    /// relative to the rest of the structure. Blocks that can't be turned are returned as is.
+    pub fn transform_block(&self, block: BlockStateId) -> BlockStateId {
+        if self.mirror == Mirror::None && self.rotation == Rotation::None {
+            return block;
+        }
+        let Some(mut data) = block.to_block_data() else {
+            return block;
+        };
+        let Some(properties) = data.properties.as_mut() else {
+            return block;
+        };
+
+        if self.mirror != Mirror::None {
+            turn_properties(properties, |direction| {
+                self.mirror.mirror_direction(direction)
+            });
+            for key in ["hinge", "type", "shape"] {
+                if let Some(value) = properties.get_mut(key) {
+                    *value = map_words(value, |word| match word {
+                        "left" => "right",
+                        "right" => "left",
+                        word => word,
+                    });
+                }
+            }
+            if let Some(value) = properties.get_mut("rotation") {
+                if let Ok(rotation) = value.parse::<u8>() {
+                    let mirrored = match self.mirror {
+                        Mirror::LeftRight => (24 - rotation) % 16,
+                        _ => (16 - rotation) % 16,
+                    };
+                    *value = mirrored.to_string();
+                }
+            }
+        }
+
+        if self.rotation != Rotation::None {
+            let turns = self.rotation.quarter_turns();
+            turn_properties(properties, |direction| {
+                self.rotation.rotate_direction(direction)
+            });
+            if let Some(axis) = properties.get_mut("axis").filter(|_| turns % 2 == 1) {
+                *axis = match axis.as_str() {
+                    "x" => "z".to_string(),
+                    "z" => "x".to_string(),
+                    other => other.to_string(),
+                };
+            }
+            if let Some(value) = properties.get_mut("rotation") {
+                if let Ok(rotation) = value.parse::<usize>() {
+                    *value = ((rotation + turns * 4) % 16).to_string();
+                }
+            }
+        }
+
+        let lookup = |data: &BlockData| BLOCK2ID.get(data).map(|id| BlockStateId(*id as u32));
+        lookup(&data)
+            .or_else(|| {
+                // Rail shapes always name north or south first, which turning them can change.
+                let shape = data.properties.as_mut()?.get_mut("shape")?;
+                let (first, second) = shape.split_once('_')?;
+                *shape = format!("{second}_{first}");
+                lookup(&data)
+            })
+            .unwrap_or(block)
+    }
+}
+
+/// Turns the directions in a block's properties, both in values like `facing=north` and in the
+/// `north`/`east`/`south`/`west` properties fences, walls and vines use for their sides.
+fn turn_properties(properties: &mut BTreeMap<String, String>, turn: impl Fn(&str) -> &str) {
+    for key in ["facing", "orientation", "shape"] {
+        if let Some(value) = properties.get_mut(key) {
+            *value = map_words(value, &turn);
+        }
+    }
+    let sides = HORIZONTAL_DIRECTIONS
+        .iter()
+        .filter_map(|direction| properties.remove_entry(*direction))
+        .collect::<Vec<_>>();
+    for (side, value) in sides {
+        properties.insert(turn(side.as_str()).to_string(), value);
+    }
+}
+
+/// Maps each underscore separated word of a property value, e.g. both halves of `north_east`.
+fn map_words(value: &str, map: impl Fn(&str) -> &str) -> String {
+    value.split('_').map(map).collect::<Vec<_>>().join("_")
+}
+
+/// Maps a palette entry to its block state. Properties left out of the entry take their default
+/// values, and unknown states are skipped with a warning.
+fn resolve_block(data: &BlockData) -> Option<BlockStateId> {
+    if let Some(id) = BLOCK2ID.get(data) {
+        return Some(BlockStateId(*id as u32));
+    }
+    let state = match &data.properties {
+        Some(properties) if !properties.is_empty() => {
+            let properties = properties
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>();
+            format!("{}[{}]", data.name, properties.join(","))
+        }
+        _ => data.name.clone(),
+    };
+    let block = BlockStateId::from_state_string(&state);
+    if block.is_none() {
+        warn!("Unknown block state in structure template, skipping it: {state}");
+    }
+    block
+}
+
+impl StructureTemplate {
+    /// The width (X), height (Y) and length (Z) of the template before it's rotated.
+    pub fn size(&self) -> (i32, i32, i32) {
+        self.size
+    }
+
+    /// How many palettes the template has to pick from with [StructurePlacement::palette].
+    pub fn palette_count(&self) -> usize {
+        self.palettes.len()
+    }
+
+    /// Reads a template from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WorldError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Reads a template by its resource location from a data pack's `data` folder, e.g.
+    /// `minecraft:igloo/top` is read from `<data>/minecraft/structure/igloo/top.nbt`. The
+    /// namespace defaults to `minecraft`.
+    pub fn load_resource(data_dir: impl AsRef<Path>, name: &str) -> Result<Self, WorldError> {
+        let (namespace, path) = name.split_once(':').unwrap_or(("minecraft", name));
+        if path.split('/').any(|part| part == "..") {
+            return Err(WorldError::InvalidStructure(format!(
+                "Invalid resource location {name}"
+            )));
+        }
+        Self::load(
+            data_dir
+                .as_ref()
+                .join(namespace)
+                .join("structure")
+                .join(format!("{path}.nbt")),
+        )
+    }
+
+    /// Parses a template, gzipped or not.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WorldError> {
+        let mut decompressed = Vec::new();
+        let bytes = read_nbt(bytes, &mut decompressed, WorldError::InvalidStructure)?;
+        let file = TemplateFile::from_bytes(bytes)?;
+
+        let [width, height, length] = file.size[..] else {
+            return Err(WorldError::InvalidStructure(format!(
+                "Size has {} values instead of 3",
+                file.size.len()
+            )));
+        };
+        let palettes = match (file.palette, file.palettes) {
+            (Some(palette), _) => vec![palette],
+            (None, Some(palettes)) if !palettes.is_empty() => palettes,
+            _ => {
+                return Err(WorldError::InvalidStructure(
+                    "Template has no palette".to_string(),
+                ))
+            }
+        };
+        let palettes = palettes
+            .iter()
+            .map(|palette| palette.iter().map(resolve_block).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let palette_size = palettes.iter().map(Vec::len).min().unwrap_or_default();
+
+        let blocks = file
+            .blocks
+            .into_iter()
+            .map(|block| {
+                let [x, y, z] = block.pos[..] else {
+                    return Err(WorldError::InvalidStructure(format!(
+                        "Block position has {} values instead of 3",
+                        block.pos.len()
+                    )));
+                };
+                match usize::try_from(block.state) {
+                    Ok(state) if state < palette_size => Ok(((x, y, z), state)),
+                    _ => Err(WorldError::InvalidStructure(format!(
+                        "Palette index {} out of bounds",
+                        block.state
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            size: (width, height, length),
+            palettes,
+            blocks,
+        })
+    }
+
+    /// Iterates over the blocks to place, relative to where the template is placed, with both the
+    /// positions and block states turned to match the placement.
+    pub fn blocks<'a>(
+        &'a self,
+        placement: &StructurePlacement,
+    ) -> impl Iterator<Item = ((i32, i32, i32), BlockStateId)> + 'a {
+        let placement = *placement;
+        let palette = &self.palettes[placement.palette % self.palettes.len()];
+        let mut turned = HashMap::new();
+        self.blocks.iter().filter_map(move |(position, state)| {
+            let block = palette[*state]?;
+            if placement.ignore_air && block == BlockStateId::default() {
+                return None;
+            }
+            let block = *turned
+                .entry(block)
+                .or_insert_with(|| placement.transform_block(block));
+            Some((placement.transform_position(*position), block))
+        })
+    }
+
+    /// The region the template covers when placed with its origin at `position`, or `None` if
+    /// the template is empty. Useful for finding the chunks to call
+    /// [StructureTemplate::place_in_chunk] on.
+    pub fn placed_region(
+        &self,
+        position: (i32, i32, i32),
+        placement: &StructurePlacement,
+    ) -> Option<Region> {
+        let (width, height, length) = self.size;
+        if width <= 0 || height <= 0 || length <= 0 {
+            return None;
+        }
+        let offset = |(x, y, z): (i32, i32, i32)| (x + position.0, y + position.1, z + position.2);
+        Some(Region::new(
+            offset(placement.transform_position((0, 0, 0))),
+            offset(placement.transform_position((width - 1, height - 1, length - 1))),
+        ))
+    }
+
+    /// Places the part of the template that falls in `chunk`, with the template's origin at
+    /// `position`. Meant for world generation, where a structure spanning several chunks is
+    /// placed into each one as it's generated.
+    ///
+    /// Returns how many blocks were placed.
+    pub fn place_in_chunk(
+        &self,
+        chunk: &mut Chunk,
+        position: (i32, i32, i32),
+        placement: &StructurePlacement,
+    ) -> Result<usize, WorldError> {
+        let y_range = Dimension::from_name(&chunk.dimension)
+            .map(|dim| dim.min_y()..dim.min_y() + dim.height());
+        let (chunk_x, chunk_z) = (chunk.x, chunk.z);
+        let mut batch = EditBatch::new(chunk);
+        let mut placed = 0;
+        for ((x, y, z), block) in self.blocks(placement) {
+            let (x, y, z) = (x + position.0, y + position.1, z + position.2);
+            if x >> 4 != chunk_x
+                || z >> 4 != chunk_z
+                || y_range.as_ref().is_some_and(|range| !range.contains(&y))
+            {
+                continue;
+            }
+            batch.set_block(x, y, z, block);
+            placed += 1;
+        }
+        if placed > 0 {
+            batch.apply()?;
+        }
+        Ok(placed)
+    }
+}
+
+impl World {
+    /// Places a structure template with its origin at `position`. See [World::edit_blocks] for
+    /// how the blocks are set.
+    pub fn place_structure(
+        &self,
+        template: &StructureTemplate,
+        position: (i32, i32, i32),
+        dimension: &str,
+        placement: &StructurePlacement,
+    ) -> Result<AppliedEdits, WorldError> {
+        let blocks = template
+            .blocks(placement)
+            .map(|((x, y, z), block)| ((x + position.0, y + position.1, z + position.2), block))
+            .collect::<HashMap<_, _>>();
+        self.edit_blocks(dimension, blocks.keys().copied(), |position, _| {
+            blocks.get(&position).copied()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_macros::block;
+    use ferrumc_nbt::{NBTSerializable, NBTSerializeOptions};
+
+    fn sample_bytes() -> Vec<u8> {
+        let palette = ["minecraft:stone", "minecraft:oak_stairs"]
+            .into_iter()
+            .map(|name| BlockData {
+                name: name.to_string(),
+                // Left out properties use the default state, bottom stairs facing north.
+                properties: None,
+            })
+            .collect();
+        let file = TemplateFile {
+            size: vec![2, 1, 3],
+            palette: Some(palette),
+            palettes: None,
+            blocks: vec![
+                TemplateBlock {
+                    pos: vec![0, 0, 0],
+                    state: 0,
+                },
+                TemplateBlock {
+                    pos: vec![1, 0, 2],
+                    state: 1,
+                },
+            ],
+        };
+        let mut bytes = Vec::new();
+        file.serialize(&mut bytes, &NBTSerializeOptions::WithHeader(""));
+        bytes
+    }
+
+    fn sample() -> StructureTemplate {
+        StructureTemplate::from_bytes(&sample_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_truncated_template() {
+        let bytes = sample_bytes();
+        for length in [0, 1, 10, bytes.len() / 2, bytes.len() - 1] {
+            assert!(matches!(
+                StructureTemplate::from_bytes(&bytes[..length]),
+                Err(WorldError::InvalidStructure(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_transform_position() {
+        let rotated = StructurePlacement {
+            rotation: Rotation::Clockwise90,
+            ..Default::default()
+        };
+        // East turns to south.
+        assert_eq!(rotated.transform_position((1, 5, 0)), (0, 5, 1));
+        let mirrored = StructurePlacement {
+            mirror: Mirror::LeftRight,
+            pivot: (1, 0, 1),
+            rotation: Rotation::Clockwise180,
+            ..Default::default()
+        };
+        assert_eq!(mirrored.transform_position((0, 0, 1)), (2, 0, 3));
+    }
+
+    #[test]
+    fn test_transform_block() {
+        let rotated = StructurePlacement {
+            rotation: Rotation::Clockwise90,
+            ..Default::default()
+        };
+        assert_eq!(
+            rotated.transform_block(
+                block!("oak_stairs", { facing: "north", half: "bottom", shape: "straight", waterlogged: false })
+            ),
+            block!("oak_stairs", { facing: "east", half: "bottom", shape: "straight", waterlogged: false })
+        );
+        assert_eq!(
+            rotated.transform_block(block!("oak_log", { axis: "x" })),
+            block!("oak_log", { axis: "z" })
+        );
+        assert_eq!(
+            rotated.transform_block(
+                block!("oak_fence", { east: false, north: true, south: false, waterlogged: false, west: false })
+            ),
+            block!("oak_fence", { east: true, north: false, south: false, waterlogged: false, west: false })
+        );
+        assert_eq!(
+            rotated.transform_block(block!("rail", { shape: "north_east", waterlogged: false })),
+            block!("rail", { shape: "south_east", waterlogged: false })
+        );
+        assert_eq!(
+            rotated.transform_block(block!("oak_sign", { rotation: 14, waterlogged: false })),
+            block!("oak_sign", { rotation: 2, waterlogged: false })
+        );
+        assert_eq!(rotated.transform_block(block!("stone")), block!("stone"));
+
+        let mirrored = StructurePlacement {
+            mirror: Mirror::LeftRight,
+            ..Default::default()
+        };
+        assert_eq!(
+            mirrored.transform_block(
+                block!("oak_stairs", { facing: "north", half: "bottom", shape: "inner_left", waterlogged: false })
+            ),
+            block!("oak_stairs", { facing: "south", half: "bottom", shape: "inner_right", waterlogged: false })
+        );
+        assert_eq!(
+            mirrored.transform_block(block!("oak_sign", { rotation: 0, waterlogged: false })),
+            block!("oak_sign", { rotation: 8, waterlogged: false })
+        );
+    }
+
+    #[test]
+    fn test_place_in_chunk() {
+        let template = sample();
+        assert_eq!(template.size(), (2, 1, 3));
+        let placement = StructurePlacement {
+            rotation: Rotation::Clockwise90,
+            ..Default::default()
+        };
+        let region = template.placed_region((4, 70, 0), &placement).unwrap();
+        assert_eq!(region, Region::new((2, 70, 0), (4, 70, 1)));
+
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        let placed = template
+            .place_in_chunk(&mut chunk, (4, 70, 0), &placement)
+            .unwrap();
+        assert_eq!(placed, 2);
+        assert_eq!(chunk.get_block(4, 70, 0).unwrap(), block!("stone"));
+        assert_eq!(
+            chunk.get_block(2, 70, 1).unwrap(),
+            block!("oak_stairs", { facing: "east", half: "bottom", shape: "straight", waterlogged: false })
+        );
+
+        // Nothing of the template reaches into the next chunk over.
+        let mut other = Chunk::new(1, 0, "overworld".to_string());
+        assert_eq!(
+            template
+                .place_in_chunk(&mut other, (4, 70, 0), &placement)
+                .unwrap(),
+            0
+        );
+    }
+}
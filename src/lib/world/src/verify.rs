@@ -0,0 +1,387 @@
+//! Checking a whole world for corrupted chunks, and getting rid of them.
+//!
+//! [World::verify] reads every stored chunk and reports the ones that can't be decoded or whose
+//! contents don't add up. The bad records can then be moved out of the way with
+//! [World::quarantine_chunks], and replaced with freshly generated chunks with
+//! [World::regenerate_chunks]. This is what `ferrumc verify` does.
+
+use crate::block_state_id::{BlockStateId, ID2BLOCK};
+use crate::chunk_format::{Chunk, PaletteType, Section};
+use crate::compression::decompress_record_checked;
+use crate::db_functions::{create_key, dimension_hash, save_chunk_internal, split_key};
+use crate::dimension::Dimension;
+use crate::errors::WorldError;
+use crate::migrations::MigrationRegistry;
+use crate::{progress_bar, World};
+use ferrumc_general_purpose::data_packing::u32::read_nbit_u32;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+/// The table corrupted chunk records are moved to by [World::quarantine_chunks], under the same
+/// key they had in the `chunks` table.
+pub const QUARANTINE_TABLE: &str = "chunks_quarantine";
+
+/// How many chunks are read per transaction while verifying.
+const VERIFY_BATCH_SIZE: usize = 1024;
+
+/// A stored chunk record that failed verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptChunk {
+    /// The key the record is stored under in the `chunks` table.
+    pub key: u128,
+    /// The chunk coordinates and dimension the key belongs to, if the dimension is a known one.
+    pub position: Option<(i32, i32, Dimension)>,
+    /// What's wrong with the record.
+    pub problem: String,
+}
+
+/// The result of [World::verify].
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// How many chunk records were checked.
+    pub checked: usize,
+    /// The records that failed verification.
+    pub corrupted: Vec<CorruptChunk>,
+}
+
+impl Chunk {
+    /// Checks that the chunk's data is consistent with itself: every section the dimension should
+    /// have is there, palette indices point inside their palette, block ids exist, the block
+    /// counts add up to the blocks actually stored and `non_air_blocks` matches them.
+    ///
+    /// Returns a description of every problem found, which is empty for a healthy chunk.
+    pub fn integrity_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let Some(dimension) = Dimension::from_name(&self.dimension) else {
+            problems.push(format!("unknown dimension {}", self.dimension));
+            return problems;
+        };
+
+        let expected_sections = dimension.section_range();
+        if self.sections.len() != expected_sections.len() {
+            problems.push(format!(
+                "has {} sections, expected {}",
+                self.sections.len(),
+                expected_sections.len()
+            ));
+        }
+        let mut seen_sections = HashSet::new();
+        for section in &self.sections {
+            if !expected_sections.contains(&section.y) {
+                problems.push(format!("section {} is out of bounds", section.y));
+            }
+            if !seen_sections.insert(section.y) {
+                problems.push(format!("section {} is duplicated", section.y));
+            }
+            for problem in section_problems(section) {
+                problems.push(format!("section {}: {}", section.y, problem));
+            }
+        }
+        problems
+    }
+}
+
+fn section_problems(section: &Section) -> Vec<String> {
+    let mut problems = Vec::new();
+    let block_states = &section.block_states;
+
+    match block_ids(&block_states.block_data) {
+        Ok(ids) => {
+            let mut counts: HashMap<BlockStateId, i32> = HashMap::new();
+            for id in ids {
+                *counts.entry(id).or_insert(0) += 1;
+            }
+            for id in counts.keys() {
+                if ID2BLOCK.get(id.0 as usize).is_none() {
+                    problems.push(format!("unknown block state id {}", id.0));
+                }
+            }
+            let stored_counts = block_states
+                .block_counts
+                .iter()
+                .filter(|(_, count)| **count != 0)
+                .map(|(id, count)| (*id, *count))
+                .collect::<HashMap<_, _>>();
+            if stored_counts != counts {
+                problems.push("block counts don't match the stored blocks".to_string());
+            }
+        }
+        Err(problem) => problems.push(problem),
+    }
+
+    let non_air_blocks = block_states.count_non_air_blocks();
+    if block_states.non_air_blocks != non_air_blocks {
+        problems.push(format!(
+            "non_air_blocks is {}, but the block counts add up to {}",
+            block_states.non_air_blocks, non_air_blocks
+        ));
+    }
+
+    if let Err(e) = section.biome_states.to_ids() {
+        problems.push(e.to_string());
+    }
+    problems
+}
+
+/// Unpacks the block state id of every block in a section.
+fn block_ids(block_data: &PaletteType) -> Result<Vec<BlockStateId>, String> {
+    let (bits_per_block, data, palette) = match block_data {
+        PaletteType::Single(id) => return Ok(vec![BlockStateId::from_varint(*id); 4096]),
+        PaletteType::Indirect {
+            bits_per_block,
+            data,
+            palette,
+        } => {
+            if palette.is_empty() {
+                return Err("palette is empty".to_string());
+            }
+            (*bits_per_block, data, Some(palette))
+        }
+        PaletteType::Direct {
+            bits_per_block,
+            data,
+        } => (*bits_per_block, data, None),
+    };
+    if bits_per_block == 0 || bits_per_block > 32 {
+        return Err(format!("invalid bits per block {bits_per_block}"));
+    }
+    let per_long = 64 / bits_per_block as usize;
+    let expected_len = 4096usize.div_ceil(per_long);
+    if data.len() < expected_len {
+        return Err(format!(
+            "block data is {} longs, expected {}",
+            data.len(),
+            expected_len
+        ));
+    }
+
+    let mut ids = Vec::with_capacity(4096);
+    for index in 0..4096 {
+        let offset = (index % per_long) * bits_per_block as usize;
+        let value = read_nbit_u32(&data[index / per_long], bits_per_block, offset as u32)
+            .map_err(|e| e.to_string())?;
+        let id = match palette {
+            Some(palette) => {
+                let entry = palette.get(value as usize).ok_or_else(|| {
+                    format!(
+                        "palette index {} is out of bounds for a palette of {}",
+                        value,
+                        palette.len()
+                    )
+                })?;
+                BlockStateId::from_varint(*entry)
+            }
+            None => BlockStateId(value),
+        };
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Decodes a stored record, always checking its checksum, and checks the chunk inside it.
+fn check_record(key: u128, record: &[u8]) -> Result<(), String> {
+    let (header, data) = decompress_record_checked(record, true).map_err(|e| e.to_string())?;
+    let data = MigrationRegistry::global()
+        .migrate(header.format_version, data)
+        .map_err(|e| e.to_string())?;
+    let chunk: Chunk = bitcode::decode(&data).map_err(|e| format!("failed to decode: {e}"))?;
+
+    if create_key(&chunk.dimension, chunk.x, chunk.z) != key {
+        return Err(format!(
+            "is stored under the wrong key for chunk ({}, {}) in {}",
+            chunk.x, chunk.z, chunk.dimension
+        ));
+    }
+    let problems = chunk.integrity_problems();
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    Ok(())
+}
+
+impl World {
+    /// Reads and checks every stored chunk. See [Chunk::integrity_problems] for what is checked
+    /// on top of the chunk decoding and its checksum matching.
+    ///
+    /// Nothing is changed, chunks that fail are only reported. Chunks that haven't been synced yet
+    /// aren't checked.
+    pub fn verify(&self) -> Result<VerifyReport, WorldError> {
+        let mut report = VerifyReport::default();
+        if !self.storage_backend.table_exists("chunks".to_string())? {
+            return Ok(report);
+        }
+        let keys = self.storage_backend.get_all_keys("chunks".to_string())?;
+        let dimension_hashes = Dimension::ALL.map(|dim| (dimension_hash(dim.as_str()), dim));
+
+        let progress = progress_bar(keys.len() as u64, "Verifying chunks...");
+
+        let start = std::time::Instant::now();
+        for batch in keys.chunks(VERIFY_BATCH_SIZE) {
+            let records = self
+                .storage_backend
+                .batch_get("chunks".to_string(), batch.to_vec())?;
+            for (key, record) in batch.iter().zip(records) {
+                let Some(record) = record else {
+                    continue;
+                };
+                report.checked += 1;
+                if let Err(problem) = check_record(*key, &record) {
+                    let (dim_hash, x, z) = split_key(*key);
+                    let position = dimension_hashes
+                        .iter()
+                        .find(|(hash, _)| *hash == dim_hash)
+                        .map(|(_, dimension)| (x, z, *dimension));
+                    report.corrupted.push(CorruptChunk {
+                        key: *key,
+                        position,
+                        problem,
+                    });
+                }
+            }
+            progress.inc(batch.len() as u64);
+        }
+
+        progress.finish_and_clear();
+        info!(
+            "Verified {} chunks in {:?}, {} are corrupted",
+            report.checked,
+            start.elapsed(),
+            report.corrupted.len()
+        );
+        Ok(report)
+    }
+
+    /// Moves the records of the given chunks out of the `chunks` table and into the
+    /// [QUARANTINE_TABLE], so they stop being loaded but can still be looked at or recovered.
+    ///
+    /// Returns how many records were moved.
+    pub fn quarantine_chunks(&self, chunks: &[CorruptChunk]) -> Result<usize, WorldError> {
+        if !self
+            .storage_backend
+            .table_exists(QUARANTINE_TABLE.to_string())?
+        {
+            self.storage_backend
+                .create_table(QUARANTINE_TABLE.to_string())?;
+        }
+        let mut moved = 0;
+        for chunk in chunks {
+            if let Some((x, z, dimension)) = chunk.position {
+                let key = (x, z, dimension.as_str().to_string());
+                self.dirty_chunks.remove(&key);
+                self.cache.invalidate(&key);
+            }
+            let Some(record) = self.storage_backend.get("chunks".to_string(), chunk.key)? else {
+                continue;
+            };
+            self.storage_backend
+                .upsert(QUARANTINE_TABLE.to_string(), chunk.key, record)?;
+            self.storage_backend
+                .delete("chunks".to_string(), chunk.key)?;
+            moved += 1;
+        }
+        self.storage_backend.flush()?;
+        Ok(moved)
+    }
+
+    /// Replaces the given chunks with ones made by `generate`, which is passed the chunk
+    /// coordinates and dimension. Chunks whose dimension is unknown can't be regenerated and are
+    /// skipped.
+    ///
+    /// The old records are overwritten, so quarantine them first to keep them around.
+    ///
+    /// Returns how many chunks were regenerated.
+    pub fn regenerate_chunks(
+        &self,
+        chunks: &[CorruptChunk],
+        generate: impl Fn(i32, i32, Dimension) -> Result<Chunk, WorldError>,
+    ) -> Result<usize, WorldError> {
+        let mut regenerated = 0;
+        for chunk in chunks {
+            let Some((x, z, dimension)) = chunk.position else {
+                warn!(
+                    "Can't regenerate chunk with key {:#x}, its dimension is unknown",
+                    chunk.key
+                );
+                continue;
+            };
+            let key = (x, z, dimension.as_str().to_string());
+            self.dirty_chunks.remove(&key);
+            self.cache.invalidate(&key);
+            save_chunk_internal(self.storage_backend.as_ref(), &generate(x, z, dimension)?)?;
+            regenerated += 1;
+        }
+        self.storage_backend.flush()?;
+        Ok(regenerated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::encode_chunk;
+    use ferrumc_macros::block;
+    use ferrumc_storage::memory::MemoryBackend;
+    use ferrumc_storage::StorageBackend;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_integrity_problems() {
+        let mut chunk = Chunk::new(0, 0, "overworld".to_string());
+        chunk.set_block(1, 2, 3, block!("stone")).unwrap();
+        assert!(chunk.integrity_problems().is_empty());
+
+        chunk.sections[4].block_states.non_air_blocks = 12;
+        if let PaletteType::Indirect { palette, .. } =
+            &mut chunk.sections[4].block_states.block_data
+        {
+            palette.truncate(1);
+        }
+        chunk.sections.pop();
+        let problems = chunk.integrity_problems();
+        assert_eq!(problems.len(), 3, "{problems:?}");
+    }
+
+    #[test]
+    fn test_verify_quarantine_and_regenerate() {
+        let backend = Arc::new(MemoryBackend::new());
+        let world = World::with_backend(backend.clone());
+        let healthy = Chunk::new(0, 0, "overworld".to_string());
+        let mut broken = Chunk::new(1, 0, "overworld".to_string());
+        broken.sections[0].block_states.non_air_blocks = 1;
+        backend
+            .batch_insert(
+                "chunks".to_string(),
+                vec![
+                    (
+                        create_key("overworld", 0, 0),
+                        encode_chunk(&healthy).unwrap(),
+                    ),
+                    (
+                        create_key("overworld", 1, 0),
+                        encode_chunk(&broken).unwrap(),
+                    ),
+                    (create_key("overworld", 2, 0), vec![0xFD, 0, 1]),
+                ],
+            )
+            .unwrap();
+
+        let report = world.verify().unwrap();
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.corrupted.len(), 2);
+        assert_eq!(
+            report.corrupted[0].position,
+            Some((1, 0, Dimension::Overworld))
+        );
+
+        assert_eq!(world.quarantine_chunks(&report.corrupted).unwrap(), 2);
+        assert_eq!(world.verify().unwrap().checked, 1);
+        let regenerated = world
+            .regenerate_chunks(&report.corrupted, |x, z, dimension| {
+                Ok(Chunk::new(x, z, dimension.as_str().to_string()))
+            })
+            .unwrap();
+        assert_eq!(regenerated, 2);
+        let report = world.verify().unwrap();
+        assert_eq!((report.checked, report.corrupted.len()), (3, 0));
+    }
+}
@@ -0,0 +1,526 @@
+//! Aquifers: the pockets of water and lava that fill caves, each up to its own level, instead of
+//! every cave below sea level flooding.
+//!
+//! This follows vanilla's noise based aquifers. The world is split into cells 16 blocks across and
+//! 12 tall, each with an aquifer centred at a random spot in it. Empty space takes its fluid from
+//! the nearest centre, and where the nearest centres have different levels or fluids, a barrier of
+//! stone is left between them so they don't spill into each other. Aquifers near the surface
+//! usually share the sea's level, deeper ones get a level of their own or stay dry, and below y -54
+//! everything is lava.
+//!
+//! Everything is worked out from the seed and the position alone, so chunks come out the same
+//! whichever order they're generated in.
+
+use crate::density_function::{ColumnSampler, FunctionId, NoiseRouter};
+use crate::features::{BlockPos, FeatureRandom, mix};
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use std::collections::HashMap;
+
+/// Below this height, empty space fills with lava.
+const LAVA_LEVEL: i32 = -54;
+
+/// The level of aquifers that are dry, far enough below the world that nothing is under it.
+const DRY_LEVEL: i32 = -32512;
+
+/// Aquifers whose level is at or below this may be lava instead of water.
+const MAX_LAVA_AQUIFER_LEVEL: i32 = -10;
+
+const CELL_WIDTH: i32 = 16;
+const CELL_HEIGHT: i32 = 12;
+
+/// How far apart, squared, the two nearest aquifer centres have to be for a block to only see the
+/// nearest one.
+const SIMILARITY_DISTANCE: f64 = 25.0;
+
+/// Where the surface is looked for around an aquifer, in chunks from its centre. The first is the
+/// aquifer's own column.
+const SURFACE_SAMPLES: [(i32, i32); 13] = [
+    (0, 0),
+    (-2, -1),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-3, 0),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Where `initialDensityWithoutJaggedness` is above this, the surface is estimated to be.
+const SURFACE_DENSITY: f64 = 0.390625;
+
+/// Erosion below this and depth above the other is the deep dark, whose caves are always dry.
+const DEEP_DARK_EROSION: f64 = -0.225;
+const DEEP_DARK_DEPTH: f64 = 0.9;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Fluid {
+    Water,
+    Lava,
+}
+
+/// How far up an aquifer is filled, and with what.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct FluidStatus {
+    level: i32,
+    fluid: Fluid,
+}
+
+impl FluidStatus {
+    /// The fluid at a height, or `None` if it's above the level.
+    fn at(self, y: i32) -> Option<Fluid> {
+        (y < self.level).then_some(self.fluid)
+    }
+}
+
+/// What aquifers are worked out from, for one seed.
+pub(crate) struct AquiferSettings {
+    seed: u64,
+    sea_level: i32,
+    min_y: i32,
+    height: i32,
+    /// How far apart the heights checked while looking for the surface are.
+    surface_step: i32,
+    barrier: FunctionId,
+    floodedness: FunctionId,
+    spread: FunctionId,
+    lava: FunctionId,
+    erosion: FunctionId,
+    depth: FunctionId,
+    initial_density: FunctionId,
+}
+
+impl AquiferSettings {
+    pub(crate) fn new(
+        router: &NoiseRouter,
+        seed: u64,
+        sea_level: i32,
+        (min_y, height): (i32, i32),
+        surface_step: i32,
+    ) -> Self {
+        Self {
+            seed,
+            sea_level,
+            min_y,
+            height,
+            surface_step,
+            barrier: router.function("barrierNoise"),
+            floodedness: router.function("fluidLevelFloodednessNoise"),
+            spread: router.function("fluidLevelSpreadNoise"),
+            lava: router.function("lavaNoise"),
+            erosion: router.function("erosion"),
+            depth: router.function("depth"),
+            initial_density: router.function("initialDensityWithoutJaggedness"),
+        }
+    }
+}
+
+/// Something worked out once for each aquifer cell around a chunk.
+///
+/// Every block looks up twelve cells, so they're kept in a flat array rather than a map. Cells
+/// outside the chunk's aren't kept at all.
+struct Cells<T> {
+    min: [i32; 3],
+    size: [i32; 3],
+    values: Vec<Option<T>>,
+}
+
+impl<T: Copy> Cells<T> {
+    fn new(min: [i32; 3], max: [i32; 3]) -> Self {
+        let size = [0, 1, 2].map(|axis| max[axis] - min[axis] + 1);
+        Self {
+            min,
+            size,
+            values: vec![None; size.iter().product::<i32>() as usize],
+        }
+    }
+
+    fn index(&self, cell: [i32; 3]) -> Option<usize> {
+        let mut index = 0;
+        for ((value, min), size) in cell.into_iter().zip(self.min).zip(self.size) {
+            let offset = value - min;
+            if !(0..size).contains(&offset) {
+                return None;
+            }
+            index = index * size + offset;
+        }
+        Some(index as usize)
+    }
+
+    fn get(&self, cell: [i32; 3]) -> Option<T> {
+        self.index(cell).and_then(|index| self.values[index])
+    }
+
+    fn insert(&mut self, cell: [i32; 3], value: T) {
+        if let Some(index) = self.index(cell) {
+            self.values[index] = Some(value);
+        }
+    }
+}
+
+/// The cells whose aquifer centres could be nearest to the blocks in a cell.
+#[derive(Clone, Copy)]
+struct Candidates {
+    cell: [i32; 3],
+    /// Each cell with its centre.
+    centres: [([i32; 3], BlockPos); 12],
+    /// The status of all of their aquifers, if it's the same.
+    shared: Option<FluidStatus>,
+}
+
+/// Works out the aquifers around a chunk, remembering the ones it's seen.
+pub(crate) struct Aquifer<'a> {
+    router: &'a NoiseRouter,
+    settings: &'a AquiferSettings,
+    /// The centre of each cell's aquifer.
+    centres: Cells<BlockPos>,
+    statuses: Cells<FluidStatus>,
+    /// The candidates of the last cell a block was in, since the blocks of a column are mostly
+    /// filled in one after another.
+    candidates: Option<Candidates>,
+    /// The estimated surface height of columns, keyed by the column's position rounded down to a
+    /// multiple of 4.
+    surfaces: HashMap<(i32, i32), i32>,
+}
+
+impl<'a> Aquifer<'a> {
+    pub(crate) fn new(
+        router: &'a NoiseRouter,
+        settings: &'a AquiferSettings,
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> Self {
+        let top = settings.min_y + settings.height - 1;
+        // The cells blocks in the chunk look at; see `substance`.
+        let min = [
+            (chunk_x * 16 - 5).div_euclid(CELL_WIDTH),
+            (settings.min_y + 1).div_euclid(CELL_HEIGHT) - 1,
+            (chunk_z * 16 - 5).div_euclid(CELL_WIDTH),
+        ];
+        let max = [
+            (chunk_x * 16 + 10).div_euclid(CELL_WIDTH) + 1,
+            (top + 1).div_euclid(CELL_HEIGHT) + 1,
+            (chunk_z * 16 + 10).div_euclid(CELL_WIDTH) + 1,
+        ];
+        Self {
+            router,
+            settings,
+            centres: Cells::new(min, max),
+            statuses: Cells::new(min, max),
+            candidates: None,
+            surfaces: HashMap::new(),
+        }
+    }
+
+    /// What fills an empty block whose density is `density`: air, water or lava, or `None` if it
+    /// stays solid as a barrier between aquifers.
+    pub(crate) fn substance(&mut self, [x, y, z]: BlockPos, density: f64) -> Option<BlockStateId> {
+        if self.global_status(y).at(y) == Some(Fluid::Lava) {
+            return Some(fluid_block(Some(Fluid::Lava)));
+        }
+
+        let cell = [
+            (x - 5).div_euclid(CELL_WIDTH),
+            (y + 1).div_euclid(CELL_HEIGHT),
+            (z - 5).div_euclid(CELL_WIDTH),
+        ];
+        let candidates = match self.candidates {
+            Some(candidates) if candidates.cell == cell => candidates,
+            _ => {
+                let candidates = self.candidates(cell);
+                self.candidates = Some(candidates);
+                candidates
+            }
+        };
+        // Aquifers that are all the same don't need barriers between them.
+        if let Some(status) = candidates.shared
+            && density <= 0.0
+        {
+            return Some(fluid_block(status.at(y)));
+        }
+
+        // The three nearest aquifer centres, nearest first, as (distance squared, cell).
+        let mut nearest = [(i32::MAX, [0; 3]); 3];
+        for (cell, [centre_x, centre_y, centre_z]) in candidates.centres {
+            let distance = (centre_x - x).pow(2) + (centre_y - y).pow(2) + (centre_z - z).pow(2);
+            let slot = nearest.iter().position(|(nearer, _)| *nearer >= distance);
+            if let Some(slot) = slot {
+                nearest[slot..].rotate_right(1);
+                nearest[slot] = (distance, cell);
+            }
+        }
+        let [
+            (first_distance, first),
+            (second_distance, second),
+            (third_distance, third),
+        ] = nearest;
+
+        let first = self.status(first);
+        let fluid = first.at(y);
+        let similarity_12 = similarity(first_distance, second_distance);
+        if similarity_12 <= 0.0 {
+            return Some(fluid_block(fluid));
+        }
+        if fluid == Some(Fluid::Water) && self.global_status(y - 1).at(y - 1) == Some(Fluid::Lava) {
+            return Some(fluid_block(fluid));
+        }
+
+        let mut barrier = None;
+        let second = self.status(second);
+        if density + similarity_12 * self.pressure([x, y, z], &mut barrier, first, second) > 0.0 {
+            return None;
+        }
+        let third = self.status(third);
+        let similarity_13 = similarity(first_distance, third_distance);
+        if similarity_13 > 0.0
+            && density
+                + similarity_12
+                    * similarity_13
+                    * self.pressure([x, y, z], &mut barrier, first, third)
+                > 0.0
+        {
+            return None;
+        }
+        let similarity_23 = similarity(second_distance, third_distance);
+        if similarity_23 > 0.0
+            && density
+                + similarity_12
+                    * similarity_23
+                    * self.pressure([x, y, z], &mut barrier, second, third)
+                > 0.0
+        {
+            return None;
+        }
+        Some(fluid_block(fluid))
+    }
+
+    /// The fluid everywhere that isn't in an aquifer of its own: the sea, and lava at the bottom.
+    fn global_status(&self, y: i32) -> FluidStatus {
+        if y < LAVA_LEVEL.min(self.settings.sea_level) {
+            FluidStatus {
+                level: LAVA_LEVEL,
+                fluid: Fluid::Lava,
+            }
+        } else {
+            FluidStatus {
+                level: self.settings.sea_level,
+                fluid: Fluid::Water,
+            }
+        }
+    }
+
+    fn candidates(&mut self, cell: [i32; 3]) -> Candidates {
+        let mut centres = [([0; 3], [0; 3]); 12];
+        let mut index = 0;
+        for dx in 0..=1 {
+            for dy in -1..=1 {
+                for dz in 0..=1 {
+                    let candidate = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                    centres[index] = (candidate, self.centre(candidate));
+                    index += 1;
+                }
+            }
+        }
+        let first = self.status(centres[0].0);
+        let shared = centres[1..]
+            .iter()
+            .all(|(candidate, _)| self.status(*candidate) == first)
+            .then_some(first);
+        Candidates {
+            cell,
+            centres,
+            shared,
+        }
+    }
+
+    fn centre(&mut self, cell: [i32; 3]) -> BlockPos {
+        if let Some(centre) = self.centres.get(cell) {
+            return centre;
+        }
+        let mut random = FeatureRandom::new(cell.iter().fold(self.settings.seed, |seed, value| {
+            mix(seed ^ i64::from(*value) as u64)
+        }));
+        let [x, y, z] = cell;
+        let centre = [
+            x * CELL_WIDTH + random.next_int(10),
+            y * CELL_HEIGHT + random.next_int(9),
+            z * CELL_WIDTH + random.next_int(10),
+        ];
+        self.centres.insert(cell, centre);
+        centre
+    }
+
+    fn status(&mut self, cell: [i32; 3]) -> FluidStatus {
+        if let Some(status) = self.statuses.get(cell) {
+            return status;
+        }
+        let centre = self.centre(cell);
+        let status = self.compute_status(centre);
+        self.statuses.insert(cell, status);
+        status
+    }
+
+    /// The level and fluid of the aquifer centred at a position.
+    fn compute_status(&mut self, [x, y, z]: BlockPos) -> FluidStatus {
+        let global = self.global_status(y);
+        let (top, bottom) = (y + CELL_HEIGHT, y - CELL_HEIGHT);
+        let mut lowest_surface = i32::MAX;
+        let mut surface_nearby = false;
+        for (index, (dx, dz)) in SURFACE_SAMPLES.into_iter().enumerate() {
+            let preliminary = self.surface(x + dx * 16, z + dz * 16);
+            let surface = preliminary.saturating_add(8);
+            let own = index == 0;
+            if own && bottom > surface {
+                return global;
+            }
+            let below_surface = top > surface;
+            if below_surface || own {
+                let at_surface = self.global_status(surface);
+                if at_surface.at(surface).is_some() {
+                    surface_nearby |= own;
+                    if below_surface {
+                        return at_surface;
+                    }
+                }
+            }
+            lowest_surface = lowest_surface.min(preliminary);
+        }
+        let level = self.level([x, y, z], global, lowest_surface, surface_nearby);
+        FluidStatus {
+            level,
+            fluid: self.fluid([x, y, z], global, level),
+        }
+    }
+
+    fn level(
+        &mut self,
+        [x, y, z]: BlockPos,
+        global: FluidStatus,
+        lowest_surface: i32,
+        surface_nearby: bool,
+    ) -> i32 {
+        let settings = self.settings;
+        let mut sampler = ColumnSampler::new(self.router, i64::from(x), i64::from(z));
+        let deep_dark = sampler.sample(settings.erosion, y) < DEEP_DARK_EROSION
+            && sampler.sample(settings.depth, y) > DEEP_DARK_DEPTH;
+        let (partly_flooded, fully_flooded) = if deep_dark {
+            (-1.0, -1.0)
+        } else {
+            let below_surface = f64::from(lowest_surface.saturating_add(8).saturating_sub(y));
+            let nearness = if surface_nearby {
+                1.0 - (below_surface / 64.0).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let floodedness = sampler.sample(settings.floodedness, y).clamp(-1.0, 1.0);
+            let threshold = |at_surface: f64, deep: f64| deep + (at_surface - deep) * nearness;
+            (
+                floodedness - threshold(-0.8, 0.4),
+                floodedness - threshold(-0.3, 0.8),
+            )
+        };
+        if fully_flooded > 0.0 {
+            global.level
+        } else if partly_flooded > 0.0 {
+            let (cell_x, cell_y, cell_z) = (x.div_euclid(16), y.div_euclid(40), z.div_euclid(16));
+            let spread = ColumnSampler::new(self.router, i64::from(cell_x), i64::from(cell_z))
+                .sample(settings.spread, cell_y)
+                * 10.0;
+            let level = cell_y * 40 + 20 + (spread / 3.0).floor() as i32 * 3;
+            lowest_surface.min(level)
+        } else {
+            DRY_LEVEL
+        }
+    }
+
+    fn fluid(&mut self, [x, y, z]: BlockPos, global: FluidStatus, level: i32) -> Fluid {
+        if level <= MAX_LAVA_AQUIFER_LEVEL && level != DRY_LEVEL && global.fluid != Fluid::Lava {
+            let (cell_x, cell_y, cell_z) = (x.div_euclid(64), y.div_euclid(40), z.div_euclid(64));
+            let lava = ColumnSampler::new(self.router, i64::from(cell_x), i64::from(cell_z))
+                .sample(self.settings.lava, cell_y);
+            if lava.abs() > 0.3 {
+                return Fluid::Lava;
+            }
+        }
+        global.fluid
+    }
+
+    /// Roughly where the surface of a column is, or `i32::MAX` if it's all empty.
+    fn surface(&mut self, x: i32, z: i32) -> i32 {
+        let (x, z) = (x & !3, z & !3);
+        let settings = self.settings;
+        let router = self.router;
+        *self.surfaces.entry((x, z)).or_insert_with(|| {
+            let mut sampler = ColumnSampler::new(router, i64::from(x), i64::from(z));
+            (settings.min_y..=settings.min_y + settings.height)
+                .rev()
+                .step_by(settings.surface_step as usize)
+                .find(|y| sampler.sample(settings.initial_density, *y) > SURFACE_DENSITY)
+                .unwrap_or(i32::MAX)
+        })
+    }
+
+    /// How strongly the barrier between two aquifers holds at a position, which is more the
+    /// further apart their levels are.
+    fn pressure(
+        &self,
+        [x, y, z]: BlockPos,
+        barrier: &mut Option<f64>,
+        first: FluidStatus,
+        second: FluidStatus,
+    ) -> f64 {
+        let (first_fluid, second_fluid) = (first.at(y), second.at(y));
+        if first_fluid.is_some() && second_fluid.is_some() && first_fluid != second_fluid {
+            return 2.0;
+        }
+        let difference = (first.level - second.level).abs();
+        if difference == 0 {
+            return 0.0;
+        }
+        let middle = 0.5 * f64::from(first.level + second.level);
+        let offset = f64::from(y) + 0.5 - middle;
+        let reach = f64::from(difference) / 2.0 - offset.abs();
+        let pressure = if offset > 0.0 {
+            if reach > 0.0 {
+                reach / 1.5
+            } else {
+                reach / 2.5
+            }
+        } else {
+            let reach = reach + 3.0;
+            if reach > 0.0 {
+                reach / 3.0
+            } else {
+                reach / 10.0
+            }
+        };
+        let noise = if (-2.0..=2.0).contains(&pressure) {
+            *barrier.get_or_insert_with(|| {
+                ColumnSampler::new(self.router, i64::from(x), i64::from(z))
+                    .sample(self.settings.barrier, y)
+            })
+        } else {
+            0.0
+        };
+        2.0 * (noise + pressure)
+    }
+}
+
+/// How alike the distances to two aquifer centres are, from 1 when they're the same down to below
+/// 0 when one is much nearer.
+fn similarity(first: i32, second: i32) -> f64 {
+    1.0 - f64::from((second - first).abs()) / SIMILARITY_DISTANCE
+}
+
+fn fluid_block(fluid: Option<Fluid>) -> BlockStateId {
+    match fluid {
+        Some(Fluid::Water) => block!("water", {level: 0}),
+        Some(Fluid::Lava) => block!("lava", {level: 0}),
+        None => BlockStateId::default(),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::OverworldBiome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+
+/// Sand dunes on top of sandstone.
+pub(crate) struct DesertBiome;
+
+impl OverworldBiome for DesertBiome {
+    fn surface_block(&self, depth: i32, _height: i32) -> Option<BlockStateId> {
+        match depth {
+            0..=3 => Some(block!("sand")),
+            4..=6 => Some(block!("sandstone")),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use crate::OverworldBiome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::dimension::Dimension;
+
+/// Grassland with a thicker layer of dirt than the plains.
+pub(crate) struct ForestBiome;
+
+impl OverworldBiome for ForestBiome {
+    fn surface_block(&self, depth: i32, height: i32) -> Option<BlockStateId> {
+        match depth {
+            0 if height >= Dimension::Overworld.sea_level() => {
+                Some(block!("grass_block", {snowy: false}))
+            }
+            0..=4 => Some(block!("dirt")),
+            _ => None,
+        }
+    }
+}
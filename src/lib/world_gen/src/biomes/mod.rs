@@ -1 +1,9 @@
+pub(crate) mod desert;
+pub(crate) mod forest;
+pub(crate) mod mountains;
+pub(crate) mod nether;
+pub(crate) mod ocean;
 pub(crate) mod plains;
+pub(crate) mod snowy_plains;
+pub(crate) mod swamp;
+pub(crate) mod the_end;
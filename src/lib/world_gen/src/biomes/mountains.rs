@@ -0,0 +1,22 @@
+use crate::OverworldBiome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+
+/// Above this height the mountains are bare stone.
+const TREE_LINE: i32 = 100;
+/// Above this height the peaks are covered in snow.
+const SNOW_LINE: i32 = 130;
+
+/// Rocky peaks with grassy foothills and snowy tops.
+pub(crate) struct MountainsBiome;
+
+impl OverworldBiome for MountainsBiome {
+    fn surface_block(&self, depth: i32, height: i32) -> Option<BlockStateId> {
+        match depth {
+            0 | 1 if height >= SNOW_LINE => Some(block!("snow_block")),
+            0 if height < TREE_LINE => Some(block!("grass_block", {snowy: false})),
+            1 | 2 if height < TREE_LINE => Some(block!("dirt")),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::errors::WorldGenError;
+use crate::{BiomeGenerator, NoiseGenerator};
+use ferrumc_data::generated::biomes::Biome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::edit_batch::EditBatch;
+
+/// Y level of the top of the lava sea.
+const LAVA_LEVEL: i32 = 31;
+/// Y level of the bedrock ceiling.
+const CEILING_Y: i32 = 127;
+
+pub(crate) struct NetherWastesBiome;
+
+impl BiomeGenerator for NetherWastesBiome {
+    fn biome_id(&self) -> u16 {
+        Biome::NETHER_WASTES.id
+    }
+
+    fn _biome_name(&self) -> String {
+        "nether_wastes".to_string()
+    }
+
+    fn generate_chunk(
+        &self,
+        x: i32,
+        z: i32,
+        noise: &NoiseGenerator,
+    ) -> Result<Chunk, WorldGenError> {
+        let mut chunk = Chunk::new(x, z, Dimension::Nether.as_str().to_string());
+        let netherrack = block!("netherrack");
+        let bedrock = block!("bedrock");
+
+        // The bottom section is always solid
+        chunk.set_section(0, netherrack)?;
+
+        let mut batch = EditBatch::new(&mut chunk);
+        for chunk_x in 0..16i64 {
+            for chunk_z in 0..16i64 {
+                let global_x = i64::from(x) * 16 + chunk_x;
+                let global_z = i64::from(z) * 16 + chunk_z;
+                let floor = noise.get_noise(global_x as f64, global_z as f64);
+                let floor = (floor * 16.0) as i32 + 40;
+                // Sample somewhere else for the ceiling so it doesn't mirror the floor
+                let ceiling = noise.get_noise(global_z as f64 + 4096.0, global_x as f64);
+                let ceiling = CEILING_Y - 4 - (ceiling.abs() * 12.0) as i32;
+
+                let (x, z) = (global_x as i32 & 0xF, global_z as i32 & 0xF);
+                batch.set_block(x, 0, z, bedrock);
+                for y in 16..floor {
+                    batch.set_block(x, y, z, netherrack);
+                }
+                for y in floor.max(16)..=LAVA_LEVEL {
+                    batch.set_block(x, y, z, block!("lava", {level: 0}));
+                }
+                for y in ceiling..CEILING_Y {
+                    batch.set_block(x, y, z, netherrack);
+                }
+                batch.set_block(x, CEILING_Y, z, bedrock);
+            }
+        }
+        batch.apply()?;
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generates_nether_chunk() {
+        let generator = NetherWastesBiome {};
+        let noise = NoiseGenerator::new(0);
+        let chunk = generator.generate_chunk(0, 0, &noise).unwrap();
+        assert_eq!(chunk.dimension, "the_nether");
+        assert_eq!(chunk.get_block(0, 0, 0).unwrap(), block!("bedrock"));
+        assert_eq!(chunk.get_block(0, CEILING_Y, 0).unwrap(), block!("bedrock"));
+    }
+}
@@ -0,0 +1,19 @@
+use crate::OverworldBiome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+
+/// Below this height the ocean floor is gravel instead of sand.
+const GRAVEL_DEPTH: i32 = 52;
+
+/// A sandy sea floor, turning to gravel in deeper water.
+pub(crate) struct OceanBiome;
+
+impl OverworldBiome for OceanBiome {
+    fn surface_block(&self, depth: i32, height: i32) -> Option<BlockStateId> {
+        match depth {
+            0..=2 if height < GRAVEL_DEPTH => Some(block!("gravel")),
+            0..=2 => Some(block!("sand")),
+            _ => None,
+        }
+    }
+}
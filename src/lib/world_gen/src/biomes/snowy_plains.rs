@@ -0,0 +1,28 @@
+use crate::OverworldBiome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::dimension::Dimension;
+
+/// Snow covered land, with frozen over water.
+pub(crate) struct SnowyPlainsBiome;
+
+impl OverworldBiome for SnowyPlainsBiome {
+    fn surface_block(&self, depth: i32, height: i32) -> Option<BlockStateId> {
+        match depth {
+            0 if height >= Dimension::Overworld.sea_level() => {
+                Some(block!("grass_block", {snowy: true}))
+            }
+            0 => Some(block!("gravel")),
+            1..=3 => Some(block!("dirt")),
+            _ => None,
+        }
+    }
+
+    fn decoration(&self, _height: i32) -> Option<BlockStateId> {
+        Some(block!("snow", {layers: 1}))
+    }
+
+    fn water_surface(&self) -> BlockStateId {
+        block!("ice")
+    }
+}
@@ -0,0 +1,20 @@
+use crate::OverworldBiome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::dimension::Dimension;
+
+/// Grassy land with clay under its shallow pools.
+pub(crate) struct SwampBiome;
+
+impl OverworldBiome for SwampBiome {
+    fn surface_block(&self, depth: i32, height: i32) -> Option<BlockStateId> {
+        match depth {
+            0 if height >= Dimension::Overworld.sea_level() => {
+                Some(block!("grass_block", {snowy: false}))
+            }
+            0 => Some(block!("clay")),
+            1..=3 => Some(block!("dirt")),
+            _ => None,
+        }
+    }
+}
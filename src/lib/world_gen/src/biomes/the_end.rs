@@ -0,0 +1,92 @@
+use crate::errors::WorldGenError;
+use crate::{BiomeGenerator, NoiseGenerator};
+use ferrumc_data::generated::biomes::Biome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::edit_batch::EditBatch;
+
+/// Radius, in blocks, of the central island.
+const ISLAND_RADIUS: f64 = 96.0;
+/// Y level of the island's surface.
+const ISLAND_SURFACE: i32 = 60;
+
+pub(crate) struct TheEndBiome;
+
+impl BiomeGenerator for TheEndBiome {
+    fn biome_id(&self) -> u16 {
+        Biome::THE_END.id
+    }
+
+    fn _biome_name(&self) -> String {
+        "the_end".to_string()
+    }
+
+    fn generate_chunk(
+        &self,
+        x: i32,
+        z: i32,
+        noise: &NoiseGenerator,
+    ) -> Result<Chunk, WorldGenError> {
+        let mut chunk = Chunk::new(x, z, Dimension::End.as_str().to_string());
+        let end_stone = block!("end_stone");
+
+        let mut batch = EditBatch::new(&mut chunk);
+        let mut has_edits = false;
+        for chunk_x in 0..16i64 {
+            for chunk_z in 0..16i64 {
+                let global_x = i64::from(x) * 16 + chunk_x;
+                let global_z = i64::from(z) * 16 + chunk_z;
+                let distance = ((global_x * global_x + global_z * global_z) as f64).sqrt();
+                if distance >= ISLAND_RADIUS {
+                    continue;
+                }
+                // The island gets thinner towards its edge, like an upside-down cone
+                let edge = 1.0 - distance / ISLAND_RADIUS;
+                let bumps = noise.get_noise(global_x as f64, global_z as f64);
+                let top = ISLAND_SURFACE + (bumps * 4.0) as i32;
+                let bottom = ISLAND_SURFACE - (edge * 48.0) as i32;
+
+                for y in bottom..=top {
+                    batch.set_block(global_x as i32 & 0xF, y, global_z as i32 & 0xF, end_stone);
+                    has_edits = true;
+                }
+            }
+        }
+        // Everything outside the main island is void
+        if has_edits {
+            batch.apply()?;
+        }
+
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_island_at_origin() {
+        let generator = TheEndBiome {};
+        let noise = NoiseGenerator::new(0);
+        let chunk = generator.generate_chunk(0, 0, &noise).unwrap();
+        assert_eq!(chunk.dimension, "the_end");
+        assert_eq!(
+            chunk.get_block(0, ISLAND_SURFACE - 4, 0).unwrap(),
+            block!("end_stone")
+        );
+    }
+
+    #[test]
+    fn test_void_far_away() {
+        let generator = TheEndBiome {};
+        let noise = NoiseGenerator::new(0);
+        let chunk = generator.generate_chunk(1000, 1000, &noise).unwrap();
+        assert_eq!(
+            chunk.get_block(0, ISLAND_SURFACE, 0).unwrap(),
+            BlockStateId::default()
+        );
+    }
+}
@@ -0,0 +1,625 @@
+//! Carvers: the tunnels, caverns and canyons cut through the terrain once its surface is placed,
+//! configured by the extracted `carver.json`.
+//!
+//! Each biome lists its carvers in `biome.json`, and every chunk gets a chance at starting each
+//! carver of the biome it's in. A cave or canyon wanders up to 8 chunks from where it starts, so
+//! carving a chunk runs the carvers of every chunk within that range and keeps what lands in the
+//! chunk being carved. Like [crate::features], each carver gets its own random, seeded from the
+//! world seed, its chunk and which carver it is, and where it goes doesn't depend on the terrain,
+//! so the same cave comes out of every chunk it passes through.
+//!
+//! Carved blocks below the carver's lava level fill with lava, and the rest take whatever the
+//! [Aquifer] puts there, so caves that cut into a flooded aquifer flood too.
+
+use crate::aquifer::Aquifer;
+use crate::features::{
+    BlockPos, FeatureRandom, HeightProvider, OneOrMany, VerticalAnchor, mix, states_of, tag_states,
+};
+use crate::overworld::{ShapedChunk, terrain_for};
+use ferrumc_data::generated::biomes::Biome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+use std::sync::LazyLock;
+
+const CARVERS_FILE: &str = include_str!("../../../../assets/extracted/carver.json");
+const BIOMES_FILE: &str = include_str!("../../../../assets/extracted/biome.json");
+
+/// How many chunks from where they start carvers can reach.
+const CARVER_RANGE: i32 = 8;
+
+/// How many blocks a cave's tunnels or a canyon can run for.
+const MAX_LENGTH: i32 = (4 * 2 - 1) * 16;
+
+/// At most this many caves start in a chunk, though usually far fewer.
+const CAVE_BOUND: i32 = 15;
+
+/// The blocks on top of the ground that show a carver has cut through the surface.
+static SURFACE_BLOCKS: LazyLock<HashSet<BlockStateId>> =
+    LazyLock::new(|| states_of(["grass_block", "mycelium"]));
+
+/// Every carver, and the carvers of each biome.
+static CARVERS: LazyLock<Carvers> = LazyLock::new(Carvers::load);
+
+struct Carvers {
+    /// Keyed by name, without the namespace. Carvers that can't be compiled are left out.
+    carvers: HashMap<String, Carver>,
+    /// The names of each biome's carvers, in order.
+    biome_carvers: HashMap<String, Vec<String>>,
+    /// The most carvers any biome has.
+    most_per_biome: usize,
+    /// The highest chance any carver has of starting in a chunk.
+    highest_probability: f32,
+}
+
+impl Carvers {
+    fn load() -> Self {
+        #[derive(Deserialize)]
+        struct RawCarver {
+            #[serde(rename = "type")]
+            kind: String,
+            config: serde_json::Value,
+        }
+        #[derive(Deserialize)]
+        struct RawBiome {
+            #[serde(default)]
+            carvers: Option<OneOrMany>,
+        }
+        let carvers: HashMap<String, RawCarver> =
+            serde_json::from_str(CARVERS_FILE).expect("carver.json should be valid");
+        let biomes: HashMap<String, RawBiome> =
+            serde_json::from_str(BIOMES_FILE).expect("biome.json should be valid");
+        let carvers: HashMap<_, _> = carvers
+            .into_iter()
+            .filter_map(|(name, raw)| Some((name, Carver::compile(&raw.kind, raw.config)?)))
+            .collect();
+        let biome_carvers: HashMap<_, Vec<_>> = biomes
+            .into_iter()
+            .map(|(name, biome)| {
+                let carvers = biome.carvers.map_or_else(Vec::new, |carvers| {
+                    carvers
+                        .names()
+                        .into_iter()
+                        .map(|name| name.trim_start_matches("minecraft:").to_string())
+                        .collect()
+                });
+                (name, carvers)
+            })
+            .collect();
+        Self {
+            most_per_biome: biome_carvers.values().map(Vec::len).max().unwrap_or(0),
+            highest_probability: carvers
+                .values()
+                .map(|carver| carver.probability)
+                .fold(0.0, f32::max),
+            carvers,
+            biome_carvers,
+        }
+    }
+}
+
+/// A random number picked from a range.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FloatProvider {
+    Constant(f32),
+    Typed(TypedFloatProvider),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum TypedFloatProvider {
+    #[serde(rename = "minecraft:constant")]
+    Constant { value: f32 },
+    #[serde(rename = "minecraft:uniform")]
+    Uniform {
+        min_inclusive: f32,
+        max_exclusive: f32,
+    },
+    #[serde(rename = "minecraft:trapezoid")]
+    Trapezoid { min: f32, max: f32, plateau: f32 },
+}
+
+impl FloatProvider {
+    fn sample(&self, random: &mut FeatureRandom) -> f32 {
+        let provider = match self {
+            FloatProvider::Constant(value) => return *value,
+            FloatProvider::Typed(provider) => provider,
+        };
+        match *provider {
+            TypedFloatProvider::Constant { value } => value,
+            TypedFloatProvider::Uniform {
+                min_inclusive,
+                max_exclusive,
+            } => min_inclusive + random.next_float() * (max_exclusive - min_inclusive),
+            TypedFloatProvider::Trapezoid { min, max, plateau } => {
+                let slope = (max - min - plateau) / 2.0;
+                min + random.next_float() * (max - min - slope) + random.next_float() * slope
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawCarverConfig {
+    probability: f32,
+    y: HeightProvider,
+    #[serde(rename = "yScale")]
+    y_scale: FloatProvider,
+    lava_level: VerticalAnchor,
+    replaceable: OneOrMany,
+}
+
+#[derive(Deserialize)]
+struct RawCaveConfig {
+    #[serde(flatten)]
+    common: RawCarverConfig,
+    horizontal_radius_multiplier: FloatProvider,
+    vertical_radius_multiplier: FloatProvider,
+    floor_level: FloatProvider,
+}
+
+#[derive(Deserialize)]
+struct RawCanyonConfig {
+    #[serde(flatten)]
+    common: RawCarverConfig,
+    vertical_rotation: FloatProvider,
+    shape: CanyonShape,
+}
+
+#[derive(Deserialize)]
+struct CanyonShape {
+    distance_factor: FloatProvider,
+    thickness: FloatProvider,
+    width_smoothness: i32,
+    horizontal_radius_factor: FloatProvider,
+    vertical_radius_default_factor: f32,
+    vertical_radius_center_factor: f32,
+}
+
+enum CarverKind {
+    Cave {
+        horizontal_radius: FloatProvider,
+        vertical_radius: FloatProvider,
+        floor_level: FloatProvider,
+    },
+    Canyon {
+        vertical_rotation: FloatProvider,
+        shape: CanyonShape,
+    },
+}
+
+struct Carver {
+    /// The chance of the carver starting in a chunk.
+    probability: f32,
+    y: HeightProvider,
+    y_scale: FloatProvider,
+    lava_level: VerticalAnchor,
+    /// The blocks the carver cuts through. Anything else is left alone.
+    replaceable: HashSet<BlockStateId>,
+    kind: CarverKind,
+}
+
+fn config<T: DeserializeOwned>(config: serde_json::Value) -> Option<T> {
+    serde_json::from_value(config).ok()
+}
+
+impl Carver {
+    /// Compiles a carver from `carver.json`, or returns `None` if it's a kind that isn't
+    /// supported. The nether's caves aren't, since the nether isn't shaped by noise yet.
+    fn compile(kind: &str, raw: serde_json::Value) -> Option<Self> {
+        let (common, kind) = match kind {
+            "minecraft:cave" => {
+                let raw: RawCaveConfig = config(raw)?;
+                (
+                    raw.common,
+                    CarverKind::Cave {
+                        horizontal_radius: raw.horizontal_radius_multiplier,
+                        vertical_radius: raw.vertical_radius_multiplier,
+                        floor_level: raw.floor_level,
+                    },
+                )
+            }
+            "minecraft:canyon" => {
+                let raw: RawCanyonConfig = config(raw)?;
+                (
+                    raw.common,
+                    CarverKind::Canyon {
+                        vertical_rotation: raw.vertical_rotation,
+                        shape: raw.shape,
+                    },
+                )
+            }
+            _ => return None,
+        };
+        if !common.y.is_supported() {
+            return None;
+        }
+        let replaceable = match common.replaceable {
+            OneOrMany::One(tag) if tag.starts_with('#') => tag_states(&tag)?,
+            names => states_of(names.names()),
+        };
+        Some(Self {
+            probability: common.probability,
+            y: common.y,
+            y_scale: common.y_scale,
+            lava_level: common.lava_level,
+            replaceable,
+            kind,
+        })
+    }
+}
+
+/// Runs the carvers of every chunk in range of a chunk, biomes being looked up with `biome_at`.
+pub(crate) fn carve(
+    seed: u64,
+    chunk: &mut ShapedChunk,
+    aquifer: &mut Aquifer,
+    biome_at: impl Fn(i32, i32) -> &'static Biome,
+) {
+    let mut carved = vec![false; 256 * chunk.height as usize];
+    for source_x in chunk.x - CARVER_RANGE..=chunk.x + CARVER_RANGE {
+        for source_z in chunk.z - CARVER_RANGE..=chunk.z + CARVER_RANGE {
+            // Each carver's chance to start doesn't depend on the biome, so it's rolled first and
+            // the biome, which is slow to look up, is skipped where nothing could start.
+            let mut rolls: Vec<_> = (0..CARVERS.most_per_biome)
+                .map(|index| {
+                    let mut random = FeatureRandom::new(
+                        [i64::from(source_x), i64::from(source_z), index as i64]
+                            .into_iter()
+                            .fold(seed, |seed, value| mix(seed ^ value as u64)),
+                    );
+                    let roll = random.next_float();
+                    (random, roll)
+                })
+                .collect();
+            if rolls
+                .iter()
+                .all(|(_, roll)| *roll > CARVERS.highest_probability)
+            {
+                continue;
+            }
+            let biome = biome_at(source_x * 16, source_z * 16);
+            let Some(carvers) = CARVERS.biome_carvers.get(biome.name) else {
+                continue;
+            };
+            for ((random, roll), name) in rolls.iter_mut().zip(carvers) {
+                let Some(carver) = CARVERS.carvers.get(name) else {
+                    continue;
+                };
+                if *roll <= carver.probability {
+                    let mut carving = Carving {
+                        lava_level: carver.lava_level.resolve(chunk),
+                        chunk: &mut *chunk,
+                        aquifer: &mut *aquifer,
+                        carved: &mut carved,
+                        carver,
+                    };
+                    carving.run(random, source_x, source_z);
+                }
+            }
+        }
+    }
+}
+
+/// A chunk being carved by one carver.
+struct Carving<'a, 'b> {
+    chunk: &'a mut ShapedChunk,
+    aquifer: &'a mut Aquifer<'b>,
+    /// Which of the chunk's blocks have been carved already, by any carver, indexed like its
+    /// blocks.
+    carved: &'a mut [bool],
+    carver: &'a Carver,
+    lava_level: i32,
+}
+
+impl Carving<'_, '_> {
+    /// Runs the carver, starting in a source chunk.
+    fn run(&mut self, random: &mut FeatureRandom, source_x: i32, source_z: i32) {
+        let carver = self.carver;
+        match &carver.kind {
+            CarverKind::Cave {
+                horizontal_radius,
+                vertical_radius,
+                floor_level,
+            } => {
+                // Picked three times over, so most chunks get few caves and some get many.
+                let bound = random.next_int(CAVE_BOUND) + 1;
+                let bound = random.next_int(bound) + 1;
+                let caves = random.next_int(bound);
+                for _ in 0..caves {
+                    let x = f64::from(source_x * 16 + random.next_int(16));
+                    let y = f64::from(carver.y.sample(random, self.chunk));
+                    let z = f64::from(source_z * 16 + random.next_int(16));
+                    let horizontal_radius = f64::from(horizontal_radius.sample(random));
+                    let vertical_radius = f64::from(vertical_radius.sample(random));
+                    let floor_level = f64::from(floor_level.sample(random));
+                    let skip =
+                        |[x, y, z]: [f64; 3], _| y <= floor_level || x * x + y * y + z * z >= 1.0;
+                    let mut tunnels = 1;
+                    if random.next_int(4) == 0 {
+                        let y_scale = f64::from(carver.y_scale.sample(random));
+                        let radius = 1.5 + f64::from(1.0 + random.next_float() * 6.0);
+                        self.carve_ellipsoid([x + 1.0, y, z], radius, radius * y_scale, &skip);
+                        tunnels += random.next_int(4);
+                    }
+                    for _ in 0..tunnels {
+                        let yaw = random.next_float() * PI * 2.0;
+                        let pitch = (random.next_float() - 0.5) / 4.0;
+                        let mut thickness = random.next_float() * 2.0 + random.next_float();
+                        if random.next_int(10) == 0 {
+                            thickness *= random.next_float() * random.next_float() * 3.0 + 1.0;
+                        }
+                        let length = MAX_LENGTH - random.next_int(MAX_LENGTH / 4);
+                        let tunnel = Tunnel {
+                            position: [x, y, z],
+                            yaw,
+                            pitch,
+                            thickness,
+                            length,
+                        };
+                        let radii = (horizontal_radius, vertical_radius);
+                        self.carve_tunnel(random.next_u64(), tunnel, 0, radii, &skip);
+                    }
+                }
+            }
+            CarverKind::Canyon {
+                vertical_rotation,
+                shape,
+            } => {
+                let x = f64::from(source_x * 16 + random.next_int(16));
+                let y = f64::from(carver.y.sample(random, self.chunk));
+                let z = f64::from(source_z * 16 + random.next_int(16));
+                let yaw = random.next_float() * PI * 2.0;
+                let pitch = vertical_rotation.sample(random);
+                let y_scale = f64::from(carver.y_scale.sample(random));
+                let thickness = shape.thickness.sample(random);
+                let length = (MAX_LENGTH as f32 * shape.distance_factor.sample(random)) as i32;
+                let tunnel = Tunnel {
+                    position: [x, y, z],
+                    yaw,
+                    pitch,
+                    thickness,
+                    length,
+                };
+                self.carve_canyon(random.next_u64(), tunnel, y_scale, shape);
+            }
+        }
+    }
+
+    /// Carves a winding tunnel, which may split in two partway along.
+    fn carve_tunnel(
+        &mut self,
+        seed: u64,
+        mut tunnel: Tunnel,
+        start: i32,
+        (horizontal_radius, vertical_radius): (f64, f64),
+        skip: &impl Fn([f64; 3], i32) -> bool,
+    ) {
+        let mut random = FeatureRandom::new(seed);
+        let split = random.next_int(tunnel.length / 2) + tunnel.length / 4;
+        let steep = random.next_int(6) == 0;
+        let (mut yaw_change, mut pitch_change) = (0.0f32, 0.0f32);
+        for step in start..tunnel.length {
+            let radius =
+                1.5 + f64::from((PI * step as f32 / tunnel.length as f32).sin() * tunnel.thickness);
+            tunnel.advance();
+            tunnel.pitch *= if steep { 0.92 } else { 0.7 };
+            tunnel.pitch += pitch_change * 0.1;
+            tunnel.yaw += yaw_change * 0.1;
+            pitch_change *= 0.9;
+            yaw_change *= 0.75;
+            pitch_change += (random.next_float() - random.next_float()) * random.next_float() * 2.0;
+            yaw_change += (random.next_float() - random.next_float()) * random.next_float() * 4.0;
+            if step == split && tunnel.thickness > 1.0 {
+                for turn in [-PI / 2.0, PI / 2.0] {
+                    let branch = Tunnel {
+                        yaw: tunnel.yaw + turn,
+                        pitch: tunnel.pitch / 3.0,
+                        thickness: random.next_float() * 0.5 + 0.5,
+                        ..tunnel
+                    };
+                    let radii = (horizontal_radius, vertical_radius);
+                    self.carve_tunnel(random.next_u64(), branch, step, radii, skip);
+                }
+                return;
+            }
+            if random.next_int(4) != 0 {
+                if !self.can_reach(&tunnel, step) {
+                    return;
+                }
+                self.carve_ellipsoid(
+                    tunnel.position,
+                    radius * horizontal_radius,
+                    radius * vertical_radius,
+                    skip,
+                );
+            }
+        }
+    }
+
+    /// Carves a canyon, which is like a tunnel that's much taller than it is wide, with walls
+    /// that get wider and narrower on the way down.
+    fn carve_canyon(&mut self, seed: u64, mut tunnel: Tunnel, y_scale: f64, shape: &CanyonShape) {
+        let mut random = FeatureRandom::new(seed);
+        // How much wider the canyon is at each height, squared.
+        let mut widths = vec![0.0f32; self.chunk.height as usize];
+        let mut width = 1.0f32;
+        for (index, slot) in widths.iter_mut().enumerate() {
+            if index == 0 || random.next_int(shape.width_smoothness) == 0 {
+                width = 1.0 + random.next_float() * random.next_float();
+            }
+            *slot = width * width;
+        }
+        let min_y = self.chunk.min_y;
+        let skip = |[x, y, z]: [f64; 3], block_y: i32| {
+            let width = widths[(block_y - min_y - 1) as usize];
+            (x * x + z * z) * f64::from(width) + y * y / 6.0 >= 1.0
+        };
+
+        let (mut yaw_change, mut pitch_change) = (0.0f32, 0.0f32);
+        for step in 0..tunnel.length {
+            let radius =
+                1.5 + f64::from((step as f32 * PI / tunnel.length as f32).sin() * tunnel.thickness);
+            let vertical_radius = radius * y_scale;
+            let horizontal_radius =
+                radius * f64::from(shape.horizontal_radius_factor.sample(&mut random));
+            let middle = 1.0 - (0.5 - step as f32 / tunnel.length as f32).abs() * 2.0;
+            let factor =
+                shape.vertical_radius_default_factor + shape.vertical_radius_center_factor * middle;
+            let vertical_radius =
+                f64::from(factor) * vertical_radius * f64::from(random.next_float() * 0.25 + 0.75);
+            tunnel.advance();
+            tunnel.pitch *= 0.7;
+            tunnel.pitch += pitch_change * 0.05;
+            tunnel.yaw += yaw_change * 0.05;
+            pitch_change *= 0.8;
+            yaw_change *= 0.5;
+            pitch_change += (random.next_float() - random.next_float()) * random.next_float() * 2.0;
+            yaw_change += (random.next_float() - random.next_float()) * random.next_float() * 4.0;
+            if random.next_int(4) != 0 {
+                if !self.can_reach(&tunnel, step) {
+                    return;
+                }
+                self.carve_ellipsoid(tunnel.position, horizontal_radius, vertical_radius, &skip);
+            }
+        }
+    }
+
+    /// Whether a tunnel could still reach this chunk in the steps it has left.
+    fn can_reach(&self, tunnel: &Tunnel, step: i32) -> bool {
+        let [x, _, z] = tunnel.position;
+        let (dx, dz) = (
+            x - f64::from(self.chunk.x * 16 + 8),
+            z - f64::from(self.chunk.z * 16 + 8),
+        );
+        let remaining = f64::from(tunnel.length - step);
+        let reach = f64::from(tunnel.thickness) + 2.0 + 16.0;
+        dx * dx + dz * dz - remaining * remaining <= reach * reach
+    }
+
+    /// Carves the blocks of this chunk inside an ellipsoid, except the ones `skip` rules out given
+    /// where they are relative to the centre, scaled by the radii, and their height.
+    fn carve_ellipsoid(
+        &mut self,
+        [x, y, z]: [f64; 3],
+        horizontal_radius: f64,
+        vertical_radius: f64,
+        skip: &impl Fn([f64; 3], i32) -> bool,
+    ) {
+        let (min_x, min_z) = (self.chunk.x * 16, self.chunk.z * 16);
+        let reach = 16.0 + horizontal_radius * 2.0;
+        if (x - f64::from(min_x + 8)).abs() > reach || (z - f64::from(min_z + 8)).abs() > reach {
+            return;
+        }
+        let local = |position: f64, offset: f64, min: i32| (position + offset).floor() as i32 - min;
+        let (from_x, to_x) = (
+            (local(x, -horizontal_radius, min_x) - 1).max(0),
+            local(x, horizontal_radius, min_x).min(15),
+        );
+        let (from_z, to_z) = (
+            (local(z, -horizontal_radius, min_z) - 1).max(0),
+            local(z, horizontal_radius, min_z).min(15),
+        );
+        let min_y = self.chunk.min_y;
+        // Carvers stay clear of the top of the world, like vanilla's do.
+        let max_y = min_y + self.chunk.height - 1 - 7;
+        let from_y = ((y - vertical_radius).floor() as i32 - 1).max(min_y + 1);
+        let to_y = ((y + vertical_radius).floor() as i32 + 1).min(max_y);
+
+        for block_x in min_x + from_x..=min_x + to_x {
+            let relative_x = (f64::from(block_x) + 0.5 - x) / horizontal_radius;
+            for block_z in min_z + from_z..=min_z + to_z {
+                let relative_z = (f64::from(block_z) + 0.5 - z) / horizontal_radius;
+                if relative_x * relative_x + relative_z * relative_z >= 1.0 {
+                    continue;
+                }
+                let mut reached_surface = false;
+                for block_y in (from_y + 1..=to_y).rev() {
+                    let relative_y = (f64::from(block_y) - 0.5 - y) / vertical_radius;
+                    if skip([relative_x, relative_y, relative_z], block_y) {
+                        continue;
+                    }
+                    let index = (block_y - min_y) as usize * 256
+                        + (block_z - min_z) as usize * 16
+                        + (block_x - min_x) as usize;
+                    if !self.carved[index] {
+                        self.carved[index] = true;
+                        self.carve_block([block_x, block_y, block_z], &mut reached_surface);
+                    }
+                }
+            }
+        }
+    }
+
+    fn carve_block(&mut self, [x, y, z]: BlockPos, reached_surface: &mut bool) {
+        let Some(block) = self.chunk.block(x, y, z) else {
+            return;
+        };
+        if SURFACE_BLOCKS.contains(&block) {
+            *reached_surface = true;
+        }
+        if !self.carver.replaceable.contains(&block) {
+            return;
+        }
+        let carved = if y <= self.lava_level {
+            Some(block!("lava", {level: 0}))
+        } else {
+            self.aquifer.substance([x, y, z], 0.0)
+        };
+        let Some(carved) = carved else {
+            return;
+        };
+        self.chunk.set_block(x, y, z, carved);
+        // Carving out the top of a column leaves the dirt below bare, so it gets the biome's top
+        // block instead.
+        if *reached_surface
+            && carved == BlockStateId::default()
+            && self.chunk.block(x, y - 1, z) == Some(block!("dirt"))
+            && let Some(biome) = self.chunk.biome(x, z)
+            && let Some(top) = terrain_for(biome).surface_block(0, y - 1)
+        {
+            self.chunk.set_block(x, y - 1, z, top);
+        }
+    }
+}
+
+/// Where a tunnel or canyon has got to, and which way it's heading.
+#[derive(Clone, Copy)]
+struct Tunnel {
+    position: [f64; 3],
+    yaw: f32,
+    pitch: f32,
+    thickness: f32,
+    /// How many steps long it is.
+    length: i32,
+}
+
+impl Tunnel {
+    /// Moves a block forward.
+    fn advance(&mut self) {
+        let horizontal = self.pitch.cos();
+        self.position[0] += f64::from(self.yaw.cos() * horizontal);
+        self.position[1] += f64::from(self.pitch.sin());
+        self.position[2] += f64::from(self.yaw.sin() * horizontal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_carvers_compile() {
+        for name in ["cave", "cave_extra_underground", "canyon"] {
+            assert!(CARVERS.carvers.contains_key(name), "{name} should compile");
+        }
+        assert_eq!(
+            CARVERS.biome_carvers["plains"],
+            ["cave", "cave_extra_underground", "canyon"]
+        );
+    }
+}
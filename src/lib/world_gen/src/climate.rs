@@ -0,0 +1,207 @@
+//! Picking biomes from climate, the way vanilla does it.
+//!
+//! Every block column has a climate made of five noise values: temperature, humidity,
+//! continentalness, erosion and weirdness. The extracted `multi_noise_biome_tree.json` lists the
+//! climate ranges each biome covers, grouped into a tree of bounding ranges, and a column gets the
+//! biome whose ranges are closest to its climate.
+
+use crate::density_function::{ColumnSampler, FunctionId, NoiseRouter};
+use ferrumc_data::generated::biomes::Biome;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const BIOME_TREE_FILE: &str =
+    include_str!("../../../../assets/extracted/multi_noise_biome_tree.json");
+
+/// How many climate parameters each biome has: the five noises, depth below the surface and an
+/// offset that makes some biomes rarer.
+const PARAMETER_COUNT: usize = 7;
+
+/// Climate values are stored in the tree multiplied by this, as whole numbers.
+const QUANTIZATION: f64 = 10000.0;
+
+/// The overworld's biome tree.
+pub(crate) static OVERWORLD_BIOMES: LazyLock<BiomeTree> = LazyLock::new(|| {
+    let mut trees: HashMap<String, RawNode> =
+        serde_json::from_str(BIOME_TREE_FILE).expect("multi_noise_biome_tree.json should be valid");
+    BiomeTree::from_raw(
+        trees
+            .remove("overworld")
+            .expect("multi_noise_biome_tree.json should have an overworld tree"),
+    )
+});
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(crate) struct ParameterRange {
+    min: i64,
+    max: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "_type", rename_all = "lowercase")]
+enum RawNode {
+    Leaf {
+        biome: String,
+        parameters: Vec<ParameterRange>,
+    },
+    Branch {
+        #[serde(rename = "subTree")]
+        sub_tree: Vec<RawNode>,
+        parameters: Vec<ParameterRange>,
+    },
+}
+
+/// A node of the biome tree. Branches hold the ranges covering all of their children, so whole
+/// branches can be skipped when even their closest point is further than the best biome so far.
+pub(crate) enum BiomeTree {
+    Leaf {
+        biome: &'static Biome,
+        parameters: Vec<ParameterRange>,
+    },
+    Branch {
+        children: Vec<BiomeTree>,
+        parameters: Vec<ParameterRange>,
+    },
+}
+
+impl BiomeTree {
+    fn from_raw(node: RawNode) -> Self {
+        match node {
+            RawNode::Leaf { biome, parameters } => BiomeTree::Leaf {
+                biome: Biome::from_name(&biome).unwrap_or_else(|| {
+                    panic!("Unknown biome {biome} in multi_noise_biome_tree.json")
+                }),
+                parameters,
+            },
+            RawNode::Branch {
+                sub_tree,
+                parameters,
+            } => BiomeTree::Branch {
+                children: sub_tree.into_iter().map(Self::from_raw).collect(),
+                parameters,
+            },
+        }
+    }
+
+    fn parameters(&self) -> &[ParameterRange] {
+        match self {
+            BiomeTree::Leaf { parameters, .. } | BiomeTree::Branch { parameters, .. } => parameters,
+        }
+    }
+
+    /// The squared distance from a climate to the closest point in this node's ranges.
+    fn distance(&self, climate: &[i64; PARAMETER_COUNT]) -> i64 {
+        self.parameters()
+            .iter()
+            .zip(climate)
+            .map(|(range, value)| {
+                let distance = (value - range.max).max(range.min - value).max(0);
+                distance * distance
+            })
+            .sum()
+    }
+
+    /// Finds the biome closest to a climate, as given by [ClimateSampler::sample].
+    pub(crate) fn nearest(&self, climate: &[i64; PARAMETER_COUNT]) -> &'static Biome {
+        let mut best = (i64::MAX, &Biome::PLAINS);
+        self.search(climate, &mut best);
+        best.1
+    }
+
+    fn search(&self, climate: &[i64; PARAMETER_COUNT], best: &mut (i64, &'static Biome)) {
+        match self {
+            BiomeTree::Leaf { biome, .. } => {
+                let distance = self.distance(climate);
+                if distance < best.0 {
+                    *best = (distance, *biome);
+                }
+            }
+            BiomeTree::Branch { children, .. } => {
+                for child in children {
+                    if child.distance(climate) < best.0 {
+                        child.search(climate, best);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Samples the climate from a noise router's climate functions, the same ones that shape the
+/// terrain, so the biomes match the land they're on.
+pub(crate) struct ClimateSampler<'a> {
+    router: &'a NoiseRouter,
+    /// Temperature, humidity, continentalness, erosion and weirdness, in that order.
+    functions: [FunctionId; 5],
+}
+
+impl<'a> ClimateSampler<'a> {
+    pub(crate) fn new(router: &'a NoiseRouter) -> Self {
+        Self {
+            router,
+            functions: [
+                "temperature",
+                "vegetation",
+                "continents",
+                "erosion",
+                "ridges",
+            ]
+            .map(|name| router.function(name)),
+        }
+    }
+
+    /// The climate at the surface of a block column, quantized like the biome tree's ranges.
+    pub(crate) fn sample(&self, x: i64, z: i64) -> [i64; PARAMETER_COUNT] {
+        let mut sampler = ColumnSampler::new(self.router, x, z);
+        let [temperature, humidity, continentalness, erosion, weirdness] = self
+            .functions
+            .map(|function| (sampler.sample(function, 0) * QUANTIZATION) as i64);
+        [
+            temperature,
+            humidity,
+            continentalness,
+            erosion,
+            // Depth is 0 at the surface, and the offset is always 0 for the column itself.
+            0,
+            weirdness,
+            0,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_biome() {
+        // Cold, dry and far inland.
+        assert_eq!(
+            OVERWORLD_BIOMES
+                .nearest(&[-8000, -8000, 5000, 0, 0, -5000, 0])
+                .name,
+            "snowy_plains"
+        );
+        // Far out at sea.
+        assert!(
+            OVERWORLD_BIOMES
+                .nearest(&[0, 0, -9000, 0, 0, 0, 0])
+                .name
+                .ends_with("ocean")
+        );
+    }
+
+    #[test]
+    fn test_climate_picks_several_biomes() {
+        let router = NoiseRouter::from_settings("overworld", 0);
+        let climate = ClimateSampler::new(&router);
+        let mut biomes = (-50..50)
+            .flat_map(|x| (-50..50).map(move |z| (x * 256, z * 256)))
+            .map(|(x, z)| OVERWORLD_BIOMES.nearest(&climate.sample(x, z)).name)
+            .collect::<Vec<_>>();
+        biomes.sort_unstable();
+        biomes.dedup();
+        assert!(biomes.len() >= 6, "only found {biomes:?}");
+    }
+}
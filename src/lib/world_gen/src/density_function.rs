@@ -0,0 +1,924 @@
+//! Vanilla's density functions, which shape terrain in three dimensions.
+//!
+//! A density function gives every point in the world a number, and the terrain is solid wherever
+//! the noise router's `finalDensity` is above zero. The extracted `density_function.json` holds each
+//! dimension's noise router with every function written out in full, so the same subtrees show up
+//! hundreds of times. They're compiled into one list of nodes where identical subtrees share a
+//! node, which takes the overworld from thousands of nodes down to about two hundred.
+//!
+//! A [ColumnSampler] evaluates the nodes for one block column, caching the ones vanilla caches:
+//! nodes that don't depend on the height once per column, and nodes used in several places once per
+//! point. The noises are seeded from [crate::normal_noise], so the terrain has vanilla's shape but
+//! won't match it block for block.
+
+use crate::normal_noise::{NormalNoise, noise_seed, octave_perlin};
+use noise::{NoiseFn, OpenSimplex, Perlin};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+const DENSITY_FUNCTIONS_FILE: &str =
+    include_str!("../../../../assets/extracted/density_function.json");
+
+/// Every noise router in `density_function.json`, compiled, keyed by the name of its settings such
+/// as `"overworld"`.
+static NOISE_ROUTERS: LazyLock<HashMap<String, Arc<DensityFunctions>>> = LazyLock::new(|| {
+    let routers: HashMap<String, HashMap<String, RawFunction>> =
+        serde_json::from_str(DENSITY_FUNCTIONS_FILE)
+            .expect("density_function.json should be valid");
+    routers
+        .into_iter()
+        .map(|(name, functions)| (name, Arc::new(DensityFunctions::compile(&functions))))
+        .collect()
+});
+
+/// A density function as it's written in the extracted files.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "_class", content = "value", rename_all_fields = "camelCase")]
+pub(crate) enum RawFunction {
+    Constant {
+        value: f64,
+    },
+    Noise {
+        noise: String,
+        xz_scale: f64,
+        y_scale: f64,
+    },
+    ShiftedNoise {
+        noise: String,
+        xz_scale: f64,
+        y_scale: f64,
+        shift_x: Box<RawFunction>,
+        shift_y: Box<RawFunction>,
+        shift_z: Box<RawFunction>,
+    },
+    ShiftA {
+        offset_noise: String,
+    },
+    ShiftB {
+        offset_noise: String,
+    },
+    YClampedGradient {
+        from_y: f64,
+        to_y: f64,
+        from_value: f64,
+        to_value: f64,
+    },
+    LinearOperation {
+        specific_type: LinearOperation,
+        input: Box<RawFunction>,
+        argument: f64,
+    },
+    BinaryOperation {
+        #[serde(rename = "type")]
+        operation: BinaryOperation,
+        argument1: Box<RawFunction>,
+        argument2: Box<RawFunction>,
+    },
+    UnaryOperation {
+        #[serde(rename = "type")]
+        operation: UnaryOperation,
+        input: Box<RawFunction>,
+    },
+    Clamp {
+        input: Box<RawFunction>,
+        min_value: f64,
+        max_value: f64,
+    },
+    RangeChoice {
+        input: Box<RawFunction>,
+        min_inclusive: f64,
+        max_exclusive: f64,
+        when_in_range: Box<RawFunction>,
+        when_out_of_range: Box<RawFunction>,
+    },
+    Spline {
+        spline: RawSpline,
+    },
+    WeirdScaledSampler {
+        input: Box<RawFunction>,
+        noise: String,
+        rarity_value_mapper: RarityValueMapper,
+    },
+    InterpolatedNoiseSampler(BlendedNoiseSettings),
+    EndIslands,
+    Wrapping {
+        #[serde(rename = "type")]
+        wrapper: Wrapper,
+        wrapped: Box<RawFunction>,
+    },
+    /// Blending smooths new terrain into chunks generated by older versions, which this server
+    /// never has, so these are all left as they'd be far from any old chunk.
+    BlendAlpha,
+    BlendOffset,
+    BlendDensity {
+        input: Box<RawFunction>,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "_type", content = "value", rename_all = "lowercase")]
+pub(crate) enum RawSpline {
+    Fixed {
+        value: f64,
+    },
+    Standard {
+        #[serde(rename = "locationFunction")]
+        location_function: Box<RawFunction>,
+        locations: Vec<f64>,
+        values: Vec<RawSpline>,
+        derivatives: Vec<f64>,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub(crate) enum LinearOperation {
+    Add,
+    Mul,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub(crate) enum BinaryOperation {
+    Add,
+    Mul,
+    Min,
+    Max,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum UnaryOperation {
+    Abs,
+    Square,
+    Cube,
+    HalfNegative,
+    QuarterNegative,
+    Squeeze,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(crate) enum RarityValueMapper {
+    #[serde(rename = "TYPE1")]
+    Tunnels,
+    #[serde(rename = "TYPE2")]
+    Caves,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(crate) enum Wrapper {
+    Interpolated,
+    FlatCache,
+    Cache2D,
+    CacheOnce,
+    CacheAllInCell,
+}
+
+/// The settings of vanilla's "old blended noise", the base 3D noise the overworld is carved from.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BlendedNoiseSettings {
+    scaled_xz_scale: f64,
+    scaled_y_scale: f64,
+    xz_factor: f64,
+    y_factor: f64,
+}
+
+impl UnaryOperation {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            UnaryOperation::Abs => value.abs(),
+            UnaryOperation::Square => value * value,
+            UnaryOperation::Cube => value * value * value,
+            UnaryOperation::HalfNegative if value < 0.0 => value * 0.5,
+            UnaryOperation::QuarterNegative if value < 0.0 => value * 0.25,
+            UnaryOperation::HalfNegative | UnaryOperation::QuarterNegative => value,
+            UnaryOperation::Squeeze => {
+                let value = value.clamp(-1.0, 1.0);
+                value / 2.0 - value * value * value / 24.0
+            }
+        }
+    }
+}
+
+impl RarityValueMapper {
+    /// How much bigger the tunnels or caves are at a point, given the rarity noise there.
+    fn scale(self, rarity: f64) -> f64 {
+        match self {
+            RarityValueMapper::Tunnels => match rarity {
+                r if r < -0.5 => 0.75,
+                r if r < 0.0 => 1.0,
+                r if r < 0.5 => 1.5,
+                _ => 2.0,
+            },
+            RarityValueMapper::Caves => match rarity {
+                r if r < -0.75 => 0.5,
+                r if r < -0.5 => 0.75,
+                r if r < 0.5 => 1.0,
+                r if r < 0.75 => 2.0,
+                _ => 3.0,
+            },
+        }
+    }
+}
+
+/// A compiled density function. Inputs are indices of other nodes, and noises are indices into
+/// the noises the functions were seeded with.
+#[derive(Debug)]
+enum Node {
+    Constant(f64),
+    Noise {
+        noise: usize,
+        xz_scale: f64,
+        y_scale: f64,
+    },
+    ShiftedNoise {
+        noise: usize,
+        xz_scale: f64,
+        y_scale: f64,
+        shift: [usize; 3],
+    },
+    ShiftA(usize),
+    ShiftB(usize),
+    YClampedGradient {
+        from_y: f64,
+        to_y: f64,
+        from_value: f64,
+        to_value: f64,
+    },
+    Linear {
+        operation: LinearOperation,
+        input: usize,
+        argument: f64,
+    },
+    Binary {
+        operation: BinaryOperation,
+        first: usize,
+        second: usize,
+    },
+    Unary {
+        operation: UnaryOperation,
+        input: usize,
+    },
+    Clamp {
+        input: usize,
+        min: f64,
+        max: f64,
+    },
+    RangeChoice {
+        input: usize,
+        min_inclusive: f64,
+        max_exclusive: f64,
+        in_range: usize,
+        out_of_range: usize,
+    },
+    Spline(Spline),
+    WeirdScaled {
+        input: usize,
+        noise: usize,
+        rarity: RarityValueMapper,
+    },
+    BlendedNoise(usize),
+    EndIslands,
+    /// Doesn't depend on the height, so it's evaluated once per column.
+    ColumnCached(usize),
+    /// Used in several places, so it's evaluated once per point.
+    PointCached(usize),
+}
+
+#[derive(Debug)]
+enum Spline {
+    Fixed(f64),
+    Points {
+        location: usize,
+        points: Vec<SplinePoint>,
+    },
+}
+
+#[derive(Debug)]
+struct SplinePoint {
+    location: f64,
+    value: Spline,
+    derivative: f64,
+}
+
+/// A set of compiled density functions, not yet seeded.
+pub(crate) struct DensityFunctions {
+    nodes: Vec<Node>,
+    /// The name of each noise the nodes use, in `noise_parameters.json`.
+    noises: Vec<String>,
+    blended_noises: Vec<BlendedNoiseSettings>,
+    /// The top level functions by name, like `finalDensity`.
+    functions: HashMap<String, usize>,
+}
+
+#[derive(Default)]
+struct Compiler {
+    nodes: Vec<Node>,
+    /// Each node's index, keyed by its debug output, which is the same for identical subtrees
+    /// since their inputs have already been merged.
+    node_indices: HashMap<String, usize>,
+    noises: Vec<String>,
+    blended_noises: Vec<BlendedNoiseSettings>,
+}
+
+impl Compiler {
+    fn add(&mut self, node: Node) -> usize {
+        let key = format!("{node:?}");
+        if let Some(index) = self.node_indices.get(&key) {
+            return *index;
+        }
+        self.nodes.push(node);
+        self.node_indices.insert(key, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn noise(&mut self, name: &str) -> usize {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        match self.noises.iter().position(|noise| noise == name) {
+            Some(index) => index,
+            None => {
+                self.noises.push(name.to_string());
+                self.noises.len() - 1
+            }
+        }
+    }
+
+    fn compile(&mut self, function: &RawFunction) -> usize {
+        let node = match function {
+            RawFunction::Constant { value } => Node::Constant(*value),
+            RawFunction::Noise {
+                noise,
+                xz_scale,
+                y_scale,
+            } => Node::Noise {
+                noise: self.noise(noise),
+                xz_scale: *xz_scale,
+                y_scale: *y_scale,
+            },
+            RawFunction::ShiftedNoise {
+                noise,
+                xz_scale,
+                y_scale,
+                shift_x,
+                shift_y,
+                shift_z,
+            } => Node::ShiftedNoise {
+                noise: self.noise(noise),
+                xz_scale: *xz_scale,
+                y_scale: *y_scale,
+                shift: [
+                    self.compile(shift_x),
+                    self.compile(shift_y),
+                    self.compile(shift_z),
+                ],
+            },
+            RawFunction::ShiftA { offset_noise } => Node::ShiftA(self.noise(offset_noise)),
+            RawFunction::ShiftB { offset_noise } => Node::ShiftB(self.noise(offset_noise)),
+            RawFunction::YClampedGradient {
+                from_y,
+                to_y,
+                from_value,
+                to_value,
+            } => Node::YClampedGradient {
+                from_y: *from_y,
+                to_y: *to_y,
+                from_value: *from_value,
+                to_value: *to_value,
+            },
+            RawFunction::LinearOperation {
+                specific_type,
+                input,
+                argument,
+            } => Node::Linear {
+                operation: *specific_type,
+                input: self.compile(input),
+                argument: *argument,
+            },
+            RawFunction::BinaryOperation {
+                operation,
+                argument1,
+                argument2,
+            } => Node::Binary {
+                operation: *operation,
+                first: self.compile(argument1),
+                second: self.compile(argument2),
+            },
+            RawFunction::UnaryOperation { operation, input } => Node::Unary {
+                operation: *operation,
+                input: self.compile(input),
+            },
+            RawFunction::Clamp {
+                input,
+                min_value,
+                max_value,
+            } => Node::Clamp {
+                input: self.compile(input),
+                min: *min_value,
+                max: *max_value,
+            },
+            RawFunction::RangeChoice {
+                input,
+                min_inclusive,
+                max_exclusive,
+                when_in_range,
+                when_out_of_range,
+            } => Node::RangeChoice {
+                input: self.compile(input),
+                min_inclusive: *min_inclusive,
+                max_exclusive: *max_exclusive,
+                in_range: self.compile(when_in_range),
+                out_of_range: self.compile(when_out_of_range),
+            },
+            RawFunction::Spline { spline } => Node::Spline(self.compile_spline(spline)),
+            RawFunction::WeirdScaledSampler {
+                input,
+                noise,
+                rarity_value_mapper,
+            } => Node::WeirdScaled {
+                input: self.compile(input),
+                noise: self.noise(noise),
+                rarity: *rarity_value_mapper,
+            },
+            RawFunction::InterpolatedNoiseSampler(settings) => {
+                self.blended_noises.push(*settings);
+                Node::BlendedNoise(self.blended_noises.len() - 1)
+            }
+            RawFunction::EndIslands => Node::EndIslands,
+            RawFunction::Wrapping { wrapper, wrapped } => {
+                let wrapped = self.compile(wrapped);
+                match wrapper {
+                    Wrapper::FlatCache | Wrapper::Cache2D => Node::ColumnCached(wrapped),
+                    Wrapper::CacheOnce | Wrapper::CacheAllInCell => Node::PointCached(wrapped),
+                    // Chunks interpolate all of their terrain between cell corners, see
+                    // [crate::overworld].
+                    Wrapper::Interpolated => return wrapped,
+                }
+            }
+            RawFunction::BlendAlpha => Node::Constant(1.0),
+            RawFunction::BlendOffset => Node::Constant(0.0),
+            RawFunction::BlendDensity { input } => return self.compile(input),
+        };
+        self.add(node)
+    }
+
+    fn compile_spline(&mut self, spline: &RawSpline) -> Spline {
+        match spline {
+            RawSpline::Fixed { value } => Spline::Fixed(*value),
+            RawSpline::Standard {
+                location_function,
+                locations,
+                values,
+                derivatives,
+            } => Spline::Points {
+                location: self.compile(location_function),
+                points: locations
+                    .iter()
+                    .zip(values)
+                    .zip(derivatives)
+                    .map(|((location, value), derivative)| SplinePoint {
+                        location: *location,
+                        value: self.compile_spline(value),
+                        derivative: *derivative,
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl DensityFunctions {
+    pub(crate) fn compile(functions: &HashMap<String, RawFunction>) -> Self {
+        let mut compiler = Compiler::default();
+        let functions = functions
+            .iter()
+            .map(|(name, function)| (name.clone(), compiler.compile(function)))
+            .collect();
+        Self {
+            nodes: compiler.nodes,
+            noises: compiler.noises,
+            blended_noises: compiler.blended_noises,
+            functions,
+        }
+    }
+}
+
+/// Vanilla's "old blended noise": a noise that blends between two detailed noises, picking how
+/// much of each to use with a third, smoother one.
+struct BlendedNoise {
+    settings: BlendedNoiseSettings,
+    min_limit: Vec<Perlin>,
+    max_limit: Vec<Perlin>,
+    main: Vec<Perlin>,
+}
+
+impl BlendedNoise {
+    fn new(seed: u64, settings: BlendedNoiseSettings) -> Self {
+        let octaves = |name: &str, count: usize| {
+            let seed = noise_seed(seed, name);
+            (0..count)
+                .map(|index| octave_perlin(seed, index))
+                .collect::<Vec<_>>()
+        };
+        Self {
+            settings,
+            min_limit: octaves("min_limit", 16),
+            max_limit: octaves("max_limit", 16),
+            main: octaves("main", 8),
+        }
+    }
+
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let settings = &self.settings;
+        let (x, y, z) = (
+            x * settings.scaled_xz_scale,
+            y * settings.scaled_y_scale,
+            z * settings.scaled_xz_scale,
+        );
+        let (main_x, main_y, main_z) = (
+            x / settings.xz_factor,
+            y / settings.y_factor,
+            z / settings.xz_factor,
+        );
+
+        let mut main = 0.0;
+        let mut scale = 1.0;
+        for perlin in &self.main {
+            main += perlin.get([main_x * scale, main_y * scale, main_z * scale]) / scale;
+            scale /= 2.0;
+        }
+        let blend = (main / 10.0 + 1.0) / 2.0;
+
+        // Only the limit noises that are blended in need sampling.
+        let (mut min, mut max) = (0.0, 0.0);
+        let mut scale = 1.0;
+        for (min_limit, max_limit) in self.min_limit.iter().zip(&self.max_limit) {
+            let point = [x * scale, y * scale, z * scale];
+            if blend < 1.0 {
+                min += min_limit.get(point) / scale;
+            }
+            if blend > 0.0 {
+                max += max_limit.get(point) / scale;
+            }
+            scale /= 2.0;
+        }
+        let blend = blend.clamp(0.0, 1.0);
+        (min / 512.0 * (1.0 - blend) + max / 512.0 * blend) / 128.0
+    }
+}
+
+/// The End's main island, with smaller islands scattered around it past 1000 blocks out.
+struct EndIslands {
+    noise: OpenSimplex,
+}
+
+impl EndIslands {
+    fn get(&self, x: f64, z: f64) -> f64 {
+        let (x, z) = (x as i64 / 8, z as i64 / 8);
+        let (cell_x, cell_z) = (x / 2, z / 2);
+        let (offset_x, offset_z) = (x % 2, z % 2);
+        let mut height = (100.0 - ((x * x + z * z) as f64).sqrt() * 8.0).clamp(-100.0, 80.0);
+        for dx in -12..=12 {
+            for dz in -12..=12 {
+                let (island_x, island_z) = (cell_x + dx, cell_z + dz);
+                if island_x * island_x + island_z * island_z <= 4096
+                    || self.noise.get([island_x as f64, island_z as f64]) >= -0.9
+                {
+                    continue;
+                }
+                let size = ((island_x.abs() * 3439 + island_z.abs() * 147) % 13 + 9) as f64;
+                let (to_x, to_z) = (offset_x - dx * 2, offset_z - dz * 2);
+                let island = 100.0 - ((to_x * to_x + to_z * to_z) as f64).sqrt() * size;
+                height = height.max(island.clamp(-100.0, 80.0));
+            }
+        }
+        (height - 8.0) / 128.0
+    }
+}
+
+/// A set of density functions seeded for a world.
+pub(crate) struct NoiseRouter {
+    functions: Arc<DensityFunctions>,
+    noises: Vec<NormalNoise>,
+    blended_noises: Vec<BlendedNoise>,
+    end_islands: EndIslands,
+}
+
+/// A top level function of a [NoiseRouter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FunctionId(usize);
+
+impl NoiseRouter {
+    pub(crate) fn new(functions: Arc<DensityFunctions>, seed: u64) -> Self {
+        Self {
+            noises: functions
+                .noises
+                .iter()
+                .map(|name| NormalNoise::from_name(seed, name))
+                .collect(),
+            blended_noises: functions
+                .blended_noises
+                .iter()
+                .map(|settings| BlendedNoise::new(seed, *settings))
+                .collect(),
+            end_islands: EndIslands {
+                noise: OpenSimplex::new(noise_seed(seed, "end_islands") as u32),
+            },
+            functions,
+        }
+    }
+
+    /// The noise router of the chunk generator settings with the given name in
+    /// `density_function.json`, such as `"overworld"`.
+    ///
+    /// # Panics
+    /// If there are no settings with that name.
+    pub(crate) fn from_settings(name: &str, seed: u64) -> Self {
+        let functions = NOISE_ROUTERS
+            .get(name)
+            .unwrap_or_else(|| panic!("No noise router named {name} in density_function.json"));
+        Self::new(functions.clone(), seed)
+    }
+
+    /// Finds a top level function, like `finalDensity` or `temperature`.
+    ///
+    /// # Panics
+    /// If there's no function with that name.
+    pub(crate) fn function(&self, name: &str) -> FunctionId {
+        FunctionId(
+            *self
+                .functions
+                .functions
+                .get(name)
+                .unwrap_or_else(|| panic!("No density function named {name}")),
+        )
+    }
+}
+
+/// Evaluates density functions in one block column.
+pub(crate) struct ColumnSampler<'a> {
+    router: &'a NoiseRouter,
+    x: f64,
+    z: f64,
+    column_cache: Vec<Option<f64>>,
+    /// The height each cached value was evaluated at, along with the value.
+    point_cache: Vec<Option<(f64, f64)>>,
+}
+
+impl<'a> ColumnSampler<'a> {
+    pub(crate) fn new(router: &'a NoiseRouter, x: i64, z: i64) -> Self {
+        let nodes = router.functions.nodes.len();
+        Self {
+            router,
+            x: x as f64,
+            z: z as f64,
+            column_cache: vec![None; nodes],
+            point_cache: vec![None; nodes],
+        }
+    }
+
+    /// The value of a function at the given height in this column.
+    pub(crate) fn sample(&mut self, function: FunctionId, y: i32) -> f64 {
+        self.evaluate(function.0, f64::from(y))
+    }
+
+    fn evaluate(&mut self, index: usize, y: f64) -> f64 {
+        let router = self.router;
+        let (x, z) = (self.x, self.z);
+        match &router.functions.nodes[index] {
+            Node::Constant(value) => *value,
+            Node::Noise {
+                noise,
+                xz_scale,
+                y_scale,
+            } => router.noises[*noise].get(x * xz_scale, y * y_scale, z * xz_scale),
+            Node::ShiftedNoise {
+                noise,
+                xz_scale,
+                y_scale,
+                shift,
+            } => {
+                let shift_x = self.evaluate(shift[0], y);
+                let shift_y = self.evaluate(shift[1], y);
+                let shift_z = self.evaluate(shift[2], y);
+                router.noises[*noise].get(
+                    x * xz_scale + shift_x,
+                    y * y_scale + shift_y,
+                    z * xz_scale + shift_z,
+                )
+            }
+            Node::ShiftA(noise) => router.noises[*noise].get(x * 0.25, 0.0, z * 0.25) * 4.0,
+            Node::ShiftB(noise) => router.noises[*noise].get(z * 0.25, x * 0.25, 0.0) * 4.0,
+            Node::YClampedGradient {
+                from_y,
+                to_y,
+                from_value,
+                to_value,
+            } => {
+                let progress = ((y - from_y) / (to_y - from_y)).clamp(0.0, 1.0);
+                from_value + (to_value - from_value) * progress
+            }
+            Node::Linear {
+                operation,
+                input,
+                argument,
+            } => {
+                let input = self.evaluate(*input, y);
+                match operation {
+                    LinearOperation::Add => input + argument,
+                    LinearOperation::Mul => input * argument,
+                }
+            }
+            Node::Binary {
+                operation,
+                first,
+                second,
+            } => {
+                let first = self.evaluate(*first, y);
+                match operation {
+                    BinaryOperation::Add => first + self.evaluate(*second, y),
+                    // Vanilla skips the second function when the product is zero anyway.
+                    BinaryOperation::Mul if first == 0.0 => 0.0,
+                    BinaryOperation::Mul => first * self.evaluate(*second, y),
+                    BinaryOperation::Min => first.min(self.evaluate(*second, y)),
+                    BinaryOperation::Max => first.max(self.evaluate(*second, y)),
+                }
+            }
+            Node::Unary { operation, input } => operation.apply(self.evaluate(*input, y)),
+            Node::Clamp { input, min, max } => self.evaluate(*input, y).clamp(*min, *max),
+            Node::RangeChoice {
+                input,
+                min_inclusive,
+                max_exclusive,
+                in_range,
+                out_of_range,
+            } => {
+                let input = self.evaluate(*input, y);
+                if (*min_inclusive..*max_exclusive).contains(&input) {
+                    self.evaluate(*in_range, y)
+                } else {
+                    self.evaluate(*out_of_range, y)
+                }
+            }
+            Node::Spline(spline) => self.evaluate_spline(spline, y),
+            Node::WeirdScaled {
+                input,
+                noise,
+                rarity,
+            } => {
+                let scale = rarity.scale(self.evaluate(*input, y));
+                scale
+                    * router.noises[*noise]
+                        .get(x / scale, y / scale, z / scale)
+                        .abs()
+            }
+            Node::BlendedNoise(noise) => router.blended_noises[*noise].get(x, y, z),
+            Node::EndIslands => router.end_islands.get(x, z),
+            Node::ColumnCached(input) => match self.column_cache[index] {
+                Some(value) => value,
+                None => {
+                    let value = self.evaluate(*input, 0.0);
+                    self.column_cache[index] = Some(value);
+                    value
+                }
+            },
+            Node::PointCached(input) => match self.point_cache[index] {
+                Some((cached_y, value)) if cached_y == y => value,
+                _ => {
+                    let value = self.evaluate(*input, y);
+                    self.point_cache[index] = Some((y, value));
+                    value
+                }
+            },
+        }
+    }
+
+    fn evaluate_spline(&mut self, spline: &Spline, y: f64) -> f64 {
+        let (location, points) = match spline {
+            Spline::Fixed(value) => return *value,
+            Spline::Points { location, points } => (self.evaluate(*location, y), points),
+        };
+        // Past either end, the spline carries on in a straight line.
+        let next = points.partition_point(|point| point.location <= location);
+        let extend = |value: f64, point: &SplinePoint| {
+            value + point.derivative * (location - point.location)
+        };
+        if next == 0 {
+            let value = self.evaluate_spline(&points[0].value, y);
+            return extend(value, &points[0]);
+        }
+        if next == points.len() {
+            let value = self.evaluate_spline(&points[next - 1].value, y);
+            return extend(value, &points[next - 1]);
+        }
+
+        // Between two points, it's a cubic curve through both with the slopes given at each.
+        let (start, end) = (&points[next - 1], &points[next]);
+        let width = end.location - start.location;
+        let progress = (location - start.location) / width;
+        let start_value = self.evaluate_spline(&start.value, y);
+        let end_value = self.evaluate_spline(&end.value, y);
+        let start_slope = start.derivative * width - (end_value - start_value);
+        let end_slope = -end.derivative * width + (end_value - start_value);
+        let lerp = |from: f64, to: f64| from + (to - from) * progress;
+        lerp(start_value, end_value) + progress * (1.0 - progress) * lerp(start_slope, end_slope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_FUNCTIONS_FILE: &str =
+        include_str!("../../../../assets/extracted/density_function_tests.json");
+
+    fn router_from_json(json: &str, seed: u64) -> NoiseRouter {
+        let functions: HashMap<String, RawFunction> = serde_json::from_str(json).unwrap();
+        NoiseRouter::new(Arc::new(DensityFunctions::compile(&functions)), seed)
+    }
+
+    #[test]
+    fn test_test_vectors() {
+        let values: HashMap<String, serde_json::Value> =
+            serde_json::from_str(TEST_FUNCTIONS_FILE).unwrap();
+        let router = router_from_json(TEST_FUNCTIONS_FILE, 0);
+        for (name, value) in values {
+            let function = router.function(&name);
+            // Vanilla works out the range of values each function can take, and writes it out for
+            // the functions that combine others.
+            let bounds = &value["value"];
+            let min = bounds["minValue"].as_f64().unwrap_or(f64::MIN);
+            let max = bounds["maxValue"].as_f64().unwrap_or(f64::MAX);
+            for (x, z) in [(0, 0), (-1000, 372), (5000, -20_000), (123_456, 654_321)] {
+                let mut sampler = ColumnSampler::new(&router, x, z);
+                for y in (-64..320).step_by(16) {
+                    let density = sampler.sample(function, y);
+                    assert!(
+                        density.is_finite() && (min..=max).contains(&density),
+                        "{name} is {density} at ({x}, {y}, {z}), outside {min}..={max}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_spline() {
+        let router = router_from_json(
+            r#"{"spline": {"_class": "Spline", "value": {"spline": {"_type": "standard", "value": {
+                "locationFunction": {"_class": "YClampedGradient",
+                    "value": {"fromY": 0, "toY": 100, "fromValue": 0.0, "toValue": 1.0}},
+                "locations": [0.2, 0.6],
+                "values": [{"_type": "fixed", "value": {"value": 1.0}},
+                    {"_type": "fixed", "value": {"value": 3.0}}],
+                "derivatives": [0.0, 5.0]
+            }}}}}"#,
+            0,
+        );
+        let spline = router.function("spline");
+        let mut sampler = ColumnSampler::new(&router, 0, 0);
+        // Flat before the first point, passing through both points and following the last
+        // point's slope past it.
+        assert_eq!(sampler.sample(spline, 0), 1.0);
+        assert_eq!(sampler.sample(spline, 20), 1.0);
+        assert!((sampler.sample(spline, 60) - 3.0).abs() < 1e-9);
+        assert!((sampler.sample(spline, 80) - 4.0).abs() < 1e-9);
+        let middle = sampler.sample(spline, 40);
+        assert!(middle > 1.0 && middle < 3.0);
+    }
+
+    #[test]
+    fn test_identical_functions_are_shared() {
+        let functions = &NOISE_ROUTERS["overworld"];
+        assert!(
+            functions.nodes.len() < 500,
+            "{} nodes",
+            functions.nodes.len()
+        );
+    }
+
+    #[test]
+    fn test_overworld_is_solid_below_and_empty_above() {
+        let router = NoiseRouter::from_settings("overworld", 0);
+        let final_density = router.function("finalDensity");
+        for (x, z) in [(0, 0), (800, -1600), (-5000, 3000)] {
+            let mut sampler = ColumnSampler::new(&router, x, z);
+            assert!(sampler.sample(final_density, -60) > 0.0);
+            assert!(sampler.sample(final_density, 310) < 0.0);
+        }
+    }
+
+    #[test]
+    fn test_every_router_compiles() {
+        for name in NOISE_ROUTERS.keys() {
+            let router = NoiseRouter::from_settings(name, 0);
+            let mut sampler = ColumnSampler::new(&router, 100, 100);
+            assert!(
+                sampler
+                    .sample(router.function("finalDensity"), 64)
+                    .is_finite()
+            );
+        }
+    }
+}
@@ -7,6 +7,8 @@ pub enum WorldGenError {
     BiomeGenerationError(String),
     #[error("Failed to generate chunk: {0}")]
     ChunkGenerationError(String),
+    #[error("Invalid world generator preset: {0}")]
+    InvalidPreset(String),
     #[error("World error: {0}")]
     WorldError(#[from] WorldError),
 }
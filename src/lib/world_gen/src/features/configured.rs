@@ -0,0 +1,884 @@
+//! Configured features, which say what a feature places once its placement modifiers have picked
+//! where.
+//!
+//! Supported are ores, trees with straight trunks, single blocks, patches of plants and the
+//! features that pick between other features. Trees with fancy trunks get straight ones, and
+//! their foliage is placed like a blob. Anything else, like trees with wider trunks, is skipped.
+
+use crate::features::placement::{
+    BlockPredicate, IntProvider, RawPlacementModifier, Weighted, pick_weighted,
+};
+use crate::features::{
+    BlockPos, FeatureRandom, PlacedFeature, PlacementContext, RawBlockState, RawConfiguredFeature,
+    RawConfiguredFeatureRef, RawFeatures, RawPlacedFeature, RawPlacedFeatureRef, place_block,
+    states_of, strip_namespace, tag_states,
+};
+use crate::overworld::ShapedChunk;
+use ferrumc_world::block_state_id::BlockStateId;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::f32::consts::PI;
+use std::sync::LazyLock;
+
+/// Where trees can put their logs and leaves.
+static TREE_REPLACEABLE: LazyLock<BlockPredicate> = LazyLock::new(|| {
+    let mut blocks = states_of(["air", "cave_air", "void_air"]);
+    blocks.extend(tag_states("replaceable_by_trees").unwrap_or_default());
+    BlockPredicate::Matching {
+        offset: [0, 0, 0],
+        blocks,
+    }
+});
+
+/// Where a tree turns the block under its trunk into dirt: anywhere that isn't already dirt, which
+/// includes grass.
+static NOT_PLAIN_DIRT: LazyLock<BlockPredicate> = LazyLock::new(|| {
+    let mut dirt = tag_states("dirt").unwrap_or_default();
+    for grassy in states_of(["grass_block", "mycelium"]) {
+        dirt.remove(&grassy);
+    }
+    BlockPredicate::Not(Box::new(BlockPredicate::Matching {
+        offset: [0, 0, 0],
+        blocks: dirt,
+    }))
+});
+
+/// Reads a configured feature's config, or returns `None` if it doesn't fit.
+fn config<T: DeserializeOwned>(feature: &RawConfiguredFeature) -> Option<T> {
+    serde_json::from_value(feature.config.clone()).ok()
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RawStateProvider {
+    #[serde(
+        rename = "minecraft:simple_state_provider",
+        alias = "minecraft:rotated_block_provider"
+    )]
+    Simple { state: RawBlockState },
+    #[serde(rename = "minecraft:weighted_state_provider")]
+    Weighted {
+        entries: Vec<Weighted<RawBlockState>>,
+    },
+    /// Picks between its states with noise in vanilla, here at random.
+    #[serde(
+        rename = "minecraft:noise_provider",
+        alias = "minecraft:dual_noise_provider"
+    )]
+    Noise { states: Vec<RawBlockState> },
+    /// Picks between its states with noise in vanilla, here at random.
+    #[serde(rename = "minecraft:noise_threshold_provider")]
+    NoiseThreshold {
+        default_state: RawBlockState,
+        low_states: Vec<RawBlockState>,
+        high_states: Vec<RawBlockState>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+impl RawStateProvider {
+    fn compile(&self) -> Option<StateProvider> {
+        let evenly = |states: Vec<&RawBlockState>| {
+            states
+                .into_iter()
+                .map(|state| {
+                    Some(Weighted {
+                        data: state.to_block()?,
+                        weight: 1,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let states = match self {
+            RawStateProvider::Simple { state } => evenly(vec![state])?,
+            RawStateProvider::Weighted { entries } => entries
+                .iter()
+                .map(|entry| {
+                    Some(Weighted {
+                        data: entry.data.to_block()?,
+                        weight: entry.weight,
+                    })
+                })
+                .collect::<Option<_>>()?,
+            RawStateProvider::Noise { states } => evenly(states.iter().collect())?,
+            RawStateProvider::NoiseThreshold {
+                default_state,
+                low_states,
+                high_states,
+            } => evenly(
+                [default_state]
+                    .into_iter()
+                    .chain(low_states)
+                    .chain(high_states)
+                    .collect(),
+            )?,
+            RawStateProvider::Unsupported => return None,
+        };
+        (!states.is_empty()).then_some(StateProvider(states))
+    }
+}
+
+/// Picks the block states a feature places.
+struct StateProvider(Vec<Weighted<BlockStateId>>);
+
+impl StateProvider {
+    fn get(&self, random: &mut FeatureRandom) -> BlockStateId {
+        pick_weighted(&self.0, random).map_or_else(BlockStateId::default, |block| *block)
+    }
+}
+
+/// What to place on, compared to the block that's there.
+#[derive(Deserialize)]
+#[serde(tag = "predicate_type")]
+enum RawRuleTest {
+    #[serde(rename = "minecraft:tag_match")]
+    TagMatch { tag: String },
+    #[serde(rename = "minecraft:block_match")]
+    BlockMatch { block: String },
+    #[serde(rename = "minecraft:blockstate_match")]
+    BlockStateMatch { block_state: RawBlockState },
+    #[serde(rename = "minecraft:always_true")]
+    AlwaysTrue,
+    #[serde(other)]
+    Unsupported,
+}
+
+impl RawRuleTest {
+    fn compile(&self) -> Option<BlockPredicate> {
+        let matching = |blocks| BlockPredicate::Matching {
+            offset: [0, 0, 0],
+            blocks,
+        };
+        Some(match self {
+            RawRuleTest::TagMatch { tag } => matching(tag_states(tag)?),
+            RawRuleTest::BlockMatch { block } => matching(states_of([block.as_str()])),
+            RawRuleTest::BlockStateMatch { block_state } => {
+                matching(HashSet::from([block_state.to_block()?]))
+            }
+            RawRuleTest::AlwaysTrue => BlockPredicate::True,
+            RawRuleTest::Unsupported => return None,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawOreTarget {
+    target: RawRuleTest,
+    state: RawBlockState,
+}
+
+#[derive(Deserialize)]
+struct RawOreConfig {
+    targets: Vec<RawOreTarget>,
+    size: i32,
+    #[serde(default)]
+    discard_chance_on_air_exposure: f32,
+}
+
+/// A block an ore replaces, and what it's replaced with.
+struct OreTarget {
+    state: BlockStateId,
+    target: BlockPredicate,
+    /// The same as `target`, but only away from air.
+    target_away_from_air: BlockPredicate,
+}
+
+/// A vein of ore, or a blob of dirt, gravel or another kind of stone.
+pub(crate) struct OreFeature {
+    targets: Vec<OreTarget>,
+    size: i32,
+    discard_chance_on_air_exposure: f32,
+    /// Whether the blocks are scattered around instead of in a vein, like ancient debris.
+    scattered: bool,
+}
+
+impl OreFeature {
+    fn compile(config: RawOreConfig, scattered: bool) -> Option<Self> {
+        let air = states_of(["air", "cave_air", "void_air"]);
+        let targets = config
+            .targets
+            .iter()
+            .map(|target| {
+                let predicate = target.target.compile()?;
+                let away_from_air = [
+                    [1, 0, 0],
+                    [-1, 0, 0],
+                    [0, 1, 0],
+                    [0, -1, 0],
+                    [0, 0, 1],
+                    [0, 0, -1],
+                ]
+                .map(|offset| {
+                    BlockPredicate::Not(Box::new(BlockPredicate::Matching {
+                        offset,
+                        blocks: air.clone(),
+                    }))
+                });
+                Some(OreTarget {
+                    state: target.state.to_block()?,
+                    target_away_from_air: BlockPredicate::AllOf(
+                        [predicate.clone()]
+                            .into_iter()
+                            .chain(away_from_air)
+                            .collect(),
+                    ),
+                    target: predicate,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Self {
+            targets,
+            size: config.size,
+            discard_chance_on_air_exposure: config.discard_chance_on_air_exposure,
+            scattered,
+        })
+    }
+
+    /// Places ore at a position, replacing the first of the targets that's there.
+    fn place_block(
+        &self,
+        random: &mut FeatureRandom,
+        position: BlockPos,
+        target: &mut ShapedChunk,
+    ) {
+        // Vanilla only rolls this for blocks the ore can replace. Rolling it for every block keeps
+        // the random the same whatever is already there.
+        let chance = self.discard_chance_on_air_exposure;
+        let skip_air_check = chance <= 0.0 || (chance < 1.0 && random.next_float() >= chance);
+        for ore in &self.targets {
+            let condition = if skip_air_check {
+                &ore.target
+            } else {
+                &ore.target_away_from_air
+            };
+            place_block(target, position, ore.state, condition);
+        }
+    }
+
+    fn place(&self, random: &mut FeatureRandom, origin: BlockPos, target: &mut ShapedChunk) {
+        if self.scattered {
+            self.place_scattered(random, origin, target);
+        } else {
+            self.place_vein(random, origin, target);
+        }
+    }
+
+    fn place_scattered(
+        &self,
+        random: &mut FeatureRandom,
+        origin: BlockPos,
+        target: &mut ShapedChunk,
+    ) {
+        let count = random.next_int(self.size + 1);
+        for placed in 0..count {
+            let spread = placed.min(7) as f32;
+            let mut offset =
+                || ((random.next_float() - random.next_float()) * spread).round() as i32;
+            let position = [
+                origin[0] + offset(),
+                origin[1] + offset(),
+                origin[2] + offset(),
+            ];
+            self.place_block(random, position, target);
+        }
+    }
+
+    /// Vanilla's ore veins: a line through the origin, with a sphere of random size around each of
+    /// `size` points along it.
+    fn place_vein(&self, random: &mut FeatureRandom, origin: BlockPos, target: &mut ShapedChunk) {
+        let [x, y, z] = origin.map(f64::from);
+        let angle = random.next_float() * PI;
+        let length = self.size as f32 / 8.0;
+        let (sin, cos) = (
+            f64::from(angle.sin() * length),
+            f64::from(angle.cos() * length),
+        );
+        let (start_x, end_x) = (x + sin, x - sin);
+        let (start_z, end_z) = (z + cos, z - cos);
+        let start_y = y + f64::from(random.next_int(3) - 2);
+        let end_y = y + f64::from(random.next_int(3) - 2);
+
+        let size = self.size.max(0) as usize;
+        let lerp = |t: f64, from: f64, to: f64| from + t * (to - from);
+        let mut spheres: Vec<[f64; 4]> = (0..size)
+            .map(|point| {
+                let t = point as f32 / size as f32;
+                let scale = random.next_double() * size as f64 / 16.0;
+                let radius = ((f64::from((PI * t).sin()) + 1.0) * scale + 1.0) / 2.0;
+                let t = f64::from(t);
+                [
+                    lerp(t, start_x, end_x),
+                    lerp(t, start_y, end_y),
+                    lerp(t, start_z, end_z),
+                    radius,
+                ]
+            })
+            .collect();
+        // Spheres inside bigger ones don't add anything.
+        for first in 0..size.saturating_sub(1) {
+            for second in first + 1..size {
+                let ([x1, y1, z1, r1], [x2, y2, z2, r2]) = (spheres[first], spheres[second]);
+                if r1 <= 0.0 || r2 <= 0.0 {
+                    continue;
+                }
+                let radius = r1 - r2;
+                let (dx, dy, dz) = (x1 - x2, y1 - y2, z1 - z2);
+                if radius * radius > dx * dx + dy * dy + dz * dz {
+                    if radius > 0.0 {
+                        spheres[second][3] = -1.0;
+                    } else {
+                        spheres[first][3] = -1.0;
+                    }
+                }
+            }
+        }
+
+        let mut placed = HashSet::new();
+        for [center_x, center_y, center_z, radius] in spheres {
+            if radius < 0.0 {
+                continue;
+            }
+            let range =
+                |center: f64| (center - radius).floor() as i32..=(center + radius).floor() as i32;
+            let offset = |block: i32, center: f64| (f64::from(block) + 0.5 - center) / radius;
+            for block_x in range(center_x) {
+                let dx = offset(block_x, center_x);
+                for block_y in range(center_y) {
+                    let dy = offset(block_y, center_y);
+                    for block_z in range(center_z) {
+                        let dz = offset(block_z, center_z);
+                        if dx * dx + dy * dy + dz * dz < 1.0
+                            && placed.insert([block_x, block_y, block_z])
+                        {
+                            self.place_block(random, [block_x, block_y, block_z], target);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawTrunkPlacer {
+    #[serde(rename = "type")]
+    kind: String,
+    base_height: i32,
+    height_rand_a: i32,
+    height_rand_b: i32,
+}
+
+#[derive(Deserialize)]
+struct RawFoliagePlacer {
+    #[serde(rename = "type")]
+    kind: String,
+    radius: IntProvider,
+    offset: IntProvider,
+    height: Option<IntProvider>,
+    trunk_height: Option<IntProvider>,
+}
+
+#[derive(Deserialize)]
+struct RawTreeConfig {
+    trunk_provider: RawStateProvider,
+    foliage_provider: RawStateProvider,
+    dirt_provider: RawStateProvider,
+    trunk_placer: RawTrunkPlacer,
+    foliage_placer: RawFoliagePlacer,
+    #[serde(default)]
+    force_dirt: bool,
+}
+
+/// The shape of a tree's leaves.
+enum FoliageShape {
+    /// Layers that get wider going down, like oaks and birches.
+    Blob { height: IntProvider },
+    /// Layers that get wider and narrower again, like spruces.
+    Spruce { trunk_height: IntProvider },
+    /// A cone, like pines.
+    Pine { height: IntProvider },
+}
+
+struct FoliagePlacer {
+    shape: FoliageShape,
+    radius: IntProvider,
+    offset: IntProvider,
+}
+
+impl FoliagePlacer {
+    fn compile(raw: &RawFoliagePlacer) -> Option<Self> {
+        let supported = |provider: &Option<IntProvider>| {
+            provider
+                .as_ref()
+                .filter(|provider| provider.is_supported())
+                .cloned()
+        };
+        let shape = match raw.kind.as_str() {
+            "minecraft:blob_foliage_placer" | "minecraft:fancy_foliage_placer" => {
+                FoliageShape::Blob {
+                    height: supported(&raw.height)?,
+                }
+            }
+            "minecraft:spruce_foliage_placer" => FoliageShape::Spruce {
+                trunk_height: supported(&raw.trunk_height)?,
+            },
+            "minecraft:pine_foliage_placer" => FoliageShape::Pine {
+                height: supported(&raw.height)?,
+            },
+            _ => return None,
+        };
+        if !raw.radius.is_supported() || !raw.offset.is_supported() {
+            return None;
+        }
+        Some(Self {
+            shape,
+            radius: raw.radius.clone(),
+            offset: raw.offset.clone(),
+        })
+    }
+
+    /// How many layers of leaves a tree with a trunk `tree_height` tall gets.
+    fn foliage_height(&self, random: &mut FeatureRandom, tree_height: i32) -> i32 {
+        match &self.shape {
+            FoliageShape::Blob { height } | FoliageShape::Pine { height } => height.sample(random),
+            FoliageShape::Spruce { trunk_height } => {
+                4.max(tree_height - trunk_height.sample(random))
+            }
+        }
+    }
+
+    /// Where the leaves go around the top of a trunk, in layers from `offset` above it down to
+    /// `foliage_height` below that.
+    fn leaves(
+        &self,
+        random: &mut FeatureRandom,
+        top: BlockPos,
+        foliage_height: i32,
+        radius: i32,
+        offset: i32,
+    ) -> Vec<BlockPos> {
+        let mut leaves = Vec::new();
+        let mut row = |random: &mut FeatureRandom, layer_radius: i32, y: i32| {
+            for dx in -layer_radius..=layer_radius {
+                for dz in -layer_radius..=layer_radius {
+                    let corner = dx.abs() == layer_radius && dz.abs() == layer_radius;
+                    let skip = match self.shape {
+                        FoliageShape::Blob { .. } => corner && (random.next_int(2) == 0 || y == 0),
+                        FoliageShape::Spruce { .. } | FoliageShape::Pine { .. } => {
+                            corner && layer_radius > 0
+                        }
+                    };
+                    if !skip {
+                        leaves.push([top[0] + dx, top[1] + y, top[2] + dz]);
+                    }
+                }
+            }
+        };
+        match self.shape {
+            FoliageShape::Blob { .. } => {
+                for y in (offset - foliage_height..=offset).rev() {
+                    row(random, (radius - 1 - y / 2).max(0), y);
+                }
+            }
+            FoliageShape::Spruce { .. } => {
+                let (mut layer_radius, mut max_radius, mut min_radius) = (random.next_int(2), 1, 0);
+                for y in (-foliage_height..=offset).rev() {
+                    row(random, layer_radius, y);
+                    if layer_radius >= max_radius {
+                        layer_radius = min_radius;
+                        min_radius = 1;
+                        max_radius = (max_radius + 1).min(radius);
+                    } else {
+                        layer_radius += 1;
+                    }
+                }
+            }
+            FoliageShape::Pine { .. } => {
+                let mut layer_radius = 0;
+                for y in (offset - foliage_height..=offset).rev() {
+                    row(random, layer_radius, y);
+                    if layer_radius >= 1 && y == offset - foliage_height + 1 {
+                        layer_radius -= 1;
+                    } else if layer_radius < radius {
+                        layer_radius += 1;
+                    }
+                }
+            }
+        }
+        leaves
+    }
+}
+
+/// A tree with a straight trunk one block wide.
+pub(crate) struct TreeFeature {
+    trunk: StateProvider,
+    foliage: StateProvider,
+    dirt: StateProvider,
+    force_dirt: bool,
+    base_height: i32,
+    height_rand_a: i32,
+    height_rand_b: i32,
+    foliage_placer: FoliagePlacer,
+}
+
+impl TreeFeature {
+    fn compile(config: RawTreeConfig) -> Option<Self> {
+        let trunk = &config.trunk_placer;
+        if !matches!(
+            trunk.kind.as_str(),
+            "minecraft:straight_trunk_placer" | "minecraft:fancy_trunk_placer"
+        ) {
+            return None;
+        }
+        Some(Self {
+            trunk: config.trunk_provider.compile()?,
+            foliage: config.foliage_provider.compile()?,
+            dirt: config.dirt_provider.compile()?,
+            force_dirt: config.force_dirt,
+            base_height: trunk.base_height,
+            height_rand_a: trunk.height_rand_a,
+            height_rand_b: trunk.height_rand_b,
+            foliage_placer: FoliagePlacer::compile(&config.foliage_placer)?,
+        })
+    }
+
+    fn place(
+        &self,
+        context: &PlacementContext,
+        random: &mut FeatureRandom,
+        origin: BlockPos,
+        target: &mut ShapedChunk,
+    ) {
+        let height = self.base_height
+            + random.next_int(self.height_rand_a + 1)
+            + random.next_int(self.height_rand_b + 1);
+        let foliage_height = self.foliage_placer.foliage_height(random, height);
+        let radius = self.foliage_placer.radius.sample(random);
+
+        // The trunk needs room to grow, which is checked in the chunk the tree is in, before any
+        // features were placed, so it comes out the same for every chunk its leaves reach.
+        let chunk = context.chunk;
+        let [x, y, z] = origin;
+        if y <= chunk.min_y || y + height + 1 > chunk.min_y + chunk.height {
+            return;
+        }
+        if !(0..height).all(|dy| TREE_REPLACEABLE.test(chunk, [x, y + dy, z])) {
+            return;
+        }
+
+        let dirt = self.dirt.get(random);
+        let dirt_condition = if self.force_dirt {
+            &BlockPredicate::True
+        } else {
+            &*NOT_PLAIN_DIRT
+        };
+        place_block(target, [x, y - 1, z], dirt, dirt_condition);
+        for dy in 0..height {
+            let log = self.trunk.get(random);
+            place_block(target, [x, y + dy, z], log, &TREE_REPLACEABLE);
+        }
+        let offset = self.foliage_placer.offset.sample(random);
+        let top = [x, y + height, z];
+        for position in self
+            .foliage_placer
+            .leaves(random, top, foliage_height, radius, offset)
+        {
+            let leaves = self.foliage.get(random);
+            place_block(target, position, leaves, &TREE_REPLACEABLE);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSimpleBlockConfig {
+    to_place: RawStateProvider,
+}
+
+/// One of the blocks a [SimpleBlockFeature] may place.
+struct PlantPlacement {
+    block: BlockStateId,
+    condition: BlockPredicate,
+    /// The top half of two block tall plants, and when to place it.
+    upper: Option<(BlockStateId, BlockPredicate)>,
+}
+
+/// A single block, usually a plant, placed if it would survive there.
+pub(crate) struct SimpleBlockFeature {
+    blocks: Vec<Weighted<PlantPlacement>>,
+}
+
+impl SimpleBlockFeature {
+    /// Compiles the feature, which also has to pass `filters` where it's placed.
+    fn compile(config: RawSimpleBlockConfig, filters: Vec<BlockPredicate>) -> Option<Self> {
+        let air = states_of(["air"]);
+        let blocks = config
+            .to_place
+            .compile()?
+            .0
+            .into_iter()
+            .map(|entry| {
+                let block = entry.data;
+                let mut conditions = filters.clone();
+                conditions.push(BlockPredicate::survival(block)?);
+                let upper = block
+                    .to_state_string()
+                    .filter(|state| state.contains("half=lower"))
+                    .and_then(|state| {
+                        BlockStateId::from_state_string(&state.replace("half=lower", "half=upper"))
+                    });
+                let upper = upper.map(|upper| {
+                    conditions.push(BlockPredicate::Matching {
+                        offset: [0, 1, 0],
+                        blocks: air.clone(),
+                    });
+                    let condition = BlockPredicate::AllOf(vec![
+                        BlockPredicate::Matching {
+                            offset: [0, -1, 0],
+                            blocks: HashSet::from([block]),
+                        },
+                        BlockPredicate::Matching {
+                            offset: [0, 0, 0],
+                            blocks: air.clone(),
+                        },
+                    ]);
+                    (upper, condition)
+                });
+                Some(Weighted {
+                    data: PlantPlacement {
+                        block,
+                        condition: BlockPredicate::AllOf(conditions),
+                        upper,
+                    },
+                    weight: entry.weight,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Self { blocks })
+    }
+
+    fn place(&self, random: &mut FeatureRandom, position: BlockPos, target: &mut ShapedChunk) {
+        let Some(plant) = pick_weighted(&self.blocks, random) else {
+            return;
+        };
+        place_block(target, position, plant.block, &plant.condition);
+        if let Some((upper, condition)) = &plant.upper {
+            let [x, y, z] = position;
+            place_block(target, [x, y + 1, z], *upper, condition);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRandomPatchConfig {
+    #[serde(default = "default_tries")]
+    tries: i32,
+    #[serde(default = "default_xz_spread")]
+    xz_spread: i32,
+    #[serde(default = "default_y_spread")]
+    y_spread: i32,
+    feature: RawPlacedFeatureRef,
+}
+
+fn default_tries() -> i32 {
+    128
+}
+
+fn default_xz_spread() -> i32 {
+    7
+}
+
+fn default_y_spread() -> i32 {
+    3
+}
+
+#[derive(Deserialize)]
+struct RawSelectorEntry {
+    chance: f32,
+    feature: RawPlacedFeatureRef,
+}
+
+#[derive(Deserialize)]
+struct RawRandomSelectorConfig {
+    features: Vec<RawSelectorEntry>,
+    default: RawPlacedFeatureRef,
+}
+
+#[derive(Deserialize)]
+struct RawSimpleRandomSelectorConfig {
+    features: Vec<RawPlacedFeatureRef>,
+}
+
+#[derive(Deserialize)]
+struct RawRandomBooleanSelectorConfig {
+    feature_true: RawPlacedFeatureRef,
+    feature_false: RawPlacedFeatureRef,
+}
+
+/// What a feature places. Features nested in selectors are `None` where they aren't supported,
+/// and place nothing when they're picked.
+pub(crate) enum ConfiguredFeature {
+    Ore(OreFeature),
+    Tree(Box<TreeFeature>),
+    SimpleBlock(SimpleBlockFeature),
+    /// Tries placing a block at random around the position a number of times.
+    RandomPatch {
+        tries: i32,
+        xz_spread: i32,
+        y_spread: i32,
+        block: SimpleBlockFeature,
+    },
+    /// Places the first feature whose chance comes up, or the default.
+    RandomSelector {
+        features: Vec<(f32, Option<PlacedFeature>)>,
+        default: Option<Box<PlacedFeature>>,
+    },
+    SimpleRandomSelector(Vec<Option<PlacedFeature>>),
+    RandomBooleanSelector(Option<Box<PlacedFeature>>, Option<Box<PlacedFeature>>),
+}
+
+impl ConfiguredFeature {
+    /// Compiles a configured feature, or returns `None` if it isn't supported.
+    pub(super) fn compile(raw: &RawFeatures, feature: &RawConfiguredFeature) -> Option<Self> {
+        Some(match feature.kind.as_str() {
+            "minecraft:ore" => {
+                ConfiguredFeature::Ore(OreFeature::compile(config(feature)?, false)?)
+            }
+            "minecraft:scattered_ore" => {
+                ConfiguredFeature::Ore(OreFeature::compile(config(feature)?, true)?)
+            }
+            "minecraft:tree" => {
+                ConfiguredFeature::Tree(Box::new(TreeFeature::compile(config(feature)?)?))
+            }
+            "minecraft:simple_block" => ConfiguredFeature::SimpleBlock(
+                SimpleBlockFeature::compile(config(feature)?, Vec::new())?,
+            ),
+            "minecraft:random_patch" | "minecraft:flower" | "minecraft:no_bonemeal_flower" => {
+                let config: RawRandomPatchConfig = config(feature)?;
+                ConfiguredFeature::RandomPatch {
+                    tries: config.tries,
+                    xz_spread: config.xz_spread,
+                    y_spread: config.y_spread,
+                    block: Self::patch_block(raw, &config.feature)?,
+                }
+            }
+            "minecraft:random_selector" => {
+                let config: RawRandomSelectorConfig = config(feature)?;
+                let features: Vec<_> = config
+                    .features
+                    .iter()
+                    .map(|entry| (entry.chance, raw.placed(&entry.feature)))
+                    .collect();
+                let default = raw.placed(&config.default).map(Box::new);
+                if default.is_none() && features.iter().all(|(_, feature)| feature.is_none()) {
+                    return None;
+                }
+                ConfiguredFeature::RandomSelector { features, default }
+            }
+            "minecraft:simple_random_selector" => {
+                let config: RawSimpleRandomSelectorConfig = config(feature)?;
+                let features: Vec<_> = config
+                    .features
+                    .iter()
+                    .map(|feature| raw.placed(feature))
+                    .collect();
+                if features.iter().all(Option::is_none) {
+                    return None;
+                }
+                ConfiguredFeature::SimpleRandomSelector(features)
+            }
+            "minecraft:random_boolean_selector" => {
+                let config: RawRandomBooleanSelectorConfig = config(feature)?;
+                let if_true = raw.placed(&config.feature_true).map(Box::new);
+                let if_false = raw.placed(&config.feature_false).map(Box::new);
+                if if_true.is_none() && if_false.is_none() {
+                    return None;
+                }
+                ConfiguredFeature::RandomBooleanSelector(if_true, if_false)
+            }
+            _ => return None,
+        })
+    }
+
+    /// The block a patch places on each try. Its placement can only filter, since it's checked
+    /// where each block lands in the chunk being generated, see [crate::features].
+    fn patch_block(raw: &RawFeatures, placed: &RawPlacedFeatureRef) -> Option<SimpleBlockFeature> {
+        let placed: &RawPlacedFeature = match placed {
+            RawPlacedFeatureRef::Named(name) => raw.placed.get(strip_namespace(name))?,
+            RawPlacedFeatureRef::Inline(placed) => placed,
+        };
+        let feature = match &placed.feature {
+            RawConfiguredFeatureRef::Named(name) => raw.configured.get(strip_namespace(name))?,
+            RawConfiguredFeatureRef::Inline(feature) => feature,
+        };
+        if feature.kind != "minecraft:simple_block" {
+            return None;
+        }
+        let filters = placed
+            .placement
+            .iter()
+            .map(|modifier| match modifier {
+                RawPlacementModifier::BlockPredicateFilter { predicate } => predicate.compile(),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        SimpleBlockFeature::compile(config(feature)?, filters)
+    }
+
+    pub(super) fn place(
+        &self,
+        context: &PlacementContext,
+        random: &mut FeatureRandom,
+        position: BlockPos,
+        target: &mut ShapedChunk,
+    ) {
+        match self {
+            ConfiguredFeature::Ore(ore) => ore.place(random, position, target),
+            ConfiguredFeature::Tree(tree) => tree.place(context, random, position, target),
+            ConfiguredFeature::SimpleBlock(block) => block.place(random, position, target),
+            ConfiguredFeature::RandomPatch {
+                tries,
+                xz_spread,
+                y_spread,
+                block,
+            } => {
+                let [x, y, z] = position;
+                let spread = |random: &mut FeatureRandom, spread: i32| {
+                    random.next_int(spread + 1) - random.next_int(spread + 1)
+                };
+                for _ in 0..*tries {
+                    let dx = spread(random, *xz_spread);
+                    let dy = spread(random, *y_spread);
+                    let dz = spread(random, *xz_spread);
+                    block.place(random, [x + dx, y + dy, z + dz], target);
+                }
+            }
+            ConfiguredFeature::RandomSelector { features, default } => {
+                let picked = features
+                    .iter()
+                    .find(|(chance, _)| random.next_float() < *chance)
+                    .map_or(default.as_deref(), |(_, feature)| feature.as_ref());
+                if let Some(feature) = picked {
+                    feature.place(context, random, position, target);
+                }
+            }
+            ConfiguredFeature::SimpleRandomSelector(features) => {
+                let picked = random.next_int(features.len() as i32) as usize;
+                if let Some(Some(feature)) = features.get(picked) {
+                    feature.place(context, random, position, target);
+                }
+            }
+            ConfiguredFeature::RandomBooleanSelector(if_true, if_false) => {
+                let picked = if random.next_int(2) == 0 {
+                    if_true
+                } else {
+                    if_false
+                };
+                if let Some(feature) = picked {
+                    feature.place(context, random, position, target);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,586 @@
+//! Features: the ores, trees, plants and everything else placed on top of the terrain, configured
+//! by the extracted `configured_features.json` and `placed_feature.json`.
+//!
+//! Each biome lists the placed features it has in each generation step in `biome.json`. A placed
+//! feature is a configured feature, which says what to place, with placement modifiers that pick
+//! where in a chunk it goes and how many times.
+//!
+//! Features can reach past the chunk they start in, like a tree at the edge of one, so placing a
+//! chunk's features also runs the features of the eight chunks around it and keeps whatever lands
+//! in the chunk being generated. For that to line up across chunk edges, a feature has to come out
+//! the same whichever of its neighbours is being generated:
+//!
+//! - Each feature gets its own random, seeded from the world seed, its chunk and which feature it
+//!   is, and always uses it the same way.
+//! - Where a feature goes is decided from its own chunk as it was before any features were placed,
+//!   see [ShapedChunk]. Positions outside that chunk can't be seen while deciding.
+//! - The blocks a feature places are checked against the chunk being generated as it is at that
+//!   point, so a flower won't replace the trunk of a tree from the chunk next door. Those checks
+//!   only ever decide about the block they're checking.
+//!
+//! Chunks that haven't been edited aren't saved, so doing it this way also means there's nothing
+//! to keep around for chunks that haven't been generated yet.
+//!
+//! Only the common kinds of features are supported, see [configured]. Placed features that use
+//! anything else are skipped, as are ones nested in the supported ones.
+
+mod configured;
+mod placement;
+
+use crate::features::configured::ConfiguredFeature;
+use crate::features::placement::{BlockPredicate, PlacementModifier, RawPlacementModifier};
+pub(crate) use crate::features::placement::{HeightProvider, OneOrMany, VerticalAnchor};
+use crate::overworld::ShapedChunk;
+use ferrumc_world::block_state_id::{BlockStateId, ID2BLOCK};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+
+const PLACED_FEATURES_FILE: &str =
+    include_str!("../../../../../assets/extracted/placed_feature.json");
+const CONFIGURED_FEATURES_FILE: &str =
+    include_str!("../../../../../assets/extracted/configured_features.json");
+const BIOMES_FILE: &str = include_str!("../../../../../assets/extracted/biome.json");
+const TAGS_FILE: &str = include_str!("../../../../../assets/extracted/tags.json");
+
+/// Every placed feature any biome has, compiled.
+static FEATURES: LazyLock<Features> = LazyLock::new(Features::load);
+
+/// The blocks in each block tag, keyed by the tag's name, e.g. `minecraft:dirt`. Tags that include
+/// other tags are already flattened in `tags.json`.
+static BLOCK_TAGS: LazyLock<HashMap<String, Vec<String>>> = LazyLock::new(|| {
+    #[derive(Deserialize)]
+    struct Tags {
+        block: HashMap<String, Vec<String>>,
+    }
+    serde_json::from_str::<Tags>(TAGS_FILE)
+        .expect("tags.json should be valid")
+        .block
+});
+
+/// Every state of each block, keyed by the block's name, e.g. `minecraft:water`.
+static STATES_BY_NAME: LazyLock<HashMap<&'static str, Vec<BlockStateId>>> = LazyLock::new(|| {
+    let mut states: HashMap<&'static str, Vec<BlockStateId>> = HashMap::new();
+    for (id, block) in ID2BLOCK.iter().enumerate() {
+        states
+            .entry(block.name.as_str())
+            .or_default()
+            .push(BlockStateId(id as u32));
+    }
+    states
+});
+
+/// A position in the world, as `[x, y, z]`.
+pub(crate) type BlockPos = [i32; 3];
+
+/// Every state of the named blocks. Names may leave out the namespace, and unknown ones are
+/// ignored.
+pub(crate) fn states_of<'a>(names: impl IntoIterator<Item = &'a str>) -> HashSet<BlockStateId> {
+    names
+        .into_iter()
+        .flat_map(|name| {
+            let name = if name.contains(':') {
+                name.to_string()
+            } else {
+                format!("minecraft:{name}")
+            };
+            STATES_BY_NAME
+                .get(name.as_str())
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Every state of the blocks in a block tag, or `None` if there's no such tag.
+pub(crate) fn tag_states(tag: &str) -> Option<HashSet<BlockStateId>> {
+    let tag = tag.strip_prefix('#').unwrap_or(tag);
+    let tag = if tag.contains(':') {
+        tag.to_string()
+    } else {
+        format!("minecraft:{tag}")
+    };
+    BLOCK_TAGS
+        .get(&tag)
+        .map(|names| states_of(names.iter().map(String::as_str)))
+}
+
+/// The name of a block state's block, e.g. `minecraft:oak_log`.
+fn block_name(block: BlockStateId) -> &'static str {
+    ID2BLOCK
+        .get(block.0 as usize)
+        .map_or("minecraft:air", |data| data.name.as_str())
+}
+
+/// A block state the way the extracted data writes them.
+#[derive(Deserialize)]
+struct RawBlockState {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Properties", default)]
+    properties: BTreeMap<String, String>,
+}
+
+impl RawBlockState {
+    fn to_block(&self) -> Option<BlockStateId> {
+        let properties = self
+            .properties
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>();
+        if properties.is_empty() {
+            BlockStateId::from_state_string(&self.name)
+        } else {
+            BlockStateId::from_state_string(&format!("{}[{}]", self.name, properties.join(",")))
+        }
+    }
+}
+
+/// A reference to a placed feature, either by name or written out in full.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawPlacedFeatureRef {
+    Named(String),
+    Inline(Box<RawPlacedFeature>),
+}
+
+#[derive(Deserialize)]
+struct RawPlacedFeature {
+    feature: RawConfiguredFeatureRef,
+    placement: Vec<RawPlacementModifier>,
+}
+
+/// A reference to a configured feature, either by name or written out in full.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawConfiguredFeatureRef {
+    Named(String),
+    Inline(Box<RawConfiguredFeature>),
+}
+
+/// A configured feature, with its config left as JSON until its type says how to read it.
+#[derive(Deserialize)]
+struct RawConfiguredFeature {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    config: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct RawBiome {
+    features: Vec<Vec<String>>,
+}
+
+/// The raw features, which placed features are compiled from.
+struct RawFeatures {
+    placed: HashMap<String, RawPlacedFeature>,
+    configured: HashMap<String, RawConfiguredFeature>,
+}
+
+impl RawFeatures {
+    fn placed(&self, placed: &RawPlacedFeatureRef) -> Option<PlacedFeature> {
+        match placed {
+            RawPlacedFeatureRef::Named(name) => {
+                self.compile_placed(self.placed.get(strip_namespace(name))?)
+            }
+            RawPlacedFeatureRef::Inline(placed) => self.compile_placed(placed),
+        }
+    }
+
+    fn compile_placed(&self, placed: &RawPlacedFeature) -> Option<PlacedFeature> {
+        let feature = match &placed.feature {
+            RawConfiguredFeatureRef::Named(name) => self.configured.get(strip_namespace(name))?,
+            RawConfiguredFeatureRef::Inline(feature) => feature,
+        };
+        Some(PlacedFeature {
+            feature: ConfiguredFeature::compile(self, feature)?,
+            placement: placed
+                .placement
+                .iter()
+                .map(RawPlacementModifier::compile)
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+fn strip_namespace(name: &str) -> &str {
+    name.strip_prefix("minecraft:").unwrap_or(name)
+}
+
+/// A configured feature and where to place it.
+pub(crate) struct PlacedFeature {
+    feature: ConfiguredFeature,
+    placement: Vec<PlacementModifier>,
+}
+
+impl PlacedFeature {
+    /// Places the feature at every position the placement modifiers turn `origin` into.
+    fn place(
+        &self,
+        context: &PlacementContext,
+        random: &mut FeatureRandom,
+        origin: BlockPos,
+        target: &mut ShapedChunk,
+    ) {
+        let mut positions = vec![origin];
+        for modifier in &self.placement {
+            positions = positions
+                .into_iter()
+                .flat_map(|position| modifier.positions(context, random, position))
+                .collect();
+        }
+        for position in positions {
+            self.feature.place(context, random, position, target);
+        }
+    }
+}
+
+/// What placement modifiers and features decide where they go from.
+pub(crate) struct PlacementContext<'a> {
+    /// The chunk the feature starts in, before any features were placed.
+    chunk: &'a ShapedChunk,
+    /// The placed feature listed in the biome, which may be placing nested ones.
+    feature: usize,
+}
+
+impl PlacementContext<'_> {
+    /// Whether the biome at a position has the placed feature being placed.
+    fn biome_has_feature(&self, x: i32, z: i32) -> bool {
+        self.chunk
+            .biome(x, z)
+            .and_then(|biome| FEATURES.biome_features.get(biome.name))
+            .is_some_and(|features| features.contains(&self.feature))
+    }
+}
+
+/// The placed features biomes have, in the order they're placed within each step.
+struct Features {
+    /// Each placed feature any biome has, or `None` if it uses something unsupported.
+    placed: Vec<Option<PlacedFeature>>,
+    /// Which step each placed feature is placed in.
+    steps: Vec<usize>,
+    /// The placed features each biome has, keyed by the biome's name without the namespace.
+    biome_features: HashMap<String, BTreeSet<usize>>,
+}
+
+impl Features {
+    fn load() -> Self {
+        let raw = RawFeatures {
+            placed: serde_json::from_str(PLACED_FEATURES_FILE)
+                .expect("placed_feature.json should be valid"),
+            configured: serde_json::from_str(CONFIGURED_FEATURES_FILE)
+                .expect("configured_features.json should be valid"),
+        };
+        let biomes: BTreeMap<String, RawBiome> =
+            serde_json::from_str(BIOMES_FILE).expect("biome.json should be valid");
+
+        // Placed features are numbered step by step, in the order the biomes first list them,
+        // which is also the order they're placed in.
+        let step_count = biomes.values().map(|biome| biome.features.len()).max();
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let mut names = Vec::new();
+        let mut steps = Vec::new();
+        for step in 0..step_count.unwrap_or(0) {
+            for biome in biomes.values() {
+                for name in biome.features.get(step).into_iter().flatten() {
+                    if !ids.contains_key(name.as_str()) {
+                        ids.insert(name, names.len());
+                        names.push(name);
+                        steps.push(step);
+                    }
+                }
+            }
+        }
+
+        let biome_features = biomes
+            .iter()
+            .map(|(name, biome)| {
+                let features = biome
+                    .features
+                    .iter()
+                    .flatten()
+                    .map(|name| ids[name.as_str()]);
+                (name.clone(), features.collect())
+            })
+            .collect();
+        let placed = names
+            .iter()
+            .map(|name| raw.placed(&RawPlacedFeatureRef::Named(name.to_string())))
+            .collect();
+        Self {
+            placed,
+            steps,
+            biome_features,
+        }
+    }
+
+    /// The placed features a chunk has, in the order they're placed.
+    fn chunk_features(&self, chunk: &ShapedChunk) -> BTreeSet<(usize, usize)> {
+        chunk
+            .distinct_biomes()
+            .iter()
+            .filter_map(|biome| self.biome_features.get(biome.name))
+            .flatten()
+            .map(|&feature| (self.steps[feature], feature))
+            .collect()
+    }
+}
+
+/// Places features into overworld chunks, see [crate::features].
+pub(crate) struct FeaturePlacer {
+    seed: u64,
+}
+
+impl FeaturePlacer {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Places the features of a chunk and the chunks around it that land in the chunk.
+    ///
+    /// `sources` are the chunk and the chunks around it before any features were placed. `target`
+    /// is the chunk being generated, which starts out the same as its own source.
+    pub(crate) fn place(&self, sources: &[Arc<ShapedChunk>], target: &mut ShapedChunk) {
+        // Features place blocks by their position in the world.
+        if !target.is_positioned() {
+            return;
+        }
+
+        let mut features: Vec<_> = sources
+            .iter()
+            .flat_map(|source| {
+                FEATURES
+                    .chunk_features(source)
+                    .into_iter()
+                    .map(move |(step, feature)| (step, source.x, source.z, feature, source))
+            })
+            .collect();
+        features.sort_by_key(|&(step, x, z, feature, _)| (step, x, z, feature));
+
+        for (_, _, _, feature, source) in features {
+            let Some(placed) = &FEATURES.placed[feature] else {
+                continue;
+            };
+            let context = PlacementContext {
+                chunk: source,
+                feature,
+            };
+            let mut random = FeatureRandom::new(self.feature_seed(source.x, source.z, feature));
+            let origin = [source.x * 16, source.min_y, source.z * 16];
+            placed.place(&context, &mut random, origin, target);
+        }
+    }
+
+    /// The seed of the random a feature placed from a chunk uses.
+    fn feature_seed(&self, x: i32, z: i32, feature: usize) -> u64 {
+        [i64::from(x), i64::from(z), feature as i64]
+            .into_iter()
+            .fold(self.seed, |seed, value| mix(seed ^ value as u64))
+    }
+}
+
+/// Places a block in the chunk being generated, if it's in that chunk and `condition` holds there.
+fn place_block(
+    target: &mut ShapedChunk,
+    position: BlockPos,
+    block: BlockStateId,
+    condition: &BlockPredicate,
+) {
+    let [x, y, z] = position;
+    if target.block(x, y, z).is_some() && condition.test(target, position) {
+        target.set_block(x, y, z, block);
+    }
+}
+
+/// SplitMix64's finalizer, which scrambles every bit of a value into every other.
+pub(crate) fn mix(value: u64) -> u64 {
+    let mut value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// A xoroshiro128++ random, like the one vanilla places features and carvers with.
+pub(crate) struct FeatureRandom {
+    low: u64,
+    high: u64,
+}
+
+impl FeatureRandom {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            low: mix(seed),
+            high: mix(seed ^ 0x6a09_e667_f3bc_c909),
+        }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let (low, mut high) = (self.low, self.high);
+        let result = low.wrapping_add(high).rotate_left(17).wrapping_add(low);
+        high ^= low;
+        self.low = low.rotate_left(49) ^ high ^ (high << 21);
+        self.high = high.rotate_left(28);
+        result
+    }
+
+    /// A number from 0 up to but not including `bound`, or 0 if `bound` isn't positive.
+    pub(crate) fn next_int(&mut self, bound: i32) -> i32 {
+        if bound <= 0 {
+            return 0;
+        }
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as i32
+    }
+
+    /// A number from `min` to `max`, both included, or `min` if `max` is smaller.
+    pub(crate) fn next_int_between(&mut self, min: i32, max: i32) -> i32 {
+        min + self.next_int(max - min + 1)
+    }
+
+    /// A number from 0 up to but not including 1.
+    pub(crate) fn next_float(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A number from 0 up to but not including 1.
+    pub(crate) fn next_double(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overworld::OverworldGenerator;
+
+    /// Shapes a chunk and the chunks around it, and places the chunk's features.
+    fn decorate(generator: &OverworldGenerator, x: i32, z: i32) -> (ShapedChunk, ShapedChunk) {
+        let sources: Vec<_> = (-1..=1)
+            .flat_map(|dz| (-1..=1).map(move |dx| (dx, dz)))
+            .map(|(dx, dz)| generator.cached_shape_chunk(x + dx, z + dz))
+            .collect();
+        let mut target = ShapedChunk::clone(&sources[4]);
+        FeaturePlacer::new(0).place(&sources, &mut target);
+        (ShapedChunk::clone(&sources[4]), target)
+    }
+
+    fn count(chunk: &ShapedChunk, blocks: &HashSet<BlockStateId>) -> usize {
+        let (min_x, min_z) = (chunk.x * 16, chunk.z * 16);
+        (chunk.min_y..chunk.min_y + chunk.height)
+            .flat_map(|y| (0..256).map(move |column| (y, column)))
+            .filter(|&(y, column)| {
+                let block = chunk.block(min_x + (column & 15), y, min_z + (column >> 4));
+                block.is_some_and(|block| blocks.contains(&block))
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_features_compile() {
+        let supported = FEATURES.placed.iter().flatten().count();
+        assert!(supported > FEATURES.placed.len() / 2);
+        for name in [
+            "ore_iron_upper",
+            "ore_coal_lower",
+            "ore_diamond",
+            "trees_plains",
+            "trees_birch",
+            "trees_taiga",
+            "patch_grass_plain",
+            "flower_default",
+        ] {
+            let raw = RawFeatures {
+                placed: serde_json::from_str(PLACED_FEATURES_FILE).unwrap(),
+                configured: serde_json::from_str(CONFIGURED_FEATURES_FILE).unwrap(),
+            };
+            let feature = RawPlacedFeatureRef::Named(format!("minecraft:{name}"));
+            assert!(raw.placed(&feature).is_some(), "{name} should be supported");
+        }
+    }
+
+    #[test]
+    fn test_random_is_deterministic() {
+        let mut first = FeatureRandom::new(42);
+        let mut second = FeatureRandom::new(42);
+        for _ in 0..100 {
+            assert_eq!(first.next_int(1000), second.next_int(1000));
+        }
+        let mut random = FeatureRandom::new(7);
+        for _ in 0..1000 {
+            assert!((0..5).contains(&random.next_int(5)));
+            assert!((-3..=3).contains(&random.next_int_between(-3, 3)));
+            assert!((0.0..1.0).contains(&random.next_float()));
+        }
+    }
+
+    #[test]
+    fn test_places_ores_and_plants() {
+        let generator = OverworldGenerator::new(0);
+        let ores = states_of(["coal_ore", "iron_ore", "deepslate_iron_ore", "copper_ore"]);
+        let plants = states_of(["short_grass", "tall_grass", "dandelion", "poppy", "fern"]);
+        let logs = tag_states("minecraft:logs").unwrap();
+        let (mut found_ores, mut found_plants, mut found_logs) = (0, 0, 0);
+        for x in 0..6 {
+            let (_, chunk) = decorate(&generator, x * 8, 0);
+            found_ores += count(&chunk, &ores);
+            found_plants += count(&chunk, &plants);
+            found_logs += count(&chunk, &logs);
+        }
+        assert!(found_ores > 0);
+        assert!(found_plants > 0);
+        assert!(found_logs > 0);
+    }
+
+    #[test]
+    fn test_trees_spill_into_neighbouring_chunks() {
+        // Leaves a chunk gains on its west edge belong to a log in it or in the chunk to its
+        // west, which is decorated on its own.
+        let generator = OverworldGenerator::new(0);
+        let logs = tag_states("minecraft:logs").unwrap();
+        let leaves = tag_states("minecraft:leaves").unwrap();
+        let has_log = |chunk: &ShapedChunk, [x, y, z]: BlockPos| {
+            (x - 4..=x + 4).any(|x| {
+                (y - 6..=y + 6).any(|y| {
+                    (z - 4..=z + 4).any(|z| {
+                        chunk
+                            .block(x, y, z)
+                            .is_some_and(|block| logs.contains(&block))
+                    })
+                })
+            })
+        };
+        let mut spilled = 0;
+        for x in 0..6 {
+            let (_, west) = decorate(&generator, x * 4, 5);
+            let (before, east) = decorate(&generator, x * 4 + 1, 5);
+            let edge = (x * 4 + 1) * 16;
+            for y in before.min_y..before.min_y + before.height {
+                for z in 80..96 {
+                    let block = east.block(edge, y, z).unwrap();
+                    if !leaves.contains(&block) || before.block(edge, y, z) == Some(block) {
+                        continue;
+                    }
+                    let from_west = has_log(&west, [edge, y, z]);
+                    assert!(from_west || has_log(&east, [edge, y, z]));
+                    if from_west && !has_log(&east, [edge, y, z]) {
+                        spilled += 1;
+                    }
+                }
+            }
+        }
+        assert!(spilled > 0);
+    }
+
+    #[test]
+    fn test_chunks_come_out_the_same() {
+        let generator = OverworldGenerator::new(0);
+        let (_, first) = decorate(&generator, 3, -2);
+        let (_, second) = decorate(&generator, 3, -2);
+        for y in first.min_y..first.min_y + first.height {
+            for column in 0..256 {
+                let (x, z) = (48 + (column & 15), -32 + (column >> 4));
+                assert_eq!(first.block(x, y, z), second.block(x, y, z));
+            }
+        }
+    }
+}
@@ -0,0 +1,858 @@
+//! Placement modifiers, which turn the corner of a chunk into the positions a feature is placed
+//! at, and the providers and block predicates they're configured with.
+
+use crate::features::{
+    BlockPos, FeatureRandom, PlacementContext, RawBlockState, states_of, tag_states,
+};
+use crate::overworld::ShapedChunk;
+use ferrumc_world::block_state_id::BlockStateId;
+use noise::{NoiseFn, OpenSimplex};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+/// The noise vanilla varies grass and flower counts with, which is seeded the same in every world.
+static BIOME_INFO_NOISE: LazyLock<OpenSimplex> = LazyLock::new(|| OpenSimplex::new(2345));
+
+/// Blocks that aren't solid, for [BlockPredicate::Solid].
+static NON_SOLID: LazyLock<HashSet<BlockStateId>> = LazyLock::new(|| {
+    let mut blocks = states_of(["air", "cave_air", "void_air"]);
+    for tag in ["replaceable", "replaceable_by_trees", "saplings", "flowers"] {
+        blocks.extend(tag_states(tag).unwrap_or_default());
+    }
+    blocks
+});
+
+/// Fluids, which the `*_FLOOR` heightmaps look through.
+static FLUIDS: LazyLock<HashSet<BlockStateId>> = LazyLock::new(|| states_of(["water", "lava"]));
+
+/// A whole number picked at random, or always the same one.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub(crate) enum IntProvider {
+    Constant(i32),
+    Typed(TypedIntProvider),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type")]
+pub(crate) enum TypedIntProvider {
+    #[serde(rename = "minecraft:constant")]
+    Constant { value: i32 },
+    #[serde(rename = "minecraft:uniform")]
+    Uniform {
+        min_inclusive: i32,
+        max_inclusive: i32,
+    },
+    #[serde(rename = "minecraft:biased_to_bottom")]
+    BiasedToBottom {
+        min_inclusive: i32,
+        max_inclusive: i32,
+    },
+    #[serde(rename = "minecraft:clamped")]
+    Clamped {
+        source: Box<IntProvider>,
+        min_inclusive: i32,
+        max_inclusive: i32,
+    },
+    #[serde(rename = "minecraft:weighted_list")]
+    WeightedList {
+        distribution: Vec<Weighted<IntProvider>>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+/// An entry of a weighted list, picked with a chance proportional to its weight.
+#[derive(Deserialize, Clone)]
+pub(crate) struct Weighted<T> {
+    pub(super) data: T,
+    pub(super) weight: i32,
+}
+
+/// Picks an entry of a weighted list, or `None` if it's empty.
+pub(super) fn pick_weighted<'a, T>(
+    entries: &'a [Weighted<T>],
+    random: &mut FeatureRandom,
+) -> Option<&'a T> {
+    let total = entries.iter().map(|entry| entry.weight).sum();
+    let mut picked = random.next_int(total);
+    for entry in entries {
+        picked -= entry.weight;
+        if picked < 0 {
+            return Some(&entry.data);
+        }
+    }
+    entries.last().map(|entry| &entry.data)
+}
+
+impl IntProvider {
+    pub(super) fn is_supported(&self) -> bool {
+        match self {
+            IntProvider::Constant(_) => true,
+            IntProvider::Typed(TypedIntProvider::Clamped { source, .. }) => source.is_supported(),
+            IntProvider::Typed(TypedIntProvider::WeightedList { distribution }) => {
+                distribution.iter().all(|entry| entry.data.is_supported())
+            }
+            IntProvider::Typed(TypedIntProvider::Unsupported) => false,
+            IntProvider::Typed(_) => true,
+        }
+    }
+
+    pub(super) fn sample(&self, random: &mut FeatureRandom) -> i32 {
+        let provider = match self {
+            IntProvider::Constant(value) => return *value,
+            IntProvider::Typed(provider) => provider,
+        };
+        match provider {
+            TypedIntProvider::Constant { value } => *value,
+            TypedIntProvider::Uniform {
+                min_inclusive,
+                max_inclusive,
+            } => random.next_int_between(*min_inclusive, *max_inclusive),
+            TypedIntProvider::BiasedToBottom {
+                min_inclusive,
+                max_inclusive,
+            } => {
+                let range = random.next_int(max_inclusive - min_inclusive + 1);
+                min_inclusive + random.next_int(range + 1)
+            }
+            TypedIntProvider::Clamped {
+                source,
+                min_inclusive,
+                max_inclusive,
+            } => source
+                .sample(random)
+                .clamp(*min_inclusive, (*max_inclusive).max(*min_inclusive)),
+            TypedIntProvider::WeightedList { distribution } => {
+                pick_weighted(distribution, random).map_or(0, |provider| provider.sample(random))
+            }
+            TypedIntProvider::Unsupported => 0,
+        }
+    }
+}
+
+/// A height relative to the top or bottom of the world.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum VerticalAnchor {
+    Absolute(i32),
+    AboveBottom(i32),
+    BelowTop(i32),
+}
+
+impl VerticalAnchor {
+    pub(crate) fn resolve(self, chunk: &ShapedChunk) -> i32 {
+        match self {
+            VerticalAnchor::Absolute(y) => y,
+            VerticalAnchor::AboveBottom(offset) => chunk.min_y + offset,
+            VerticalAnchor::BelowTop(offset) => chunk.min_y + chunk.height - 1 - offset,
+        }
+    }
+}
+
+/// A height picked at random between two anchors.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub(crate) enum HeightProvider {
+    Typed(TypedHeightProvider),
+    Constant(VerticalAnchor),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type")]
+pub(crate) enum TypedHeightProvider {
+    #[serde(rename = "minecraft:constant")]
+    Constant { value: VerticalAnchor },
+    #[serde(rename = "minecraft:uniform")]
+    Uniform {
+        min_inclusive: VerticalAnchor,
+        max_inclusive: VerticalAnchor,
+    },
+    #[serde(rename = "minecraft:trapezoid")]
+    Trapezoid {
+        min_inclusive: VerticalAnchor,
+        max_inclusive: VerticalAnchor,
+        #[serde(default)]
+        plateau: i32,
+    },
+    #[serde(rename = "minecraft:biased_to_bottom")]
+    BiasedToBottom {
+        min_inclusive: VerticalAnchor,
+        max_inclusive: VerticalAnchor,
+        #[serde(default = "one")]
+        inner: i32,
+    },
+    #[serde(rename = "minecraft:very_biased_to_bottom")]
+    VeryBiasedToBottom {
+        min_inclusive: VerticalAnchor,
+        max_inclusive: VerticalAnchor,
+        #[serde(default = "one")]
+        inner: i32,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+fn one() -> i32 {
+    1
+}
+
+/// Vanilla's `Mth.nextInt`, which allows an empty range.
+fn next_int_between_or_min(random: &mut FeatureRandom, min: i32, max: i32) -> i32 {
+    if min >= max {
+        min
+    } else {
+        random.next_int_between(min, max)
+    }
+}
+
+impl HeightProvider {
+    pub(crate) fn is_supported(&self) -> bool {
+        !matches!(
+            self,
+            HeightProvider::Typed(TypedHeightProvider::Unsupported)
+        )
+    }
+
+    pub(crate) fn sample(&self, random: &mut FeatureRandom, chunk: &ShapedChunk) -> i32 {
+        let provider = match self {
+            HeightProvider::Constant(anchor) => return anchor.resolve(chunk),
+            HeightProvider::Typed(provider) => provider,
+        };
+        match provider {
+            TypedHeightProvider::Constant { value } => value.resolve(chunk),
+            TypedHeightProvider::Uniform {
+                min_inclusive,
+                max_inclusive,
+            } => {
+                let (min, max) = (min_inclusive.resolve(chunk), max_inclusive.resolve(chunk));
+                next_int_between_or_min(random, min, max)
+            }
+            TypedHeightProvider::Trapezoid {
+                min_inclusive,
+                max_inclusive,
+                plateau,
+            } => {
+                let (min, max) = (min_inclusive.resolve(chunk), max_inclusive.resolve(chunk));
+                let range = max - min;
+                if range < 0 {
+                    return min;
+                }
+                if *plateau >= range {
+                    return next_int_between_or_min(random, min, max);
+                }
+                let slope = (range - plateau) / 2;
+                let rest = range - slope;
+                min + random.next_int_between(0, rest) + random.next_int_between(0, slope)
+            }
+            TypedHeightProvider::BiasedToBottom {
+                min_inclusive,
+                max_inclusive,
+                inner,
+            } => {
+                let (min, max) = (min_inclusive.resolve(chunk), max_inclusive.resolve(chunk));
+                if max - min - inner < 0 {
+                    return min;
+                }
+                let range = random.next_int(max - min - inner + 1);
+                min + random.next_int(range + inner)
+            }
+            TypedHeightProvider::VeryBiasedToBottom {
+                min_inclusive,
+                max_inclusive,
+                inner,
+            } => {
+                let (min, max) = (min_inclusive.resolve(chunk), max_inclusive.resolve(chunk));
+                if max - min - inner < 0 {
+                    return min;
+                }
+                let top = next_int_between_or_min(random, min + inner, max);
+                let middle = next_int_between_or_min(random, min, top - 1);
+                next_int_between_or_min(random, min, middle - 1 + inner)
+            }
+            TypedHeightProvider::Unsupported => chunk.min_y,
+        }
+    }
+}
+
+/// Which blocks count when finding the top of a column.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum Heightmap {
+    WorldSurfaceWg,
+    WorldSurface,
+    OceanFloorWg,
+    OceanFloor,
+    MotionBlocking,
+    MotionBlockingNoLeaves,
+}
+
+impl ShapedChunk {
+    /// The height just above the top block of a column that counts for `heightmap`, or `None` if
+    /// the column isn't in this chunk.
+    pub(super) fn surface_height(&self, heightmap: Heightmap, x: i32, z: i32) -> Option<i32> {
+        let counts = |block: BlockStateId| match heightmap {
+            Heightmap::OceanFloorWg | Heightmap::OceanFloor => {
+                block != BlockStateId::default() && !FLUIDS.contains(&block)
+            }
+            _ => block != BlockStateId::default(),
+        };
+        self.biome(x, z)?;
+        let top = (self.min_y..self.min_y + self.height)
+            .rev()
+            .find(|&y| self.block(x, y, z).is_some_and(counts));
+        Some(top.map_or(self.min_y, |y| y + 1))
+    }
+}
+
+/// One or more names, which the extracted data writes as a string when there's only one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub(crate) fn names(&self) -> Vec<&str> {
+        match self {
+            OneOrMany::One(name) => vec![name.as_str()],
+            OneOrMany::Many(names) => names.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub(super) enum RawBlockPredicate {
+    #[serde(rename = "minecraft:matching_blocks")]
+    MatchingBlocks {
+        blocks: OneOrMany,
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    #[serde(rename = "minecraft:matching_block_tag")]
+    MatchingBlockTag {
+        tag: String,
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    #[serde(rename = "minecraft:matching_fluids")]
+    MatchingFluids {
+        fluids: OneOrMany,
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    #[serde(rename = "minecraft:would_survive")]
+    WouldSurvive {
+        state: RawBlockState,
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    #[serde(rename = "minecraft:solid")]
+    Solid {
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    /// Whether the face of the block in some direction is sturdy, which is close enough to it
+    /// being solid.
+    #[serde(rename = "minecraft:has_sturdy_face")]
+    HasSturdyFace {
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    #[serde(rename = "minecraft:replaceable")]
+    Replaceable {
+        #[serde(default)]
+        offset: BlockPos,
+    },
+    #[serde(rename = "minecraft:all_of")]
+    AllOf { predicates: Vec<RawBlockPredicate> },
+    #[serde(rename = "minecraft:any_of")]
+    AnyOf { predicates: Vec<RawBlockPredicate> },
+    #[serde(rename = "minecraft:not")]
+    Not { predicate: Box<RawBlockPredicate> },
+    #[serde(rename = "minecraft:true", alias = "minecraft:inside_world_bounds")]
+    True,
+    #[serde(other)]
+    Unsupported,
+}
+
+impl RawBlockPredicate {
+    pub(super) fn compile(&self) -> Option<BlockPredicate> {
+        Some(match self {
+            RawBlockPredicate::MatchingBlocks { blocks, offset } => BlockPredicate::Matching {
+                offset: *offset,
+                blocks: states_of(blocks.names()),
+            },
+            RawBlockPredicate::MatchingBlockTag { tag, offset } => BlockPredicate::Matching {
+                offset: *offset,
+                blocks: tag_states(tag)?,
+            },
+            RawBlockPredicate::MatchingFluids { fluids, offset } => BlockPredicate::Matching {
+                offset: *offset,
+                blocks: states_of(fluids.names().into_iter().map(|fluid| {
+                    let fluid = fluid.strip_prefix("minecraft:").unwrap_or(fluid);
+                    fluid.strip_prefix("flowing_").unwrap_or(fluid)
+                })),
+            },
+            RawBlockPredicate::WouldSurvive { state, offset } => {
+                BlockPredicate::survival(state.to_block()?)?.offset(*offset)
+            }
+            RawBlockPredicate::Solid { offset } | RawBlockPredicate::HasSturdyFace { offset } => {
+                BlockPredicate::Solid { offset: *offset }
+            }
+            RawBlockPredicate::Replaceable { offset } => BlockPredicate::Matching {
+                offset: *offset,
+                blocks: tag_states("replaceable")?,
+            },
+            RawBlockPredicate::AllOf { predicates } => BlockPredicate::AllOf(
+                predicates
+                    .iter()
+                    .map(RawBlockPredicate::compile)
+                    .collect::<Option<_>>()?,
+            ),
+            RawBlockPredicate::AnyOf { predicates } => BlockPredicate::AnyOf(
+                predicates
+                    .iter()
+                    .map(RawBlockPredicate::compile)
+                    .collect::<Option<_>>()?,
+            ),
+            RawBlockPredicate::Not { predicate } => {
+                BlockPredicate::Not(Box::new(predicate.compile()?))
+            }
+            RawBlockPredicate::True => BlockPredicate::True,
+            RawBlockPredicate::Unsupported => return None,
+        })
+    }
+}
+
+/// Plants that only grow on dirt, grass and the like, besides saplings and flowers.
+const SOIL_PLANTS: [&str; 10] = [
+    "short_grass",
+    "fern",
+    "tall_grass",
+    "large_fern",
+    "bush",
+    "firefly_bush",
+    "sweet_berry_bush",
+    "leaf_litter",
+    "wildflowers",
+    "pink_petals",
+];
+
+/// Plants that grow on sand and terracotta as well as dirt.
+const DRY_PLANTS: [&str; 3] = ["dead_bush", "short_dry_grass", "tall_dry_grass"];
+
+/// Blocks that need something [BlockPredicate::survival] doesn't know how to check, like water
+/// next to them or darkness, so features placing them are skipped.
+const UNKNOWN_SURVIVAL: [&str; 17] = [
+    "lily_pad",
+    "cactus",
+    "sugar_cane",
+    "brown_mushroom",
+    "red_mushroom",
+    "small_dripleaf",
+    "big_dripleaf",
+    "spore_blossom",
+    "crimson_roots",
+    "warped_roots",
+    "nether_sprouts",
+    "fire",
+    "soul_fire",
+    "vine",
+    "glow_lichen",
+    "cocoa",
+    "bamboo",
+];
+
+/// A check on the blocks around a position.
+#[derive(Clone)]
+pub(crate) enum BlockPredicate {
+    True,
+    /// Whether the block at the offset is one of `blocks`.
+    Matching {
+        offset: BlockPos,
+        blocks: HashSet<BlockStateId>,
+    },
+    Solid {
+        offset: BlockPos,
+    },
+    AllOf(Vec<BlockPredicate>),
+    AnyOf(Vec<BlockPredicate>),
+    Not(Box<BlockPredicate>),
+}
+
+impl BlockPredicate {
+    /// Whether `block` would stay where it's placed, or `None` if that isn't known.
+    pub(super) fn survival(block: BlockStateId) -> Option<BlockPredicate> {
+        let name = super::block_name(block);
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        let in_tag = |tag| tag_states(tag).is_some_and(|blocks| blocks.contains(&block));
+        let on = |blocks| {
+            Some(BlockPredicate::Matching {
+                offset: [0, -1, 0],
+                blocks,
+            })
+        };
+        if SOIL_PLANTS.contains(&name)
+            || in_tag("saplings")
+            || in_tag("small_flowers")
+            || in_tag("c:flowers/tall")
+        {
+            let mut soil = tag_states("dirt")?;
+            soil.extend(states_of(["farmland"]));
+            on(soil)
+        } else if DRY_PLANTS.contains(&name) {
+            on(tag_states("dry_vegetation_may_place_on")?)
+        } else if name.ends_with("carpet") {
+            Some(BlockPredicate::Solid { offset: [0, -1, 0] })
+        } else if UNKNOWN_SURVIVAL.contains(&name) {
+            None
+        } else {
+            Some(BlockPredicate::True)
+        }
+    }
+
+    /// The same check, made that much further from the position.
+    fn offset(self, by: BlockPos) -> BlockPredicate {
+        let add = |offset: BlockPos| [offset[0] + by[0], offset[1] + by[1], offset[2] + by[2]];
+        match self {
+            BlockPredicate::True => BlockPredicate::True,
+            BlockPredicate::Matching { offset, blocks } => BlockPredicate::Matching {
+                offset: add(offset),
+                blocks,
+            },
+            BlockPredicate::Solid { offset } => BlockPredicate::Solid {
+                offset: add(offset),
+            },
+            BlockPredicate::AllOf(predicates) => {
+                BlockPredicate::AllOf(predicates.into_iter().map(|p| p.offset(by)).collect())
+            }
+            BlockPredicate::AnyOf(predicates) => {
+                BlockPredicate::AnyOf(predicates.into_iter().map(|p| p.offset(by)).collect())
+            }
+            BlockPredicate::Not(predicate) => BlockPredicate::Not(Box::new(predicate.offset(by))),
+        }
+    }
+
+    /// Whether the check holds at a position in a chunk. Blocks outside the chunk don't match
+    /// anything.
+    pub(crate) fn test(&self, chunk: &ShapedChunk, position: BlockPos) -> bool {
+        let block = |offset: &BlockPos| {
+            chunk.block(
+                position[0] + offset[0],
+                position[1] + offset[1],
+                position[2] + offset[2],
+            )
+        };
+        match self {
+            BlockPredicate::True => true,
+            BlockPredicate::Matching { offset, blocks } => {
+                block(offset).is_some_and(|block| blocks.contains(&block))
+            }
+            BlockPredicate::Solid { offset } => {
+                block(offset).is_some_and(|block| !NON_SOLID.contains(&block))
+            }
+            BlockPredicate::AllOf(predicates) => predicates
+                .iter()
+                .all(|predicate| predicate.test(chunk, position)),
+            BlockPredicate::AnyOf(predicates) => predicates
+                .iter()
+                .any(|predicate| predicate.test(chunk, position)),
+            BlockPredicate::Not(predicate) => !predicate.test(chunk, position),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub(super) enum RawPlacementModifier {
+    #[serde(rename = "minecraft:count")]
+    Count { count: IntProvider },
+    #[serde(rename = "minecraft:in_square")]
+    InSquare,
+    #[serde(rename = "minecraft:height_range")]
+    HeightRange { height: HeightProvider },
+    #[serde(rename = "minecraft:heightmap")]
+    Heightmap { heightmap: Heightmap },
+    #[serde(rename = "minecraft:rarity_filter")]
+    RarityFilter { chance: i32 },
+    #[serde(rename = "minecraft:biome")]
+    Biome,
+    #[serde(rename = "minecraft:block_predicate_filter")]
+    BlockPredicateFilter { predicate: RawBlockPredicate },
+    #[serde(rename = "minecraft:surface_water_depth_filter")]
+    SurfaceWaterDepthFilter { max_water_depth: i32 },
+    #[serde(rename = "minecraft:random_offset")]
+    RandomOffset {
+        xz_spread: IntProvider,
+        y_spread: IntProvider,
+    },
+    #[serde(rename = "minecraft:noise_threshold_count")]
+    NoiseThresholdCount {
+        noise_level: f64,
+        below_noise: i32,
+        above_noise: i32,
+    },
+    #[serde(rename = "minecraft:noise_based_count")]
+    NoiseBasedCount {
+        noise_to_count_ratio: i32,
+        noise_factor: f64,
+        #[serde(default)]
+        noise_offset: f64,
+    },
+    #[serde(rename = "minecraft:surface_relative_threshold_filter")]
+    SurfaceRelativeThresholdFilter {
+        heightmap: Heightmap,
+        min_inclusive: Option<i32>,
+        max_inclusive: Option<i32>,
+    },
+    #[serde(rename = "minecraft:environment_scan")]
+    EnvironmentScan {
+        direction_of_search: Direction,
+        max_steps: i32,
+        target_condition: RawBlockPredicate,
+        allowed_search_condition: Option<RawBlockPredicate>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+impl RawPlacementModifier {
+    /// Compiles the modifier, or returns `None` if it or anything in it isn't supported.
+    pub(super) fn compile(&self) -> Option<PlacementModifier> {
+        let supported = |provider: &IntProvider| provider.is_supported().then(|| provider.clone());
+        Some(match self {
+            RawPlacementModifier::Count { count } => PlacementModifier::Count(supported(count)?),
+            RawPlacementModifier::InSquare => PlacementModifier::InSquare,
+            RawPlacementModifier::HeightRange { height } => {
+                PlacementModifier::HeightRange(height.is_supported().then(|| height.clone())?)
+            }
+            RawPlacementModifier::Heightmap { heightmap } => {
+                PlacementModifier::Heightmap(*heightmap)
+            }
+            RawPlacementModifier::RarityFilter { chance } => {
+                PlacementModifier::RarityFilter(*chance)
+            }
+            RawPlacementModifier::Biome => PlacementModifier::Biome,
+            RawPlacementModifier::BlockPredicateFilter { predicate } => {
+                PlacementModifier::Filter(predicate.compile()?)
+            }
+            RawPlacementModifier::SurfaceWaterDepthFilter { max_water_depth } => {
+                PlacementModifier::SurfaceWaterDepthFilter(*max_water_depth)
+            }
+            RawPlacementModifier::RandomOffset {
+                xz_spread,
+                y_spread,
+            } => PlacementModifier::RandomOffset {
+                xz_spread: supported(xz_spread)?,
+                y_spread: supported(y_spread)?,
+            },
+            RawPlacementModifier::NoiseThresholdCount {
+                noise_level,
+                below_noise,
+                above_noise,
+            } => PlacementModifier::NoiseThresholdCount {
+                noise_level: *noise_level,
+                below_noise: *below_noise,
+                above_noise: *above_noise,
+            },
+            RawPlacementModifier::NoiseBasedCount {
+                noise_to_count_ratio,
+                noise_factor,
+                noise_offset,
+            } => PlacementModifier::NoiseBasedCount {
+                noise_to_count_ratio: *noise_to_count_ratio,
+                noise_factor: *noise_factor,
+                noise_offset: *noise_offset,
+            },
+            RawPlacementModifier::SurfaceRelativeThresholdFilter {
+                heightmap,
+                min_inclusive,
+                max_inclusive,
+            } => PlacementModifier::SurfaceRelativeThresholdFilter {
+                heightmap: *heightmap,
+                min_inclusive: min_inclusive.map_or(i64::MIN, i64::from),
+                max_inclusive: max_inclusive.map_or(i64::MAX, i64::from),
+            },
+            RawPlacementModifier::EnvironmentScan {
+                direction_of_search,
+                max_steps,
+                target_condition,
+                allowed_search_condition,
+            } => PlacementModifier::EnvironmentScan {
+                step: match direction_of_search {
+                    Direction::Up => 1,
+                    Direction::Down => -1,
+                },
+                max_steps: *max_steps,
+                target_condition: target_condition.compile()?,
+                allowed_search_condition: match allowed_search_condition {
+                    Some(condition) => condition.compile()?,
+                    None => BlockPredicate::True,
+                },
+            },
+            RawPlacementModifier::Unsupported => return None,
+        })
+    }
+}
+
+/// Turns a position into any number of positions, see [PlacementModifier::positions].
+pub(crate) enum PlacementModifier {
+    Count(IntProvider),
+    InSquare,
+    HeightRange(HeightProvider),
+    Heightmap(Heightmap),
+    RarityFilter(i32),
+    Biome,
+    Filter(BlockPredicate),
+    SurfaceWaterDepthFilter(i32),
+    RandomOffset {
+        xz_spread: IntProvider,
+        y_spread: IntProvider,
+    },
+    NoiseThresholdCount {
+        noise_level: f64,
+        below_noise: i32,
+        above_noise: i32,
+    },
+    NoiseBasedCount {
+        noise_to_count_ratio: i32,
+        noise_factor: f64,
+        noise_offset: f64,
+    },
+    SurfaceRelativeThresholdFilter {
+        heightmap: Heightmap,
+        min_inclusive: i64,
+        max_inclusive: i64,
+    },
+    EnvironmentScan {
+        /// 1 to search up, -1 to search down.
+        step: i32,
+        max_steps: i32,
+        target_condition: BlockPredicate,
+        allowed_search_condition: BlockPredicate,
+    },
+}
+
+impl PlacementModifier {
+    /// The positions a position turns into. Counts repeat it, filters keep it or drop it, and the
+    /// rest move it.
+    pub(super) fn positions(
+        &self,
+        context: &PlacementContext,
+        random: &mut FeatureRandom,
+        position: BlockPos,
+    ) -> Vec<BlockPos> {
+        let chunk = context.chunk;
+        let [x, y, z] = position;
+        let keep = |keep: bool| if keep { vec![position] } else { Vec::new() };
+        let repeat = |count: i32| vec![position; count.max(0) as usize];
+        match self {
+            PlacementModifier::Count(count) => repeat(count.sample(random)),
+            PlacementModifier::InSquare => {
+                let x = x + random.next_int(16);
+                vec![[x, y, z + random.next_int(16)]]
+            }
+            PlacementModifier::HeightRange(height) => vec![[x, height.sample(random, chunk), z]],
+            PlacementModifier::Heightmap(heightmap) => chunk
+                .surface_height(*heightmap, x, z)
+                .filter(|&height| height > chunk.min_y)
+                .map(|height| vec![[x, height, z]])
+                .unwrap_or_default(),
+            PlacementModifier::RarityFilter(chance) => {
+                keep(random.next_float() < 1.0 / *chance as f32)
+            }
+            PlacementModifier::Biome => keep(context.biome_has_feature(x, z)),
+            PlacementModifier::Filter(predicate) => keep(predicate.test(chunk, position)),
+            PlacementModifier::SurfaceWaterDepthFilter(max_water_depth) => {
+                let floor = chunk.surface_height(Heightmap::OceanFloor, x, z);
+                let surface = chunk.surface_height(Heightmap::WorldSurface, x, z);
+                keep(
+                    floor
+                        .zip(surface)
+                        .is_some_and(|(floor, surface)| surface - floor <= *max_water_depth),
+                )
+            }
+            PlacementModifier::RandomOffset {
+                xz_spread,
+                y_spread,
+            } => {
+                let x = x + xz_spread.sample(random);
+                let y = y + y_spread.sample(random);
+                vec![[x, y, z + xz_spread.sample(random)]]
+            }
+            PlacementModifier::NoiseThresholdCount {
+                noise_level,
+                below_noise,
+                above_noise,
+            } => {
+                let noise = BIOME_INFO_NOISE.get([f64::from(x) / 200.0, f64::from(z) / 200.0]);
+                repeat(if noise < *noise_level {
+                    *below_noise
+                } else {
+                    *above_noise
+                })
+            }
+            PlacementModifier::NoiseBasedCount {
+                noise_to_count_ratio,
+                noise_factor,
+                noise_offset,
+            } => {
+                let point = [f64::from(x) / noise_factor, f64::from(z) / noise_factor];
+                let noise = BIOME_INFO_NOISE.get(point);
+                repeat(((noise + noise_offset) * f64::from(*noise_to_count_ratio)).ceil() as i32)
+            }
+            PlacementModifier::SurfaceRelativeThresholdFilter {
+                heightmap,
+                min_inclusive,
+                max_inclusive,
+            } => keep(
+                chunk
+                    .surface_height(*heightmap, x, z)
+                    .is_some_and(|surface| {
+                        let (surface, y) = (i64::from(surface), i64::from(y));
+                        surface.saturating_add(*min_inclusive) <= y
+                            && y <= surface.saturating_add(*max_inclusive)
+                    }),
+            ),
+            PlacementModifier::EnvironmentScan {
+                step,
+                max_steps,
+                target_condition,
+                allowed_search_condition,
+            } => {
+                let mut position = position;
+                if !allowed_search_condition.test(chunk, position) {
+                    return Vec::new();
+                }
+                for _ in 0..*max_steps {
+                    if target_condition.test(chunk, position) {
+                        return vec![position];
+                    }
+                    position[1] += step;
+                    if !(chunk.min_y..chunk.min_y + chunk.height).contains(&position[1]) {
+                        return Vec::new();
+                    }
+                    if !allowed_search_condition.test(chunk, position) {
+                        break;
+                    }
+                }
+                if target_condition.test(chunk, position) {
+                    vec![position]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Superflat worlds: the same layers of blocks in every chunk, in one biome.
+//!
+//! Layers are written the way vanilla's superflat presets write them, from the bottom of the world
+//! up, e.g. `bedrock,2*dirt,grass_block`. A void world is a flat world without any layers.
+
+use crate::errors::WorldGenError;
+use ferrumc_data::generated::biomes::Biome;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::edit_batch::EditBatch;
+
+pub(crate) struct FlatGenerator {
+    /// The block of each layer, starting at the bottom of the world.
+    layers: Vec<BlockStateId>,
+    biome: &'static Biome,
+}
+
+impl FlatGenerator {
+    /// A flat world made of `layers`, in the biome called `biome`.
+    pub(crate) fn new(layers: &str, biome: &str) -> Result<Self, WorldGenError> {
+        let biome = Biome::from_name(biome)
+            .ok_or_else(|| WorldGenError::InvalidPreset(format!("unknown biome '{biome}'")))?;
+        let layers = parse_layers(layers, Dimension::Overworld.height() as usize)?;
+        Ok(Self { layers, biome })
+    }
+
+    /// An empty world, in the void biome.
+    pub(crate) fn void() -> Self {
+        Self {
+            layers: Vec::new(),
+            biome: &Biome::THE_VOID,
+        }
+    }
+
+    pub(crate) fn generate_chunk(&self, x: i32, z: i32) -> Result<Chunk, WorldGenError> {
+        let dimension = Dimension::Overworld;
+        let mut chunk = Chunk::new(x, z, dimension.as_str().to_string());
+        chunk.fill_biome(self.biome.id);
+
+        let air = BlockStateId::default();
+        let mut batch = EditBatch::new(&mut chunk);
+        let mut has_edits = false;
+        for (y, block) in (dimension.min_y()..).zip(&self.layers) {
+            if *block == air {
+                continue;
+            }
+            for block_x in 0..16 {
+                for block_z in 0..16 {
+                    batch.set_block(block_x, y, block_z, *block);
+                }
+            }
+            has_edits = true;
+        }
+        if has_edits {
+            batch.apply()?;
+        }
+
+        Ok(chunk)
+    }
+}
+
+/// Reads a list of layers like `bedrock,2*dirt,grass_block` into one block per layer, as long as
+/// they fit in a world `height` blocks high.
+fn parse_layers(layers: &str, height: usize) -> Result<Vec<BlockStateId>, WorldGenError> {
+    let mut blocks = Vec::new();
+    for layer in split_layers(layers) {
+        let layer = layer.trim();
+        if layer.is_empty() {
+            continue;
+        }
+        let (count, block) = match layer.split_once('*') {
+            Some((count, block)) => {
+                let count = count.trim().parse::<usize>().map_err(|_| {
+                    WorldGenError::InvalidPreset(format!("invalid layer count in '{layer}'"))
+                })?;
+                (count, block.trim())
+            }
+            None => (1, layer),
+        };
+        let block = BlockStateId::from_state_string(block)
+            .ok_or_else(|| WorldGenError::InvalidPreset(format!("unknown block '{block}'")))?;
+        // Checked before the blocks are added, so a huge count can't run out of memory.
+        if count > height - blocks.len() {
+            return Err(WorldGenError::InvalidPreset(format!(
+                "the layers don't fit in a world {height} blocks high"
+            )));
+        }
+        blocks.extend(std::iter::repeat_n(block, count));
+    }
+    Ok(blocks)
+}
+
+/// Splits a list of layers at its commas, except those between the brackets of a block's
+/// properties.
+fn split_layers(layers: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut depth) = (0, 0);
+    for (index, char) in layers.char_indices() {
+        match char {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&layers[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&layers[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_macros::block;
+
+    #[test]
+    fn test_layers_from_the_bottom_up() {
+        let generator = FlatGenerator::new("bedrock,2*dirt,grass_block", "plains").unwrap();
+        let chunk = generator.generate_chunk(3, -7).unwrap();
+        assert_eq!(chunk.get_block(0, -64, 0).unwrap(), block!("bedrock"));
+        assert_eq!(chunk.get_block(5, -63, 9).unwrap(), block!("dirt"));
+        assert_eq!(chunk.get_block(15, -62, 15).unwrap(), block!("dirt"));
+        assert_eq!(
+            chunk.get_block(8, -61, 8).unwrap(),
+            block!("grass_block", { snowy: false })
+        );
+        assert_eq!(chunk.get_block(8, -60, 8).unwrap(), BlockStateId::default());
+        assert_eq!(chunk.get_biome(0, 0, 0).unwrap(), Biome::PLAINS.id);
+    }
+
+    #[test]
+    fn test_layers_with_properties() {
+        let layers = parse_layers("stone, 3*furnace[facing=east,lit=true]", 384).unwrap();
+        assert_eq!(layers.len(), 4);
+        assert_eq!(layers[3], block!("furnace", {facing: "east", lit: true}));
+    }
+
+    #[test]
+    fn test_invalid_presets() {
+        assert!(FlatGenerator::new("bedrock,not_a_block", "plains").is_err());
+        assert!(FlatGenerator::new("x*dirt", "plains").is_err());
+        assert!(FlatGenerator::new("bedrock", "not_a_biome").is_err());
+        assert!(FlatGenerator::new("1000*stone", "plains").is_err());
+        assert!(FlatGenerator::new("stone,1000000000*stone", "plains").is_err());
+    }
+
+    #[test]
+    fn test_void_is_empty() {
+        let chunk = FlatGenerator::void().generate_chunk(0, 0).unwrap();
+        assert_eq!(chunk.get_block(0, -64, 0).unwrap(), BlockStateId::default());
+        assert_eq!(chunk.get_biome(0, 64, 0).unwrap(), Biome::THE_VOID.id);
+    }
+}
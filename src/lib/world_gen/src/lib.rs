@@ -1,15 +1,66 @@
+mod aquifer;
 mod biomes;
+mod carvers;
+mod climate;
+mod density_function;
 pub mod errors;
+mod features;
+mod flat;
+mod normal_noise;
+mod overworld;
 
 use crate::errors::WorldGenError;
+use crate::features::FeaturePlacer;
+use crate::flat::FlatGenerator;
+use crate::overworld::{OverworldGenerator, ShapedChunk};
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
 use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::metadata::{GeneratorPreset, GeneratorSettings};
 use noise::{Clamp, NoiseFn, OpenSimplex};
+use std::sync::{Arc, RwLock};
 
-/// Trait for generating a biome
+/// The generator registered with [register_chunk_generator], if any.
+static REGISTERED_GENERATOR: RwLock<Option<Arc<dyn ChunkGenerator>>> = RwLock::new(None);
+
+/// How many blocks below the surface [`OverworldBiome::surface_block`] is asked about.
+pub(crate) const SURFACE_DEPTH: i32 = 8;
+
+/// Trait for anything that makes the terrain of new chunks
+///
+/// [WorldGenerator] is the built-in one. Other crates can implement this to supply their own
+/// terrain, and hand it to the server with [register_chunk_generator].
+pub trait ChunkGenerator: Send + Sync {
+    /// Generates the chunk at the given chunk coordinates in the given dimension.
+    ///
+    /// The same coordinates should always give the same chunk, since chunks that are never edited
+    /// aren't saved and get generated again every time they're loaded.
+    fn generate_chunk(&self, x: i32, z: i32, dimension: Dimension) -> Result<Chunk, WorldGenError>;
+}
+
+/// Makes the server generate new chunks with `generator` instead of the built-in [WorldGenerator].
 ///
-/// Should be implemented for each biome's generator
+/// This has to be called before the server starts, e.g. from a `#[ctor]` function the way
+/// commands register themselves. If it's called more than once, the last generator wins.
+pub fn register_chunk_generator(generator: Arc<dyn ChunkGenerator>) {
+    *REGISTERED_GENERATOR
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(generator);
+}
+
+/// The generator given to [register_chunk_generator], if there was one.
+pub fn registered_chunk_generator() -> Option<Arc<dyn ChunkGenerator>> {
+    REGISTERED_GENERATOR
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Trait for generating a dimension with a single biome, a whole chunk at a time
 pub(crate) trait BiomeGenerator {
-    fn _biome_id(&self) -> u8;
+    /// The network id of the biome, see [`ferrumc_data::generated::biomes::Biome`].
+    fn biome_id(&self) -> u16;
     fn _biome_name(&self) -> String;
     fn generate_chunk(
         &self,
@@ -19,6 +70,24 @@ pub(crate) trait BiomeGenerator {
     ) -> Result<Chunk, WorldGenError>;
 }
 
+/// Trait for the surface of an overworld biome
+///
+/// The shape of the terrain comes from the density functions, and each biome then covers its own
+/// columns with its surface blocks. See [`overworld`] for how they're put together.
+pub(crate) trait OverworldBiome {
+    /// The block `depth` blocks below the top of a column whose top block is at `height`, or
+    /// `None` for stone.
+    fn surface_block(&self, depth: i32, height: i32) -> Option<BlockStateId>;
+    /// What's placed on top of the surface where it isn't underwater, like snow.
+    fn decoration(&self, _height: i32) -> Option<BlockStateId> {
+        None
+    }
+    /// The top block of water above the biome, which freezes in cold biomes.
+    fn water_surface(&self) -> BlockStateId {
+        block!("water", {level: 0})
+    }
+}
+
 pub(crate) struct NoiseGenerator {
     pub(crate) layers: Vec<Clamp<f64, OpenSimplex, 2>>,
 }
@@ -26,6 +95,17 @@ pub(crate) struct NoiseGenerator {
 pub struct WorldGenerator {
     _seed: u64,
     noise_generator: NoiseGenerator,
+    overworld: OverworldTerrain,
+}
+
+/// What makes the overworld's terrain, which is the only dimension the presets change, like in
+/// vanilla.
+enum OverworldTerrain {
+    Noise {
+        shape: Box<OverworldGenerator>,
+        features: FeaturePlacer,
+    },
+    Flat(FlatGenerator),
 }
 
 impl NoiseGenerator {
@@ -50,20 +130,162 @@ impl NoiseGenerator {
 }
 
 impl WorldGenerator {
+    /// A generator for vanilla-like terrain.
     pub fn new(seed: u64) -> Self {
         Self {
             _seed: seed,
             noise_generator: NoiseGenerator::new(seed),
+            overworld: OverworldTerrain::Noise {
+                shape: Box::new(OverworldGenerator::new(seed)),
+                features: FeaturePlacer::new(seed),
+            },
+        }
+    }
+
+    /// A generator for a world with the given settings, see
+    /// [World::load_metadata](ferrumc_world::World::load_metadata).
+    pub fn from_settings(settings: &GeneratorSettings) -> Result<Self, WorldGenError> {
+        let overworld = match &settings.preset {
+            GeneratorPreset::Noise => return Ok(Self::new(settings.seed)),
+            GeneratorPreset::Flat { layers, biome } => FlatGenerator::new(layers, biome)?,
+            GeneratorPreset::Void => FlatGenerator::void(),
+        };
+        Ok(Self {
+            _seed: settings.seed,
+            noise_generator: NoiseGenerator::new(settings.seed),
+            overworld: OverworldTerrain::Flat(overworld),
+        })
+    }
+
+    /// Generates the chunk at the given chunk coordinates in the given dimension.
+    pub fn generate_chunk(
+        &self,
+        x: i32,
+        z: i32,
+        dimension: Dimension,
+    ) -> Result<Chunk, WorldGenError> {
+        let mut chunk = match dimension {
+            Dimension::Overworld => match &self.overworld {
+                OverworldTerrain::Noise { shape, features } => {
+                    Self::generate_overworld_chunk(shape, features, x, z)?
+                }
+                OverworldTerrain::Flat(flat) => flat.generate_chunk(x, z)?,
+            },
+            Dimension::Nether => {
+                self.generate_single_biome(&biomes::nether::NetherWastesBiome, x, z)?
+            }
+            Dimension::End => self.generate_single_biome(&biomes::the_end::TheEndBiome, x, z)?,
+        };
+
+        chunk.calculate_light()?;
+
+        Ok(chunk)
+    }
+
+    /// Generates an overworld chunk, with the features of the chunks around it that reach into it.
+    fn generate_overworld_chunk(
+        shape: &OverworldGenerator,
+        features: &FeaturePlacer,
+        x: i32,
+        z: i32,
+    ) -> Result<Chunk, WorldGenError> {
+        let sources: Vec<_> = (-1..=1)
+            .flat_map(|dz| (-1..=1).map(move |dx| (dx, dz)))
+            .map(|(dx, dz)| shape.cached_shape_chunk(x + dx, z + dz))
+            .collect();
+        let mut chunk = ShapedChunk::clone(&sources[4]);
+        features.place(&sources, &mut chunk);
+        shape.build_chunk(&chunk)
+    }
+
+    /// Generates a chunk of a dimension that only has one biome.
+    fn generate_single_biome(
+        &self,
+        biome: &dyn BiomeGenerator,
+        x: i32,
+        z: i32,
+    ) -> Result<Chunk, WorldGenError> {
+        let mut chunk = biome.generate_chunk(x, z, &self.noise_generator)?;
+        // Biomes are stored in 4x4 columns
+        for column_x in (0..16).step_by(4) {
+            for column_z in (0..16).step_by(4) {
+                chunk.set_biome_column(column_x, column_z, biome.biome_id())?;
+            }
         }
+        Ok(chunk)
     }
+}
+
+impl ChunkGenerator for WorldGenerator {
+    fn generate_chunk(&self, x: i32, z: i32, dimension: Dimension) -> Result<Chunk, WorldGenError> {
+        WorldGenerator::generate_chunk(self, x, z, dimension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ferrumc_data::generated::biomes::Biome;
+
+    #[test]
+    fn test_generated_chunks_have_biomes() {
+        let generator = WorldGenerator::new(0);
+        let chunk = generator
+            .generate_chunk(0, 0, Dimension::Overworld)
+            .unwrap();
+        let OverworldTerrain::Noise { shape, .. } = &generator.overworld else {
+            unreachable!("WorldGenerator::new always makes noise terrain");
+        };
+        assert_eq!(chunk.get_biome(0, 64, 0).unwrap(), shape.biome_at(0, 0).id);
 
-    fn get_biome(&self, _x: i32, _z: i32) -> Box<dyn BiomeGenerator> {
-        // Implement biome selection here
-        Box::new(biomes::plains::PlainsBiome)
+        let chunk = generator.generate_chunk(0, 0, Dimension::Nether).unwrap();
+        assert_eq!(
+            chunk.get_biome(15, 100, 15).unwrap(),
+            Biome::NETHER_WASTES.id
+        );
     }
 
-    pub fn generate_chunk(&self, x: i32, z: i32) -> Result<Chunk, WorldGenError> {
-        let biome = self.get_biome(x, z);
-        biome.generate_chunk(x, z, &self.noise_generator)
+    #[test]
+    fn test_presets_only_change_the_overworld() {
+        let generator = WorldGenerator::from_settings(&GeneratorSettings {
+            preset: GeneratorPreset::Void,
+            seed: 0,
+        })
+        .unwrap();
+        let chunk = generator
+            .generate_chunk(0, 0, Dimension::Overworld)
+            .unwrap();
+        assert_eq!(chunk.get_block(0, 0, 0).unwrap(), BlockStateId::default());
+        assert_eq!(chunk.get_biome(0, 0, 0).unwrap(), Biome::THE_VOID.id);
+
+        let chunk = generator.generate_chunk(0, 0, Dimension::Nether).unwrap();
+        assert_eq!(
+            chunk.get_biome(15, 100, 15).unwrap(),
+            Biome::NETHER_WASTES.id
+        );
+    }
+
+    #[test]
+    fn test_registered_generator() {
+        struct StoneGenerator;
+        impl ChunkGenerator for StoneGenerator {
+            fn generate_chunk(
+                &self,
+                x: i32,
+                z: i32,
+                dimension: Dimension,
+            ) -> Result<Chunk, WorldGenError> {
+                let mut chunk = Chunk::new(x, z, dimension.as_str().to_string());
+                chunk.set_section(0, block!("stone"))?;
+                Ok(chunk)
+            }
+        }
+
+        register_chunk_generator(Arc::new(StoneGenerator));
+        let generator = registered_chunk_generator().unwrap();
+        let chunk = generator
+            .generate_chunk(2, 5, Dimension::Overworld)
+            .unwrap();
+        assert_eq!(chunk.get_block(3, 4, 5).unwrap(), block!("stone"));
     }
 }
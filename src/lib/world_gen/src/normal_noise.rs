@@ -0,0 +1,158 @@
+//! The noise vanilla builds its terrain and climate from, configured by the extracted
+//! `noise_parameters.json`.
+//!
+//! Each noise is made of octaves of Perlin noise, starting at a frequency of `2^firstOctave` and
+//! doubling with each octave, weighted by that octave's amplitude. Like vanilla's "normal noise",
+//! two sets of octaves are sampled at slightly different scales and summed, which hides the grid
+//! Perlin noise is built on. The octaves aren't seeded the way vanilla seeds them, so the noise has
+//! the same character as vanilla's but won't match it block for block.
+
+use noise::{NoiseFn, Perlin};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const NOISE_PARAMETERS_FILE: &str =
+    include_str!("../../../../assets/extracted/noise_parameters.json");
+
+/// How much more the second set of octaves is scaled than the first.
+const SECOND_INPUT_FACTOR: f64 = 1.018_126_888_217_522_7;
+
+/// Every noise in `noise_parameters.json`, keyed by name without the namespace.
+static NOISE_PARAMETERS: LazyLock<HashMap<String, NoiseParameters>> = LazyLock::new(|| {
+    serde_json::from_str(NOISE_PARAMETERS_FILE).expect("noise_parameters.json should be valid")
+});
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NoiseParameters {
+    pub(crate) first_octave: i32,
+    pub(crate) amplitudes: Vec<f64>,
+}
+
+/// A sum of Perlin noise octaves.
+struct OctaveNoise {
+    /// Each octave with its amplitude, from the lowest frequency up.
+    octaves: Vec<(Perlin, f64)>,
+    lowest_frequency: f64,
+    lowest_value_factor: f64,
+}
+
+/// Vanilla's normal noise: two sets of octaves summed and scaled so the result mostly falls
+/// between -1 and 1.
+pub(crate) struct NormalNoise {
+    first: OctaveNoise,
+    second: OctaveNoise,
+    value_factor: f64,
+}
+
+/// Mixes the world seed with a noise's name, so every noise gets its own seed.
+pub(crate) fn noise_seed(seed: u64, name: &str) -> u64 {
+    name.bytes()
+        .fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// The Perlin noise for one octave of a noise seeded with `seed`.
+pub(crate) fn octave_perlin(seed: u64, index: usize) -> Perlin {
+    let seed = seed.wrapping_add((index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    Perlin::new((seed ^ (seed >> 32)) as u32)
+}
+
+impl OctaveNoise {
+    fn new(seed: u64, parameters: &NoiseParameters) -> Self {
+        let octaves = parameters
+            .amplitudes
+            .iter()
+            .enumerate()
+            .map(|(index, amplitude)| (octave_perlin(seed, index), *amplitude))
+            .collect::<Vec<_>>();
+        let count = octaves.len() as i32;
+        Self {
+            octaves,
+            lowest_frequency: 2f64.powi(parameters.first_octave),
+            lowest_value_factor: 2f64.powi(count - 1) / (2f64.powi(count) - 1.0),
+        }
+    }
+
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut frequency = self.lowest_frequency;
+        let mut value_factor = self.lowest_value_factor;
+        let mut value = 0.0;
+        for (perlin, amplitude) in &self.octaves {
+            if *amplitude != 0.0 {
+                value += amplitude
+                    * value_factor
+                    * perlin.get([x * frequency, y * frequency, z * frequency]);
+            }
+            frequency *= 2.0;
+            value_factor /= 2.0;
+        }
+        value
+    }
+}
+
+impl NormalNoise {
+    pub(crate) fn new(seed: u64, parameters: &NoiseParameters) -> Self {
+        // Octaves with no amplitude at either end don't count towards how much the noise varies.
+        let used = parameters
+            .amplitudes
+            .iter()
+            .enumerate()
+            .filter(|(_, amplitude)| **amplitude != 0.0)
+            .map(|(index, _)| index as i32);
+        let (first_used, last_used) = used.clone().min().zip(used.max()).unwrap_or_default();
+        let expected_deviation = 0.1 * (1.0 + 1.0 / f64::from(last_used - first_used + 1));
+        Self {
+            first: OctaveNoise::new(seed, parameters),
+            second: OctaveNoise::new(!seed, parameters),
+            value_factor: (1.0 / 6.0) / expected_deviation,
+        }
+    }
+
+    /// Creates the noise with the given name in `noise_parameters.json`, such as `"temperature"`.
+    ///
+    /// # Panics
+    /// If there's no noise with that name.
+    pub(crate) fn from_name(seed: u64, name: &str) -> Self {
+        let parameters = NOISE_PARAMETERS
+            .get(name)
+            .unwrap_or_else(|| panic!("No noise named {name} in noise_parameters.json"));
+        Self::new(noise_seed(seed, name), parameters)
+    }
+
+    pub(crate) fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (second_x, second_y, second_z) = (
+            x * SECOND_INPUT_FACTOR,
+            y * SECOND_INPUT_FACTOR,
+            z * SECOND_INPUT_FACTOR,
+        );
+        (self.first.get(x, y, z) + self.second.get(second_x, second_y, second_z))
+            * self.value_factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_noise() {
+        let noise = NormalNoise::from_name(0, "temperature");
+        assert_eq!(
+            noise.get(12.0, 0.0, -40.0),
+            NormalNoise::from_name(0, "temperature").get(12.0, 0.0, -40.0)
+        );
+        assert_ne!(
+            noise.get(12.0, 0.0, -40.0),
+            NormalNoise::from_name(1, "temperature").get(12.0, 0.0, -40.0)
+        );
+        // The noise should stay in roughly -1 to 1 and actually vary across that range.
+        let values = (0..1000)
+            .map(|i| noise.get(f64::from(i) * 37.0, 0.0, f64::from(i) * -53.0))
+            .collect::<Vec<_>>();
+        assert!(values.iter().all(|value| value.abs() < 2.0));
+        assert!(values.iter().any(|value| value.abs() > 0.1));
+    }
+}
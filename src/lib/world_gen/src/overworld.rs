@@ -0,0 +1,622 @@
+//! Overworld terrain, shaped in 3D by vanilla's density functions.
+//!
+//! The noise router's `finalDensity` is evaluated at the corners of cells 4 blocks across and 8
+//! blocks tall, and interpolated between them, like vanilla does. Blocks where it's above zero are
+//! solid, and empty space below sea level fills with water. The corners are shared with the
+//! neighbouring chunks, so the terrain lines up across chunk edges. Each 4x4 block column of cells
+//! then gets the biome closest to its climate, see [crate::climate], and the top of each column is
+//! covered with that biome's surface blocks.
+//!
+//! Empty space below the surface is filled by [crate::aquifer]s, and once the surface is on, the
+//! [crate::carvers] cut their caves and canyons through it.
+
+use crate::aquifer::{Aquifer, AquiferSettings};
+use crate::biomes::desert::DesertBiome;
+use crate::biomes::forest::ForestBiome;
+use crate::biomes::mountains::MountainsBiome;
+use crate::biomes::ocean::OceanBiome;
+use crate::biomes::plains::PlainsBiome;
+use crate::biomes::snowy_plains::SnowyPlainsBiome;
+use crate::biomes::swamp::SwampBiome;
+use crate::climate::{ClimateSampler, OVERWORLD_BIOMES};
+use crate::density_function::{ColumnSampler, FunctionId, NoiseRouter};
+use crate::errors::WorldGenError;
+use crate::{OverworldBiome, SURFACE_DEPTH, carvers};
+use ferrumc_data::generated::biomes::Biome;
+use ferrumc_macros::block;
+use ferrumc_world::block_state_id::BlockStateId;
+use ferrumc_world::chunk_format::Chunk;
+use ferrumc_world::dimension::Dimension;
+use ferrumc_world::edit_batch::EditBatch;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// How many chunks' corner densities are kept. Placing a chunk's features shapes the chunks around
+/// it too, and those are usually generated soon after, so this saves working most of them out
+/// again.
+const CORNER_CACHE_SIZE: usize = 1024;
+
+/// How many shaped chunks are kept, see [OverworldGenerator::cached_shape_chunk]. Each is a few
+/// hundred KB, so this is enough for a few rows of chunks being generated next to each other
+/// without holding on to too much memory.
+const SHAPED_CACHE_SIZE: usize = 128;
+
+/// The furthest chunks out whose surroundings' block positions still fit in an i32. That's far past
+/// the world border, so chunks beyond it are generated without anything that works by block
+/// position: aquifers, carvers and features.
+const MAX_POSITIONED_CHUNK: i32 = i32::MAX / 16 - 64;
+
+const CHUNK_GEN_SETTINGS_FILE: &str =
+    include_str!("../../../../assets/extracted/chunk_gen_settings.json");
+
+/// Every set of chunk generator settings in `chunk_gen_settings.json`, keyed by name.
+static CHUNK_GEN_SETTINGS: LazyLock<HashMap<String, GeneratorSettings>> = LazyLock::new(|| {
+    serde_json::from_str(CHUNK_GEN_SETTINGS_FILE).expect("chunk_gen_settings.json should be valid")
+});
+
+#[derive(Deserialize, Debug)]
+struct GeneratorSettings {
+    sea_level: i32,
+    noise: NoiseShape,
+}
+
+/// The part of the world the density functions fill, and the size of the cells they're
+/// interpolated across.
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct NoiseShape {
+    min_y: i32,
+    height: i32,
+    /// The width of a cell, in quarters of a block.
+    size_horizontal: i32,
+    /// The height of a cell, in quarters of a block.
+    size_vertical: i32,
+}
+
+impl NoiseShape {
+    fn cell_width(self) -> i32 {
+        self.size_horizontal * 4
+    }
+
+    fn cell_height(self) -> i32 {
+        self.size_vertical * 4
+    }
+
+    /// How many cell corners there are across a chunk, including the ones on its far edges.
+    fn corners_across(self) -> usize {
+        (16 / self.cell_width()) as usize + 1
+    }
+
+    fn corners_up(self) -> usize {
+        (self.height / self.cell_height()) as usize + 1
+    }
+
+    /// Where a block is in a chunk's list of blocks, given its coordinates within the chunk.
+    fn index(self, x: usize, y: i32, z: usize) -> usize {
+        (y - self.min_y) as usize * 256 + z * 16 + x
+    }
+}
+
+pub(crate) struct OverworldGenerator {
+    seed: u64,
+    router: NoiseRouter,
+    final_density: FunctionId,
+    settings: &'static GeneratorSettings,
+    aquifers: AquiferSettings,
+    corner_cache: Mutex<ChunkCache<Corners>>,
+    shaped_cache: Mutex<ChunkCache<ShapedChunk>>,
+}
+
+/// The final density at each cell corner of a chunk, indexed by `[z][x][y]` in corners.
+type Corners = Vec<Vec<Vec<f64>>>;
+
+/// Something worked out per chunk for the chunks used most recently, keyed by chunk position.
+/// Once it's full, the least recently used chunk is dropped for each new one.
+struct ChunkCache<T> {
+    capacity: usize,
+    entries: HashMap<(i32, i32), Arc<T>>,
+    /// The cached chunks, least recently used first.
+    order: VecDeque<(i32, i32)>,
+}
+
+impl<T> ChunkCache<T> {
+    fn new(capacity: usize) -> Mutex<Self> {
+        Mutex::new(Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        })
+    }
+
+    /// The value for a chunk, from the cache if it's there and from `make` if it isn't.
+    fn get_or_insert_with(
+        cache: &Mutex<Self>,
+        key: (i32, i32),
+        make: impl FnOnce() -> T,
+    ) -> Arc<T> {
+        {
+            let mut cache = cache.lock().unwrap();
+            if let Some(value) = cache.entries.get(&key).cloned() {
+                if let Some(index) = cache.order.iter().position(|cached| *cached == key) {
+                    cache.order.remove(index);
+                }
+                cache.order.push_back(key);
+                return value;
+            }
+        }
+        // Made without holding the lock, so other threads can use the cache meanwhile.
+        let value = Arc::new(make());
+        let mut cache = cache.lock().unwrap();
+        if cache.entries.insert(key, value.clone()).is_none() {
+            cache.order.push_back(key);
+            if cache.order.len() > cache.capacity
+                && let Some(oldest) = cache.order.pop_front()
+            {
+                cache.entries.remove(&oldest);
+            }
+        }
+        value
+    }
+}
+
+/// A chunk's blocks and biomes before it's turned into a [Chunk]. Features are placed into it,
+/// and decide where they go from the chunk as it was before any were, see [crate::features].
+#[derive(Clone)]
+pub(crate) struct ShapedChunk {
+    pub(crate) x: i32,
+    pub(crate) z: i32,
+    pub(crate) min_y: i32,
+    pub(crate) height: i32,
+    /// Indexed by [NoiseShape::index].
+    blocks: Vec<BlockStateId>,
+    /// The biome of each 4x4 block cell, indexed by `[z][x]` in cells.
+    biomes: [[&'static Biome; 4]; 4],
+}
+
+impl ShapedChunk {
+    fn index(&self, x: i32, y: i32, z: i32) -> Option<usize> {
+        (x >> 4 == self.x
+            && z >> 4 == self.z
+            && (self.min_y..self.min_y + self.height).contains(&y))
+        .then(|| (y - self.min_y) as usize * 256 + (z & 15) as usize * 16 + (x & 15) as usize)
+    }
+
+    /// Whether the positions of the blocks in and around this chunk fit in an i32, see
+    /// [MAX_POSITIONED_CHUNK].
+    pub(crate) fn is_positioned(&self) -> bool {
+        self.x.abs() <= MAX_POSITIONED_CHUNK && self.z.abs() <= MAX_POSITIONED_CHUNK
+    }
+
+    /// The block at a position in the world, or `None` if it isn't in this chunk.
+    pub(crate) fn block(&self, x: i32, y: i32, z: i32) -> Option<BlockStateId> {
+        self.index(x, y, z).map(|index| self.blocks[index])
+    }
+
+    /// Sets the block at a position in the world, if it's in this chunk.
+    pub(crate) fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockStateId) {
+        if let Some(index) = self.index(x, y, z) {
+            self.blocks[index] = block;
+        }
+    }
+
+    /// The biome of the column at a position in the world, or `None` if it isn't in this chunk.
+    pub(crate) fn biome(&self, x: i32, z: i32) -> Option<&'static Biome> {
+        (x >> 4 == self.x && z >> 4 == self.z)
+            .then(|| self.biomes[((z & 15) / 4) as usize][((x & 15) / 4) as usize])
+    }
+
+    /// The biomes of this chunk's cells, each once.
+    pub(crate) fn distinct_biomes(&self) -> Vec<&'static Biome> {
+        let mut biomes: Vec<&'static Biome> = Vec::new();
+        for biome in self.biomes.iter().flatten() {
+            if !biomes.iter().any(|seen| seen.id == biome.id) {
+                biomes.push(biome);
+            }
+        }
+        biomes
+    }
+}
+
+/// The terrain generator used for a biome. Biomes without one of their own use the closest match.
+pub(crate) fn terrain_for(biome: &Biome) -> &'static dyn OverworldBiome {
+    match biome.name {
+        name if name.ends_with("ocean") || name.ends_with("river") => &OceanBiome,
+        "desert" | "badlands" | "eroded_badlands" | "wooded_badlands" | "beach" | "stony_shore" => {
+            &DesertBiome
+        }
+        "forest"
+        | "flower_forest"
+        | "birch_forest"
+        | "old_growth_birch_forest"
+        | "dark_forest"
+        | "pale_garden"
+        | "taiga"
+        | "old_growth_pine_taiga"
+        | "old_growth_spruce_taiga"
+        | "jungle"
+        | "sparse_jungle"
+        | "bamboo_jungle" => &ForestBiome,
+        "jagged_peaks"
+        | "frozen_peaks"
+        | "stony_peaks"
+        | "snowy_slopes"
+        | "grove"
+        | "meadow"
+        | "cherry_grove"
+        | "windswept_hills"
+        | "windswept_gravelly_hills"
+        | "windswept_forest"
+        | "windswept_savanna"
+        | "savanna_plateau" => &MountainsBiome,
+        "snowy_plains" | "ice_spikes" | "snowy_taiga" | "snowy_beach" => &SnowyPlainsBiome,
+        "swamp" | "mangrove_swamp" => &SwampBiome,
+        _ => &PlainsBiome,
+    }
+}
+
+impl OverworldGenerator {
+    pub(crate) fn new(seed: u64) -> Self {
+        let router = NoiseRouter::from_settings("overworld", seed);
+        let settings = &CHUNK_GEN_SETTINGS["overworld"];
+        let shape = settings.noise;
+        Self {
+            seed,
+            final_density: router.function("finalDensity"),
+            aquifers: AquiferSettings::new(
+                &router,
+                seed,
+                settings.sea_level,
+                (shape.min_y, shape.height),
+                shape.cell_height(),
+            ),
+            router,
+            settings,
+            corner_cache: ChunkCache::new(CORNER_CACHE_SIZE),
+            shaped_cache: ChunkCache::new(SHAPED_CACHE_SIZE),
+        }
+    }
+
+    /// The biome of the 4x4 block cell a column is in.
+    pub(crate) fn biome_at(&self, x: i64, z: i64) -> &'static Biome {
+        OVERWORLD_BIOMES.nearest(&ClimateSampler::new(&self.router).sample(x & !3, z & !3))
+    }
+
+    /// The biome of each of a chunk's 4x4 block cells, indexed by `[z][x]` in cells.
+    fn biomes(&self, x: i32, z: i32) -> [[&'static Biome; 4]; 4] {
+        let mut biomes = [[&Biome::PLAINS; 4]; 4];
+        for (cell_z, row) in biomes.iter_mut().enumerate() {
+            for (cell_x, biome) in row.iter_mut().enumerate() {
+                let block_x = i64::from(x) * 16 + cell_x as i64 * 4;
+                let block_z = i64::from(z) * 16 + cell_z as i64 * 4;
+                *biome = self.biome_at(block_x, block_z);
+            }
+        }
+        biomes
+    }
+
+    /// The final density at each cell corner of a chunk, indexed by `[z][x][y]` in corners.
+    fn corner_densities(&self, x: i32, z: i32) -> Corners {
+        let shape = self.settings.noise;
+        (0..shape.corners_across())
+            .map(|corner_z| {
+                (0..shape.corners_across())
+                    .map(|corner_x| {
+                        let offset = |corner: usize| i64::from(corner as i32 * shape.cell_width());
+                        let block_x = i64::from(x) * 16 + offset(corner_x);
+                        let block_z = i64::from(z) * 16 + offset(corner_z);
+                        let mut sampler = ColumnSampler::new(&self.router, block_x, block_z);
+                        (0..shape.corners_up())
+                            .map(|corner_y| {
+                                let y = shape.min_y + corner_y as i32 * shape.cell_height();
+                                sampler.sample(self.final_density, y)
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The final density of each block of a chunk, which is solid where it's above zero, indexed
+    /// by [NoiseShape::index].
+    fn terrain(&self, x: i32, z: i32) -> Vec<f64> {
+        let shape = self.settings.noise;
+        let corners = ChunkCache::get_or_insert_with(&self.corner_cache, (x, z), || {
+            self.corner_densities(x, z)
+        });
+        let (width, height) = (shape.cell_width(), shape.cell_height());
+        let lerp = |from: f64, to: f64, t: f64| from + (to - from) * t;
+        let mut densities = vec![0.0; 16 * 16 * shape.height as usize];
+        for y in shape.min_y..shape.min_y + shape.height {
+            let (corner_y, ty) = ((y - shape.min_y) / height, (y - shape.min_y) % height);
+            let (corner_y, ty) = (corner_y as usize, f64::from(ty) / f64::from(height));
+            for block_z in 0..16 {
+                let (corner_z, tz) = (block_z / width, f64::from(block_z % width));
+                let (corner_z, tz) = (corner_z as usize, tz / f64::from(width));
+                for block_x in 0..16 {
+                    let (corner_x, tx) = (block_x / width, f64::from(block_x % width));
+                    let (corner_x, tx) = (corner_x as usize, tx / f64::from(width));
+                    let column = |dx: usize, dz: usize| {
+                        let column = &corners[corner_z + dz][corner_x + dx];
+                        lerp(column[corner_y], column[corner_y + 1], ty)
+                    };
+                    let density = lerp(
+                        lerp(column(0, 0), column(1, 0), tx),
+                        lerp(column(0, 1), column(1, 1), tx),
+                        tz,
+                    );
+                    densities[shape.index(block_x as usize, y, block_z as usize)] = density;
+                }
+            }
+        }
+        densities
+    }
+
+    /// A chunk shaped by [OverworldGenerator::shape_chunk], from the cache if it's been shaped
+    /// recently. Each chunk's features are placed from the chunks around it too, so without this
+    /// every chunk would be shaped again for each of its neighbours.
+    pub(crate) fn cached_shape_chunk(&self, x: i32, z: i32) -> Arc<ShapedChunk> {
+        ChunkCache::get_or_insert_with(&self.shaped_cache, (x, z), || self.shape_chunk(x, z))
+    }
+
+    /// A chunk's terrain and biomes, with each column covered by its biome's surface blocks and
+    /// carved out, but no features yet.
+    pub(crate) fn shape_chunk(&self, x: i32, z: i32) -> ShapedChunk {
+        let shape = self.settings.noise;
+        let sea_level = self.settings.sea_level;
+        let (air, stone, water) = (
+            BlockStateId::default(),
+            block!("stone"),
+            block!("water", {level: 0}),
+        );
+        let densities = self.terrain(x, z);
+        let biomes = self.biomes(x, z);
+        let positioned = x.abs() <= MAX_POSITIONED_CHUNK && z.abs() <= MAX_POSITIONED_CHUNK;
+        let mut aquifer = positioned.then(|| Aquifer::new(&self.router, &self.aquifers, x, z));
+
+        let mut blocks = vec![air; densities.len()];
+        for column_z in 0..16 {
+            for column_x in 0..16 {
+                let biome = terrain_for(biomes[column_z / 4][column_x / 4]);
+                // The top block of the column, while still going down through the solid blocks
+                // beneath it.
+                let mut surface = None;
+                let mut below_surface = false;
+                for y in (shape.min_y..shape.min_y + shape.height).rev() {
+                    let index = shape.index(column_x, y, column_z);
+                    let density = densities[index];
+                    // Aquifers leave some empty space solid, as barriers between them.
+                    let fluid = match &mut aquifer {
+                        _ if density > 0.0 => None,
+                        Some(aquifer) => {
+                            let position = [x * 16 + column_x as i32, y, z * 16 + column_z as i32];
+                            aquifer.substance(position, density)
+                        }
+                        None => Some(if y < sea_level { water } else { air }),
+                    };
+                    blocks[index] = match fluid {
+                        None => {
+                            let top = *surface.get_or_insert(y);
+                            let depth = top - y;
+                            (!below_surface && depth < SURFACE_DEPTH)
+                                .then(|| biome.surface_block(depth, top))
+                                .flatten()
+                                .unwrap_or(stone)
+                        }
+                        Some(fluid) => {
+                            below_surface |= surface.is_some();
+                            if fluid == water && y == sea_level - 1 {
+                                biome.water_surface()
+                            } else {
+                                fluid
+                            }
+                        }
+                    };
+                }
+                if let Some(top) = surface
+                    && top >= sea_level
+                    && top + 1 < shape.min_y + shape.height
+                    && blocks[shape.index(column_x, top + 1, column_z)] == air
+                    && let Some(decoration) = biome.decoration(top)
+                {
+                    blocks[shape.index(column_x, top + 1, column_z)] = decoration;
+                }
+            }
+        }
+
+        let mut chunk = ShapedChunk {
+            x,
+            z,
+            min_y: shape.min_y,
+            height: shape.height,
+            blocks,
+            biomes,
+        };
+        if let Some(aquifer) = &mut aquifer {
+            carvers::carve(self.seed, &mut chunk, aquifer, |x, z| {
+                self.biome_at(i64::from(x), i64::from(z))
+            });
+        }
+        chunk
+    }
+
+    /// Turns a shaped chunk, with or without its features, into a chunk.
+    pub(crate) fn build_chunk(&self, shaped: &ShapedChunk) -> Result<Chunk, WorldGenError> {
+        let air = BlockStateId::default();
+        let mut chunk = Chunk::new(
+            shaped.x,
+            shaped.z,
+            Dimension::Overworld.as_str().to_string(),
+        );
+
+        // Sections that are all one block are much faster to fill whole than block by block.
+        let mut mixed_sections = Vec::new();
+        for (section, section_blocks) in shaped.blocks.chunks(4096).enumerate() {
+            let section_y = (shaped.min_y >> 4) + section as i32;
+            if section_blocks
+                .iter()
+                .all(|block| *block == section_blocks[0])
+            {
+                if section_blocks[0] != air {
+                    chunk.set_section(section_y as i8, section_blocks[0])?;
+                }
+            } else {
+                mixed_sections.push((section_y, section_blocks));
+            }
+        }
+        if !mixed_sections.is_empty() {
+            let mut batch = EditBatch::new(&mut chunk);
+            for (section_y, section_blocks) in mixed_sections {
+                for (index, block) in section_blocks.iter().enumerate() {
+                    if *block != air {
+                        let (x, z) = ((index & 15) as i32, ((index >> 4) & 15) as i32);
+                        let y = section_y * 16 + (index >> 8) as i32;
+                        batch.set_block(x, y, z, *block);
+                    }
+                }
+            }
+            batch.apply()?;
+        }
+
+        for (cell_z, row) in shaped.biomes.iter().enumerate() {
+            for (cell_x, biome) in row.iter().enumerate() {
+                chunk.set_biome_column(cell_x as i32 * 4, cell_z as i32 * 4, biome.id)?;
+            }
+        }
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ok() {
+        let generator = OverworldGenerator::new(0);
+        assert!(generator.build_chunk(&generator.shape_chunk(0, 0)).is_ok());
+    }
+
+    #[test]
+    fn test_random_chunk_generation() {
+        let generator = OverworldGenerator::new(0);
+        for _ in 0..100 {
+            let x = rand::random::<i32>();
+            let z = rand::random::<i32>();
+            assert!(generator.build_chunk(&generator.shape_chunk(x, z)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_very_high_coordinates() {
+        let generator = OverworldGenerator::new(0);
+        assert!(
+            generator
+                .build_chunk(&generator.shape_chunk(1610612735, 1610612735))
+                .is_ok()
+        );
+        assert!(
+            generator
+                .build_chunk(&generator.shape_chunk(-1610612735, -1610612735))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_random_seeds() {
+        for _ in 0..100 {
+            let seed = rand::random::<u64>();
+            let generator = OverworldGenerator::new(seed);
+            assert!(generator.build_chunk(&generator.shape_chunk(0, 0)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_chunk_edges_line_up() {
+        let generator = OverworldGenerator::new(0);
+        let shape = generator.settings.noise;
+        let last = shape.corners_across() - 1;
+        for chunk_x in -5..5 {
+            let west = generator.corner_densities(chunk_x, 3);
+            let east = generator.corner_densities(chunk_x + 1, 3);
+            for corner_z in 0..=last {
+                assert_eq!(west[corner_z][last], east[corner_z][0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_terrain_has_overhangs_or_caves() {
+        let generator = OverworldGenerator::new(0);
+        let shape = generator.settings.noise;
+        // Somewhere in these chunks there should be empty space with solid ground above it, which
+        // a heightmap can't make.
+        let found = (0..8).any(|chunk_x| {
+            let densities = generator.terrain(chunk_x, 0);
+            let solid = |y| densities[shape.index(8, y, 8)] > 0.0;
+            (shape.min_y..shape.min_y + shape.height - 1).any(|y| !solid(y) && solid(y + 1))
+        });
+        assert!(found);
+    }
+
+    #[test]
+    fn test_sea_level() {
+        let generator = OverworldGenerator::new(0);
+        let sea_level = generator.settings.sea_level;
+        let chunk = generator.build_chunk(&generator.shape_chunk(0, 0)).unwrap();
+        let water = block!("water", {level: 0});
+        for y in sea_level..sea_level + 16 {
+            for (x, z) in [(0, 0), (7, 9), (15, 15)] {
+                assert_ne!(chunk.get_block(x, y, z).unwrap(), water);
+            }
+        }
+    }
+
+    #[test]
+    fn test_caves_are_carved_and_flooded() {
+        let generator = OverworldGenerator::new(0);
+        let shape = generator.settings.noise;
+        let (air, water, lava) = (
+            BlockStateId::default(),
+            block!("water", {level: 0}),
+            block!("lava", {level: 0}),
+        );
+        let (mut carved, mut flooded) = (false, false);
+        for chunk_x in 0..4 {
+            let densities = generator.terrain(chunk_x, 0);
+            let chunk = generator.shape_chunk(chunk_x, 0);
+            for (index, block) in chunk.blocks.iter().enumerate() {
+                // Carvers empty out blocks the terrain left solid.
+                carved |= densities[index] > 0.0 && *block == air;
+                // Underground, only aquifers fill empty space with water or lava.
+                flooded |= index < shape.index(0, 0, 0) && (*block == water || *block == lava);
+            }
+        }
+        assert!(carved);
+        assert!(flooded);
+    }
+
+    #[test]
+    fn test_caves_are_the_same_every_time() {
+        let first = OverworldGenerator::new(42).shape_chunk(3, -2);
+        let second = OverworldGenerator::new(42).shape_chunk(3, -2);
+        assert_eq!(first.blocks, second.blocks);
+    }
+
+    #[test]
+    fn test_shaped_chunks_are_cached() {
+        let generator = OverworldGenerator::new(0);
+        let first = generator.cached_shape_chunk(1, 1);
+        assert!(Arc::ptr_eq(&first, &generator.cached_shape_chunk(1, 1)));
+        assert_eq!(first.blocks, generator.shape_chunk(1, 1).blocks);
+
+        let cache = ChunkCache::new(2);
+        ChunkCache::get_or_insert_with(&cache, (0, 0), || 0);
+        ChunkCache::get_or_insert_with(&cache, (1, 0), || 1);
+        // Using the first chunk again means the second is the one dropped for a third.
+        ChunkCache::get_or_insert_with(&cache, (0, 0), || unreachable!());
+        ChunkCache::get_or_insert_with(&cache, (2, 0), || 2);
+        let entries = &cache.lock().unwrap().entries;
+        assert!(entries.contains_key(&(0, 0)) && !entries.contains_key(&(1, 0)));
+    }
+}
@@ -300,3 +300,45 @@ fn test_list_compound_derive() {
 
     assert_eq!(list_compound.list.len(), 2);
 }
+
+#[test]
+fn test_validate_rejects_broken_data() {
+    #[derive(NBTSerialize, NBTDeserialize, Debug)]
+    struct Nested {
+        name: String,
+        list: Vec<i32>,
+        longs: Vec<i64>,
+    }
+
+    let buffer = Nested {
+        name: "hello".to_string(),
+        list: vec![1, 2, 3],
+        longs: vec![4, 5],
+    }
+    .serialize_with_header();
+    assert!(ferrumc_nbt::NbtTape::validate(&buffer).is_ok());
+    for length in 0..buffer.len() {
+        assert!(ferrumc_nbt::NbtTape::validate(&buffer[..length]).is_err());
+    }
+
+    // A list of end tags with elements would be skipped over forever.
+    let mut ends = buffer.clone();
+    ends.truncate(ends.len() - 1);
+    ends.extend([9, 0, 1, b'x', 0, 0x7f, 0xff, 0xff, 0xff, 0]);
+    assert!(ferrumc_nbt::NbtTape::validate(&ends).is_err());
+
+    // A list of strings can't be read as a list of ints.
+    #[derive(NBTSerialize)]
+    struct Strings {
+        list: Vec<String>,
+    }
+    #[derive(NBTDeserialize, Debug)]
+    struct Ints {
+        list: Vec<i32>,
+    }
+    let buffer = Strings {
+        list: vec!["a".to_string()],
+    }
+    .serialize_with_header();
+    assert!(Ints::from_bytes(&buffer).is_err());
+}